@@ -0,0 +1,45 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+fn bench_render(c: &mut Criterion) {
+    const GRID_X: usize = 630;
+    const GRID_Y: usize = 330;
+    const BUFFER_X: usize = 1280;
+    const BUFFER_Y: usize = 720;
+
+    // grow some molds so the grid is not empty
+    let mut s = rustymold::Simulation::new(GRID_X, GRID_Y, 16);
+    s.seed(4);
+    s.generate_random_molds(300);
+    s.run(500);
+
+    let mut buffer = vec![0; BUFFER_X * BUFFER_Y];
+
+    let mut group = c.benchmark_group(format!("render to {BUFFER_X}x{BUFFER_Y} buffer"));
+    for zoom in [1, 4, 8] {
+        group.bench_with_input(BenchmarkId::new("render", zoom), &zoom, |b, &zoom| {
+            b.iter(|| s.render(&mut buffer, (BUFFER_X, BUFFER_Y), (3, 5), zoom))
+        });
+        group.bench_with_input(
+            BenchmarkId::new("render_sampled", zoom),
+            &zoom,
+            |b, &zoom| b.iter(|| s.render_sampled(&mut buffer, (BUFFER_X, BUFFER_Y), (3, 5), zoom)),
+        );
+    }
+    group.finish();
+
+    #[cfg(feature = "parallel")]
+    for (width, height) in [(1920, 1080), (3840, 2160)] {
+        let mut buffer = vec![0; width * height];
+        let mut group = c.benchmark_group(format!("render to {width}x{height} buffer"));
+        group.bench_function("render", |b| {
+            b.iter(|| s.render(&mut buffer, (width, height), (3, 5), 2))
+        });
+        group.bench_function("render_parallel", |b| {
+            b.iter(|| s.render_parallel(&mut buffer, (width, height), (3, 5), 2))
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_render);
+criterion_main!(benches);
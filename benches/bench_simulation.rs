@@ -17,7 +17,7 @@ fn bench_simulation(c: &mut Criterion) {
         |b| {
             b.iter(|| {
                 // seed the RNG
-                fastrand::seed(4);
+                s.seed(4);
 
                 // reset simulation state
                 s.clear();
@@ -0,0 +1,223 @@
+//! Painting walls and light and erasing cells with the mouse.
+
+use rustymold::{Edges, Simulation};
+
+/// brush radius in cells when the window opens
+pub const BRUSH_RADIUS: usize = 2;
+pub const MAX_BRUSH_RADIUS: usize = 32;
+/// change of the light intensity of a cell, out of FULL_LIGHT, for every point of a stroke of
+/// the light brush covering it
+pub const LIGHT_STEP: u8 = 8;
+
+/// What the mouse buttons do.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tool {
+    /// Left-drag erases an area, right-drag pans and the scroll wheel zooms.
+    View,
+    /// Left-drag paints walls, right-drag erases everything and the scroll wheel sizes the brush.
+    Walls,
+    /// Left-click picks up the genome of a mold, right-drag pans and the scroll wheel zooms.
+    Eyedropper,
+    /// Left-click creates a mold with the picked up genome on an empty cell, right-drag pans and
+    /// the scroll wheel zooms.
+    Stamp,
+    /// Left-drag raises the light of cells, right-drag lowers it and the scroll wheel sizes the
+    /// brush.
+    Light,
+}
+
+impl Tool {
+    /// The tool after cycling from viewing to painting walls to painting light.
+    pub fn next(self) -> Self {
+        match self {
+            Tool::Walls => Tool::Light,
+            Tool::Light => Tool::View,
+            Tool::View | Tool::Eyedropper | Tool::Stamp => Tool::Walls,
+        }
+    }
+
+    /// Whether the tool paints with a brush, which the mouse buttons and scroll wheel control
+    /// instead of the view.
+    pub fn is_brush(self) -> bool {
+        matches!(self, Tool::Walls | Tool::Light)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Tool::View => "view",
+            Tool::Walls => "walls",
+            Tool::Eyedropper => "eyedropper",
+            Tool::Stamp => "stamp",
+            Tool::Light => "light",
+        }
+    }
+}
+
+/// What a stroke of the brush does to the cells it covers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stroke {
+    Wall,
+    Erase,
+    /// Raise the light intensity by LIGHT_STEP.
+    Brighten,
+    /// Lower the light intensity by LIGHT_STEP.
+    Darken,
+}
+
+/// Cells on the line from `from` to `to`, both included, without gaps between consecutive ones.
+pub fn line(from: (isize, isize), to: (isize, isize)) -> Vec<(isize, isize)> {
+    let (dx, dy) = ((to.0 - from.0).abs(), -(to.1 - from.1).abs());
+    let step = ((to.0 - from.0).signum(), (to.1 - from.1).signum());
+    let mut cells = Vec::with_capacity(dx.max(-dy) as usize + 1);
+    let (mut x, mut y) = from;
+    let mut error = dx + dy;
+    loop {
+        cells.push((x, y));
+        if (x, y) == to {
+            return cells;
+        }
+        // both steps compare against the error before either of them
+        let double = 2 * error;
+        if double >= dy {
+            error += dy;
+            x += step.0;
+        }
+        if double <= dx {
+            error += dx;
+            y += step.1;
+        }
+    }
+}
+
+/// Apply a stroke to every cell within `radius` of `center`, which may lie outside the grid.
+/// Cells past the edges wrap around when the edges wrap, and are skipped when they are bounded.
+pub fn paint(simulation: &mut Simulation, center: (isize, isize), radius: usize, stroke: Stroke) {
+    let (size_x, size_y) = simulation.size();
+    // positions past the edges show the grid repeated, also when the edges are bounded
+    let center = (
+        center.0.rem_euclid(size_x as isize),
+        center.1.rem_euclid(size_y as isize),
+    );
+    let radius = radius as isize;
+    for dx in -radius..=radius {
+        for dy in -radius..=radius {
+            if dx * dx + dy * dy > radius * radius + radius {
+                continue;
+            }
+            let (x, y) = (center.0 + dx, center.1 + dy);
+            let (x, y) = match simulation.edges() {
+                Edges::Wrap => (
+                    x.rem_euclid(size_x as isize) as usize,
+                    y.rem_euclid(size_y as isize) as usize,
+                ),
+                Edges::Bounded => {
+                    if x < 0 || y < 0 || x >= size_x as isize || y >= size_y as isize {
+                        continue;
+                    }
+                    (x as usize, y as usize)
+                }
+            };
+            let light = simulation.light_intensity(x, y);
+            match stroke {
+                Stroke::Wall => simulation.set_wall(x, y, true),
+                Stroke::Erase => simulation.erase_cell(x, y),
+                Stroke::Brighten => simulation.set_light(x, y, light.saturating_add(LIGHT_STEP)),
+                Stroke::Darken => simulation.set_light(x, y, light.saturating_sub(LIGHT_STEP)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustymold::FULL_LIGHT;
+
+    /// Light intensity of every cell, row by row.
+    fn light_rows(simulation: &Simulation) -> Vec<Vec<u8>> {
+        let (size_x, size_y) = simulation.size();
+        (0..size_y)
+            .map(|y| {
+                (0..size_x)
+                    .map(|x| simulation.light_intensity(x, y))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn tools_cycle_through_brushes() {
+        assert_eq!(Tool::View.next(), Tool::Walls);
+        assert_eq!(Tool::Walls.next(), Tool::Light);
+        assert_eq!(Tool::Light.next(), Tool::View);
+        assert!(Tool::Walls.is_brush() && Tool::Light.is_brush());
+        assert!(!Tool::View.is_brush() && !Tool::Stamp.is_brush());
+    }
+
+    #[test]
+    fn line_covers_cells_without_gaps() {
+        assert_eq!(line((2, 3), (2, 3)), [(2, 3)]);
+        assert_eq!(line((0, 0), (3, 0)), [(0, 0), (1, 0), (2, 0), (3, 0)]);
+        assert_eq!(line((1, 2), (1, -1)), [(1, 2), (1, 1), (1, 0), (1, -1)]);
+        assert_eq!(line((0, 0), (-2, 2)), [(0, 0), (-1, 1), (-2, 2)]);
+        assert_eq!(
+            line((0, 0), (4, 2)),
+            [(0, 0), (1, 1), (2, 1), (3, 2), (4, 2)]
+        );
+        for to in [(7, 3), (-5, 9), (2, -8), (-6, -6), (0, -4), (9, 1)] {
+            let from = (1, -2);
+            let cells = line(from, to);
+            let (dx, dy) = ((to.0 - from.0).abs(), (to.1 - from.1).abs());
+            assert_eq!(cells.len() as isize, dx.max(dy) + 1, "to {to:?}");
+            assert_eq!((cells[0], cells[cells.len() - 1]), (from, to));
+            for pair in cells.windows(2) {
+                let step = (pair[1].0 - pair[0].0, pair[1].1 - pair[0].1);
+                assert!(step != (0, 0) && step.0.abs() <= 1 && step.1.abs() <= 1);
+            }
+        }
+    }
+
+    #[test]
+    fn light_brush_wraps_around() {
+        let mut simulation = Simulation::builder(5, 4).build();
+        paint(&mut simulation, (0, 0), 1, Stroke::Darken);
+        let (full, dark) = (FULL_LIGHT, FULL_LIGHT - LIGHT_STEP);
+        assert_eq!(
+            light_rows(&simulation),
+            [
+                [dark, dark, full, full, dark],
+                [dark, dark, full, full, dark],
+                [full, full, full, full, full],
+                [dark, dark, full, full, dark],
+            ]
+        );
+        paint(&mut simulation, (0, 0), 1, Stroke::Brighten);
+        assert!(light_rows(&simulation)
+            .iter()
+            .flatten()
+            .all(|&light| light == full));
+    }
+
+    #[test]
+    fn light_brush_stops_at_bounded_edges_and_saturates() {
+        let mut simulation = Simulation::builder(4, 3).edges(Edges::Bounded).build();
+        for _ in 0..40 {
+            paint(&mut simulation, (0, 0), 0, Stroke::Darken);
+        }
+        paint(&mut simulation, (3, 2), 1, Stroke::Darken);
+        let full = FULL_LIGHT;
+        let dark = FULL_LIGHT - LIGHT_STEP;
+        assert_eq!(
+            light_rows(&simulation),
+            [
+                [0, full, full, full],
+                [full, full, dark, dark],
+                [full, full, dark, dark],
+            ]
+        );
+        for _ in 0..40 {
+            paint(&mut simulation, (3, 2), 1, Stroke::Brighten);
+        }
+        assert_eq!(simulation.light_intensity(3, 2), full);
+    }
+}
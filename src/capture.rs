@@ -0,0 +1,95 @@
+//! Screenshots of the window and of the whole grid.
+
+use std::path::PathBuf;
+
+use rustymold::Simulation;
+
+#[cfg(feature = "image")]
+use crate::saves::timestamped_path;
+
+/// directory screenshots are written to, relative to the working directory
+#[cfg(feature = "image")]
+pub const SCREENSHOT_DIR: &str = "screenshots";
+
+/// Convert pixels stored as 0RGB to bytes in the order red, green, blue, pixel by pixel.
+#[cfg(any(feature = "image", feature = "recording"))]
+pub fn xrgb_to_rgb8(pixels: &[u32]) -> Vec<u8> {
+    pixels
+        .iter()
+        .flat_map(|pixel| [(pixel >> 16) as u8, (pixel >> 8) as u8, *pixel as u8])
+        .collect()
+}
+
+/// Write the first size.0 x size.1 pixels of a 0RGB buffer as a PNG to a new file in
+/// SCREENSHOT_DIR and return its path.
+pub fn save_screenshot(pixels: &[u32], size: (usize, usize)) -> Result<PathBuf, String> {
+    #[cfg(feature = "image")]
+    {
+        let path = timestamped_path(SCREENSHOT_DIR, "screenshot", "png")?;
+        let rgb = xrgb_to_rgb8(&pixels[..size.0 * size.1]);
+        image::save_buffer(
+            &path,
+            &rgb,
+            size.0 as u32,
+            size.1 as u32,
+            image::ExtendedColorType::Rgb8,
+        )
+        .map_err(|e| format!("cannot write {}: {e}", path.display()))?;
+        Ok(path)
+    }
+    #[cfg(not(feature = "image"))]
+    {
+        let _ = (pixels, size);
+        Err("cannot take screenshots: built without the image feature".to_string())
+    }
+}
+
+/// Write the whole grid at zoom 1 in the current view mode as a PNG to a new file in
+/// SCREENSHOT_DIR and return its path.
+pub fn save_grid_screenshot(simulation: &Simulation) -> Result<PathBuf, String> {
+    #[cfg(feature = "image")]
+    {
+        let path = timestamped_path(SCREENSHOT_DIR, "grid", "png")?;
+        let options = rustymold::RenderOptions {
+            view_mode: simulation.view_mode(),
+            ..rustymold::RenderOptions::default()
+        };
+        simulation
+            .save_png(&path, options)
+            .map_err(|e| format!("cannot write {}: {e}", path.display()))?;
+        Ok(path)
+    }
+    #[cfg(not(feature = "image"))]
+    {
+        let _ = simulation;
+        Err("cannot take screenshots: built without the image feature".to_string())
+    }
+}
+
+#[cfg(all(test, feature = "image"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rgb8_bytes_are_red_green_blue() {
+        let pixels = [0x123456, 0xFF0000, 0x00FF00, 0x0000FF];
+        assert_eq!(
+            xrgb_to_rgb8(&pixels),
+            [0x12, 0x34, 0x56, 0xFF, 0, 0, 0, 0xFF, 0, 0, 0, 0xFF]
+        );
+        // whatever is above the color is dropped
+        assert_eq!(xrgb_to_rgb8(&[0xAB123456]), [0x12, 0x34, 0x56]);
+        assert!(xrgb_to_rgb8(&[]).is_empty());
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn rgb8_bytes_make_image_of_same_colors() {
+        let pixels = [0x123456, 0xFF8000, 0x00FF80, 0x8000FF, 0x000000, 0xFFFFFF];
+        let image = image::RgbImage::from_raw(3, 2, xrgb_to_rgb8(&pixels)).unwrap();
+        for (x, y, pixel) in image.enumerate_pixels() {
+            let [_, r, g, b] = pixels[y as usize * 3 + x as usize].to_be_bytes();
+            assert_eq!(pixel.0, [r, g, b], "({x}, {y})");
+        }
+    }
+}
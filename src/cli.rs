@@ -0,0 +1,445 @@
+//! Command line argument parsing for the rustymold binary.
+
+use std::path::PathBuf;
+
+use rustymold::{MAX_ENERGY_LIGHT, MIN_ENERGY_LIGHT};
+
+use crate::config;
+use crate::stats_log::STATS_INTERVAL;
+use crate::timelapse::{Timelapse, TIMELAPSE_INTERVAL};
+
+pub const USAGE: &str = "\
+usage:
+    rustymold [options]              open the interactive window
+    rustymold --headless [<N>] [options]
+    rustymold run [options]          run a simulation without a window
+
+options:
+    --config <FILE>   settings file, written with the defaults if missing
+                      (default config.toml)
+    --width <N>       width of the grid (default from the settings, 630)
+    --height <N>      height of the grid (default from the settings, 330)
+    --grid <W>x<H>    width and height of the grid at once
+    --light <N>       light level from 0 to 20 (default from the settings, 16)
+    --seed <N>        seed for the random number generator (default: random)
+    --density <F>     fraction of the cells from 0 to 1 that start as molds
+                      (default: none in the window, 300 molds without it)
+    --load <FILE>     start from a file saved with F5 instead of a new grid
+    --timelapse <DIR> write the whole grid to a PNG file in DIR every few ticks
+    --timelapse-interval <N>
+                      ticks between two time-lapse frames (default 500)
+
+options for the window:
+    --paused          start paused
+
+options without a window:
+    --headless [<N>]  run N updates without opening the window, or as many as
+                      --ticks gives (default 1000)
+    --ticks <N>       number of updates to run with --headless
+    --steps <N>       number of updates to run with the run command (default 1000)
+    --out <FILE>      write the final state to a PNG file
+    --stats-out <FILE>
+                      write the stats to a CSV file every few ticks, stopping
+                      cleanly on Ctrl-C
+    --stats-interval <N>
+                      ticks between two rows of the stats (default 100)";
+
+/// options that only apply to the window
+const WINDOW_ONLY: &[&str] = &["--paused"];
+/// options that only apply without a window
+const HEADLESS_ONLY: &[&str] = &[
+    "--ticks",
+    "--steps",
+    "--out",
+    "--stats-out",
+    "--stats-interval",
+];
+/// options describing a new grid, which a loaded save already has
+const NEW_GRID_ONLY: &[&str] = &["--width", "--height", "--grid", "--density"];
+
+pub enum Command {
+    /// Open the interactive window.
+    Window(Options),
+    /// Run a simulation without a window.
+    Run(Options),
+}
+
+#[derive(Clone, Debug)]
+pub struct Options {
+    pub config: PathBuf,
+    /// Width and height of the grid, or None for the ones in the settings.
+    pub grid: (Option<usize>, Option<usize>),
+    /// Light level, or None for the one in the settings or that of a loaded save.
+    pub light: Option<i32>,
+    pub steps: usize,
+    pub seed: Option<u64>,
+    /// Fraction of the cells that start as molds, or None for the default number.
+    pub density: Option<f64>,
+    pub paused: bool,
+    pub load: Option<PathBuf>,
+    pub out: Option<PathBuf>,
+    /// Directory and interval of the time-lapse frames, if they are written.
+    pub timelapse: Option<Timelapse>,
+    /// CSV file the stats are written to while running without a window, if any.
+    pub stats_out: Option<PathBuf>,
+    /// Ticks between two rows of the stats, at least 1.
+    pub stats_interval: u64,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            config: PathBuf::from(config::DEFAULT_PATH),
+            grid: (None, None),
+            light: None,
+            steps: 1000,
+            seed: None,
+            density: None,
+            paused: false,
+            load: None,
+            out: None,
+            timelapse: None,
+            stats_out: None,
+            stats_interval: STATS_INTERVAL,
+        }
+    }
+}
+
+/// Parse the command line arguments, excluding the name of the binary.
+pub fn parse(args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let mut args = args.peekable();
+    let is_run = args.peek().is_some_and(|arg| arg == "run");
+    if is_run {
+        args.next();
+    }
+    let (options, seen) = parse_options(args)?;
+    let headless = is_run || seen.contains(&"--headless");
+    if is_run && seen.contains(&"--headless") {
+        return Err("--headless cannot be used with the run command, use --steps".to_string());
+    }
+
+    let disallowed = if headless { WINDOW_ONLY } else { HEADLESS_ONLY };
+    if let Some(flag) = seen.iter().find(|flag| disallowed.contains(flag)) {
+        return Err(if headless {
+            format!("{flag} only applies to the window")
+        } else {
+            format!("{flag} only applies without a window, see --headless")
+        });
+    }
+    if options.load.is_some() {
+        if let Some(flag) = seen.iter().find(|flag| NEW_GRID_ONLY.contains(flag)) {
+            return Err(format!("{flag} cannot be combined with --load"));
+        }
+    }
+    if seen.contains(&"--timelapse-interval") && options.timelapse.is_none() {
+        return Err("--timelapse-interval needs --timelapse".to_string());
+    }
+    if seen.contains(&"--ticks") && !seen.contains(&"--headless") {
+        return Err("--ticks needs --headless".to_string());
+    }
+    if seen.contains(&"--stats-interval") && options.stats_out.is_none() {
+        return Err("--stats-interval needs --stats-out".to_string());
+    }
+    Ok(if headless {
+        Command::Run(options)
+    } else {
+        Command::Window(options)
+    })
+}
+
+/// Parse options until the arguments run out, returning them with the flags that were given.
+fn parse_options(
+    args: impl Iterator<Item = String>,
+) -> Result<(Options, Vec<&'static str>), String> {
+    let mut args = args.peekable();
+    let mut options = Options::default();
+    let mut seen = Vec::new();
+    // whether --headless was given the number of updates itself
+    let mut headless_steps = false;
+    let mut timelapse_interval = TIMELAPSE_INTERVAL;
+    while let Some(flag) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value for {flag}"));
+        let flag: &'static str = match flag.as_str() {
+            "--config" => {
+                options.config = PathBuf::from(value()?);
+                "--config"
+            }
+            "--width" => {
+                options.grid.0 = Some(parse_grid_length(&flag, &value()?)?);
+                "--width"
+            }
+            "--height" => {
+                options.grid.1 = Some(parse_grid_length(&flag, &value()?)?);
+                "--height"
+            }
+            "--grid" => {
+                let (width, height) = parse_grid(&value()?)?;
+                options.grid = (Some(width), Some(height));
+                "--grid"
+            }
+            "--light" => {
+                options.light = Some(parse_light(&value()?)?);
+                "--light"
+            }
+            "--seed" => {
+                options.seed = Some(parse_number(&flag, &value()?)?);
+                "--seed"
+            }
+            "--density" => {
+                options.density = Some(parse_density(&value()?)?);
+                "--density"
+            }
+            "--load" => {
+                options.load = Some(PathBuf::from(value()?));
+                "--load"
+            }
+            "--paused" => {
+                options.paused = true;
+                "--paused"
+            }
+            "--headless" => {
+                // the number of updates is optional, it can be given with --ticks instead
+                if let Some(steps) = args.next_if(|arg| !arg.starts_with("--")) {
+                    options.steps = parse_number(&flag, &steps)?;
+                    headless_steps = true;
+                }
+                "--headless"
+            }
+            "--ticks" => {
+                options.steps = parse_number(&flag, &value()?)?;
+                "--ticks"
+            }
+            "--steps" => {
+                options.steps = parse_number(&flag, &value()?)?;
+                "--steps"
+            }
+            "--out" => {
+                options.out = Some(PathBuf::from(value()?));
+                "--out"
+            }
+            "--timelapse" => {
+                options.timelapse = Some(Timelapse {
+                    dir: PathBuf::from(value()?),
+                    interval: TIMELAPSE_INTERVAL,
+                });
+                "--timelapse"
+            }
+            "--timelapse-interval" => {
+                timelapse_interval = parse_number(&flag, &value()?)?;
+                if timelapse_interval == 0 {
+                    return Err(format!("{flag} must not be zero"));
+                }
+                "--timelapse-interval"
+            }
+            "--stats-out" => {
+                options.stats_out = Some(PathBuf::from(value()?));
+                "--stats-out"
+            }
+            "--stats-interval" => {
+                options.stats_interval = parse_number(&flag, &value()?)?;
+                if options.stats_interval == 0 {
+                    return Err(format!("{flag} must not be zero"));
+                }
+                "--stats-interval"
+            }
+            _ => return Err(format!("unknown option '{flag}'")),
+        };
+        if seen.contains(&flag) {
+            return Err(format!("{flag} is given more than once"));
+        }
+        seen.push(flag);
+    }
+    if headless_steps && seen.contains(&"--ticks") {
+        return Err("--ticks cannot be combined with a number after --headless".to_string());
+    }
+    if let Some(timelapse) = &mut options.timelapse {
+        timelapse.interval = timelapse_interval;
+    }
+    Ok((options, seen))
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value '{value}' for {flag}"))
+}
+
+/// Parse a light level, which must lie within the bounds used by the window.
+fn parse_light(value: &str) -> Result<i32, String> {
+    let light = parse_number("--light", value)?;
+    if !(MIN_ENERGY_LIGHT..=MAX_ENERGY_LIGHT).contains(&light) {
+        return Err(format!(
+            "light level {light} is not between {MIN_ENERGY_LIGHT} and {MAX_ENERGY_LIGHT}"
+        ));
+    }
+    Ok(light)
+}
+
+/// Parse the fraction of cells that start as molds, which must lie between 0 and 1.
+fn parse_density(value: &str) -> Result<f64, String> {
+    let density: f64 = parse_number("--density", value)?;
+    if !(0.0..=1.0).contains(&density) {
+        return Err(format!("density {value} is not between 0 and 1"));
+    }
+    Ok(density)
+}
+
+/// Parse the width or height of the grid, which must not be zero.
+fn parse_grid_length(flag: &str, value: &str) -> Result<usize, String> {
+    let length = parse_number(flag, value)?;
+    if length == 0 {
+        return Err(format!("{flag} must not be zero"));
+    }
+    Ok(length)
+}
+
+/// Parse a grid size given as "<width>x<height>".
+fn parse_grid(value: &str) -> Result<(usize, usize), String> {
+    let (width, height) = value
+        .split_once('x')
+        .ok_or(format!("invalid grid size '{value}', expected <W>x<H>"))?;
+    let width: usize = parse_number("--grid", width)?;
+    let height: usize = parse_number("--grid", height)?;
+    if width == 0 || height == 0 {
+        return Err(format!("grid size '{value}' must not be empty"));
+    }
+    Ok((width, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_args(args: &[&str]) -> Result<Command, String> {
+        parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn nonsense_is_rejected() {
+        let cases: &[(&[&str], &str)] = &[
+            (&["--grid", "0x5"], "grid size '0x5' must not be empty"),
+            (&["--grid", "5x0"], "grid size '5x0' must not be empty"),
+            (&["--grid", "5"], "invalid grid size '5', expected <W>x<H>"),
+            (&["--width", "0"], "--width must not be zero"),
+            (&["--height", "-3"], "invalid value '-3' for --height"),
+            (&["--density", "1.5"], "density 1.5 is not between 0 and 1"),
+            (
+                &["--density", "-0.1"],
+                "density -0.1 is not between 0 and 1",
+            ),
+            (&["--light", "99"], "light level 99 is not between 0 and 20"),
+            (&["--seed", "many"], "invalid value 'many' for --seed"),
+            (&["--seed"], "missing value for --seed"),
+            (&["--frobnicate"], "unknown option '--frobnicate'"),
+            (
+                &["run", "--frobnicate", "1"],
+                "unknown option '--frobnicate'",
+            ),
+            (
+                &["--seed", "1", "--seed", "2"],
+                "--seed is given more than once",
+            ),
+            (
+                &["--timelapse-interval", "5"],
+                "--timelapse-interval needs --timelapse",
+            ),
+            (
+                &["--headless", "many"],
+                "invalid value 'many' for --headless",
+            ),
+            (
+                &["--headless", "10", "--ticks", "20"],
+                "--ticks cannot be combined with a number after --headless",
+            ),
+        ];
+        for (args, expected) in cases {
+            match parse_args(args) {
+                Err(message) => assert_eq!(message, *expected, "{args:?}"),
+                Ok(_) => panic!("{args:?} was accepted"),
+            }
+        }
+    }
+
+    #[test]
+    fn options_of_the_other_mode_are_rejected() {
+        let cases: &[(&[&str], &str)] = &[
+            (
+                &["--headless", "10", "--paused"],
+                "--paused only applies to the window",
+            ),
+            (&["run", "--paused"], "--paused only applies to the window"),
+            (
+                &["--out", "grid.png"],
+                "--out only applies without a window, see --headless",
+            ),
+            (
+                &["--steps", "10"],
+                "--steps only applies without a window, see --headless",
+            ),
+            (
+                &["--stats-out", "stats.csv"],
+                "--stats-out only applies without a window, see --headless",
+            ),
+            (
+                &["--ticks", "10"],
+                "--ticks only applies without a window, see --headless",
+            ),
+            (&["run", "--ticks", "10"], "--ticks needs --headless"),
+            (
+                &["run", "--headless", "10"],
+                "--headless cannot be used with the run command, use --steps",
+            ),
+            (
+                &["--load", "save.bin", "--grid", "3x3"],
+                "--grid cannot be combined with --load",
+            ),
+        ];
+        for (args, expected) in cases {
+            match parse_args(args) {
+                Err(message) => assert_eq!(message, *expected, "{args:?}"),
+                Ok(_) => panic!("{args:?} was accepted"),
+            }
+        }
+    }
+
+    #[test]
+    fn valid_options_are_parsed() {
+        let Ok(Command::Window(options)) = parse_args(&[
+            "--grid",
+            "40x30",
+            "--density",
+            "1",
+            "--paused",
+            "--seed",
+            "7",
+        ]) else {
+            panic!("window options were rejected");
+        };
+        assert_eq!(options.grid, (Some(40), Some(30)));
+        assert_eq!(options.density, Some(1.));
+        assert_eq!(options.seed, Some(7));
+        assert!(options.paused);
+
+        let Ok(Command::Run(options)) = parse_args(&["--headless", "25", "--out", "grid.png"])
+        else {
+            panic!("headless options were rejected");
+        };
+        assert_eq!(options.steps, 25);
+        assert_eq!(options.out, Some(PathBuf::from("grid.png")));
+
+        for (args, steps) in [
+            (&["--headless", "--ticks", "40"][..], 40),
+            (&["--ticks", "40", "--headless"], 40),
+            (&["--headless", "--seed", "3"], 1000),
+            (&["--headless"], 1000),
+        ] {
+            let Ok(Command::Run(options)) = parse_args(args) else {
+                panic!("{args:?} was rejected");
+            };
+            assert_eq!(options.steps, steps, "{args:?}");
+        }
+
+        assert!(matches!(parse_args(&["run"]), Ok(Command::Run(_))));
+        assert!(matches!(parse_args(&[]), Ok(Command::Window(_))));
+    }
+}
@@ -0,0 +1,214 @@
+//! Helpers for colors packed as 0RGB u32s.
+
+/// Convert a color given as hue (in degrees), saturation and value (both 0 to 1) to 0RGB.
+pub(crate) fn hsv_to_rgb(hue: f32, saturation: f32, value: f32) -> u32 {
+    let hue = hue.rem_euclid(360.) / 60.;
+    let chroma = value * saturation;
+    let x = chroma * (1. - (hue % 2. - 1.).abs());
+    let (r, g, b) = match hue as u32 {
+        0 => (chroma, x, 0.),
+        1 => (x, chroma, 0.),
+        2 => (0., chroma, x),
+        3 => (0., x, chroma),
+        4 => (x, 0., chroma),
+        _ => (chroma, 0., x),
+    };
+    let m = value - chroma;
+    let to_byte = |channel: f32| ((channel + m) * 255.).round() as u32;
+    (to_byte(r) << 16) | (to_byte(g) << 8) | to_byte(b)
+}
+
+/// Convert a 0RGB color to hue (in degrees), saturation and value (both 0 to 1).
+pub(crate) fn rgb_to_hsv(color: u32) -> (f32, f32, f32) {
+    let [_, r, g, b] = color.to_be_bytes().map(|channel| channel as f32 / 255.);
+    let max = r.max(g).max(b);
+    let chroma = max - r.min(g).min(b);
+    let hue = if chroma == 0. {
+        0.
+    } else if max == r {
+        60. * ((g - b) / chroma).rem_euclid(6.)
+    } else if max == g {
+        60. * ((b - r) / chroma + 2.)
+    } else {
+        60. * ((r - g) / chroma + 4.)
+    };
+    let saturation = if max == 0. { 0. } else { chroma / max };
+    (hue, saturation, max)
+}
+
+/// Channels of a 0RGB color in linear light, from 0 to 1.
+pub(crate) fn linear_rgb(color: u32) -> [f32; 3] {
+    let [_, r, g, b] = color.to_be_bytes();
+    [r, g, b].map(|channel| {
+        let c = channel as f32 / 255.;
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    })
+}
+
+/// CIE L*a*b* coordinates of a color given in linear RGB, for a D65 white point. Euclidean
+/// distances between them roughly follow how different colors look.
+pub(crate) fn lab([r, g, b]: [f32; 3]) -> [f32; 3] {
+    let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.9505;
+    let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.089;
+    let f = |t: f32| {
+        if t > 0.008856 {
+            t.cbrt()
+        } else {
+            7.787 * t + 16. / 116.
+        }
+    };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    [116. * fy - 16., 500. * (fx - fy), 200. * (fy - fz)]
+}
+
+/// Multiply every channel of a color by factor / 256, for factors in 0..=256.
+#[inline]
+pub(crate) fn scale_color(color: u32, factor: u32) -> u32 {
+    // red and blue are scaled together, they have enough room between them to not overflow
+    let red_blue = (((color & 0xFF00FF) * factor) >> 8) & 0xFF00FF;
+    let green = (((color & 0x00FF00) * factor) >> 8) & 0x00FF00;
+    red_blue | green
+}
+
+/// Add two colors channel by channel, saturating at full intensity.
+#[inline]
+pub(crate) fn add_colors(a: u32, b: u32) -> u32 {
+    let channel = |shift: u32| (((a >> shift) & 0xFF) + ((b >> shift) & 0xFF)).min(0xFF) << shift;
+    channel(16) | channel(8) | channel(0)
+}
+
+/// Move every channel of a color halfway towards white.
+#[inline]
+pub(crate) fn brighten(color: u32) -> u32 {
+    ((color >> 1) & 0x7F7F7F) + 0x808080
+}
+
+/// Mix two colors, with alpha (in 0..=256) the weight of `over`.
+#[inline]
+pub(crate) fn blend(base: u32, over: u32, alpha: u32) -> u32 {
+    let inverse = 256 - alpha;
+    let red_blue = (((base & 0xFF00FF) * inverse + (over & 0xFF00FF) * alpha) >> 8) & 0xFF00FF;
+    let green = (((base & 0x00FF00) * inverse + (over & 0x00FF00) * alpha) >> 8) & 0x00FF00;
+    red_blue | green
+}
+
+/// Layout of a pixel in an output buffer of u32s. Colors are stored as 0RGB internally and
+/// converted when written out.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// 0x00RRGGBB, as used by minifb.
+    #[default]
+    Xrgb,
+    /// 0xAARRGGBB with an opaque alpha channel.
+    Argb,
+    /// Bytes blue, green, red, alpha in memory order, as used by softbuffer and SDL textures.
+    Bgra,
+    /// Bytes red, green, blue, alpha in memory order, as used by web canvases and image files.
+    Rgba8,
+}
+
+impl PixelFormat {
+    /// Convert a 0RGB color to this format.
+    #[inline]
+    pub fn from_xrgb(self, color: u32) -> u32 {
+        let [_, r, g, b] = color.to_be_bytes();
+        match self {
+            PixelFormat::Xrgb => color & 0xFFFFFF,
+            PixelFormat::Argb => color | 0xFF000000,
+            PixelFormat::Bgra => u32::from_ne_bytes([b, g, r, 0xFF]),
+            PixelFormat::Rgba8 => u32::from_ne_bytes([r, g, b, 0xFF]),
+        }
+    }
+
+    /// Convert a pixel in this format back to a 0RGB color, dropping the alpha channel.
+    #[inline]
+    pub fn to_xrgb(self, pixel: u32) -> u32 {
+        let (r, g, b) = match self {
+            PixelFormat::Xrgb | PixelFormat::Argb => return pixel & 0xFFFFFF,
+            PixelFormat::Bgra => {
+                let [b, g, r, _] = pixel.to_ne_bytes();
+                (r, g, b)
+            }
+            PixelFormat::Rgba8 => {
+                let [r, g, b, _] = pixel.to_ne_bytes();
+                (r, g, b)
+            }
+        };
+        u32::from_be_bytes([0, r, g, b])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RenderPalette, Simulation};
+
+    const FORMATS: [PixelFormat; 4] = [
+        PixelFormat::Xrgb,
+        PixelFormat::Argb,
+        PixelFormat::Bgra,
+        PixelFormat::Rgba8,
+    ];
+
+    #[test]
+    fn formats_of_known_color() {
+        assert_eq!(PixelFormat::Xrgb.from_xrgb(0x123456), 0x123456);
+        assert_eq!(PixelFormat::Argb.from_xrgb(0x123456), 0xFF123456);
+        assert_eq!(
+            PixelFormat::Bgra.from_xrgb(0x123456).to_ne_bytes(),
+            [0x56, 0x34, 0x12, 0xFF]
+        );
+        assert_eq!(
+            PixelFormat::Rgba8.from_xrgb(0x123456).to_ne_bytes(),
+            [0x12, 0x34, 0x56, 0xFF]
+        );
+        // stray bits above the color are dropped
+        assert_eq!(PixelFormat::Xrgb.from_xrgb(0xAB123456), 0x123456);
+    }
+
+    #[test]
+    fn formats_round_trip() {
+        for format in FORMATS {
+            for color in [0x000000, 0xFFFFFF, 0x123456, 0xFF0000, 0x00FF00, 0x0000FF] {
+                let pixel = format.from_xrgb(color);
+                assert_eq!(format.to_xrgb(pixel), color, "{format:?} {color:06X}");
+            }
+            // the alpha channel is dropped on the way back
+            let pixel = format.from_xrgb(0x123456);
+            let with_alpha = match format {
+                PixelFormat::Xrgb | PixelFormat::Argb => pixel | 0x7F000000,
+                PixelFormat::Bgra | PixelFormat::Rgba8 => {
+                    let mut bytes = pixel.to_ne_bytes();
+                    bytes[3] = 0x7F;
+                    u32::from_ne_bytes(bytes)
+                }
+            };
+            assert_eq!(format.to_xrgb(with_alpha), 0x123456, "{format:?}");
+        }
+    }
+
+    #[test]
+    fn render_in_format_converts_every_pixel() {
+        let mut simulation = Simulation::builder(3, 2).build();
+        simulation.set_palette(RenderPalette {
+            background: 0x123456,
+            wall: 0xABCDEF,
+            ..RenderPalette::default()
+        });
+        simulation.set_wall(1, 1, true);
+        let mut plain = vec![0; 6];
+        simulation.render(&mut plain, (3, 2), (0, 0), 1);
+        for format in FORMATS {
+            let mut buffer = vec![0; 7];
+            simulation.render_in_format(&mut buffer, (3, 2), (0, 0), 1, format);
+            let expected: Vec<u32> = plain.iter().map(|&color| format.from_xrgb(color)).collect();
+            assert_eq!(buffer[..6], expected, "{format:?}");
+            assert_eq!(buffer[6], 0, "{format:?}");
+        }
+    }
+}
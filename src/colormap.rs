@@ -0,0 +1,139 @@
+//! Gradients used to map values to colors, such as the energy view.
+
+use crate::color::blend;
+
+/// Gradient through a few control colors (0RGB), evenly spaced and linearly interpolated.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Colormap {
+    stops: Vec<u32>,
+}
+
+impl Default for Colormap {
+    fn default() -> Self {
+        Self::viridis()
+    }
+}
+
+impl Colormap {
+    /// Gradient through the given colors, from the lowest to the highest value. A single color
+    /// gives a constant map.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `stops` is empty.
+    pub fn new(stops: &[u32]) -> Self {
+        assert!(!stops.is_empty(), "a colormap needs at least one color");
+        Self {
+            stops: stops.to_vec(),
+        }
+    }
+
+    /// Dark purple through blue and green to yellow, readable in grayscale and by most color
+    /// blind viewers.
+    pub fn viridis() -> Self {
+        Self::new(&[0x440154, 0x3B528B, 0x21918C, 0x5EC962, 0xFDE725])
+    }
+
+    /// Red through pale yellow to green.
+    pub fn red_green() -> Self {
+        Self::new(&[0xD7191C, 0xFDAE61, 0xFFFFBF, 0xA6D96A, 0x1A9641])
+    }
+
+    /// Black to white, for printing.
+    pub fn grayscale() -> Self {
+        Self::new(&[0x000000, 0xFFFFFF])
+    }
+
+    pub fn stops(&self) -> &[u32] {
+        &self.stops
+    }
+
+    /// Color at `value` from 0 (the first stop) to 255 (the last stop).
+    pub fn color_at(&self, value: u8) -> u32 {
+        let segments = self.stops.len() - 1;
+        if segments == 0 {
+            return self.stops[0];
+        }
+        // position along the gradient in 255ths of a segment
+        let position = value as usize * segments;
+        let segment = position / 255;
+        if segment == segments {
+            return self.stops[segments];
+        }
+        let alpha = (position % 255 * 256 / 255) as u32;
+        blend(self.stops[segment], self.stops[segment + 1], alpha)
+    }
+
+    /// Color of every value, so the map can be applied with a single lookup.
+    pub(crate) fn bake(&self) -> Box<[u32; 256]> {
+        let mut lut = Box::new([0; 256]);
+        for (value, color) in lut.iter_mut().enumerate() {
+            *color = self.color_at(value as u8);
+        }
+        lut
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Genome, Simulation, ViewMode};
+
+    #[test]
+    fn ends_and_stops() {
+        for colormap in [
+            Colormap::viridis(),
+            Colormap::red_green(),
+            Colormap::grayscale(),
+        ] {
+            let stops = colormap.stops();
+            assert_eq!(colormap.color_at(0), stops[0]);
+            assert_eq!(colormap.color_at(255), stops[stops.len() - 1]);
+        }
+        // with three segments, 85 and 170 fall exactly on the inner stops
+        let colormap = Colormap::new(&[0x000000, 0xFF0000, 0x00FF00, 0x0000FF]);
+        assert_eq!(colormap.color_at(85), 0xFF0000);
+        assert_eq!(colormap.color_at(170), 0x00FF00);
+        assert_eq!(Colormap::new(&[0x123456]).color_at(200), 0x123456);
+    }
+
+    #[test]
+    fn midpoints_blend_neighboring_stops() {
+        assert_eq!(Colormap::grayscale().color_at(128), 0x7F7F7F);
+        assert_eq!(Colormap::grayscale().color_at(64), 0x3F3F3F);
+        let colormap = Colormap::new(&[0x000000, 0xFF0000, 0x00FF00, 0x0000FF]);
+        assert_eq!(colormap.color_at(43), 0x800000);
+        assert_eq!(colormap.color_at(213), 0x007E80);
+    }
+
+    #[test]
+    fn baked_lut_matches_colors() {
+        let colormap = Colormap::red_green();
+        let lut = colormap.bake();
+        for value in 0..=255 {
+            assert_eq!(lut[value as usize], colormap.color_at(value));
+        }
+    }
+
+    #[test]
+    fn energy_view_uses_current_colormap() {
+        let mut simulation = Simulation::new(2, 1, 0);
+        simulation.set_view_mode(ViewMode::Energy);
+        let mut rng = fastrand::Rng::with_seed(1);
+        assert!(simulation.spawn_mold(0, 0, Genome::new(&mut rng)));
+        let render = |simulation: &Simulation| {
+            let mut buffer = vec![0; 2];
+            simulation.render(&mut buffer, (2, 1), (0, 0), 1);
+            buffer[0]
+        };
+        let viridis = render(&simulation);
+        simulation.set_colormap(Colormap::grayscale());
+        let grayscale = render(&simulation);
+        assert_ne!(grayscale, viridis);
+        assert_eq!(grayscale & 0xFF, grayscale >> 16);
+
+        simulation.set_colormap(Colormap::new(&[0x123456]));
+        assert_eq!(render(&simulation), 0x123456);
+        assert_eq!(simulation.colormap(), &Colormap::new(&[0x123456]));
+    }
+}
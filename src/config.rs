@@ -0,0 +1,898 @@
+//! Settings read from a TOML config file at startup, with defaults for everything the file
+//! leaves out.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use rustymold::{
+    ColorMutation, Edges, EnergyPolicy, LightDirection, MutationWeights, Ploidy, RenderPalette,
+    Shading, SimulationBuilder, SporeStyle, ViewMode, ENERGY_LOSS, MAX_ENERGY_LIGHT,
+    MIN_ENERGY_LIGHT, MUTATION_ODDS,
+};
+use serde::de::{self, Deserializer};
+use serde::Deserialize;
+
+use crate::{
+    DEFAULT_ENERGY_LIGHT, FIXED_SPORE_COLOR, GRID_X, GRID_Y, MAX_MUTATION_ODDS,
+    MAX_STEPS_PER_FRAME, MAX_TICKS_PER_SECOND, RESEED_DELAY, SPORE_BLINK_PERIOD, TARGET_FPS,
+    TICKS_PER_SECOND,
+};
+
+/// config file read when no other one is given with --config
+pub const DEFAULT_PATH: &str = "config.toml";
+
+/// Everything that can be set in the config file. Command line options take precedence.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub window: WindowSettings,
+    pub simulation: SimulationSettings,
+    pub render: RenderSettings,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct WindowSettings {
+    /// Size of a pixel of the buffer in pixels of the screen: 1, 2, 4, 8, 16 or 32.
+    #[serde(deserialize_with = "scale")]
+    pub scale: usize,
+    #[serde(deserialize_with = "integer::<_, _, 1, MAX_TARGET_FPS>")]
+    pub target_fps: u64,
+    /// Updates per second at startup, changed with the -/+ keys.
+    #[serde(deserialize_with = "integer::<_, _, 0, { MAX_TICKS_PER_SECOND as i64 }>")]
+    pub ticks_per_second: u32,
+    #[serde(deserialize_with = "integer::<_, _, 1, { u32::MAX as i64 }>")]
+    pub max_steps_per_frame: usize,
+    pub paused: bool,
+    /// Size of the window on the screen in pixels, or 0 by 0 to fit the grid. Updated in the
+    /// config file when the window closes, see save_window_size.
+    #[serde(deserialize_with = "integer::<_, _, 0, MAX_WINDOW_LENGTH>")]
+    pub width: usize,
+    #[serde(deserialize_with = "integer::<_, _, 0, MAX_WINDOW_LENGTH>")]
+    pub height: usize,
+    /// Size of the window in fullscreen, which minifb can't find out from the monitor.
+    #[serde(deserialize_with = "integer::<_, _, 1, MAX_WINDOW_LENGTH>")]
+    pub fullscreen_width: usize,
+    #[serde(deserialize_with = "integer::<_, _, 1, MAX_WINDOW_LENGTH>")]
+    pub fullscreen_height: usize,
+    /// Scatter new molds when all molds died, toggled with the A key. Updated in the config
+    /// file when the window closes.
+    pub autoreseed: bool,
+    /// Ticks to wait after all molds died before scattering new ones.
+    #[serde(deserialize_with = "integer::<_, _, 0, { u32::MAX as i64 }>")]
+    pub reseed_delay: u64,
+    /// Resize the grids with the window so they fill it at zoom 1, toggled with the E key.
+    pub fit_grid: bool,
+    pub clamp_to_grid: bool,
+}
+
+/// Settings of new simulations. Simulations loaded from a save keep the ones they were saved with.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct SimulationSettings {
+    #[serde(deserialize_with = "integer::<_, _, 1, MAX_GRID_LENGTH>")]
+    pub width: usize,
+    #[serde(deserialize_with = "integer::<_, _, 1, MAX_GRID_LENGTH>")]
+    pub height: usize,
+    /// Light level at startup, changed with the Q/W keys.
+    #[serde(
+        deserialize_with = "integer::<_, _, { MIN_ENERGY_LIGHT as i64 }, { MAX_ENERGY_LIGHT as i64 }>"
+    )]
+    pub light: i32,
+    #[serde(deserialize_with = "integer::<_, _, 1, MAX_SUBSTEPS>")]
+    pub substeps: u32,
+    /// Age at which mold cells turn into a final spore or die, none if 0.
+    #[serde(deserialize_with = "integer::<_, _, 0, { u32::MAX as i64 }>")]
+    pub max_age: u32,
+    #[serde(deserialize_with = "integer::<_, _, 0, { i64::MAX }>")]
+    pub max_population: usize,
+    /// Energy molds pay for every cell and spore they grow.
+    #[serde(deserialize_with = "integer::<_, _, 0, { i32::MAX as i64 }>")]
+    pub growth_cost: i32,
+    /// Energy every cell costs its mold each tick, more as it ages.
+    #[serde(deserialize_with = "integer::<_, _, 0, { i32::MAX as i64 }>")]
+    pub energy_loss: i32,
+    #[serde(deserialize_with = "named")]
+    pub color_mutation: ColorMutation,
+    /// Weights of the kinds of mutations, see mutation_weights.
+    #[serde(deserialize_with = "integer::<_, _, 0, { u32::MAX as i64 }>")]
+    pub point_mutation_weight: u32,
+    #[serde(deserialize_with = "integer::<_, _, 0, { u32::MAX as i64 }>")]
+    pub duplication_weight: u32,
+    #[serde(deserialize_with = "integer::<_, _, 0, { u32::MAX as i64 }>")]
+    pub insertion_weight: u32,
+    #[serde(deserialize_with = "integer::<_, _, 0, { u32::MAX as i64 }>")]
+    pub deletion_weight: u32,
+    #[serde(deserialize_with = "integer::<_, _, 1, { MAX_MUTATION_ODDS as i64 }>")]
+    pub mutation_odds: u32,
+    #[serde(deserialize_with = "named")]
+    pub ploidy: Ploidy,
+    #[serde(deserialize_with = "named")]
+    pub energy_policy: EnergyPolicy,
+    #[serde(deserialize_with = "named")]
+    pub edges: Edges,
+    /// Edge light shines in from when occupied cells shade the cells behind them, none if off.
+    #[serde(deserialize_with = "named")]
+    pub shading: Option<LightDirection>,
+    #[serde(deserialize_with = "integer::<_, _, 0, { u8::MAX as i64 }>")]
+    pub shading_attenuation: u8,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct RenderSettings {
+    /// Spore style, with the period and color of the blink and fixed styles set separately.
+    #[serde(deserialize_with = "named")]
+    spore_style: SporeStyle,
+    #[serde(deserialize_with = "integer::<_, _, 1, { u32::MAX as i64 }>")]
+    pub blink_period: u64,
+    #[serde(deserialize_with = "color")]
+    pub spore_color: u32,
+    pub spore_ripening: bool,
+    /// Colors of the palette, see palette.
+    #[serde(deserialize_with = "color")]
+    pub background: u32,
+    #[serde(deserialize_with = "color")]
+    pub wall: u32,
+    #[serde(deserialize_with = "color")]
+    pub light: u32,
+    #[serde(deserialize_with = "color")]
+    pub predator: u32,
+    #[serde(deserialize_with = "color")]
+    pub trail: u32,
+    #[serde(deserialize_with = "color")]
+    pub activity: u32,
+    #[serde(deserialize_with = "color")]
+    pub sparkline: u32,
+    #[serde(deserialize_with = "color")]
+    pub viewport: u32,
+    #[serde(deserialize_with = "fraction")]
+    pub highlight_dim: f32,
+    #[serde(deserialize_with = "named")]
+    pub view_mode: ViewMode,
+}
+
+impl Default for WindowSettings {
+    fn default() -> Self {
+        Self {
+            scale: 2,
+            target_fps: TARGET_FPS,
+            ticks_per_second: TICKS_PER_SECOND,
+            max_steps_per_frame: MAX_STEPS_PER_FRAME,
+            paused: false,
+            width: 0,
+            height: 0,
+            fullscreen_width: FULLSCREEN_SIZE.0,
+            fullscreen_height: FULLSCREEN_SIZE.1,
+            autoreseed: false,
+            reseed_delay: RESEED_DELAY,
+            fit_grid: false,
+            clamp_to_grid: false,
+        }
+    }
+}
+
+impl Default for SimulationSettings {
+    fn default() -> Self {
+        let mutation_weights = MutationWeights::default();
+        Self {
+            width: GRID_X,
+            height: GRID_Y,
+            light: DEFAULT_ENERGY_LIGHT,
+            substeps: 1,
+            max_age: 0,
+            max_population: 0,
+            growth_cost: 0,
+            energy_loss: ENERGY_LOSS,
+            color_mutation: ColorMutation::default(),
+            point_mutation_weight: mutation_weights.point,
+            duplication_weight: mutation_weights.duplication,
+            insertion_weight: mutation_weights.insertion,
+            deletion_weight: mutation_weights.deletion,
+            mutation_odds: MUTATION_ODDS,
+            ploidy: Ploidy::default(),
+            energy_policy: EnergyPolicy::default(),
+            edges: Edges::default(),
+            shading: None,
+            shading_attenuation: DEFAULT_SHADING_ATTENUATION,
+        }
+    }
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        let palette = RenderPalette::default();
+        Self {
+            spore_style: SporeStyle::default(),
+            blink_period: SPORE_BLINK_PERIOD,
+            spore_color: FIXED_SPORE_COLOR,
+            spore_ripening: false,
+            background: palette.background,
+            wall: palette.wall,
+            light: palette.light,
+            predator: palette.predator,
+            trail: palette.trail,
+            activity: palette.activity,
+            sparkline: palette.sparkline,
+            viewport: palette.viewport,
+            highlight_dim: palette.highlight_dim,
+            view_mode: ViewMode::default(),
+        }
+    }
+}
+
+impl WindowSettings {
+    pub fn minifb_scale(&self) -> minifb::Scale {
+        match self.scale {
+            1 => minifb::Scale::X1,
+            2 => minifb::Scale::X2,
+            4 => minifb::Scale::X4,
+            8 => minifb::Scale::X8,
+            16 => minifb::Scale::X16,
+            _ => minifb::Scale::X32,
+        }
+    }
+
+    /// Size of the window on the screen in pixels set in the config file, if any.
+    pub fn size(&self) -> Option<(usize, usize)> {
+        (self.width > 0 && self.height > 0).then_some((self.width, self.height))
+    }
+}
+
+impl RenderSettings {
+    pub fn spore_style(&self) -> SporeStyle {
+        match self.spore_style {
+            SporeStyle::Blink { .. } => SporeStyle::Blink {
+                period: self.blink_period,
+            },
+            SporeStyle::Fixed(_) => SporeStyle::Fixed(self.spore_color),
+            style => style,
+        }
+    }
+
+    pub fn palette(&self) -> RenderPalette {
+        RenderPalette {
+            background: self.background,
+            wall: self.wall,
+            light: self.light,
+            predator: self.predator,
+            trail: self.trail,
+            activity: self.activity,
+            sparkline: self.sparkline,
+            viewport: self.viewport,
+            highlight_dim: self.highlight_dim,
+        }
+    }
+}
+
+impl SimulationSettings {
+    pub fn mutation_weights(&self) -> MutationWeights {
+        MutationWeights {
+            point: self.point_mutation_weight,
+            duplication: self.duplication_weight,
+            insertion: self.insertion_weight,
+            deletion: self.deletion_weight,
+        }
+    }
+
+    /// Builder for a new simulation of the given size with these settings.
+    pub fn builder(&self, width: usize, height: usize) -> SimulationBuilder {
+        let builder = rustymold::Simulation::builder(width, height)
+            .energy_light(self.light)
+            .substeps(self.substeps)
+            .color_mutation(self.color_mutation)
+            .mutation_weights(self.mutation_weights())
+            .mutation_odds(self.mutation_odds)
+            .growth_cost(self.growth_cost)
+            .energy_loss(self.energy_loss)
+            .ploidy(self.ploidy)
+            .energy_policy(self.energy_policy)
+            .edges(self.edges);
+        let builder = match self.shading {
+            Some(direction) => builder.shading(Shading {
+                direction,
+                attenuation: self.shading_attenuation,
+            }),
+            None => builder,
+        };
+        let builder = match self.max_population {
+            0 => builder,
+            max_population => builder.max_population(max_population),
+        };
+        match self.max_age {
+            0 => builder,
+            max_age => builder.max_age(max_age),
+        }
+    }
+}
+
+/// Read the settings from a config file, writing one with the defaults and comments explaining
+/// them first if it doesn't exist. Returns the settings with warnings about anything in the file
+/// that was not understood, which is left at its default.
+pub fn load(path: &Path) -> (Settings, Vec<String>) {
+    if !path.exists() {
+        let warnings = match fs::write(path, default_config()) {
+            Ok(()) => Vec::new(),
+            Err(e) => vec![format!("cannot write {}: {e}", path.display())],
+        };
+        return (Settings::default(), warnings);
+    }
+    match fs::read_to_string(path) {
+        Ok(text) => parse(&text, &path.display().to_string()),
+        Err(e) => (
+            Settings::default(),
+            vec![format!(
+                "cannot read {}: {e}, using defaults",
+                path.display()
+            )],
+        ),
+    }
+}
+
+/// Parse the text of a config file named `name` in warnings. Settings that are missing keep their
+/// default, and so do the ones on lines that are invalid: every such line gives a warning and is
+/// skipped, parsing the rest again.
+pub fn parse(text: &str, name: &str) -> (Settings, Vec<String>) {
+    let mut lines: Vec<&str> = text.lines().collect();
+    // index and error of every line skipped so far
+    let mut skipped = Vec::new();
+    let mut unknown = Vec::new();
+    let (settings, failure) = loop {
+        let text = lines.join("\n");
+        unknown.clear();
+        let result = serde_ignored::deserialize(toml::Deserializer::new(&text), |path| {
+            unknown.push(path.to_string())
+        });
+        let error = match result {
+            Ok(settings) => break (settings, None),
+            Err(error) => error,
+        };
+        // messages about the syntax can take several lines
+        let message = error.message().split_whitespace().collect::<Vec<_>>();
+        let message = message.join(" ");
+        let line = error
+            .span()
+            .map(|span| text[..span.start].matches('\n').count())
+            .filter(|&line| lines.get(line).is_some_and(|line| !line.trim().is_empty()));
+        match line {
+            Some(line) => {
+                lines[line] = "";
+                skipped.push((line, message));
+            }
+            None => {
+                unknown.clear();
+                break (Settings::default(), Some(message));
+            }
+        }
+    };
+    skipped.sort_by_key(|&(line, _)| line);
+    let mut warnings: Vec<String> = skipped
+        .into_iter()
+        .map(|(line, message)| format!("{name}:{}: {message}", line + 1))
+        .collect();
+    if let Some(message) = failure {
+        warnings.push(format!("{name}: {message}, using defaults"));
+    }
+    if !unknown.is_empty() {
+        warnings.push(format!(
+            "unknown settings in {name}: {}",
+            unknown.join(", ")
+        ));
+    }
+    (settings, warnings)
+}
+
+/// Store the size of the window and whether autoreseed is on in the window section of the config
+/// file at `path`, keeping the rest of the file as it is. Does nothing if the file doesn't exist.
+pub fn save_window_state(path: &Path, size: (usize, usize), autoreseed: bool) -> io::Result<()> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    let values = [
+        ("width", size.0.to_string()),
+        ("height", size.1.to_string()),
+        ("autoreseed", autoreseed.to_string()),
+    ];
+    fs::write(path, with_values(&text, "window", &values))
+}
+
+/// Text of a config file with keys of a section set to new values, replacing the lines that set
+/// them or adding lines after the last setting of the section, or a new section at the end.
+fn with_values(text: &str, section: &str, values: &[(&str, String)]) -> String {
+    let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+    let mut missing: Vec<&(&str, String)> = values.iter().collect();
+    let mut current = String::new();
+    // index of the line after the header or last setting of the section
+    let mut end = None;
+    for (i, line) in lines.iter_mut().enumerate() {
+        let trimmed = line.trim();
+        if let Some(header) = trimmed.strip_prefix('[') {
+            let header = strip_comment(header).unwrap_or(header).trim_end();
+            current = header.trim_end_matches(']').trim().to_string();
+            if current == section {
+                end = Some(i + 1);
+            }
+            continue;
+        }
+        if current != section || trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        end = Some(i + 1);
+        let Some((key, _)) = trimmed.split_once('=') else {
+            continue;
+        };
+        if let Some(index) = missing.iter().position(|(name, _)| *name == key.trim()) {
+            let (name, value) = missing.remove(index);
+            *line = format!("{name} = {value}");
+        }
+    }
+    let added = missing
+        .iter()
+        .map(|(name, value)| format!("{name} = {value}"));
+    match end {
+        Some(end) => {
+            lines.splice(end..end, added);
+        }
+        None => {
+            if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push(format!("[{section}]"));
+            lines.extend(added);
+        }
+    }
+    let mut text = lines.join("\n");
+    text.push('\n');
+    text
+}
+
+/// The part of a line before a comment, or None if a string in it is not closed.
+fn strip_comment(text: &str) -> Option<&str> {
+    let mut in_string = false;
+    for (i, c) in text.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '#' if !in_string => return Some(&text[..i]),
+            _ => (),
+        }
+    }
+    (!in_string).then_some(text)
+}
+
+const MAX_TARGET_FPS: i64 = 1000;
+const MAX_GRID_LENGTH: i64 = 100_000;
+const MAX_SUBSTEPS: i64 = 100;
+const MAX_WINDOW_LENGTH: i64 = 16_384;
+/// size of the window in fullscreen unless the config file sets it
+const FULLSCREEN_SIZE: (usize, usize) = (1920, 1080);
+/// part of the light absorbed by every occupied cell when shading is turned on without saying how
+/// much
+const DEFAULT_SHADING_ATTENUATION: u8 = 64;
+
+/// A setting written in the config file as one of a few names.
+trait Named: Copy + PartialEq + 'static {
+    const NAMES: &'static [(&'static str, Self)];
+}
+
+impl Named for ColorMutation {
+    const NAMES: &'static [(&'static str, Self)] = &[
+        ("jump", ColorMutation::Jump),
+        ("drift", ColorMutation::Drift),
+    ];
+}
+
+impl Named for Ploidy {
+    const NAMES: &'static [(&'static str, Self)] =
+        &[("haploid", Ploidy::Haploid), ("diploid", Ploidy::Diploid)];
+}
+
+impl Named for EnergyPolicy {
+    const NAMES: &'static [(&'static str, Self)] = &[
+        ("exclusive", EnergyPolicy::Exclusive),
+        ("split", EnergyPolicy::Split),
+        ("neediest", EnergyPolicy::Neediest),
+    ];
+}
+
+impl Named for Edges {
+    const NAMES: &'static [(&'static str, Self)] =
+        &[("wrap", Edges::Wrap), ("bounded", Edges::Bounded)];
+}
+
+/// the direction of shading, or none if it is off
+impl Named for Option<LightDirection> {
+    const NAMES: &'static [(&'static str, Self)] = &[
+        ("off", None),
+        ("top", Some(LightDirection::Top)),
+        ("bottom", Some(LightDirection::Bottom)),
+        ("left", Some(LightDirection::Left)),
+        ("right", Some(LightDirection::Right)),
+    ];
+}
+
+impl Named for ViewMode {
+    const NAMES: &'static [(&'static str, Self)] = &[
+        ("color", ViewMode::Color),
+        ("species", ViewMode::SpeciesHash),
+        ("lineage", ViewMode::Lineage),
+        ("energy", ViewMode::Energy),
+        ("activity", ViewMode::Activity),
+    ];
+}
+
+/// spore styles by name, the period of blink and color of fixed are set separately
+impl Named for SporeStyle {
+    const NAMES: &'static [(&'static str, Self)] = &[
+        ("invert", SporeStyle::Invert),
+        ("blink", SporeStyle::Blink { period: 0 }),
+        ("ring", SporeStyle::Ring),
+        ("plain", SporeStyle::Plain),
+        ("fixed", SporeStyle::Fixed(0)),
+        ("brighten", SporeStyle::Brighten),
+    ];
+}
+
+/// Name of a setting as written in the config file.
+fn name<T: Named>(value: T) -> &'static str {
+    T::NAMES
+        .iter()
+        .find(|(_, choice)| *choice == value)
+        .map_or("", |(name, _)| name)
+}
+
+/// Name of a view mode as written in the config file.
+pub fn view_mode_name(view_mode: ViewMode) -> &'static str {
+    name(view_mode)
+}
+
+/// Deserialize one of the names of a setting, giving its value.
+fn named<'de, D: Deserializer<'de>, T: Named>(deserializer: D) -> Result<T, D::Error> {
+    let text = String::deserialize(deserializer)?;
+    T::NAMES
+        .iter()
+        .find(|(name, _)| *name == text)
+        .map(|(_, value)| *value)
+        .ok_or_else(|| {
+            let names: Vec<_> = T::NAMES
+                .iter()
+                .map(|(name, _)| format!("\"{name}\""))
+                .collect();
+            de::Error::custom(format!("expected one of {}", names.join(", ")))
+        })
+}
+
+/// Deserialize an integer from MIN to MAX.
+fn integer<'de, D, T, const MIN: i64, const MAX: i64>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: TryFrom<i64>,
+{
+    let value = i64::deserialize(deserializer)?;
+    (MIN..=MAX)
+        .contains(&value)
+        .then(|| T::try_from(value).ok())
+        .flatten()
+        .ok_or_else(|| de::Error::custom(format!("expected an integer from {MIN} to {MAX}")))
+}
+
+fn scale<'de, D: Deserializer<'de>>(deserializer: D) -> Result<usize, D::Error> {
+    match u64::deserialize(deserializer)? {
+        scale @ (1 | 2 | 4 | 8 | 16 | 32) => Ok(scale as usize),
+        _ => Err(de::Error::custom("expected 1, 2, 4, 8, 16 or 32")),
+    }
+}
+
+/// Deserialize a number from 0 to 1.
+fn fraction<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f32, D::Error> {
+    let value = f64::deserialize(deserializer)?;
+    if (0. ..=1.).contains(&value) {
+        Ok(value as f32)
+    } else {
+        Err(de::Error::custom("expected a number from 0 to 1"))
+    }
+}
+
+/// Deserialize a color written as "#RRGGBB".
+fn color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    let text = String::deserialize(deserializer)?;
+    text.strip_prefix('#')
+        .filter(|hex| hex.len() == 6)
+        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+        .ok_or_else(|| de::Error::custom("expected a color like \"#FF8000\""))
+}
+
+/// Contents of a config file with the default settings and what they mean.
+fn default_config() -> String {
+    let Settings {
+        window,
+        simulation,
+        render,
+    } = Settings::default();
+    format!(
+        "\
+# Settings for rustymold, read at startup. Command line options take precedence.
+# Remove a line to use its default, which is the value written here.
+
+[window]
+# size of a simulated pixel on the screen: 1, 2, 4, 8, 16 or 32
+scale = {}
+target_fps = {}
+# updates per second at startup, halved/doubled with -/+ (0 to {MAX_TICKS_PER_SECOND})
+ticks_per_second = {}
+# most updates done in one frame when the simulation falls behind
+max_steps_per_frame = {}
+# start paused, like --paused
+paused = {}
+# size of the window in screen pixels, 0 to fit the grid, stored here when the window closes
+width = {}
+height = {}
+# size of the window in fullscreen (F11), as the resolution of the monitor is not known
+fullscreen_width = {}
+fullscreen_height = {}
+# scatter new molds when all molds died (A), and the ticks to wait before doing so
+autoreseed = {}
+reseed_delay = {}
+# resize the grid with the window so it fills it at zoom 1 (E), cropping only after Enter
+fit_grid = {}
+# stop panning at the edges of grids that don't wrap around
+clamp_to_grid = {}
+
+[simulation]
+# these apply to new grids, saves keep the settings they were made with
+width = {}
+height = {}
+# light level at startup, changed with Q/W ({MIN_ENERGY_LIGHT} to {MAX_ENERGY_LIGHT})
+light = {}
+# energy passes per growth step
+substeps = {}
+# age at which mold cells turn into a final spore or die, 0 for never
+max_age = {}
+# number of mold cells at which molds stop growing until some die, 0 for no limit
+max_population = {}
+# energy molds pay for every cell and spore they grow, skipping growth they can't afford, 0 for
+# free growth
+growth_cost = {}
+# energy every cell costs its mold each tick, that much more for every 200 ticks of its age
+energy_loss = {}
+# chance that a sprouting spore mutates as one in this many, halved/doubled with [/] (1 to
+# {MAX_MUTATION_ODDS})
+mutation_odds = {}
+# \"jump\" to a random color or \"drift\" slowly when genomes mutate
+color_mutation = \"{}\"
+# relative chances of changing one number of a gene, copying a whole gene, and inserting or
+# deleting a gene while keeping the references to the genes after it intact; with all four 0,
+# mutations only change the color
+point_mutation_weight = {}
+duplication_weight = {}
+insertion_weight = {}
+deletion_weight = {}
+# \"haploid\" or \"diploid\"
+ploidy = \"{}\"
+# how light reaching several molds is shared: \"exclusive\", \"split\" or \"neediest\"
+energy_policy = \"{}\"
+# \"wrap\" around or stop at the edges (\"bounded\")
+edges = \"{}\"
+# edge light shines in from so occupied cells shade the cells behind them, or \"off\":
+# \"top\", \"bottom\", \"left\" or \"right\"
+shading = \"{}\"
+# part of the light absorbed by every occupied cell it passes while shading, out of 255
+shading_attenuation = {}
+
+[render]
+# \"color\", \"species\", \"lineage\", \"energy\" or \"activity\"
+view_mode = \"{}\"
+# \"invert\", \"blink\", \"ring\", \"plain\", \"fixed\" or \"brighten\"
+spore_style = \"{}\"
+# ticks between color changes of blinking spores, and the color of all spores with \"fixed\"
+blink_period = {}
+spore_color = \"#{:06X}\"
+# fade unripe spores toward their ripe color as they age instead of dimming them until ripe
+spore_ripening = {}
+background = \"#{:06X}\"
+wall = \"#{:06X}\"
+light = \"#{:06X}\"
+predator = \"#{:06X}\"
+trail = \"#{:06X}\"
+# glow where molds recently grew in the activity view
+activity = \"#{:06X}\"
+sparkline = \"#{:06X}\"
+viewport = \"#{:06X}\"
+# brightness of everything but a highlighted mold, from 0 to 1
+highlight_dim = {}
+",
+        window.scale,
+        window.target_fps,
+        window.ticks_per_second,
+        window.max_steps_per_frame,
+        window.paused,
+        window.width,
+        window.height,
+        window.fullscreen_width,
+        window.fullscreen_height,
+        window.autoreseed,
+        window.reseed_delay,
+        window.fit_grid,
+        window.clamp_to_grid,
+        simulation.width,
+        simulation.height,
+        simulation.light,
+        simulation.substeps,
+        simulation.max_age,
+        simulation.max_population,
+        simulation.growth_cost,
+        simulation.energy_loss,
+        simulation.mutation_odds,
+        name(simulation.color_mutation),
+        simulation.point_mutation_weight,
+        simulation.duplication_weight,
+        simulation.insertion_weight,
+        simulation.deletion_weight,
+        name(simulation.ploidy),
+        name(simulation.energy_policy),
+        name(simulation.edges),
+        name(simulation.shading),
+        simulation.shading_attenuation,
+        name(render.view_mode),
+        name(render.spore_style),
+        render.blink_period,
+        render.spore_color,
+        render.spore_ripening,
+        render.background,
+        render.wall,
+        render.light,
+        render.predator,
+        render.trail,
+        render.activity,
+        render.sparkline,
+        render.viewport,
+        render.highlight_dim,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_config_gives_defaults() {
+        let (settings, warnings) = parse(&default_config(), "config.toml");
+        assert_eq!(settings, Settings::default());
+        assert!(warnings.is_empty(), "{warnings:?}");
+    }
+
+    #[test]
+    fn missing_settings_keep_defaults() {
+        let text = include_str!("../tests/fixtures/config_custom.toml");
+        let (settings, warnings) = parse(text, "custom.toml");
+        assert!(warnings.is_empty(), "{warnings:?}");
+        let mut expected = Settings::default();
+        expected.window.scale = 4;
+        expected.window.paused = true;
+        expected.window.max_steps_per_frame = 1000;
+        expected.simulation.width = 200;
+        expected.simulation.height = 100;
+        expected.simulation.edges = Edges::Bounded;
+        expected.simulation.energy_policy = EnergyPolicy::Split;
+        expected.simulation.substeps = 3;
+        expected.simulation.energy_loss = 2;
+        expected.simulation.duplication_weight = 0;
+        expected.render.view_mode = ViewMode::Energy;
+        expected.render.spore_style = SporeStyle::Fixed(0);
+        expected.render.spore_color = 0x12AB34;
+        expected.render.background = 0xFFFFFF;
+        expected.render.highlight_dim = 0.5;
+        assert_eq!(settings, expected);
+        assert_eq!(settings.render.spore_style(), SporeStyle::Fixed(0x12AB34));
+        let simulation = settings.simulation.builder(10, 10).build();
+        assert_eq!(simulation.energy_loss(), 2);
+        assert_eq!(simulation.config().mutation_weights.duplication, 0);
+        assert_eq!(simulation.substeps(), 3);
+    }
+
+    #[test]
+    fn invalid_settings_keep_defaults() {
+        let text = include_str!("../tests/fixtures/config_invalid.toml");
+        let (settings, warnings) = parse(text, "invalid.toml");
+        let mut expected = Settings::default();
+        expected.window.target_fps = 30;
+        expected.simulation.height = 50;
+        expected.render.view_mode = ViewMode::Lineage;
+        assert_eq!(settings, expected);
+        let light = format!("an integer from {MIN_ENERGY_LIGHT} to {MAX_ENERGY_LIGHT}");
+        assert_eq!(
+            warnings,
+            [
+                "invalid.toml:4: expected 1, 2, 4, 8, 16 or 32".to_string(),
+                "invalid.toml:6: invalid type: string \"yes\", expected a boolean".into(),
+                "invalid.toml:9: expected an integer from 1 to 100000".into(),
+                format!("invalid.toml:11: expected {light}"),
+                "invalid.toml:12: expected one of \"wrap\", \"bounded\"".into(),
+                "invalid.toml:14: expected `.`, `=`".into(),
+                "invalid.toml:16: invalid table header expected `.`, `]`".into(),
+                "invalid.toml:19: expected a color like \"#FF8000\"".into(),
+                "invalid.toml:20: invalid basic string".into(),
+                "invalid.toml:21: expected a number from 0 to 1".into(),
+                "unknown settings in invalid.toml: simulation.colour, render.speed".into(),
+            ]
+        );
+    }
+
+    #[test]
+    fn comments() {
+        assert_eq!(strip_comment(" 4 # four"), Some(" 4 "));
+        assert_eq!(strip_comment("\"#FFFFFF\" # white"), Some("\"#FFFFFF\" "));
+        assert_eq!(strip_comment("\"#FFF"), None);
+    }
+
+    #[test]
+    fn window_state_replaces_and_adds_lines() {
+        let values = [
+            ("width", "800".to_string()),
+            ("autoreseed", "true".to_string()),
+        ];
+        let text = "[window]\nwidth = 1 # old\nscale = 2\n\n[render]\nwidth = 3\n";
+        assert_eq!(
+            with_values(text, "window", &values),
+            "[window]\nwidth = 800\nscale = 2\nautoreseed = true\n\n[render]\nwidth = 3\n"
+        );
+        assert_eq!(
+            with_values("[render]\nwall = 1", "window", &values),
+            "[render]\nwall = 1\n\n[window]\nwidth = 800\nautoreseed = true\n"
+        );
+    }
+
+    #[test]
+    fn window_size_is_written_back_in_place() {
+        let values = [("width", "1280".to_string()), ("height", "720".to_string())];
+        let text = "\
+# sizes
+[window] # of the window
+height=100
+# width = 5
+fullscreen_width = 2560
+
+[simulation]
+width = 300
+";
+        assert_eq!(
+            with_values(text, "window", &values),
+            "\
+# sizes
+[window] # of the window
+height = 720
+# width = 5
+fullscreen_width = 2560
+width = 1280
+
+[simulation]
+width = 300
+"
+        );
+        let (settings, warnings) = parse(&with_values(text, "window", &values), "config.toml");
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert_eq!(settings.window.size(), Some((1280, 720)));
+        assert_eq!(settings.window.fullscreen_width, 2560);
+        assert_eq!(settings.simulation.width, 300);
+        assert_eq!(parse("", "config.toml").0.window.size(), None);
+    }
+
+    #[test]
+    fn load_writes_missing_file() {
+        let dir = std::env::temp_dir().join(format!("rustymold-config-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        let (settings, warnings) = load(&path);
+        assert_eq!(settings, Settings::default());
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert_eq!(fs::read_to_string(&path).unwrap(), default_config());
+        save_window_state(&path, (640, 480), true).unwrap();
+        let (settings, warnings) = load(&path);
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert_eq!(settings.window.size(), Some((640, 480)));
+        assert!(settings.window.autoreseed);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,171 @@
+//! Notifying code outside the simulation of what happens in it during updates.
+
+use crate::{MoldId, Simulation};
+
+/// Something that happened during an update, passed to the subscribers of a simulation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// The mold grew a new cell or spore at (x, y).
+    Grew { x: usize, y: usize, mold: MoldId },
+    /// A ripe spore of `parent` at (x, y) sprouted into the new mold `mold`.
+    Bloomed {
+        x: usize,
+        y: usize,
+        parent: MoldId,
+        mold: MoldId,
+    },
+    /// The mold got too old and lost its last cell at (x, y), see Simulation::set_max_age.
+    Died { x: usize, y: usize, mold: MoldId },
+    /// The mold ran out of energy and lost its last cell at (x, y).
+    Starved { x: usize, y: usize, mold: MoldId },
+    /// The predator at (x, y) took energy from the mold next to it.
+    Invaded { x: usize, y: usize, mold: MoldId },
+}
+
+/// Function called with every event of a simulation, see Simulation::subscribe.
+pub type Subscriber = Box<dyn FnMut(&Event)>;
+
+impl Simulation {
+    /// Call `subscriber` with every event from now on, in the order they happen during updates.
+    /// Subscribers are not part of snapshots, and events cost nothing without any.
+    pub fn subscribe(&mut self, subscriber: Subscriber) {
+        self.subscribers.push(subscriber);
+    }
+
+    /// Pass an event to every subscriber.
+    #[inline]
+    pub(crate) fn emit(&mut self, event: Event) {
+        for subscriber in &mut self.subscribers {
+            subscriber(&event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::{Cell, Edges, Genome, GENE_DIRECTIONS, GENOME_SIZE, SPORE_RIPING_AGE};
+
+    /// A bounded grid without light, with a mold of `genes` and `energy` at (0, 0).
+    fn simulation(size: (usize, usize), genes: &[isize], energy: i32) -> Simulation {
+        let mut simulation = Simulation::builder(size.0, size.1)
+            .edges(Edges::Bounded)
+            .energy_light(0)
+            .build();
+        assert!(simulation.spawn_mold(0, 0, Genome::from_genes(genes, 0x808080).unwrap()));
+        if energy > 0 {
+            assert!(simulation.add_energy(0, 0, energy));
+        }
+        simulation
+    }
+
+    /// Every event of the simulation from now on.
+    fn collect(simulation: &mut Simulation) -> Rc<RefCell<Vec<Event>>> {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let collected = events.clone();
+        simulation.subscribe(Box::new(move |event| collected.borrow_mut().push(*event)));
+        events
+    }
+
+    /// A copy of the cell at (x, y) at (to_x, to_y), so the mold has one more cell.
+    fn copy_cell(
+        simulation: &mut Simulation,
+        (x, y): (usize, usize),
+        (to_x, to_y): (usize, usize),
+    ) {
+        let cell = simulation.grid[x][y].clone();
+        simulation.set_cell(to_x, to_y, cell);
+    }
+
+    const INERT: [isize; GENOME_SIZE * GENE_DIRECTIONS] = [-2; GENOME_SIZE * GENE_DIRECTIONS];
+
+    #[test]
+    fn growing_emits_grew() {
+        // grows straight down from gene 0 into gene 0
+        let mut genes = INERT;
+        genes[1] = 0;
+        let mut simulation = simulation((1, 3), &genes, 1000);
+        let mold = simulation.mold_at(0, 0).unwrap().id;
+        let events = collect(&mut simulation);
+        simulation.run(2);
+        assert_eq!(
+            *events.borrow(),
+            [
+                Event::Grew { x: 0, y: 1, mold },
+                Event::Grew { x: 0, y: 2, mold },
+            ]
+        );
+    }
+
+    #[test]
+    fn ripe_spore_emits_bloomed_and_starved() {
+        let mut simulation = simulation((2, 1), &INERT, 0);
+        let parent = simulation.mold_at(0, 0).unwrap().id;
+        let Cell::MoldPart { mold, .. } = simulation.grid[0][0].clone() else {
+            unreachable!();
+        };
+        let spore = Cell::Spore {
+            mold,
+            age: SPORE_RIPING_AGE,
+            direction: 0,
+        };
+        simulation.set_cell(0, 0, spore);
+        let events = collect(&mut simulation);
+        simulation.update();
+        let sprouted = simulation.mold_at(0, 0).unwrap().id;
+        assert_ne!(sprouted, parent);
+        assert_eq!(
+            *events.borrow(),
+            [
+                Event::Bloomed {
+                    x: 0,
+                    y: 0,
+                    parent,
+                    mold: sprouted,
+                },
+                Event::Starved {
+                    x: 0,
+                    y: 0,
+                    mold: parent
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn running_out_of_energy_emits_starved_once() {
+        let mut simulation = simulation((3, 1), &INERT, 0);
+        copy_cell(&mut simulation, (0, 0), (2, 0));
+        let mold = simulation.mold_at(0, 0).unwrap().id;
+        let events = collect(&mut simulation);
+        simulation.update();
+        // only for the last cell to go
+        assert_eq!(*events.borrow(), [Event::Starved { x: 2, y: 0, mold }]);
+        assert_eq!(simulation.stats().molds, 0);
+    }
+
+    #[test]
+    fn old_age_emits_died() {
+        let mut simulation = simulation((2, 1), &INERT, 1000);
+        simulation.set_max_age(Some(2));
+        let mold = simulation.mold_at(0, 0).unwrap().id;
+        let events = collect(&mut simulation);
+        simulation.run(2);
+        assert!(events.borrow().is_empty());
+        simulation.update();
+        assert_eq!(*events.borrow(), [Event::Died { x: 0, y: 0, mold }]);
+    }
+
+    #[test]
+    fn predator_next_to_a_mold_emits_invaded() {
+        let mut simulation = simulation((2, 1), &INERT, 1000);
+        assert!(simulation.spawn_predator(1, 0));
+        let mold = simulation.mold_at(0, 0).unwrap().id;
+        let events = collect(&mut simulation);
+        simulation.update();
+        assert_eq!(*events.borrow(), [Event::Invaded { x: 1, y: 0, mold }]);
+    }
+}
@@ -0,0 +1,357 @@
+use std::fmt;
+use std::path::Path;
+
+use crate::{RenderOptions, Simulation};
+
+/// Error returned when writing an image of the simulation fails.
+#[derive(Debug)]
+pub enum SaveError {
+    /// The file could not be created or written.
+    Io(std::io::Error),
+    /// The image could not be encoded.
+    Encode(image::ImageError),
+    /// The scaled image is too large for the image format.
+    TooLarge { width: usize, height: usize },
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(e) => write!(f, "{e}"),
+            SaveError::Encode(e) => write!(f, "could not encode image: {e}"),
+            SaveError::TooLarge { width, height } => {
+                write!(f, "image of {width}x{height} pixels is too large")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SaveError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SaveError::Io(e) => Some(e),
+            SaveError::Encode(e) => Some(e),
+            SaveError::TooLarge { .. } => None,
+        }
+    }
+}
+
+impl From<image::ImageError> for SaveError {
+    fn from(error: image::ImageError) -> Self {
+        match error {
+            image::ImageError::IoError(e) => SaveError::Io(e),
+            e => SaveError::Encode(e),
+        }
+    }
+}
+
+impl Simulation {
+    /// Run the simulation and tile `frames` snapshots of the whole grid horizontally into one
+    /// image, starting with the current state and advancing `steps_between` ticks before each
+    /// following snapshot. Every cell takes up zoom x zoom pixels. Fails without running if the
+    /// image would be too large.
+    pub fn render_filmstrip(
+        &mut self,
+        steps_between: usize,
+        frames: usize,
+        zoom: usize,
+    ) -> Result<image::RgbaImage, SaveError> {
+        let options = RenderOptions {
+            view_mode: self.view_mode,
+            scale: zoom.max(1),
+            ..RenderOptions::default()
+        };
+        let (frame_width, height) = self.checked_dimensions(options, 1)?;
+        let (width, _) = self.checked_dimensions(options, frames)?;
+        let (row_len, height) = (frame_width as usize * 4, height as usize);
+        let mut strip = vec![0; frames * row_len * height];
+        for frame in 0..frames {
+            if frame > 0 {
+                self.run(steps_between);
+            }
+            let rgba = self.render_to_rgba_with(options);
+            for (y, row) in rgba.chunks_exact(row_len).enumerate() {
+                let start = (y * frames + frame) * row_len;
+                strip[start..start + row_len].copy_from_slice(row);
+            }
+        }
+        Ok(image::RgbaImage::from_raw(width, height as u32, strip)
+            .expect("filmstrip buffer matches its size"))
+    }
+
+    /// Run `steps` ticks and capture the whole grid every time the tick counter reaches a multiple
+    /// of `every`, with every cell taking up zoom x zoom pixels. All frames are kept until the run
+    /// is over; run_capturing_with hands them over one at a time instead. Fails without running
+    /// if the frames would be too large.
+    pub fn run_capturing(
+        &mut self,
+        steps: usize,
+        every: usize,
+        zoom: usize,
+    ) -> Result<Vec<image::RgbaImage>, SaveError> {
+        let mut frames = Vec::new();
+        self.run_capturing_with(steps, every, zoom, |frame| frames.push(frame))?;
+        Ok(frames)
+    }
+
+    /// Like run_capturing, but pass every frame to `capture` as soon as it is taken, so a long run
+    /// can write them out or shrink them without holding all of them in memory.
+    pub fn run_capturing_with(
+        &mut self,
+        steps: usize,
+        every: usize,
+        zoom: usize,
+        mut capture: impl FnMut(image::RgbaImage),
+    ) -> Result<(), SaveError> {
+        let options = RenderOptions {
+            view_mode: self.view_mode,
+            scale: zoom.max(1),
+            ..RenderOptions::default()
+        };
+        self.checked_dimensions(options, 1)?;
+        let every = every.max(1) as u64;
+        let mut remaining = steps as u64;
+        while remaining > 0 {
+            let until_capture = every - self.tick % every;
+            let steps = until_capture.min(remaining);
+            self.run(steps as usize);
+            remaining -= steps;
+            if self.tick.is_multiple_of(every) {
+                capture(self.render_image(options)?);
+            }
+        }
+        Ok(())
+    }
+
+    /// Image of the whole grid, with every cell taking up options.scale pixels in both directions,
+    /// or every pixel covering options.downscale cells in both directions. Fails if the image
+    /// would be too large.
+    pub fn render_image(&self, options: RenderOptions) -> Result<image::RgbaImage, SaveError> {
+        let (width, height) = self.checked_dimensions(options, 1)?;
+        Ok(
+            image::RgbaImage::from_raw(width, height, self.render_to_rgba_with(options))
+                .expect("rendered buffer matches its size"),
+        )
+    }
+
+    /// Write the whole grid to a PNG file, with every cell taking up options.scale pixels in both
+    /// directions, or every pixel covering options.downscale cells in both directions.
+    pub fn save_png(
+        &self,
+        path: impl AsRef<Path>,
+        options: RenderOptions,
+    ) -> Result<(), SaveError> {
+        let (width, height) = self.checked_dimensions(options, 1)?;
+        image::save_buffer(
+            path,
+            &self.render_to_rgba_with(options),
+            width,
+            height,
+            image::ExtendedColorType::Rgba8,
+        )?;
+        Ok(())
+    }
+
+    /// Width and height of an image of `frames` renders of the whole grid side by side, or
+    /// TooLarge if they don't fit the image format or its RGBA bytes don't fit in memory.
+    fn checked_dimensions(
+        &self,
+        options: RenderOptions,
+        frames: usize,
+    ) -> Result<(u32, u32), SaveError> {
+        let Some((width, height)) = self
+            .checked_image_size(options)
+            .and_then(|(width, height)| Some((width.checked_mul(frames)?, height)))
+        else {
+            // the size doesn't even fit in a usize, report it as large as it gets
+            let scale = options.scale.max(1);
+            return Err(SaveError::TooLarge {
+                width: self.size_x.saturating_mul(scale).saturating_mul(frames),
+                height: self.size_y.saturating_mul(scale),
+            });
+        };
+        let too_large = || SaveError::TooLarge { width, height };
+        let width_u32 = u32::try_from(width).map_err(|_| too_large())?;
+        let height_u32 = u32::try_from(height).map_err(|_| too_large())?;
+        // the RGBA bytes of the image must fit in memory too
+        width
+            .checked_mul(height)
+            .and_then(|pixels| pixels.checked_mul(4))
+            .ok_or_else(too_large)?;
+        Ok((width_u32, height_u32))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Genome, RenderPalette};
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("rustymold-{}-{name}", std::process::id()))
+    }
+
+    /// A 7x5 grid with molds of color 0x102030 on every third cell, on a 0x405060 background.
+    fn patterned() -> Simulation {
+        let mut simulation = Simulation::new(7, 5, 0);
+        simulation.set_palette(RenderPalette {
+            background: 0x405060,
+            ..RenderPalette::default()
+        });
+        let mut rng = fastrand::Rng::with_seed(1);
+        for x in 0..7 {
+            for y in 0..5 {
+                if (x + y) % 3 == 0 {
+                    let genome = Genome {
+                        color: 0x102030,
+                        ..Genome::new(&mut rng)
+                    };
+                    assert!(simulation.spawn_mold(x, y, genome));
+                }
+            }
+        }
+        simulation
+    }
+
+    #[test]
+    fn saved_png_reads_back() {
+        let simulation = patterned();
+        let path = temp_path("saved.png");
+        let options = RenderOptions {
+            scale: 3,
+            ..RenderOptions::default()
+        };
+        simulation.save_png(&path, options).unwrap();
+        let image = image::open(&path).unwrap().to_rgba8();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(image.dimensions(), (21, 15));
+        assert_eq!(image.as_raw(), &simulation.render_to_rgba_with(options));
+        for (x, y, pixel) in image.enumerate_pixels() {
+            let (cell_x, cell_y) = (x as usize / 3, y as usize / 3);
+            let expected = if (cell_x + cell_y) % 3 == 0 {
+                [0x10, 0x20, 0x30, 0xFF]
+            } else {
+                [0x40, 0x50, 0x60, 0xFF]
+            };
+            assert_eq!(pixel.0, expected, "pixel ({x}, {y})");
+        }
+    }
+
+    #[test]
+    fn downscaled_png_reads_back() {
+        let simulation = patterned();
+        let path = temp_path("downscaled.png");
+        let options = RenderOptions {
+            downscale: 2,
+            ..RenderOptions::default()
+        };
+        simulation.save_png(&path, options).unwrap();
+        let image = image::open(&path).unwrap().to_rgba8();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(image.dimensions(), (4, 3));
+        assert_eq!(image.as_raw(), &simulation.render_to_rgba_with(options));
+        // one mold and three empty cells, and a single empty cell in the corner
+        assert_eq!(image.get_pixel(0, 0).0, [0x34, 0x44, 0x54, 0xFF]);
+        assert_eq!(image.get_pixel(3, 2).0, [0x40, 0x50, 0x60, 0xFF]);
+    }
+
+    #[test]
+    fn save_into_missing_directory_fails() {
+        let path = temp_path("missing").join("saved.png");
+        let result = patterned().save_png(path, RenderOptions::default());
+        assert!(matches!(result, Err(SaveError::Io(_))), "{result:?}");
+    }
+
+    #[test]
+    fn oversized_png_fails() {
+        let path = temp_path("oversized.png");
+        for scale in [1 << 30, usize::MAX / 3, usize::MAX] {
+            let options = RenderOptions {
+                scale,
+                ..RenderOptions::default()
+            };
+            let result = patterned().save_png(&path, options);
+            assert!(
+                matches!(result, Err(SaveError::TooLarge { .. })),
+                "scale {scale}: {result:?}"
+            );
+        }
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn frames_follow_the_tick_counter() {
+        let build = || {
+            let mut simulation = Simulation::builder(20, 15).seed(6).build();
+            simulation.generate_random_molds(8);
+            simulation
+        };
+        let mut simulation = build();
+        let frames = simulation.run_capturing(10, 3, 2).unwrap();
+        assert_eq!(simulation.tick(), 10);
+        assert_eq!(frames.len(), 3);
+        let mut twin = build();
+        for frame in &frames {
+            twin.run(3);
+            assert_eq!(frame.dimensions(), (40, 30));
+            let options = RenderOptions {
+                scale: 2,
+                ..RenderOptions::default()
+            };
+            assert!(
+                *frame == twin.render_image(options).unwrap(),
+                "tick {}",
+                twin.tick()
+            );
+        }
+
+        // frames line up with the ticks also when the run doesn't start at 0, here 12, 16 and 20
+        let mut frames = Vec::new();
+        simulation
+            .run_capturing_with(10, 4, 1, |frame| frames.push(frame))
+            .unwrap();
+        assert_eq!(simulation.tick(), 20);
+        assert_eq!(frames.len(), 3);
+        twin.run(3);
+        for frame in &frames {
+            assert!(
+                *frame == twin.render_image(RenderOptions::default()).unwrap(),
+                "tick {}",
+                twin.tick()
+            );
+            twin.run(4);
+        }
+
+        assert!(simulation.run_capturing(0, 1, 1).unwrap().is_empty());
+        // an interval of 0 captures every tick
+        assert_eq!(simulation.run_capturing(3, 0, 1).unwrap().len(), 3);
+        assert_eq!(simulation.tick(), 23);
+    }
+
+    #[test]
+    fn oversized_frames_fail_without_running() {
+        let mut simulation = patterned();
+        let strip = simulation.render_filmstrip(2, 3, 2).unwrap();
+        assert_eq!(strip.dimensions(), (42, 10));
+        assert_eq!(simulation.tick(), 4);
+
+        let too_large =
+            |result: Result<(), SaveError>| matches!(result, Err(SaveError::TooLarge { .. }));
+        for zoom in [1 << 30, usize::MAX] {
+            assert!(too_large(simulation.render_filmstrip(1, 2, zoom).map(drop)));
+            assert!(too_large(simulation.run_capturing(5, 1, zoom).map(drop)));
+            let options = RenderOptions {
+                scale: zoom,
+                ..RenderOptions::default()
+            };
+            assert!(too_large(simulation.render_image(options).map(drop)));
+        }
+        // frames that fit on their own but not side by side
+        assert!(too_large(
+            simulation.render_filmstrip(1, 1 << 30, 1).map(drop)
+        ));
+        assert_eq!(simulation.tick(), 4);
+    }
+}
@@ -0,0 +1,237 @@
+//! Population of a simulation over the last few thousand ticks, sampled while it runs whether or
+//! not the graph is shown, and plotted in a panel over the window.
+
+use std::collections::VecDeque;
+
+use rustymold::{BufferRegion, Simulation};
+
+use crate::hud::{self, ADVANCE, GLYPH_HEIGHT, LINE_HEIGHT};
+
+/// number of ticks shown in the graph, older samples are dropped
+pub const GRAPH_TICKS: u64 = 4000;
+/// ticks between two samples, counting the molds takes a pass over the grid
+pub const SAMPLE_TICKS: u64 = 10;
+/// largest size of the panel in pixels, and the smallest it is drawn at
+pub const GRAPH_SIZE: (usize, usize) = (360, 140);
+pub const MIN_GRAPH_SIZE: (usize, usize) = (160, 70);
+
+const BACKGROUND_COLOR: u32 = 0x101010;
+const BORDER_COLOR: u32 = 0x808080;
+const AXIS_COLOR: u32 = 0x404040;
+const LABEL_COLOR: u32 = 0xC0C0C0;
+const CELLS_COLOR: u32 = 0x40C040;
+const MOLDS_COLOR: u32 = 0xE0C040;
+const GAP_COLOR: u32 = 0xC04040;
+/// distance in pixels between the edge of the panel and what is drawn in it
+const PADDING: usize = 4;
+/// largest number of characters in a label of the vertical axes
+const LABEL_CHARS: usize = 5;
+
+#[derive(Clone, Copy, Debug)]
+enum Sample {
+    Counts {
+        tick: u64,
+        /// cells and spores of all molds
+        cells: usize,
+        molds: usize,
+    },
+    /// The simulation was cleared or replaced, the lines are not joined across it.
+    Gap { tick: u64 },
+}
+
+impl Sample {
+    fn tick(&self) -> u64 {
+        match *self {
+            Sample::Counts { tick, .. } | Sample::Gap { tick } => tick,
+        }
+    }
+}
+
+/// Samples of the number of mold cells and of molds of a simulation, oldest first.
+#[derive(Clone, Debug, Default)]
+pub struct PopulationGraph {
+    samples: VecDeque<Sample>,
+}
+
+impl PopulationGraph {
+    /// Take a sample of the simulation if SAMPLE_TICKS passed since the last one, or right after
+    /// a gap. Meant to be called every frame, nothing is sampled while the simulation is paused.
+    pub fn record(&mut self, simulation: &Simulation) {
+        let tick = simulation.tick();
+        let is_due = match self.samples.back() {
+            None | Some(Sample::Gap { .. }) => true,
+            Some(sample) => tick >= sample.tick() + SAMPLE_TICKS || tick < sample.tick(),
+        };
+        if !is_due {
+            return;
+        }
+        let stats = simulation.stats();
+        self.push(Sample::Counts {
+            tick,
+            cells: stats.mold_cells + stats.spores,
+            molds: stats.molds,
+        });
+    }
+
+    /// Break the lines at `tick`, after the simulation was cleared or replaced.
+    pub fn mark_gap(&mut self, tick: u64) {
+        self.push(Sample::Gap { tick });
+    }
+
+    /// Add a sample, dropping the samples after it, which belong to a simulation that is gone,
+    /// and those that are too old to be shown.
+    fn push(&mut self, sample: Sample) {
+        let tick = sample.tick();
+        while self.samples.back().is_some_and(|last| last.tick() > tick) {
+            self.samples.pop_back();
+        }
+        self.samples.push_back(sample);
+        while self
+            .samples
+            .front()
+            .is_some_and(|first| first.tick() + GRAPH_TICKS < tick)
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Draw the panel filling a region of a buffer: the number of mold cells and of molds over
+    /// the last GRAPH_TICKS ticks, each scaled to fit with its axis labelled on its own side,
+    /// and a red line at every gap.
+    pub fn draw(&self, buffer: &mut [u32], region: BufferRegion) {
+        let (left, top) = region.origin;
+        let (width, height) = region.size;
+        let stride = region.stride;
+        for y in top..top + height {
+            let row = &mut buffer[y * stride + left..y * stride + left + width];
+            row.fill(BACKGROUND_COLOR);
+            if y == top || y == top + height - 1 {
+                row.fill(BORDER_COLOR);
+            } else {
+                row[0] = BORDER_COLOR;
+                row[width - 1] = BORDER_COLOR;
+            }
+        }
+
+        // the plot between the labels of the axes, the legend above it and the ticks below it
+        let label_width = LABEL_CHARS * ADVANCE;
+        let plot_left = left + PADDING + label_width + PADDING;
+        let plot_right = left + width - 1 - PADDING - label_width - PADDING;
+        let plot_top = top + PADDING + LINE_HEIGHT;
+        let plot_bottom = top + height - 1 - PADDING - LINE_HEIGHT;
+        if plot_right <= plot_left || plot_bottom <= plot_top {
+            return;
+        }
+        let (plot_width, plot_height) = (plot_right - plot_left, plot_bottom - plot_top);
+
+        let (max_cells, max_molds) = self
+            .samples
+            .iter()
+            .fold((0, 0), |max, sample| match sample {
+                Sample::Counts { cells, molds, .. } => (max.0.max(*cells), max.1.max(*molds)),
+                Sample::Gap { .. } => max,
+            });
+        let (cells_scale, molds_scale) = (nice_ceiling(max_cells), nice_ceiling(max_molds));
+        let end = self.samples.back().map_or(0, Sample::tick);
+        let start = end.saturating_sub(GRAPH_TICKS);
+        let x_of =
+            |tick: u64| plot_left + ((tick - start) * plot_width as u64 / GRAPH_TICKS) as usize;
+        let y_of = |value: usize, scale: usize| plot_bottom - value * plot_height / scale;
+
+        // axis lines at zero, half and full scale, labelled left for cells and right for molds
+        let text = |buffer: &mut [u32], x: usize, y: usize, text: &str, color: u32| {
+            hud::draw_text(buffer, stride, x as isize, y as isize, text, color);
+        };
+        for (numerator, denominator) in [(0, 1), (1, 2), (1, 1)] {
+            let y = y_of(cells_scale * numerator / denominator, cells_scale);
+            buffer[y * stride + plot_left..=y * stride + plot_right].fill(AXIS_COLOR);
+            let label_y = (y - GLYPH_HEIGHT / 2).clamp(top + 1, top + height - 1 - GLYPH_HEIGHT);
+            let cells = short_number(cells_scale * numerator / denominator);
+            let cells_x = plot_left - PADDING - cells.len() * ADVANCE;
+            text(buffer, cells_x, label_y, &cells, CELLS_COLOR);
+            let molds = short_number(molds_scale * numerator / denominator);
+            text(
+                buffer,
+                plot_right + PADDING + 1,
+                label_y,
+                &molds,
+                MOLDS_COLOR,
+            );
+        }
+        text(buffer, plot_left, top + PADDING, "cells", CELLS_COLOR);
+        text(
+            buffer,
+            plot_left + 6 * ADVANCE,
+            top + PADDING,
+            "molds",
+            MOLDS_COLOR,
+        );
+        let ticks_y = plot_bottom + PADDING;
+        text(
+            buffer,
+            plot_left,
+            ticks_y,
+            &format!("tick {start}"),
+            LABEL_COLOR,
+        );
+        let end_label = end.to_string();
+        let end_x = (plot_right + 1).saturating_sub(end_label.len() * ADVANCE);
+        text(buffer, end_x, ticks_y, &end_label, LABEL_COLOR);
+
+        let mut previous: Option<(usize, usize, usize)> = None;
+        for sample in &self.samples {
+            match *sample {
+                Sample::Counts { tick, cells, molds } => {
+                    let point = (
+                        x_of(tick),
+                        y_of(cells, cells_scale),
+                        y_of(molds, molds_scale),
+                    );
+                    let from = previous.unwrap_or(point);
+                    for (y_from, y_to, color) in [
+                        (from.1, point.1, CELLS_COLOR),
+                        (from.2, point.2, MOLDS_COLOR),
+                    ] {
+                        let line = crate::brush::line(
+                            (from.0 as isize, y_from as isize),
+                            (point.0 as isize, y_to as isize),
+                        );
+                        for (x, y) in line {
+                            buffer[y as usize * stride + x as usize] = color;
+                        }
+                    }
+                    previous = Some(point);
+                }
+                Sample::Gap { tick } => {
+                    let x = x_of(tick);
+                    for y in (plot_top..=plot_bottom).step_by(2) {
+                        buffer[y * stride + x] = GAP_COLOR;
+                    }
+                    previous = None;
+                }
+            }
+        }
+    }
+}
+
+/// The smallest of 1, 2 and 5 times a power of 10 that is at least `value`.
+fn nice_ceiling(value: usize) -> usize {
+    let mut power = 1;
+    loop {
+        for step in [1, 2, 5] {
+            if step * power >= value {
+                return step * power;
+            }
+        }
+        power *= 10;
+    }
+}
+
+/// A number in at most LABEL_CHARS characters, in thousands or millions when it is large.
+fn short_number(value: usize) -> String {
+    match value {
+        0..=99_999 => value.to_string(),
+        100_000..=9_999_999 => format!("{}K", value / 1000),
+        _ => format!("{}M", value / 1_000_000),
+    }
+}
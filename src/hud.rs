@@ -0,0 +1,230 @@
+//! Text drawn over the window buffer with a small embedded bitmap font.
+
+/// width in pixels of a glyph
+pub const GLYPH_WIDTH: usize = 5;
+/// height in pixels of a glyph
+pub const GLYPH_HEIGHT: usize = 7;
+/// horizontal distance in pixels from the start of one character to the next
+pub const ADVANCE: usize = GLYPH_WIDTH + 1;
+/// vertical distance in pixels from the top of one line to the next, leaving room for the shadow
+pub const LINE_HEIGHT: usize = GLYPH_HEIGHT + 3;
+
+/// Rows of the glyph of a character from top to bottom, with the leftmost pixel in bit 4.
+/// Lowercase letters are drawn as uppercase ones, and characters without a glyph as a box.
+pub fn glyph(character: char) -> [u8; GLYPH_HEIGHT] {
+    match character.to_ascii_uppercase() {
+        ' ' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+        '0' => [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E],
+        '1' => [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        '2' => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F],
+        '3' => [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E],
+        '4' => [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02],
+        '5' => [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E],
+        '6' => [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E],
+        '7' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E],
+        '9' => [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C],
+        'A' => [0x0E, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'B' => [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E],
+        'C' => [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E],
+        'D' => [0x1C, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1C],
+        'E' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F],
+        'F' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10],
+        'G' => [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F],
+        'H' => [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'I' => [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        'J' => [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C],
+        'K' => [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+        'L' => [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F],
+        'M' => [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11],
+        'N' => [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+        'O' => [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'P' => [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10],
+        'Q' => [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D],
+        'R' => [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11],
+        'S' => [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E],
+        'T' => [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+        'U' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'V' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04],
+        'W' => [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A],
+        'X' => [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11],
+        'Y' => [0x11, 0x11, 0x11, 0x0A, 0x04, 0x04, 0x04],
+        'Z' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F],
+        '.' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C],
+        ',' => [0x00, 0x00, 0x00, 0x00, 0x0C, 0x04, 0x08],
+        ':' => [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00],
+        '-' => [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00],
+        '+' => [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00],
+        '=' => [0x00, 0x00, 0x1F, 0x00, 0x1F, 0x00, 0x00],
+        '/' => [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00],
+        '(' => [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02],
+        ')' => [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08],
+        '[' => [0x0E, 0x08, 0x08, 0x08, 0x08, 0x08, 0x0E],
+        ']' => [0x0E, 0x02, 0x02, 0x02, 0x02, 0x02, 0x0E],
+        '<' => [0x02, 0x04, 0x08, 0x10, 0x08, 0x04, 0x02],
+        '>' => [0x08, 0x04, 0x02, 0x01, 0x02, 0x04, 0x08],
+        '#' => [0x0A, 0x0A, 0x1F, 0x0A, 0x1F, 0x0A, 0x0A],
+        '%' => [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03],
+        '*' => [0x00, 0x04, 0x15, 0x0E, 0x15, 0x04, 0x00],
+        '!' => [0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x04],
+        '?' => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04],
+        '\'' => [0x0C, 0x04, 0x08, 0x00, 0x00, 0x00, 0x00],
+        '_' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1F],
+        _ => [0x1F, 0x11, 0x11, 0x11, 0x11, 0x11, 0x1F],
+    }
+}
+
+/// Draw a single line of text with its top left corner at (x, y) in a buffer `width` pixels wide.
+/// Pixels falling outside the buffer are skipped, so the text may start or run past any edge.
+pub fn draw_text(buffer: &mut [u32], width: usize, x: isize, y: isize, text: &str, color: u32) {
+    let height = (buffer.len() / width.max(1)) as isize;
+    for (i, character) in text.chars().enumerate() {
+        let left = x + (i * ADVANCE) as isize;
+        if left >= width as isize {
+            break;
+        }
+        for (row, bits) in glyph(character).into_iter().enumerate() {
+            let py = y + row as isize;
+            if py < 0 || py >= height {
+                continue;
+            }
+            for column in 0..GLYPH_WIDTH {
+                let px = left + column as isize;
+                if bits & (0x10 >> column) != 0 && px >= 0 && px < width as isize {
+                    buffer[py as usize * width + px as usize] = color;
+                }
+            }
+        }
+    }
+}
+
+/// Draw pieces of text one after another on a single line starting at (x, y), each in its own
+/// color and with a shadow like draw_lines.
+pub fn draw_spans(
+    buffer: &mut [u32],
+    width: usize,
+    (x, y): (isize, isize),
+    spans: &[(String, u32)],
+    shadow: u32,
+) {
+    let mut left = x;
+    for (text, color) in spans {
+        draw_text(buffer, width, left + 1, y + 1, text, shadow);
+        draw_text(buffer, width, left, y, text, *color);
+        left += (text.chars().count() * ADVANCE) as isize;
+    }
+}
+
+/// Draw lines of text below each other starting at (x, y), each on top of a copy shifted one
+/// pixel right and down in the shadow color, so it stays readable on light and dark cells alike.
+pub fn draw_lines(
+    buffer: &mut [u32],
+    width: usize,
+    (x, y): (isize, isize),
+    lines: &[String],
+    color: u32,
+    shadow: u32,
+) {
+    for (i, line) in lines.iter().enumerate() {
+        let top = y + (i * LINE_HEIGHT) as isize;
+        draw_text(buffer, width, x + 1, top + 1, line, shadow);
+        draw_text(buffer, width, x, top, line, color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rows of a buffer `width` pixels wide, with pixels of `color` as '#' and others as '.'.
+    fn pixels(buffer: &[u32], width: usize, color: u32) -> Vec<String> {
+        buffer
+            .chunks(width)
+            .map(|row| {
+                row.iter()
+                    .map(|&pixel| if pixel == color { '#' } else { '.' })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Rows of the glyph of a character.
+    fn pattern(character: char) -> Vec<String> {
+        let mut buffer = vec![0; GLYPH_WIDTH * GLYPH_HEIGHT];
+        draw_text(&mut buffer, GLYPH_WIDTH, 0, 0, &character.to_string(), 1);
+        pixels(&buffer, GLYPH_WIDTH, 1)
+    }
+
+    #[test]
+    fn glyphs() {
+        let a = [
+            ".###.", "#...#", "#...#", "#####", "#...#", "#...#", "#...#",
+        ];
+        assert_eq!(pattern('A'), a);
+        assert_eq!(pattern('a'), a);
+        let zero = [
+            ".###.", "#...#", "#..##", "#.#.#", "##..#", "#...#", ".###.",
+        ];
+        assert_eq!(pattern('0'), zero);
+        let colon = [
+            ".....", ".##..", ".##..", ".....", ".##..", ".##..", ".....",
+        ];
+        assert_eq!(pattern(':'), colon);
+        let slash = [
+            ".....", "....#", "...#.", "..#..", ".#...", "#....", ".....",
+        ];
+        assert_eq!(pattern('/'), slash);
+        assert_eq!(pattern(' '), ["....."; GLYPH_HEIGHT]);
+        let unknown = [
+            "#####", "#...#", "#...#", "#...#", "#...#", "#...#", "#####",
+        ];
+        assert_eq!(pattern('~'), unknown);
+        assert_eq!(pattern('é'), unknown);
+    }
+
+    #[test]
+    fn glyphs_are_distinct() {
+        let characters = "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ.,:-+=/()[]<>#%*!?'_ ";
+        let unknown = glyph('~');
+        for (i, a) in characters.chars().enumerate() {
+            assert_ne!(glyph(a), unknown, "{a}");
+            for b in characters.chars().skip(i + 1) {
+                assert_ne!(glyph(a), glyph(b), "{a} and {b}");
+            }
+        }
+    }
+
+    #[test]
+    fn text_is_clipped_at_edges() {
+        let width = 8;
+        let mut buffer = vec![0; width * 4];
+        draw_text(&mut buffer, width, -3, -2, "AB", 1);
+        assert_eq!(
+            pixels(&buffer, width, 1),
+            [".#.#...#", "##.####.", ".#.#...#", ".#.#...#"]
+        );
+        // nothing is drawn outside the buffer, not even wrapped onto other rows
+        let mut buffer = vec![0; width * 4];
+        draw_text(&mut buffer, width, 6, 2, "H", 1);
+        assert_eq!(
+            pixels(&buffer, width, 1),
+            ["........", "........", "......#.", "......#."]
+        );
+    }
+
+    #[test]
+    fn lines_have_shadows() {
+        let width = 8;
+        let mut buffer = vec![0; width * (LINE_HEIGHT + GLYPH_HEIGHT + 1)];
+        let lines = ["-".to_string(), "_".to_string()];
+        draw_lines(&mut buffer, width, (1, 0), &lines, 1, 2);
+        let rows = pixels(&buffer, width, 1);
+        let shadows = pixels(&buffer, width, 2);
+        assert_eq!(rows[3], ".#####..");
+        assert_eq!(shadows[4], "..#####.");
+        assert_eq!(rows[LINE_HEIGHT + 6], ".#####..");
+        assert_eq!(shadows[LINE_HEIGHT + 7], "..#####.");
+        let drawn = |rows: &[String]| rows.iter().filter(|row| row.contains('#')).count();
+        assert_eq!((drawn(&rows), drawn(&shadows)), (2, 2));
+    }
+}
@@ -0,0 +1,34 @@
+//! Noticing Ctrl-C during a headless run, so it can stop between two updates and close its
+//! files instead of being killed halfway through writing them.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Catch the first Ctrl-C from now on and only remember it, see is_interrupted. A second one
+/// kills the process as usual. Without unix signals Ctrl-C keeps killing the process right away.
+pub fn install() {
+    #[cfg(unix)]
+    // SAFETY: the handler only stores to an atomic and resets the disposition of the signal,
+    // both of which are async-signal-safe
+    unsafe {
+        libc::signal(
+            libc::SIGINT,
+            on_interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+    }
+}
+
+/// Whether Ctrl-C was pressed since install was called.
+pub fn is_interrupted() -> bool {
+    INTERRUPTED.load(Ordering::Relaxed)
+}
+
+#[cfg(unix)]
+extern "C" fn on_interrupt(_: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::Relaxed);
+    // SAFETY: signal is async-signal-safe
+    unsafe {
+        libc::signal(libc::SIGINT, libc::SIG_DFL);
+    }
+}
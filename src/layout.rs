@@ -0,0 +1,142 @@
+//! Placement of simulations next to each other in the window buffer.
+
+use rustymold::BufferRegion;
+
+/// width in pixels of the divider between two panes
+pub const DIVIDER_WIDTH: usize = 2;
+
+/// Division of a buffer into panes of equal width next to each other, with dividers in between.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Layout {
+    pub buffer_size: (usize, usize),
+    /// Number of panes, at least 1.
+    pub panes: usize,
+}
+
+impl Layout {
+    /// Size of every pane in pixels.
+    pub fn pane_size(&self) -> (usize, usize) {
+        let dividers = DIVIDER_WIDTH * (self.panes - 1);
+        (
+            self.buffer_size.0.saturating_sub(dividers) / self.panes,
+            self.buffer_size.1,
+        )
+    }
+
+    /// Horizontal position in the buffer of the left edge of a pane.
+    pub fn pane_x(&self, pane: usize) -> usize {
+        pane * (self.pane_size().0 + DIVIDER_WIDTH)
+    }
+
+    /// The pane containing a position in the buffer, with the position relative to the pane.
+    pub fn pane_at(&self, position: (f32, f32)) -> Option<(usize, (f32, f32))> {
+        let width = self.pane_size().0 as f32;
+        (0..self.panes).find_map(|pane| {
+            let x = position.0 - self.pane_x(pane) as f32;
+            (x >= 0. && x < width).then_some((pane, (x, position.1)))
+        })
+    }
+
+    /// Region of the buffer a pane is rendered into.
+    pub fn pane_region(&self, pane: usize) -> BufferRegion {
+        BufferRegion {
+            origin: (self.pane_x(pane), 0),
+            size: self.pane_size(),
+            stride: self.buffer_size.0,
+        }
+    }
+}
+
+/// Fill the dividers between the panes, and any columns left over right of the last one, with
+/// the divider color.
+pub fn draw_dividers(buffer: &mut [u32], layout: Layout, divider_color: u32) {
+    let (width, height) = layout.buffer_size;
+    let pane_width = layout.pane_size().0;
+    // the columns from the right edge of every pane up to the next one or the edge of the buffer
+    let gaps: Vec<_> = (0..layout.panes)
+        .map(|pane| {
+            let start = (layout.pane_x(pane) + pane_width).min(width);
+            let end = if pane + 1 == layout.panes {
+                width
+            } else {
+                layout.pane_x(pane + 1).min(width)
+            };
+            start..end
+        })
+        .collect();
+    for row in buffer[..width * height].chunks_exact_mut(width.max(1)) {
+        for gap in &gaps {
+            row[gap.clone()].fill(divider_color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_pane_fills_buffer() {
+        let layout = Layout {
+            buffer_size: (40, 30),
+            panes: 1,
+        };
+        assert_eq!(layout.pane_size(), (40, 30));
+        assert_eq!(layout.pane_at((0., 0.)), Some((0, (0., 0.))));
+        assert_eq!(layout.pane_at((39.5, 29.)), Some((0, (39.5, 29.))));
+        assert_eq!(layout.pane_at((40., 0.)), None);
+        let mut buffer = vec![0; 40 * 30];
+        draw_dividers(&mut buffer, layout, 7);
+        assert!(buffer.iter().all(|&pixel| pixel == 0));
+    }
+
+    #[test]
+    fn panes_tile_with_dividers_between() {
+        // the pixel left over from the odd width goes right of the last pane
+        let layout = Layout {
+            buffer_size: (101, 3),
+            panes: 2,
+        };
+        assert_eq!(layout.pane_size(), (49, 3));
+        assert_eq!((layout.pane_x(0), layout.pane_x(1)), (0, 51));
+        assert_eq!(
+            layout.pane_region(1),
+            BufferRegion {
+                origin: (51, 0),
+                size: (49, 3),
+                stride: 101,
+            }
+        );
+        assert_eq!(layout.pane_at((48.5, 1.)), Some((0, (48.5, 1.))));
+        assert_eq!(layout.pane_at((49., 1.)), None);
+        assert_eq!(layout.pane_at((50.9, 1.)), None);
+        assert_eq!(layout.pane_at((51., 2.)), Some((1, (0., 2.))));
+        assert_eq!(layout.pane_at((100., 0.)), None);
+        assert_eq!(layout.pane_at((-1., 0.)), None);
+
+        let mut buffer = vec![0; 101 * 3];
+        draw_dividers(&mut buffer, layout, 7);
+        for (index, &pixel) in buffer.iter().enumerate() {
+            let x = index % 101;
+            let expected = if (49..51).contains(&x) || x == 100 {
+                7
+            } else {
+                0
+            };
+            assert_eq!(pixel, expected, "column {x}");
+        }
+    }
+
+    #[test]
+    fn narrow_buffer_gives_empty_panes() {
+        let layout = Layout {
+            buffer_size: (1, 2),
+            panes: 2,
+        };
+        assert_eq!(layout.pane_size(), (0, 2));
+        assert_eq!(layout.pane_at((0., 0.)), None);
+        let mut buffer = vec![0; 2];
+        draw_dividers(&mut buffer, layout, 7);
+        assert_eq!(buffer, [7, 7]);
+    }
+}
@@ -1,38 +1,273 @@
 use std::cell::RefCell;
+use std::collections::{HashSet, VecDeque};
+use std::ops::RangeInclusive;
 use std::rc::Rc;
 
 use arrayvec::ArrayVec;
 
+mod color;
+mod colormap;
+mod events;
+#[cfg(feature = "image")]
+mod export;
+mod render;
+mod rng;
+mod run_config;
+mod snapshot;
+mod stamp;
+mod stats;
+mod watch;
+
+use render::{PaletteRemap, RenderStamp};
+use watch::Watch;
+
+pub use color::PixelFormat;
+pub use colormap::Colormap;
+pub use events::{Event, Subscriber};
+#[cfg(feature = "image")]
+pub use export::SaveError;
+pub use render::{
+    screen_to_buffer, AgeShading, BufferRegion, Camera, Overlay, PaletteConstraint, RenderOptions,
+    RenderPalette, SporeStyle, ViewMode,
+};
+pub use rng::Rng;
+pub use run_config::Config;
+pub use snapshot::SnapshotError;
+pub use stamp::Stamp;
+pub use stats::{EnergyLedger, Stats};
+pub use watch::WatchedMold;
+
 /// number of genes in each genome
 const GENOME_SIZE: usize = 100;
-/// increase in energy loss per tick for a cell per passing age
-const ENERGY_LOSS: i32 = 5;
+/// number of numbers in a gene, one for each direction a cell can grow in relative to its own
+/// direction: left, straight ahead and right
+pub const GENE_DIRECTIONS: usize = 3;
+/// default increase in energy loss per tick for a cell per passing age, see
+/// Simulation::set_energy_loss
+pub const ENERGY_LOSS: i32 = 5;
 /// number of ticks elapsed before aging
 const TICKS_TO_AGE: i32 = 200;
 /// minimum age for spore to bloom
 const SPORE_RIPING_AGE: u32 = 100;
+/// ticks between two checks of the running cell counts against the grid in debug builds
+#[cfg(debug_assertions)]
+const CELL_COUNT_CHECK_INTERVAL: u64 = 64;
 /// chance that a gene will stop growth in a direction
 const STOP_CHANCE: f32 = 0.5;
 /// chance that a non-stopping gene will create a spore
 const SPORE_CHANCE: f32 = 0.01;
-/// chance of a mutation ocuring when a spore sprouts
-const MUTATION_CHANCE: f32 = 1. / 50.;
+/// default chance of a mutation ocuring when a spore sprouts, as one in this many, see
+/// Simulation::set_mutation_odds
+pub const MUTATION_ODDS: u32 = 50;
+/// light level of simulations created with Simulation::builder
+const DEFAULT_ENERGY_LIGHT: i32 = 16;
+/// default bounds of the light level, see Simulation::set_energy_light
+pub const MIN_ENERGY_LIGHT: i32 = 0;
+pub const MAX_ENERGY_LIGHT: i32 = 20;
 
-const BACKGROUND_COLOR: u32 = 0;
+/// light intensity of a cell receiving all light, see Simulation::set_light
+pub const FULL_LIGHT: u8 = 255;
 
-#[derive(Clone)]
-struct Genome {
-    /// Genes of a mold. A gene is three numbers, one for each relative growth direction.
+/// increase of the trail of an occupied cell per tick, see Simulation::set_trail
+const TRAIL_GAIN: u16 = 4;
+/// highest value of the trail of a cell; an empty cell loses 1 per tick, so it takes this many
+/// ticks for the trail of a long occupied cell to fade completely
+pub const TRAIL_MAX: u16 = 4096;
+
+/// energy taken by a predator from every neighboring mold cell per tick
+const PREDATOR_DRAIN: i32 = 20;
+/// energy used by a predator per tick
+const PREDATOR_UPKEEP: i32 = 10;
+/// energy of a predator created with Simulation::spawn_predator
+const PREDATOR_START_ENERGY: i32 = 200;
+/// energy at which a predator splits in two, sharing its energy with its offspring
+const PREDATOR_BIRTH_ENERGY: i32 = 400;
+
+/// hue difference in degrees between the molds of consecutive characters, see
+/// Simulation::from_pattern
+const PATTERN_HUE_STEP: f32 = 137.507_77;
+
+/// number of random positions tried when placing an imported genome
+const PLACEMENT_ATTEMPTS: usize = 10;
+/// maximum change of a single color channel when colors drift on mutation
+const COLOR_DRIFT: i32 = 12;
+
+/// What happens to the light of an empty cell that neighbors two or more different molds.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EnergyPolicy {
+    /// Contested light is lost, no mold gets any of it.
+    #[default]
+    Exclusive,
+    /// The light is divided evenly between all neighboring molds, rounding down.
+    Split,
+    /// All the light goes to the neighboring mold with the lowest energy.
+    Neediest,
+}
+
+/// Edge of the grid that light shines in from when molds shade each other, with y = 0 at the top.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LightDirection {
+    #[default]
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+/// Light absorbed by occupied cells on its way into the grid, see Simulation::set_shading.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Shading {
+    pub direction: LightDirection,
+    /// Part of the light passing through an occupied cell that it absorbs, out of FULL_LIGHT.
+    pub attenuation: u8,
+}
+
+/// What lies beyond the edges of the grid.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Edges {
+    /// The grid wraps around: cells on opposite edges are neighbors.
+    #[default]
+    Wrap,
+    /// Nothing: molds can't grow past the edges and cells on the edges have fewer neighbors.
+    Bounded,
+}
+
+/// How the color of a genome changes when it mutates.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ColorMutation {
+    /// Pick a completely new random color.
+    #[default]
+    Jump,
+    /// Shift every channel by a small random amount, so related lineages keep similar colors.
+    Drift,
+}
+
+/// Relative chances of the kinds of change made to the genes when a genome mutates. A kind with
+/// weight 0 never happens, so with every weight 0 a mutation only changes the color.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MutationWeights {
+    /// Replace a single number of a gene with a random one.
+    pub point: u32,
+    /// Copy a whole gene over another one, so a successful growth pattern can be reused.
+    pub duplication: u32,
+    /// Insert a random gene, shifting the genes after it up by one index and dropping the last.
+    pub insertion: u32,
+    /// Delete a gene, shifting the genes after it down by one index and adding a random last one.
+    pub deletion: u32,
+}
+
+impl Default for MutationWeights {
+    fn default() -> Self {
+        Self {
+            point: 1,
+            duplication: 0,
+            insertion: 0,
+            deletion: 0,
+        }
+    }
+}
+
+/// Number of gene sets carried by the molds of a simulation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Ploidy {
+    /// One set of genes, copied to the spores with occasional mutations.
+    #[default]
+    Haploid,
+    /// Two sets of genes of which the dominant one is expressed per gene. A sprouting spore gets
+    /// one set from its parent and one from a neighboring mold, both mixed by crossover.
+    Diploid,
+}
+
+/// One set of genes, see Genome::genes.
+type Genes = [isize; GENOME_SIZE * GENE_DIRECTIONS];
+
+/// Error returned when genes given to Genome::from_genes don't form a valid genome.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GenomeError {
+    /// The number of genes is not the number of genes times the number of directions.
+    WrongLength { expected: usize, found: usize },
+    /// A value is not -2, -1 or the index of a gene.
+    InvalidGene { index: usize, value: isize },
+}
+
+impl std::fmt::Display for GenomeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GenomeError::WrongLength { expected, found } => {
+                write!(f, "expected {expected} gene values, found {found}")
+            }
+            GenomeError::InvalidGene { index, value } => {
+                write!(f, "invalid gene value {value} at index {index}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for GenomeError {}
+
+/// Genetic code of a mold, shared by all of its cells and passed on to its spores.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Genome {
+    /// Genes of a mold. A gene is GENE_DIRECTIONS numbers, one for each relative growth direction.
     /// Growth of a cell depends on the current active gene's values.
     /// -2: no growth.
     /// -1: create spore.
     /// 0 to GENOME_SIZE: growth with new active gene set to this value.
-    genes: [isize; GENOME_SIZE * 3],
+    genes: Genes,
+    /// Both gene sets of a diploid genome, from which `genes` is expressed. None when haploid.
+    alleles: Option<Box<[Genes; 2]>>,
     /// A u32 representing the mold's color using the pattern 0RGB: one byte of zeros, and one byte for red, green and blue.
     color: u32,
+    /// Cached result of compute_hash, updated whenever the genes change.
+    hash: u64,
+    /// Species assigned when the genome first appeared in a simulation, None before that.
+    species: Option<SpeciesId>,
+}
+
+/// Identifier of a mold, unique within a simulation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MoldId(pub u64);
+
+/// Identifier of a genome, unique within a simulation. Assigned when a mold is placed or when a
+/// spore sprouts with changed genes or color, and kept by offspring that didn't change.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SpeciesId(pub u64);
+
+/// Snapshot of a mold and the cell it was looked up through, see Simulation::mold_at.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MoldInfo {
+    pub id: MoldId,
+    /// Id of the first mold of the lineage.
+    pub founder: MoldId,
+    /// Number of spores between the founder and this mold.
+    pub generation: u32,
+    pub energy: i32,
+    /// Number of cells of the mold, including its spores.
+    pub cells: usize,
+    /// Age of the cell that was looked up.
+    pub age: u32,
+    /// Gene that decides how the looked up cell grows, or None if it is a spore.
+    pub active_gene: Option<u32>,
+    pub genome: Genome,
+    /// Smallest area covering the cells of the mold. When the grid wraps, a mold straddling an
+    /// edge is covered by an area that extends past it and wraps around, instead of one spanning
+    /// the whole grid.
+    pub bounds: Rect,
 }
 
 struct Mold {
+    id: MoldId,
+    /// id of the first mold of this lineage: the mold itself if it was not grown from a spore
+    founder: MoldId,
+    /// number of spores between the founder and this mold
+    generation: u32,
     genome: Rc<Genome>,
     energy: RefCell<i32>,
 }
@@ -51,64 +286,606 @@ enum Cell {
         active_gene: u32,
         direction: u32,
     },
+    /// Feeds on the energy of neighboring molds instead of light, and splits when well fed.
+    Predator {
+        energy: i32,
+        age: u32,
+    },
+    /// Blocks growth and provides no light.
+    Wall,
+}
+
+impl Cell {
+    /// Whether this is a spore old enough to sprout once its mold runs out of energy.
+    fn is_ripe_spore(&self) -> bool {
+        matches!(self, Cell::Spore { age, .. } if *age >= SPORE_RIPING_AGE)
+    }
+}
+
+/// Randomly generate a color with every channel in the range 10..=245.
+fn random_color(rng: &mut dyn Rng) -> u32 {
+    ((10 + rng.u32(0..236)) << 16) | ((10 + rng.u32(0..236)) << 8) | (10 + rng.u32(0..236))
+}
+
+/// Shift every channel of a color by a random amount of at most COLOR_DRIFT, keeping it within 10..=245.
+fn drift_color(color: u32, rng: &mut dyn Rng) -> u32 {
+    let mut drifted = 0;
+    for shift in [16, 8, 0] {
+        let channel = ((color >> shift) & 0xFF) as i32;
+        let channel = (channel + rng.i32(-COLOR_DRIFT..COLOR_DRIFT + 1)).clamp(10, 245);
+        drifted |= (channel as u32) << shift;
+    }
+    drifted
 }
 
 /// Randomly generate a single gene
-fn generate_gene() -> isize {
-    if fastrand::f32() < STOP_CHANCE {
+fn generate_gene(rng: &mut dyn Rng) -> isize {
+    if rng.f32() < STOP_CHANCE {
         -2
-    } else if fastrand::f32() < SPORE_CHANCE {
+    } else if rng.f32() < SPORE_CHANCE {
         -1
     } else {
-        fastrand::isize(0..GENOME_SIZE as isize)
+        rng.isize(0..GENOME_SIZE as isize)
     }
 }
 
 impl Genome {
     /// Create a new genome by mutating this one.
-    fn make_mutation(&self) -> Genome {
+    fn make_mutation(
+        &self,
+        color_mutation: ColorMutation,
+        weights: MutationWeights,
+        odds: u32,
+        rng: &mut dyn Rng,
+    ) -> Genome {
         let mut new_genome = self.clone();
-        if fastrand::f32() < MUTATION_CHANCE {
-            new_genome.color = ((10 + fastrand::u32(0..236)) << 16)
-                | ((10 + fastrand::u32(0..236)) << 8)
-                | (10 + fastrand::u32(0..236));
-            let mutation_location = fastrand::usize(0..(GENOME_SIZE * 3));
-            new_genome.genes[mutation_location] = generate_gene();
+        if rng.f32() < 1. / odds as f32 {
+            new_genome.color = match color_mutation {
+                ColorMutation::Jump => random_color(rng),
+                ColorMutation::Drift => drift_color(self.color, rng),
+            };
+            // only draw the kind of mutation when there is a choice, so default weights give the
+            // same results as before the other kinds existed
+            let others = weights
+                .duplication
+                .saturating_add(weights.insertion)
+                .saturating_add(weights.deletion);
+            let total = weights.point.saturating_add(others);
+            // with every weight 0 no kind of change may happen, so the genes stay as they are
+            if total > 0 {
+                let roll = if others > 0 {
+                    rng.u32(0..total)
+                } else {
+                    u32::MAX
+                };
+                let genes = match &mut new_genome.alleles {
+                    Some(alleles) => &mut alleles[rng.usize(0..2)],
+                    None => &mut new_genome.genes,
+                };
+                if roll < weights.duplication {
+                    let source = rng.usize(0..GENOME_SIZE) * GENE_DIRECTIONS;
+                    let target = rng.usize(0..GENOME_SIZE) * GENE_DIRECTIONS;
+                    genes.copy_within(source..source + GENE_DIRECTIONS, target);
+                } else if roll - weights.duplication < weights.insertion {
+                    insert_gene(genes, rng.usize(0..GENOME_SIZE), rng);
+                } else if roll - weights.duplication - weights.insertion < weights.deletion {
+                    delete_gene(genes, rng.usize(0..GENOME_SIZE), rng);
+                } else {
+                    let mutation_location = rng.usize(0..(GENOME_SIZE * GENE_DIRECTIONS));
+                    genes[mutation_location] = generate_gene(rng);
+                }
+                if let Some(alleles) = &new_genome.alleles {
+                    new_genome.genes = express(alleles);
+                }
+            }
+            new_genome.hash = new_genome.compute_hash();
         }
         new_genome
     }
 
-    /// Randomly generate a new genome.
-    fn new() -> Self {
+    /// Create a haploid genome from its genes, laid out like Genome::genes: GENE_DIRECTIONS values
+    /// per gene, each -2 (no growth), -1 (spore) or the index of the next gene.
+    pub fn from_genes(genes: &[isize], color: u32) -> Result<Self, GenomeError> {
+        let mut genome = Self {
+            genes: genes.try_into().map_err(|_| GenomeError::WrongLength {
+                expected: GENOME_SIZE * GENE_DIRECTIONS,
+                found: genes.len(),
+            })?,
+            alleles: None,
+            color: color & 0xFFFFFF,
+            hash: 0,
+            species: None,
+        };
+        let valid = -2..GENOME_SIZE as isize;
+        if let Some((index, &value)) = genes
+            .iter()
+            .enumerate()
+            .find(|(_, value)| !valid.contains(value))
+        {
+            return Err(GenomeError::InvalidGene { index, value });
+        }
+        genome.hash = genome.compute_hash();
+        Ok(genome)
+    }
+
+    /// Genes of the genome, GENE_DIRECTIONS per gene index: one for each relative growth direction.
+    /// For diploid genomes these are the expressed genes.
+    pub fn genes(&self) -> &[isize] {
+        &self.genes
+    }
+
+    /// Both gene sets of a diploid genome, or None for a haploid one.
+    pub fn alleles(&self) -> Option<(&[isize], &[isize])> {
+        self.alleles
+            .as_ref()
+            .map(|alleles| (&alleles[0][..], &alleles[1][..]))
+    }
+
+    /// One set of genes to pass on to offspring: a crossover of both sets of a diploid genome at
+    /// a random gene, or a copy of the genes of a haploid one.
+    fn gamete(&self, rng: &mut dyn Rng) -> Genes {
+        let Some(alleles) = &self.alleles else {
+            return self.genes;
+        };
+        let (first, second) = if rng.bool() {
+            (&alleles[0], &alleles[1])
+        } else {
+            (&alleles[1], &alleles[0])
+        };
+        let crossover = rng.usize(0..GENOME_SIZE + 1) * GENE_DIRECTIONS;
+        let mut genes = *first;
+        genes[crossover..].copy_from_slice(&second[crossover..]);
+        genes
+    }
+
+    /// Create a diploid genome from two gene sets.
+    fn from_alleles(alleles: [Genes; 2], color: u32) -> Self {
         let mut genome = Self {
-            genes: [0; GENOME_SIZE * 3],
+            genes: express(&alleles),
+            alleles: Some(Box::new(alleles)),
+            color,
+            hash: 0,
+            species: None,
+        };
+        genome.hash = genome.compute_hash();
+        genome
+    }
+
+    /// Color of the genome as 0RGB.
+    pub fn color(&self) -> u32 {
+        self.color
+    }
+
+    /// Species of the genome in the simulation it was taken from, or None if it was never part
+    /// of one. See SpeciesId.
+    pub fn species_id(&self) -> Option<SpeciesId> {
+        self.species
+    }
+
+    /// Whether both genomes have the same genes and color, regardless of their species.
+    fn same_code(&self, other: &Genome) -> bool {
+        self.genes == other.genes && self.alleles == other.alleles && self.color == other.color
+    }
+
+    /// Stable 64 bit hash of the genes, identical for identical genomes across runs.
+    fn hash64(&self) -> u64 {
+        self.hash
+    }
+
+    /// FNV-1a hash of the genes.
+    fn compute_hash(&self) -> u64 {
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for gene in self.genes.iter() {
+            for byte in (*gene as i64).to_le_bytes() {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x100000001b3);
+            }
+        }
+        hash
+    }
 
-            color: ((10 + fastrand::u32(0..236)) << 16)
-                | ((10 + fastrand::u32(0..236)) << 8)
-                | (10 + fastrand::u32(0..236)),
+    /// Randomly generate a new genome.
+    fn new(rng: &mut dyn Rng) -> Self {
+        let mut genome = Self {
+            genes: [0; GENOME_SIZE * GENE_DIRECTIONS],
+            alleles: None,
+            color: random_color(rng),
+            hash: 0,
+            species: None,
         };
         for gene in genome.genes.iter_mut() {
-            *gene = generate_gene();
+            *gene = generate_gene(rng);
         }
+        genome.hash = genome.compute_hash();
+        genome
+    }
+
+    /// Genome of the molds of `character` in Simulation::from_pattern.
+    fn for_character(character: char) -> Self {
+        let code = character as u32;
+        let mut genome = Self::new(&mut fastrand::Rng::with_seed(code as u64));
+        genome.color = color::hsv_to_rgb(code as f32 * PATTERN_HUE_STEP, 0.8, 0.95);
         genome
     }
+
+    /// Randomly generate a new diploid genome with two identical gene sets, so it grows like a
+    /// haploid genome until it mates.
+    fn new_diploid(rng: &mut dyn Rng) -> Self {
+        let haploid = Self::new(rng);
+        Self::from_alleles([haploid.genes; 2], haploid.color)
+    }
+}
+
+/// Insert a random gene at `index`, shifting the genes from there on up by one and dropping the
+/// last one. References to shifted genes are shifted along so every gene keeps growing into the
+/// same genes as before, and references to the dropped gene are reset to gene 0.
+fn insert_gene(genes: &mut Genes, index: usize, rng: &mut dyn Rng) {
+    let start = index * GENE_DIRECTIONS;
+    let last = genes.len() - GENE_DIRECTIONS;
+    genes.copy_within(start..last, start + GENE_DIRECTIONS);
+    for value in genes.iter_mut() {
+        if *value >= index as isize {
+            *value += 1;
+            if *value == GENOME_SIZE as isize {
+                *value = 0;
+            }
+        }
+    }
+    for value in &mut genes[start..start + GENE_DIRECTIONS] {
+        *value = generate_gene(rng);
+    }
+}
+
+/// Delete the gene at `index`, shifting the genes after it down by one and adding a random last
+/// one. References to shifted genes are shifted along so every gene keeps growing into the same
+/// genes as before, and references to the deleted gene are reset to gene 0.
+fn delete_gene(genes: &mut Genes, index: usize, rng: &mut dyn Rng) {
+    let start = index * GENE_DIRECTIONS;
+    genes.copy_within(start + GENE_DIRECTIONS.., start);
+    let last = genes.len() - GENE_DIRECTIONS;
+    for value in &mut genes[last..] {
+        *value = generate_gene(rng);
+    }
+    for value in &mut genes[..last] {
+        match (*value).cmp(&(index as isize)) {
+            std::cmp::Ordering::Equal => *value = 0,
+            std::cmp::Ordering::Greater => *value -= 1,
+            std::cmp::Ordering::Less => {}
+        }
+    }
+}
+
+/// Expressed genes of a diploid genome: a gene that grows dominates one that doesn't, otherwise
+/// the first set dominates.
+fn express(alleles: &[Genes; 2]) -> Genes {
+    let mut genes = alleles[0];
+    for (gene, &other) in genes.iter_mut().zip(alleles[1].iter()) {
+        if *gene == -2 {
+            *gene = other;
+        }
+    }
+    genes
+}
+
+/// Values indexed by x * size_y + y for a grid of old_size, moved to the same positions in a grid
+/// of new_size. Positions only in the new grid get `fill`.
+fn resized<T: Clone>(
+    values: &[T],
+    old_size: (usize, usize),
+    new_size: (usize, usize),
+    fill: T,
+) -> Vec<T> {
+    let mut result = vec![fill; new_size.0 * new_size.1];
+    for x in 0..old_size.0.min(new_size.0) {
+        let height = old_size.1.min(new_size.1);
+        result[x * new_size.1..x * new_size.1 + height]
+            .clone_from_slice(&values[x * old_size.1..x * old_size.1 + height]);
+    }
+    result
+}
+
+/// Start and length of the shortest span covering the occupied coordinates along one axis of the
+/// grid, or (0, 0) if there are none. When the axis wraps the span may start near its end and
+/// wrap around: it leaves out the longest run of free coordinates, wherever it lies on the ring.
+fn covering_span(occupied: &[bool], wraps: bool) -> (usize, usize) {
+    let Some(first) = occupied.iter().position(|&occupied| occupied) else {
+        return (0, 0);
+    };
+    let last = occupied
+        .iter()
+        .rposition(|&occupied| occupied)
+        .unwrap_or(first);
+    if !wraps {
+        return (first, last - first + 1);
+    }
+    // the run across the end of the axis, then the longest run between occupied coordinates
+    let len = occupied.len();
+    let mut longest_gap = (first + len - 1 - last, first);
+    let mut gap_start = first;
+    for (coordinate, &occupied) in occupied.iter().enumerate().skip(first) {
+        if occupied {
+            let gap = coordinate - gap_start;
+            if gap > longest_gap.0 {
+                longest_gap = (gap, coordinate);
+            }
+            gap_start = coordinate + 1;
+        }
+    }
+    let (gap, start) = longest_gap;
+    (start, len - gap)
+}
+
+/// Rectangular area of the grid or of a buffer.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
 }
 
 /// Full simulation state.
 pub struct Simulation {
-    pub energy_light: i32,
+    /// energy given by an empty cell in full light, within energy_light_range
+    energy_light: i32,
+    energy_light_range: RangeInclusive<i32>,
+    color_mutation: ColorMutation,
+    mutation_weights: MutationWeights,
+    /// sprouting spores mutate with a chance of one in this many, see set_mutation_odds
+    mutation_odds: u32,
+    ploidy: Ploidy,
+    energy_policy: EnergyPolicy,
+    /// age after which mold cells turn into a final spore or die, see set_max_age
+    max_age: Option<u32>,
+    /// number of mold cells beyond which molds stop growing, see set_max_population
+    max_population: Option<usize>,
+    /// energy a mold pays for every cell and spore it grows, see set_growth_cost
+    growth_cost: i32,
+    /// energy a cell costs its mold every tick per passing age, see set_energy_loss
+    energy_loss: i32,
+    /// number of MoldPart and Spore cells on the grid, kept up to date by set_cell
+    mold_part_count: usize,
+    spore_count: usize,
+    /// number of energy passes per growth pass in every update, at least 1, see set_substeps
+    substeps: u32,
+    shading: Option<Shading>,
+    /// fraction of light (out of FULL_LIGHT) that reaches every cell past the occupied cells
+    /// toward the light, indexed like changed_at, computed at the start of every energy pass
+    /// while shading is enabled
+    shade: Vec<u8>,
+    view_mode: ViewMode,
+    age_shading: Option<AgeShading>,
+    /// mold drawn at full brightness while everything else is dimmed, see set_highlight
+    highlight: Option<MoldId>,
+    spore_style: SporeStyle,
+    /// whether unripe spores fade toward their ripe color as they age, see set_spore_ripening
+    spore_ripening: bool,
+    palette: RenderPalette,
+    colormap: Colormap,
+    /// colors of the colormap for every value, see Colormap::bake
+    colormap_lut: Box<[u32; 256]>,
+    palette_constraint: Option<PaletteConstraint>,
+    /// colors rendered instead of the mold colors on the grid under the palette constraint, for
+    /// every view mode rendered since it was set
+    palette_remaps: RefCell<Vec<PaletteRemap>>,
+    /// layers drawn over empty cells, from bottom to top
+    overlays: Vec<Overlay>,
+    /// state of the buffer after the last call to render_dirty
+    last_render: std::cell::Cell<Option<RenderStamp>>,
     grid: Vec<Vec<Cell>>,
+    /// for every cell (indexed by x * size_y + y) the revision in which it last changed appearance
+    changed_at: Vec<u64>,
+    /// counter increased with every change to the grid
+    revision: u64,
+    /// fraction of energy_light (out of FULL_LIGHT) provided by every cell, indexed like changed_at
+    light_map: Vec<u8>,
+    /// whether every cell provides light at all when it is empty, indexed like changed_at
+    fertility: Vec<bool>,
+    /// number of occupied cells in each of the last population_history_len ticks, oldest first
+    population_history: VecDeque<usize>,
+    population_history_len: usize,
+    /// number of occupied cells counted by the last energy pass, recorded by finish_tick
+    occupied: usize,
+    /// energy that entered and left the simulation so far
+    energy_ledger: EnergyLedger,
+    /// number of cells grown in each absolute direction since the last reset, see
+    /// growth_by_direction
+    growth_by_direction: [u64; 4],
+    /// how long ago and how long every cell was occupied, indexed like changed_at, if tracked
+    trail: Option<Vec<u16>>,
+    /// how recently every cell grew or sprouted, from 1 right after down to 0, indexed like
+    /// changed_at, if tracked
+    activity: Option<Vec<f32>>,
+    /// fraction of the activity of a cell kept every energy pass
+    activity_decay: f32,
+    /// mold followed with Simulation::watch, if any
+    watch: Option<Watch>,
+    /// functions called with every event, see subscribe
+    subscribers: Vec<Subscriber>,
     size_x: usize,
     size_y: usize,
+    edges: Edges,
+    rng: Box<dyn Rng>,
+    /// seed the random number generator was last seeded with, None after reading a snapshot
+    seed: Option<u64>,
+    /// number of updates performed so far
+    tick: u64,
+    /// id of the most recently created mold
+    last_mold_id: u64,
+    /// id of the most recently assigned species
+    last_species_id: u64,
 }
 
-impl Simulation {
-    pub fn new(size_x: usize, size_y: usize, energy_light: i32) -> Self {
+/// Configures a Simulation before it is created, see Simulation::builder.
+#[derive(Clone, Debug)]
+pub struct SimulationBuilder {
+    size_x: usize,
+    size_y: usize,
+    energy_light: i32,
+    energy_light_range: RangeInclusive<i32>,
+    color_mutation: ColorMutation,
+    mutation_weights: MutationWeights,
+    mutation_odds: u32,
+    ploidy: Ploidy,
+    energy_policy: EnergyPolicy,
+    max_age: Option<u32>,
+    max_population: Option<usize>,
+    growth_cost: i32,
+    energy_loss: i32,
+    substeps: u32,
+    shading: Option<Shading>,
+    edges: Edges,
+    seed: Option<u64>,
+}
+
+impl SimulationBuilder {
+    /// Initial light level, clamped to the energy light range.
+    pub fn energy_light(mut self, energy_light: i32) -> Self {
+        self.energy_light = energy_light;
+        self
+    }
+
+    /// Bounds of the light level, MIN_ENERGY_LIGHT..=MAX_ENERGY_LIGHT by default. The range must
+    /// not be empty.
+    pub fn energy_light_range(mut self, energy_light_range: RangeInclusive<i32>) -> Self {
+        self.energy_light_range = energy_light_range;
+        self
+    }
+
+    pub fn color_mutation(mut self, color_mutation: ColorMutation) -> Self {
+        self.color_mutation = color_mutation;
+        self
+    }
+
+    /// Relative chances of point mutations, gene duplications, insertions and deletions.
+    pub fn mutation_weights(mut self, mutation_weights: MutationWeights) -> Self {
+        self.mutation_weights = mutation_weights;
+        self
+    }
+
+    /// Chance that a sprouting spore mutates, as one in this many, see
+    /// Simulation::set_mutation_odds.
+    pub fn mutation_odds(mut self, mutation_odds: u32) -> Self {
+        self.mutation_odds = mutation_odds;
+        self
+    }
+
+    /// Number of gene sets of generated molds and their offspring.
+    pub fn ploidy(mut self, ploidy: Ploidy) -> Self {
+        self.ploidy = ploidy;
+        self
+    }
+
+    pub fn energy_policy(mut self, energy_policy: EnergyPolicy) -> Self {
+        self.energy_policy = energy_policy;
+        self
+    }
+
+    /// Age after which mold cells turn into a final spore or die, see Simulation::set_max_age.
+    pub fn max_age(mut self, max_age: u32) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Number of mold cells at which molds stop growing, see Simulation::set_max_population.
+    pub fn max_population(mut self, max_population: usize) -> Self {
+        self.max_population = Some(max_population);
+        self
+    }
+
+    /// Energy a mold pays for every cell and spore it grows, see Simulation::set_growth_cost.
+    pub fn growth_cost(mut self, growth_cost: i32) -> Self {
+        self.growth_cost = growth_cost;
+        self
+    }
+
+    /// Energy a cell costs its mold every tick per passing age, see Simulation::set_energy_loss.
+    pub fn energy_loss(mut self, energy_loss: i32) -> Self {
+        self.energy_loss = energy_loss;
+        self
+    }
+
+    /// Number of energy passes per growth pass, see Simulation::set_substeps.
+    pub fn substeps(mut self, substeps: u32) -> Self {
+        self.substeps = substeps;
+        self
+    }
+
+    /// Let occupied cells shade the cells behind them, see Simulation::set_shading.
+    pub fn shading(mut self, shading: Shading) -> Self {
+        self.shading = Some(shading);
+        self
+    }
+
+    /// Whether the grid wraps around at its edges.
+    pub fn edges(mut self, edges: Edges) -> Self {
+        self.edges = edges;
+        self
+    }
+
+    /// Seed the random number generator, see Simulation::seed.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn build(self) -> Simulation {
+        let (size_x, size_y) = (self.size_x, self.size_y);
         let mut s = Simulation {
-            energy_light,
+            energy_light: self.energy_light.clamp(
+                *self.energy_light_range.start(),
+                *self.energy_light_range.end(),
+            ),
+            energy_light_range: self.energy_light_range,
+            color_mutation: self.color_mutation,
+            mutation_weights: self.mutation_weights,
+            mutation_odds: self.mutation_odds.max(1),
+            ploidy: self.ploidy,
+            energy_policy: self.energy_policy,
+            max_age: self.max_age,
+            max_population: self.max_population,
+            growth_cost: self.growth_cost.max(0),
+            energy_loss: self.energy_loss.max(0),
+            mold_part_count: 0,
+            spore_count: 0,
+            substeps: self.substeps.max(1),
+            shading: self.shading,
+            shade: Vec::new(),
+            view_mode: ViewMode::default(),
+            age_shading: None,
+            highlight: None,
+            spore_style: SporeStyle::default(),
+            spore_ripening: false,
+            palette: RenderPalette::default(),
+            colormap: Colormap::default(),
+            colormap_lut: Colormap::default().bake(),
+            palette_constraint: None,
+            palette_remaps: RefCell::new(Vec::new()),
+            overlays: Vec::new(),
+            last_render: std::cell::Cell::new(None),
             grid: Vec::new(),
+            changed_at: vec![0; size_x * size_y],
+            revision: 0,
+            light_map: vec![FULL_LIGHT; size_x * size_y],
+            fertility: vec![true; size_x * size_y],
+            population_history: VecDeque::new(),
+            population_history_len: 0,
+            occupied: 0,
+            energy_ledger: EnergyLedger::default(),
+            growth_by_direction: [0; 4],
+            trail: None,
+            activity: None,
+            activity_decay: 0.,
+            watch: None,
+            subscribers: Vec::new(),
             size_x,
             size_y,
+            edges: self.edges,
+            rng: Box::new(fastrand::Rng::new()),
+            seed: None,
+            tick: 0,
+            last_mold_id: 0,
+            last_species_id: 0,
         };
         for _ in 0..size_x {
             let mut v = Vec::new();
@@ -117,211 +894,2480 @@ impl Simulation {
             }
             s.grid.push(v);
         }
+        // remember the random seed too, so Simulation::config can repeat an unseeded run
+        let seed = self.seed.unwrap_or_else(|| s.rng.state());
+        s.seed(seed);
         s
     }
+}
+
+impl Simulation {
+    pub fn new(size_x: usize, size_y: usize, energy_light: i32) -> Self {
+        Self::builder(size_x, size_y)
+            .energy_light(energy_light)
+            .build()
+    }
+
+    /// Create a simulation from a multi-line text pattern, with a grid just large enough to hold
+    /// it: one column per character of the longest line and one row per line. Every character
+    /// other than whitespace becomes a new mold. The grid is at least 1x1, so an empty pattern
+    /// gives a single empty cell.
+    ///
+    /// All cells with the same character share the same genome, generated from a random number
+    /// generator seeded with the character's code point, so a pattern always grows the same way.
+    /// The color of the genome has a hue of code point * 137.5 degrees (the golden angle, which
+    /// keeps neighboring characters such as 'a' and 'b' far apart), a saturation of 0.8 and a
+    /// value of 0.95.
+    pub fn from_pattern(pattern: &str, energy_light: i32) -> Self {
+        let lines: Vec<&str> = pattern.lines().collect();
+        let size_x = lines
+            .iter()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0);
+        let mut simulation = Self::new(size_x.max(1), lines.len().max(1), energy_light);
+        for (y, line) in lines.iter().enumerate() {
+            for (x, character) in line.chars().enumerate() {
+                if !character.is_whitespace() {
+                    simulation.spawn_mold(x, y, Genome::for_character(character));
+                }
+            }
+        }
+        simulation
+    }
+
+    /// Start configuring a simulation with a grid of size_x by size_y cells.
+    pub fn builder(size_x: usize, size_y: usize) -> SimulationBuilder {
+        SimulationBuilder {
+            size_x,
+            size_y,
+            energy_light: DEFAULT_ENERGY_LIGHT,
+            energy_light_range: MIN_ENERGY_LIGHT..=MAX_ENERGY_LIGHT,
+            color_mutation: ColorMutation::default(),
+            mutation_weights: MutationWeights::default(),
+            mutation_odds: MUTATION_ODDS,
+            ploidy: Ploidy::default(),
+            energy_policy: EnergyPolicy::default(),
+            max_age: None,
+            max_population: None,
+            growth_cost: 0,
+            energy_loss: ENERGY_LOSS,
+            substeps: 1,
+            shading: None,
+            edges: Edges::default(),
+            seed: None,
+        }
+    }
 
     /// If position (x, y) is empty, create a new mold with a newly generated genome and return true.
     /// If (x, y) is occupied, return false.
     pub fn generate_mold(&mut self, x: usize, y: usize) -> bool {
+        if !matches!(self.grid[x][y], Cell::Empty) {
+            return false;
+        }
+        let genome = match self.ploidy {
+            Ploidy::Haploid => Genome::new(&mut *self.rng),
+            Ploidy::Diploid => Genome::new_diploid(&mut *self.rng),
+        };
+        self.spawn_mold(x, y, genome)
+    }
+
+    /// If position (x, y) is empty, create a new mold with the given genome and return true.
+    /// If (x, y) is occupied, return false.
+    pub fn spawn_mold(&mut self, x: usize, y: usize, mut genome: Genome) -> bool {
         match self.grid[x][y] {
             Cell::Empty => {
-                let genome = Genome::new();
-                let mold = Mold {
-                    genome: Rc::new(genome),
-                    energy: RefCell::new(0),
-                };
+                genome.species = Some(self.new_species_id());
                 let cell = Cell::MoldPart {
-                    mold: Rc::new(mold),
+                    mold: self.new_mold(genome, None),
                     age: 0,
                     active_gene: 0,
                     direction: 0,
                 };
-                self.grid[x][y] = cell;
+                self.set_cell(x, y, cell);
                 true
             }
             _ => false,
         }
     }
 
-    pub fn clear(&mut self) {
-        for row in self.grid.iter_mut() {
-            for cell in row.iter_mut() {
-                *cell = Cell::Empty;
+    /// Create a mold without energy and with a new id, descending from `parent` if it grew from one of its spores.
+    fn new_mold(&mut self, genome: Genome, parent: Option<&Mold>) -> Rc<Mold> {
+        self.last_mold_id += 1;
+        let id = MoldId(self.last_mold_id);
+        Rc::new(Mold {
+            id,
+            founder: parent.map_or(id, |parent| parent.founder),
+            generation: parent.map_or(0, |parent| parent.generation + 1),
+            genome: Rc::new(genome),
+            energy: RefCell::new(0),
+        })
+    }
+
+    fn new_species_id(&mut self) -> SpeciesId {
+        self.last_species_id += 1;
+        SpeciesId(self.last_species_id)
+    }
+
+    /// Try to create new molds with random genomes at `count` random positions.
+    /// Returns the number of molds that were created, positions that were already occupied are skipped.
+    pub fn generate_random_molds(&mut self, count: usize) -> usize {
+        let mut created = 0;
+        for _ in 0..count {
+            let x = self.rng.usize(0..self.size_x);
+            let y = self.rng.usize(0..self.size_y);
+            if self.generate_mold(x, y) {
+                created += 1;
             }
         }
+        created
     }
 
-    /// Evolve the state of the simulation forward by one time step.
-    pub fn update(&mut self) {
-        // first pass: increase age, apply energy cost, give energy from empty cells
-        for x in 0..self.grid.len() {
-            for y in 0..self.grid[x].len() {
-                match self.grid[x][y] {
-                    Cell::MoldPart {
-                        ref mut age,
-                        ref mold,
-                        ..
-                    }
-                    | Cell::Spore {
-                        ref mut age,
-                        ref mold,
-                        ..
-                    } => {
-                        *mold.energy.borrow_mut() -= ENERGY_LOSS * (1 + *age as i32 / TICKS_TO_AGE);
-                        *age += 1;
-                    }
-                    Cell::Empty => {
-                        self.distribute_energy(x, y);
-                    }
+    /// Create a new mold for every genome at a random empty position.
+    /// Returns the number of molds created, a genome is skipped when no empty position is found after a few attempts.
+    pub fn import_genomes(&mut self, genomes: impl IntoIterator<Item = Genome>) -> usize {
+        let mut created = 0;
+        for genome in genomes {
+            for _ in 0..PLACEMENT_ATTEMPTS {
+                let x = self.rng.usize(0..self.size_x);
+                let y = self.rng.usize(0..self.size_y);
+                if matches!(self.grid[x][y], Cell::Empty) {
+                    self.spawn_mold(x, y, genome);
+                    created += 1;
+                    break;
                 }
             }
         }
+        created
+    }
 
-        // second pass: grow molds, remove molds that are out of energy and awaken their spores
-        for x in 0..self.grid.len() {
-            for y in 0..self.grid[x].len() {
-                match &self.grid[x][y].clone() {
-                    Cell::Spore {
-                        mold,
-                        age,
-                        direction,
-                    } if *mold.energy.borrow() <= 0 => {
-                        if *age >= SPORE_RIPING_AGE {
-                            self.grid[x][y] = Cell::MoldPart {
-                                mold: Rc::new(Mold {
-                                    genome: Rc::new((*mold.genome).make_mutation()),
-                                    energy: RefCell::new(0),
-                                }),
-                                age: 0,
-                                active_gene: 0,
-                                direction: *direction,
-                            }
-                        } else {
-                            self.grid[x][y] = Cell::Empty;
-                        }
-                    }
-                    Cell::MoldPart { mold, .. } if *mold.energy.borrow() <= 0 => {
-                        self.grid[x][y] = Cell::Empty;
-                    }
-                    Cell::MoldPart {
-                        mold,
-                        age,
-                        active_gene,
-                        direction,
-                    } if *age > 0 => {
-                        // todo: make void grow from neighboring cells to make grid[x][y] the only modified cell
-                        for rel_grow_direction in 0..3 {
-                            let next_active_gene =
-                                mold.genome.genes[*active_gene as usize * 3 + rel_grow_direction];
-
-                            // gene -2 indicates no growth in this direction
-                            if next_active_gene < -1 {
-                                continue;
-                            }
-
-                            // target_offset (with size of canvas added to ensure positive values)
-                            let abs_grow_direction =
-                                (3 + *direction + rel_grow_direction as u32) % 4;
-                            let (target_dx, target_dy): (usize, usize) = match abs_grow_direction {
-                                0 => (self.size_x, self.size_y + 1),
-                                1 => (self.size_x + 1, self.size_y),
-                                2 => (self.size_x, self.size_y - 1),
-                                3.. => (self.size_x - 1, self.size_y),
-                            };
-                            let target_x = (x + target_dx) % self.size_x;
-                            let target_y = (y + target_dy) % self.size_y;
+    /// If position (x, y) is empty, place a predator there and return true.
+    /// If (x, y) is occupied, return false.
+    pub fn spawn_predator(&mut self, x: usize, y: usize) -> bool {
+        match self.grid[x][y] {
+            Cell::Empty => {
+                let cell = Cell::Predator {
+                    energy: PREDATOR_START_ENERGY,
+                    age: 0,
+                };
+                self.set_cell(x, y, cell);
+                self.energy_ledger.spawned += PREDATOR_START_ENERGY as i64;
+                true
+            }
+            _ => false,
+        }
+    }
 
-                            // if target cell is empty, add new MoldPart or spore referring to the same mold
-                            if matches!(&self.grid[target_x][target_y], Cell::Empty) {
-                                if next_active_gene == -1 {
-                                    self.grid[target_x][target_y] = Cell::Spore {
-                                        mold: mold.clone(),
-                                        age: 0,
-                                        direction: abs_grow_direction,
-                                    };
-                                } else {
-                                    self.grid[target_x][target_y] = Cell::MoldPart {
-                                        mold: mold.clone(),
-                                        age: 0,
-                                        active_gene: next_active_gene as u32,
-                                        direction: abs_grow_direction,
-                                    };
-                                }
-                            }
-                        }
-                    }
-                    _ => (),
+    /// Copy of every distinct genome of the living molds and spores.
+    pub fn export_genomes(&self) -> Vec<Genome> {
+        let mut seen: HashSet<*const Genome> = HashSet::new();
+        let mut genomes = Vec::new();
+        for cell in self.grid.iter().flatten() {
+            if let Cell::MoldPart { mold, .. } | Cell::Spore { mold, .. } = cell {
+                if seen.insert(Rc::as_ptr(&mold.genome)) {
+                    genomes.push((*mold.genome).clone());
                 }
             }
         }
+        genomes
     }
 
-    /// If there is only one mold neighboring (x, y), give it energy equal to energy_light.
-    #[inline]
-    fn distribute_energy(&mut self, x: usize, y: usize) {
-        let mut neighbors: ArrayVec<Rc<Mold>, 4> = ArrayVec::new();
+    /// Seed the random number generator of this simulation, making all following updates reproducible.
+    pub fn seed(&mut self, seed: u64) {
+        self.rng.seed(seed);
+        self.seed = Some(seed);
+    }
+
+    /// Current state of the random number generator. Restoring it with set_rng_state on a
+    /// simulation with the same grid and settings makes it continue exactly like this one.
+    pub fn rng_state(&self) -> u64 {
+        self.rng.state()
+    }
+
+    /// Restore the state of the random number generator returned by rng_state. The simulation
+    /// no longer continues from a seed afterwards, so config returns no seed.
+    pub fn set_rng_state(&mut self, state: u64) {
+        self.rng.seed(state);
+        self.seed = None;
+    }
+
+    /// Use `rng` for everything random from now on instead of the fastrand generator every
+    /// simulation starts with. It is used in the state it is given in, so the simulation no longer
+    /// continues from a seed afterwards and config returns no seed until the next call to seed.
+    pub fn set_rng(&mut self, rng: Box<dyn Rng>) {
+        self.rng = rng;
+        self.seed = None;
+    }
+
+    /// Energy given by an empty cell in full light.
+    pub fn energy_light(&self) -> i32 {
+        self.energy_light
+    }
+
+    /// Change the light level, clamped to the energy light range.
+    pub fn set_energy_light(&mut self, energy_light: i32) {
+        self.energy_light = energy_light.clamp(
+            *self.energy_light_range.start(),
+            *self.energy_light_range.end(),
+        );
+    }
+
+    /// Bounds of the light level, see SimulationBuilder::energy_light_range.
+    pub fn energy_light_range(&self) -> RangeInclusive<i32> {
+        self.energy_light_range.clone()
+    }
+
+    /// Number of updates performed so far.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Number of values in every gene of a genome, see Genome::genes.
+    pub fn directions(&self) -> usize {
+        GENE_DIRECTIONS
+    }
+
+    /// Size of the grid as (width, height).
+    pub fn size(&self) -> (usize, usize) {
+        (self.size_x, self.size_y)
+    }
+
+    /// Choose how genome colors change when a spore mutates.
+    pub fn set_color_mutation(&mut self, color_mutation: ColorMutation) {
+        self.color_mutation = color_mutation;
+    }
+
+    /// Let spores that sprout from now on mutate with a chance of one in `mutation_odds`, at
+    /// least 1 which makes every one mutate. The genomes of existing molds stay as they are.
+    pub fn set_mutation_odds(&mut self, mutation_odds: u32) {
+        self.mutation_odds = mutation_odds.max(1);
+    }
+
+    pub fn mutation_odds(&self) -> u32 {
+        self.mutation_odds
+    }
+
+    /// Choose whether the grid wraps around at its edges.
+    pub fn set_edges(&mut self, edges: Edges) {
+        self.edges = edges;
+    }
+
+    pub fn edges(&self) -> Edges {
+        self.edges
+    }
+
+    /// Place a wall at (x, y), replacing whatever was there, or remove the wall at (x, y).
+    pub fn set_wall(&mut self, x: usize, y: usize, wall: bool) {
+        match (&self.grid[x][y], wall) {
+            (Cell::Wall, true) => (),
+            (Cell::Wall, false) => self.set_cell(x, y, Cell::Empty),
+            (_, false) => (),
+            (_, true) => {
+                self.remove_cell(x, y);
+                self.set_cell(x, y, Cell::Wall);
+            }
+        }
+    }
+
+    /// Empty the cell at (x, y), whatever is there, including walls.
+    pub fn erase_cell(&mut self, x: usize, y: usize) {
+        self.remove_cell(x, y);
+        self.set_wall(x, y, false);
+    }
+
+    /// Whether there is a wall at (x, y).
+    pub fn is_wall(&self, x: usize, y: usize) -> bool {
+        matches!(self.grid[x][y], Cell::Wall)
+    }
+
+    /// Choose what happens to light that is contested by several molds.
+    pub fn set_energy_policy(&mut self, energy_policy: EnergyPolicy) {
+        self.energy_policy = energy_policy;
+    }
+
+    pub fn energy_policy(&self) -> EnergyPolicy {
+        self.energy_policy
+    }
+
+    /// Limit the age of mold cells, or let them live as long as their mold with None. A cell
+    /// older than max_age turns into a ripe spore if its active gene grows straight ahead, and
+    /// dies otherwise. Spores are not affected.
+    pub fn set_max_age(&mut self, max_age: Option<u32>) {
+        self.max_age = max_age;
+    }
+
+    pub fn max_age(&self) -> Option<u32> {
+        self.max_age
+    }
+
+    /// Stop molds from growing new cells and spores while there are at least `max_population`
+    /// mold cells on the grid, not counting spores, or let them grow as far as energy allows with
+    /// None. Spores still sprout into new molds, so the number of cells can exceed the limit
+    /// until enough of them die.
+    pub fn set_max_population(&mut self, max_population: Option<usize>) {
+        self.max_population = max_population;
+    }
+
+    pub fn max_population(&self) -> Option<usize> {
+        self.max_population
+    }
+
+    /// Make molds pay `growth_cost` energy, at least 0, for every cell and spore they grow from
+    /// now on. A mold with less energy than that grows nothing until it gathers enough, so with a
+    /// cost spreading out competes with surviving. 0, the default, makes growing free.
+    pub fn set_growth_cost(&mut self, growth_cost: i32) {
+        self.growth_cost = growth_cost.max(0);
+    }
+
+    pub fn growth_cost(&self) -> i32 {
+        self.growth_cost
+    }
+
+    /// Make every mold cell and spore cost its mold `energy_loss` energy, at least 0, every tick,
+    /// and that much more for every 200 ticks of its age. Defaults to ENERGY_LOSS. Lower losses
+    /// let molds grow larger on the same light.
+    pub fn set_energy_loss(&mut self, energy_loss: i32) {
+        self.energy_loss = energy_loss.max(0);
+    }
+
+    pub fn energy_loss(&self) -> i32 {
+        self.energy_loss
+    }
+
+    /// Set how many times cells age and energy is handed out in every update before molds grow,
+    /// clamped to at least 1. More substeps let molds build up energy between growth steps, but
+    /// also age their cells faster in ticks.
+    pub fn set_substeps(&mut self, substeps: u32) {
+        self.substeps = substeps.max(1);
+    }
+
+    pub fn substeps(&self) -> u32 {
+        self.substeps
+    }
+
+    /// Let light shine in from one edge of the grid and lose `attenuation` (out of FULL_LIGHT) of
+    /// what is left of it in every occupied cell it passes, so an empty cell behind a dense
+    /// colony provides less energy than one in the open. The light enters anew at the edge, also
+    /// when the grid wraps around. Off by default, as it makes density costly and changes which
+    /// molds thrive.
+    pub fn set_shading(&mut self, enabled: bool, direction: LightDirection, attenuation: u8) {
+        self.shading = enabled.then_some(Shading {
+            direction,
+            attenuation,
+        });
+        if !enabled {
+            self.shade = Vec::new();
+        }
+    }
+
+    pub fn shading(&self) -> Option<Shading> {
+        self.shading
+    }
+
+    /// Id of the mold occupying (x, y), if any.
+    pub fn mold_id_at(&self, x: usize, y: usize) -> Option<MoldId> {
+        self.mold_at_cell(x, y).map(|mold| mold.id)
+    }
+
+    /// Id of the founder of the lineage of the mold occupying (x, y), if any.
+    pub fn founder_at(&self, x: usize, y: usize) -> Option<MoldId> {
+        self.mold_at_cell(x, y).map(|mold| mold.founder)
+    }
+
+    /// Energy of the mold occupying (x, y), if any. Shared by all cells of the mold.
+    pub fn mold_energy_at(&self, x: usize, y: usize) -> Option<i32> {
+        self.mold_at_cell(x, y).map(|mold| *mold.energy.borrow())
+    }
+
+    /// Add `amount` to the energy of the mold occupying (x, y), saturating instead of
+    /// overflowing. Returns false, changing nothing, if no mold occupies the cell.
+    pub fn add_energy(&mut self, x: usize, y: usize, amount: i32) -> bool {
+        let Some(mold) = self.mold_at_cell(x, y) else {
+            return false;
+        };
+        let mut energy = mold.energy.borrow_mut();
+        // the energy that was actually added, less than amount when it saturated
+        let added = energy.saturating_add(amount) - *energy;
+        *energy += added;
+        drop(energy);
+        self.energy_ledger.spawned += added as i64;
+        true
+    }
+
+    /// Species of the genome of the mold occupying (x, y), if any.
+    pub fn species_id_at(&self, x: usize, y: usize) -> Option<SpeciesId> {
+        self.mold_at_cell(x, y).and_then(|mold| mold.genome.species)
+    }
+
+    /// Genome color of the mold occupying (x, y), if any.
+    pub fn mold_color_at(&self, x: usize, y: usize) -> Option<u32> {
+        self.mold_at_cell(x, y).map(|mold| mold.genome.color)
+    }
+
+    /// Whether (x, y) holds a spore that is old enough to sprout into a new mold once its mold
+    /// runs out of energy. False for every other cell, unripe spores included.
+    pub fn is_ripe_spore(&self, x: usize, y: usize) -> bool {
+        self.grid[x][y].is_ripe_spore()
+    }
+
+    /// Positions of all mold cells with at least one empty neighbor, which are the cells a
+    /// colony can still grow from. Follows the edge setting, so with bounded edges nothing
+    /// beyond the border counts as empty.
+    pub fn growth_frontier(&self) -> Vec<(usize, usize)> {
+        let mut frontier = Vec::new();
+        for x in 0..self.size_x {
+            for y in 0..self.size_y {
+                if self.mold_at_cell(x, y).is_some()
+                    && self
+                        .neighbors(x, y)
+                        .any(|(nx, ny)| matches!(self.grid[nx][ny], Cell::Empty))
+                {
+                    frontier.push((x, y));
+                }
+            }
+        }
+        frontier
+    }
+
+    /// Describe the mold occupying (x, y), if any. Counts the cells of the mold, so this takes
+    /// a pass over the whole grid.
+    pub fn mold_at(&self, x: usize, y: usize) -> Option<MoldInfo> {
+        let (mold, age, active_gene) = match &self.grid[x][y] {
+            Cell::Spore { mold, age, .. } => (mold, *age, None),
+            Cell::MoldPart {
+                mold,
+                age,
+                active_gene,
+                ..
+            } => (mold, *age, Some(*active_gene)),
+            Cell::Empty | Cell::Predator { .. } | Cell::Wall => return None,
+        };
+        let mut cells = 0;
+        let mut columns = vec![false; self.size_x];
+        let mut rows = vec![false; self.size_y];
+        for (x, column) in self.grid.iter().enumerate() {
+            for (y, cell) in column.iter().enumerate() {
+                let is_part = match cell {
+                    Cell::MoldPart { mold: other, .. } | Cell::Spore { mold: other, .. } => {
+                        Rc::ptr_eq(mold, other)
+                    }
+                    Cell::Empty | Cell::Predator { .. } | Cell::Wall => false,
+                };
+                if is_part {
+                    cells += 1;
+                    columns[x] = true;
+                    rows[y] = true;
+                }
+            }
+        }
+        let wraps = self.edges == Edges::Wrap;
+        let (bounds_x, width) = covering_span(&columns, wraps);
+        let (bounds_y, height) = covering_span(&rows, wraps);
+        Some(MoldInfo {
+            id: mold.id,
+            founder: mold.founder,
+            generation: mold.generation,
+            energy: *mold.energy.borrow(),
+            cells,
+            age,
+            active_gene,
+            genome: (*mold.genome).clone(),
+            bounds: Rect {
+                x: bounds_x,
+                y: bounds_y,
+                width,
+                height,
+            },
+        })
+    }
+
+    fn mold_at_cell(&self, x: usize, y: usize) -> Option<&Rc<Mold>> {
+        match &self.grid[x][y] {
+            Cell::MoldPart { mold, .. } | Cell::Spore { mold, .. } => Some(mold),
+            Cell::Empty | Cell::Predator { .. } | Cell::Wall => None,
+        }
+    }
+
+    /// Number of neighbors of (x, y) that are not empty: occupied by a mold, spore, predator or
+    /// wall. Neighbors across the edges count when the grid wraps, so a cell on a grid 1 cell
+    /// wide or high can count itself.
+    pub fn occupied_neighbors(&self, x: usize, y: usize) -> u8 {
+        self.neighbors(x, y)
+            .filter(|&(nx, ny)| !matches!(self.grid[nx][ny], Cell::Empty))
+            .count() as u8
+    }
+
+    /// Set the fraction of energy_light that an empty cell at (x, y) provides to its neighbor,
+    /// from 0 (darkness) to FULL_LIGHT (all of it, the default).
+    pub fn set_light(&mut self, x: usize, y: usize, intensity: u8) {
+        self.light_map[x * self.size_y + y] = intensity;
+        self.mark_changed(x, y);
+    }
+
+    /// Fraction of energy_light that the cell at (x, y) provides, out of FULL_LIGHT.
+    pub fn light_intensity(&self, x: usize, y: usize) -> u8 {
+        self.light_map[x * self.size_y + y]
+    }
+
+    /// Give every cell FULL_LIGHT again, undoing all set_light calls.
+    pub fn reset_light(&mut self) {
+        self.light_map.fill(FULL_LIGHT);
+        self.revision += 1;
+        self.changed_at.fill(self.revision);
+    }
+
+    /// Make the cell at (x, y) fertile, providing light to its neighbors when it is empty as set
+    /// with set_light (the default), or barren, providing none whatever its light intensity.
+    pub fn set_fertility(&mut self, x: usize, y: usize, fertile: bool) {
+        self.fertility[x * self.size_y + y] = fertile;
+    }
+
+    /// Whether the cell at (x, y) provides light when it is empty, see set_fertility.
+    pub fn is_fertile(&self, x: usize, y: usize) -> bool {
+        self.fertility[x * self.size_y + y]
+    }
+
+    /// Energy that the cell at (x, y) provides to a neighboring mold when it is empty.
+    pub fn light_at(&self, x: usize, y: usize) -> i32 {
+        self.energy_light * self.light_intensity(x, y) as i32 / FULL_LIGHT as i32
+    }
+
+    /// Start or stop tracking the trail of every cell: a value that grows while the cell is
+    /// occupied and slowly fades once it is empty, showing where molds have been. The trail
+    /// overlay is enabled and disabled along with it. Stopping discards the trail.
+    pub fn set_trail(&mut self, enabled: bool) {
+        match (enabled, &self.trail) {
+            (true, None) => self.trail = Some(vec![0; self.size_x * self.size_y]),
+            (false, Some(_)) => self.trail = None,
+            _ => return,
+        }
+        self.set_overlay(Overlay::Trail, enabled);
+    }
+
+    /// Whether trails are tracked, see set_trail.
+    pub fn has_trail(&self) -> bool {
+        self.trail.is_some()
+    }
+
+    /// Trail of the cell at (x, y) from 0 to TRAIL_MAX, or None if trails are not tracked.
+    pub fn trail_at(&self, x: usize, y: usize) -> Option<u16> {
+        self.trail.as_ref().map(|trail| trail[x * self.size_y + y])
+    }
+
+    /// Start tracking where molds grow or sprout, keeping `decay` of the activity of every cell
+    /// from one tick to the next, or stop tracking with None. The activity view shows it as a
+    /// glow. Changing the decay keeps the activity tracked so far, stopping discards it.
+    pub fn set_activity_trail(&mut self, decay: Option<f32>) {
+        match decay {
+            Some(decay) => {
+                self.activity_decay = decay.clamp(0., 1.);
+                if self.activity.is_none() {
+                    self.activity = Some(vec![0.; self.size_x * self.size_y]);
+                }
+            }
+            None => self.activity = None,
+        }
+        self.last_render.set(None);
+    }
+
+    /// Fraction of the activity kept every tick, or None if activity is not tracked, see
+    /// set_activity_trail.
+    pub fn activity_trail(&self) -> Option<f32> {
+        self.activity.as_ref().map(|_| self.activity_decay)
+    }
+
+    /// Activity of the cell at (x, y) from 0 to 1, or None if activity is not tracked.
+    pub fn activity_at(&self, x: usize, y: usize) -> Option<f32> {
+        self.activity
+            .as_ref()
+            .map(|activity| activity[x * self.size_y + y])
+    }
+
+    /// Record the number of occupied cells for each of the last `len` ticks, or stop recording
+    /// when `len` is 0. Samples already recorded are kept as far as they fit.
+    pub fn set_population_history(&mut self, len: usize) {
+        self.population_history_len = len;
+        while self.population_history.len() > len {
+            self.population_history.pop_front();
+        }
+    }
+
+    /// Number of occupied cells in each recorded tick, oldest first.
+    pub fn population_history(&self) -> &VecDeque<usize> {
+        &self.population_history
+    }
+
+    /// Remove all molds and predators. Walls stay in place.
+    pub fn clear(&mut self) {
+        if let Some(trail) = &mut self.trail {
+            trail.fill(0);
+        }
+        if let Some(activity) = &mut self.activity {
+            activity.fill(0.);
+        }
+        for x in 0..self.size_x {
+            for y in 0..self.size_y {
+                match &self.grid[x][y].clone() {
+                    Cell::Empty | Cell::Wall => continue,
+                    Cell::MoldPart { mold, .. } | Cell::Spore { mold, .. } => {
+                        self.retire_mold(mold)
+                    }
+                    Cell::Predator { energy, .. } => self.energy_ledger.removed += *energy as i64,
+                }
+                self.set_cell(x, y, Cell::Empty);
+            }
+        }
+    }
+
+    /// Book the energy left in a mold that is being removed and empty it, so that it is only
+    /// booked once however many cells the mold has.
+    fn retire_mold(&mut self, mold: &Mold) {
+        self.energy_ledger.removed += mold.energy.replace(0) as i64;
+    }
+
+    /// Replace the cell at (x, y) and remember that it changed.
+    fn set_cell(&mut self, x: usize, y: usize, cell: Cell) {
+        match cell {
+            Cell::MoldPart { .. } => self.mold_part_count += 1,
+            Cell::Spore { .. } => self.spore_count += 1,
+            _ => (),
+        }
+        match std::mem::replace(&mut self.grid[x][y], cell) {
+            Cell::MoldPart { .. } => self.mold_part_count -= 1,
+            Cell::Spore { .. } => self.spore_count -= 1,
+            _ => (),
+        }
+        self.mark_changed(x, y);
+    }
+
+    /// Remember that the appearance of the cell at (x, y) changed since the last render.
+    fn mark_changed(&mut self, x: usize, y: usize) {
+        self.revision += 1;
+        self.changed_at[x * self.size_y + y] = self.revision;
+    }
+
+    /// Remove all molds and predators inside `rect`. Walls stay in place. A rectangle extending
+    /// past the right or bottom edge of the grid wraps around when the edges wrap, and is cut off
+    /// at the edge when they are bounded. Molds that lose their last cell are removed with it.
+    pub fn clear_region(&mut self, rect: Rect) {
+        for (_, (x, y)) in self.region_positions(rect) {
+            self.remove_cell(x, y);
+        }
+    }
+
+    /// Destroy everything within `radius` cells of (x, y), walls included, and scorch the light
+    /// map there: every cell keeps less of its light the closer it is to (x, y), and (x, y) keeps
+    /// none. The disk wraps around when the edges wrap and is cut off at the edge when they are
+    /// bounded. Molds that lose their last cell are removed with it.
+    pub fn impact(&mut self, x: usize, y: usize, radius: usize) {
+        let (size_x, size_y) = (self.size_x as isize, self.size_y as isize);
+        let radius = radius as isize;
+        for dx in -radius..=radius {
+            for dy in -radius..=radius {
+                let distance = dx * dx + dy * dy;
+                if distance > radius * radius + radius {
+                    continue;
+                }
+                let (nx, ny) = (x as isize + dx, y as isize + dy);
+                let (nx, ny) = match self.edges {
+                    Edges::Wrap => (nx.rem_euclid(size_x), ny.rem_euclid(size_y)),
+                    Edges::Bounded => {
+                        if nx < 0 || ny < 0 || nx >= size_x || ny >= size_y {
+                            continue;
+                        }
+                        (nx, ny)
+                    }
+                };
+                let (nx, ny) = (nx as usize, ny as usize);
+                self.erase_cell(nx, ny);
+                let kept = ((distance as f32).sqrt() / (radius + 1) as f32).min(1.);
+                let light = self.light_intensity(nx, ny);
+                self.set_light(nx, ny, (light as f32 * kept) as u8);
+            }
+        }
+    }
+
+    /// Change the size of the grid to size_x x size_y cells, at least 1 x 1, keeping the top left
+    /// corner in place. New cells on the right and bottom are empty, with full light, and cells
+    /// cut off past the new right and bottom edges are removed like with clear_region, walls
+    /// included. Molds that lose their last cell are removed with it.
+    pub fn resize(&mut self, size_x: usize, size_y: usize) {
+        let (size_x, size_y) = (size_x.max(1), size_y.max(1));
+        let (old_x, old_y) = (self.size_x, self.size_y);
+        if (size_x, size_y) == (old_x, old_y) {
+            return;
+        }
+        for x in 0..old_x {
+            for y in 0..old_y {
+                if x >= size_x || y >= size_y {
+                    self.remove_cell(x, y);
+                }
+            }
+        }
+
+        self.grid.truncate(size_x);
+        for column in &mut self.grid {
+            column.resize(size_y, Cell::Empty);
+        }
+        self.grid.resize(size_x, vec![Cell::Empty; size_y]);
+        self.light_map = resized(
+            &self.light_map,
+            (old_x, old_y),
+            (size_x, size_y),
+            FULL_LIGHT,
+        );
+        self.fertility = resized(&self.fertility, (old_x, old_y), (size_x, size_y), true);
+        if let Some(trail) = &mut self.trail {
+            *trail = resized(trail, (old_x, old_y), (size_x, size_y), 0);
+        }
+        if let Some(activity) = &mut self.activity {
+            *activity = resized(activity, (old_x, old_y), (size_x, size_y), 0.);
+        }
+        // recomputed in the next energy pass
+        self.shade = Vec::new();
+        (self.size_x, self.size_y) = (size_x, size_y);
+
+        // every cell moved in the buffer, so everything is rendered again
+        self.revision += 1;
+        self.changed_at = vec![self.revision; size_x * size_y];
+        self.last_render.set(None);
+    }
+
+    /// Positions of the cells inside `rect` with their offsets from its top left corner, column
+    /// by column. Past the right or bottom edge the rectangle wraps around when the edges wrap,
+    /// and is cut off when they are bounded. It never covers a cell twice.
+    fn region_positions(&self, rect: Rect) -> Vec<((usize, usize), (usize, usize))> {
+        let (left, top) = match self.edges {
+            Edges::Wrap => (rect.x % self.size_x, rect.y % self.size_y),
+            Edges::Bounded => (rect.x, rect.y),
+        };
+        let mut positions = Vec::new();
+        for dx in 0..rect.width.min(self.size_x) {
+            for dy in 0..rect.height.min(self.size_y) {
+                let (x, y) = (left + dx, top + dy);
+                if self.edges == Edges::Bounded && (x >= self.size_x || y >= self.size_y) {
+                    continue;
+                }
+                positions.push(((dx, dy), (x % self.size_x, y % self.size_y)));
+            }
+        }
+        positions
+    }
+
+    /// Empty the cell at (x, y) unless it is a wall, booking the energy of what was removed.
+    fn remove_cell(&mut self, x: usize, y: usize) {
+        match &self.grid[x][y].clone() {
+            Cell::Empty | Cell::Wall => (),
+            Cell::MoldPart { mold, .. } | Cell::Spore { mold, .. } => {
+                self.set_cell(x, y, Cell::Empty);
+                // only the clone matched on is left when this was the last cell of the mold
+                if Rc::strong_count(mold) == 1 {
+                    self.retire_mold(mold);
+                }
+            }
+            Cell::Predator { energy, .. } => {
+                self.energy_ledger.removed += *energy as i64;
+                self.set_cell(x, y, Cell::Empty);
+            }
+        }
+    }
+
+    /// Evolve the state of the simulation forward by `steps` time steps.
+    pub fn run(&mut self, steps: usize) {
+        for _ in 0..steps {
+            self.update();
+        }
+    }
+
+    /// Evolve the state of the simulation forward by one time step: `substeps` energy passes
+    /// followed by one growth pass, then finish_tick.
+    pub fn update(&mut self) {
+        for _ in 0..self.substeps {
+            self.update_energy();
+        }
+        self.update_growth();
+        self.finish_tick();
+    }
+
+    /// Run only the energy pass of update once: age every cell, take the upkeep of molds from
+    /// their energy and hand out the energy of empty cells. Together with update_growth and
+    /// finish_tick it can be interleaved with other logic.
+    pub fn update_energy(&mut self) {
+        self.occupied = self.energy_pass();
+
+        #[cfg(debug_assertions)]
+        self.check_energy_balance();
+    }
+
+    /// Run only the growth pass of update once: grow molds, remove molds that are out of energy
+    /// and sprout their spores.
+    pub fn update_growth(&mut self) {
+        self.growth_pass();
+
+        #[cfg(debug_assertions)]
+        self.check_energy_balance();
+    }
+
+    /// Close a time step run with update_energy and update_growth the way update does: advance
+    /// the tick and record the population counted by the last energy pass.
+    pub fn finish_tick(&mut self) {
+        self.tick += 1;
+        if self.population_history_len > 0 {
+            if self.population_history.len() == self.population_history_len {
+                self.population_history.pop_front();
+            }
+            self.population_history.push_back(self.occupied);
+        }
+
+        #[cfg(debug_assertions)]
+        if self.tick.is_multiple_of(CELL_COUNT_CHECK_INTERVAL) {
+            self.check_cell_counts();
+        }
+    }
+
+    /// Increase age, apply energy cost and give energy from empty cells. Returns the number of
+    /// occupied cells.
+    fn energy_pass(&mut self) -> usize {
+        self.update_shade();
+        if let Some(activity) = &mut self.activity {
+            for value in activity.iter_mut() {
+                *value *= self.activity_decay;
+            }
+        }
+        if let Some(watch) = &mut self.watch {
+            watch.cells = 0;
+            watch.oldest_age = 0;
+        }
+        let mut occupied = 0;
+        for x in 0..self.grid.len() {
+            for y in 0..self.grid[x].len() {
+                if !matches!(self.grid[x][y], Cell::Empty | Cell::Wall) {
+                    occupied += 1;
+                }
+                match self.grid[x][y] {
+                    Cell::MoldPart {
+                        ref mut age,
+                        ref mold,
+                        ..
+                    }
+                    | Cell::Spore {
+                        ref mut age,
+                        ref mold,
+                        ..
+                    } => {
+                        let loss = self.energy_loss * (1 + *age as i32 / TICKS_TO_AGE);
+                        *mold.energy.borrow_mut() -= loss;
+                        self.energy_ledger.upkeep += loss as i64;
+                        *age += 1;
+                        if let Some(watch) = &mut self.watch {
+                            watch.count(mold, *age);
+                        }
+                    }
+                    Cell::Empty => {
+                        self.distribute_energy(x, y);
+                    }
+                    Cell::Predator { .. } => {
+                        self.feed_predator(x, y);
+                    }
+                    Cell::Wall => (),
+                }
+                // spores look different once they are ripe
+                if let Cell::Spore {
+                    age: SPORE_RIPING_AGE,
+                    ..
+                } = self.grid[x][y]
+                {
+                    self.mark_changed(x, y);
+                }
+                if let Some(trail) = &mut self.trail {
+                    let value = &mut trail[x * self.size_y + y];
+                    *value = match self.grid[x][y] {
+                        Cell::Empty | Cell::Wall => value.saturating_sub(1),
+                        _ => (*value + TRAIL_GAIN).min(TRAIL_MAX),
+                    };
+                }
+            }
+        }
+
+        occupied
+    }
+
+    /// Compute how much light reaches every cell with shading enabled, keeping a running
+    /// fraction along every column or row in the direction the light travels.
+    fn update_shade(&mut self) {
+        let Some(shading) = self.shading else {
+            return;
+        };
+        let (size_x, size_y) = (self.size_x, self.size_y);
+        self.shade.resize(size_x * size_y, FULL_LIGHT);
+        let kept = (FULL_LIGHT - shading.attenuation) as u32;
+        let (lines, length) = match shading.direction {
+            LightDirection::Top | LightDirection::Bottom => (size_x, size_y),
+            LightDirection::Left | LightDirection::Right => (size_y, size_x),
+        };
+        for line in 0..lines {
+            let mut light = FULL_LIGHT as u32;
+            for step in 0..length {
+                let (x, y) = match shading.direction {
+                    LightDirection::Top => (line, step),
+                    LightDirection::Bottom => (line, length - 1 - step),
+                    LightDirection::Left => (step, line),
+                    LightDirection::Right => (length - 1 - step, line),
+                };
+                self.shade[x * size_y + y] = light as u8;
+                if !matches!(self.grid[x][y], Cell::Empty) {
+                    light = light * kept / FULL_LIGHT as u32;
+                }
+            }
+        }
+    }
+
+    /// Grow molds, remove molds that are out of energy and awaken their spores.
+    fn growth_pass(&mut self) {
+        for x in 0..self.grid.len() {
+            for y in 0..self.grid[x].len() {
+                match &self.grid[x][y].clone() {
+                    Cell::Spore {
+                        mold, direction, ..
+                    } if *mold.energy.borrow() <= 0 => {
+                        self.retire_mold(mold);
+                        if self.grid[x][y].is_ripe_spore() {
+                            let offspring = match self.ploidy {
+                                Ploidy::Haploid => None,
+                                Ploidy::Diploid => Some(self.cross(x, y, mold)),
+                            };
+                            let mut genome =
+                                offspring.as_ref().unwrap_or(&mold.genome).make_mutation(
+                                    self.color_mutation,
+                                    self.mutation_weights,
+                                    self.mutation_odds,
+                                    &mut *self.rng,
+                                );
+                            genome.species = if genome.same_code(&mold.genome) {
+                                mold.genome.species
+                            } else {
+                                Some(self.new_species_id())
+                            };
+                            let sprouted = self.new_mold(genome, Some(mold));
+                            let event = Event::Bloomed {
+                                x,
+                                y,
+                                parent: mold.id,
+                                mold: sprouted.id,
+                            };
+                            let cell = Cell::MoldPart {
+                                mold: sprouted,
+                                age: 0,
+                                active_gene: 0,
+                                direction: *direction,
+                            };
+                            self.set_cell(x, y, cell);
+                            self.mark_active(x, y);
+                            self.emit(event);
+                        } else {
+                            self.set_cell(x, y, Cell::Empty);
+                        }
+                        // only the clone matched on is left when this was the last cell of the mold
+                        if Rc::strong_count(mold) == 1 {
+                            self.emit(Event::Starved {
+                                x,
+                                y,
+                                mold: mold.id,
+                            });
+                        }
+                    }
+                    Cell::MoldPart { mold, .. } if *mold.energy.borrow() <= 0 => {
+                        self.retire_mold(mold);
+                        self.set_cell(x, y, Cell::Empty);
+                        if Rc::strong_count(mold) == 1 {
+                            self.emit(Event::Starved {
+                                x,
+                                y,
+                                mold: mold.id,
+                            });
+                        }
+                    }
+                    Cell::MoldPart {
+                        mold,
+                        age,
+                        active_gene,
+                        direction,
+                    } if self.max_age.is_some_and(|max_age| *age > max_age) => {
+                        // the middle value of a gene is the one for growing straight ahead
+                        let straight =
+                            mold.genome.genes[*active_gene as usize * GENE_DIRECTIONS + 1];
+                        let cell = if straight == -2 {
+                            Cell::Empty
+                        } else {
+                            Cell::Spore {
+                                mold: mold.clone(),
+                                age: SPORE_RIPING_AGE,
+                                direction: *direction,
+                            }
+                        };
+                        self.set_cell(x, y, cell);
+                        // only the clone matched on is left when this was the last cell of the mold
+                        if Rc::strong_count(mold) == 1 {
+                            self.retire_mold(mold);
+                            self.emit(Event::Died {
+                                x,
+                                y,
+                                mold: mold.id,
+                            });
+                        }
+                    }
+                    Cell::Predator { energy, .. } if *energy <= 0 => {
+                        self.energy_ledger.removed += *energy as i64;
+                        self.set_cell(x, y, Cell::Empty);
+                    }
+                    Cell::Predator { energy, age }
+                        if *age > 0 && *energy >= PREDATOR_BIRTH_ENERGY =>
+                    {
+                        self.split_predator(x, y, *energy, *age);
+                    }
+                    Cell::MoldPart {
+                        mold,
+                        age,
+                        active_gene,
+                        direction,
+                    } if *age > 0 => {
+                        // todo: make void grow from neighboring cells to make grid[x][y] the only modified cell
+                        for rel_grow_direction in 0..GENE_DIRECTIONS {
+                            if self.is_crowded() {
+                                break;
+                            }
+                            let next_active_gene = mold.genome.genes
+                                [*active_gene as usize * GENE_DIRECTIONS + rel_grow_direction];
+
+                            // gene -2 indicates no growth in this direction
+                            if next_active_gene < -1 {
+                                continue;
+                            }
+
+                            let abs_grow_direction =
+                                (3 + *direction + rel_grow_direction as u32) % 4;
+                            // nothing grows past a bounded edge or into a wall
+                            let Some((target_x, target_y)) =
+                                self.neighbor(x, y, abs_grow_direction)
+                            else {
+                                continue;
+                            };
+                            if matches!(&self.grid[target_x][target_y], Cell::Wall) {
+                                continue;
+                            }
+
+                            // if target cell is empty and the mold can pay for it, add new
+                            // MoldPart or spore referring to the same mold
+                            if matches!(&self.grid[target_x][target_y], Cell::Empty)
+                                && *mold.energy.borrow() >= self.growth_cost
+                            {
+                                *mold.energy.borrow_mut() -= self.growth_cost;
+                                self.energy_ledger.growth += self.growth_cost as i64;
+                                let cell = if next_active_gene == -1 {
+                                    Cell::Spore {
+                                        mold: mold.clone(),
+                                        age: 0,
+                                        direction: abs_grow_direction,
+                                    }
+                                } else {
+                                    Cell::MoldPart {
+                                        mold: mold.clone(),
+                                        age: 0,
+                                        active_gene: next_active_gene as u32,
+                                        direction: abs_grow_direction,
+                                    }
+                                };
+                                self.set_cell(target_x, target_y, cell);
+                                self.mark_active(target_x, target_y);
+                                self.growth_by_direction[abs_grow_direction as usize] += 1;
+                                self.emit(Event::Grew {
+                                    x: target_x,
+                                    y: target_y,
+                                    mold: mold.id,
+                                });
+                            }
+                        }
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
 
-        let offsets: [(usize, usize); 4] = [
-            (self.size_x, self.size_y + 1),
-            (self.size_x + 1, self.size_y),
-            (self.size_x, self.size_y - 1),
-            (self.size_x - 1, self.size_y),
+    /// Whether there are as many mold cells as the population limit allows, see
+    /// set_max_population.
+    fn is_crowded(&self) -> bool {
+        self.max_population
+            .is_some_and(|max_population| self.mold_part_count >= max_population)
+    }
+
+    /// Record that a mold grew or sprouted at (x, y), if activity is tracked.
+    fn mark_active(&mut self, x: usize, y: usize) {
+        if let Some(activity) = &mut self.activity {
+            activity[x * self.size_y + y] = 1.;
+        }
+    }
+
+    /// Genome of a diploid spore of `mold` sprouting at (x, y): one gamete from the mold and one
+    /// from a random neighboring mold, or from the mold itself if it has no other neighbors.
+    fn cross(&mut self, x: usize, y: usize, mold: &Rc<Mold>) -> Genome {
+        let mut mates: ArrayVec<Rc<Mold>, 4> = ArrayVec::new();
+        for (nx, ny) in self.neighbors(x, y) {
+            let n = &self.grid[nx][ny];
+            if let Cell::MoldPart { mold: mate, .. } | Cell::Spore { mold: mate, .. } = n {
+                if !Rc::ptr_eq(mate, mold) {
+                    mates.push(mate.clone());
+                }
+            }
+        }
+        let mate = match mates.len() {
+            0 => mold.clone(),
+            n => mates.swap_remove(self.rng.usize(0..n)),
+        };
+        let alleles = [
+            mold.genome.gamete(&mut *self.rng),
+            mate.genome.gamete(&mut *self.rng),
         ];
-        for (dx, dy) in offsets.iter() {
-            let n = &self.grid[(x + dx) % self.size_x][(y + dy) % self.size_y];
+        Genome::from_alleles(alleles, mold.genome.color)
+    }
+
+    /// Let the predator at (x, y) take energy from the molds around it and pay its upkeep.
+    fn feed_predator(&mut self, x: usize, y: usize) {
+        let mut gained = 0;
+        let mut invaded: ArrayVec<MoldId, 4> = ArrayVec::new();
+        for (nx, ny) in self.neighbors(x, y) {
+            let n = &self.grid[nx][ny];
             if let Cell::MoldPart { mold, .. } | Cell::Spore { mold, .. } = n {
-                if neighbors.iter().all(|neighbor| !Rc::ptr_eq(neighbor, mold)) {
-                    neighbors.push(mold.clone());
+                let mut energy = mold.energy.borrow_mut();
+                let drained = PREDATOR_DRAIN.min((*energy).max(0));
+                *energy -= drained;
+                gained += drained;
+                if drained > 0 {
+                    invaded.push(mold.id);
                 }
             }
         }
-        if neighbors.len() == 1 {
-            *neighbors[0].energy.borrow_mut() += self.energy_light;
+        for mold in invaded {
+            self.emit(Event::Invaded { x, y, mold });
+        }
+        if let Cell::Predator { energy, age } = &mut self.grid[x][y] {
+            *energy += gained - PREDATOR_UPKEEP;
+            *age += 1;
         }
+        self.energy_ledger.upkeep += PREDATOR_UPKEEP as i64;
     }
 
-    /// Render the state of the simulation into a buffer.
-    pub fn render(
-        &self,
-        buffer: &mut [u32],
-        buffer_size: (usize, usize),
-        camera_offset: (usize, usize),
-        zoom: usize,
-    ) {
-        let mut buffer_index = 0;
-        for y in 0..buffer_size.1 {
-            for x in 0..buffer_size.0 {
-                let (x_grid, y_grid) = self.pixel_to_grid_coords(x, y, camera_offset, zoom);
-
-                match &self.grid[x_grid][y_grid] {
-                    Cell::Empty => {
-                        buffer[buffer_index] = BACKGROUND_COLOR;
+    /// Move half of the energy of the predator at (x, y) into a new predator on a random empty
+    /// neighboring cell, if there is one.
+    fn split_predator(&mut self, x: usize, y: usize, energy: i32, age: u32) {
+        let empty: ArrayVec<(usize, usize), 4> = self
+            .neighbors(x, y)
+            .filter(|&(nx, ny)| matches!(self.grid[nx][ny], Cell::Empty))
+            .collect();
+        if empty.is_empty() {
+            return;
+        }
+        let (target_x, target_y) = empty[self.rng.usize(0..empty.len())];
+        // the parent looks the same with less energy, so it doesn't need to be marked as changed
+        self.grid[x][y] = Cell::Predator {
+            energy: energy - energy / 2,
+            age,
+        };
+        let offspring = Cell::Predator {
+            energy: energy / 2,
+            age: 0,
+        };
+        self.set_cell(target_x, target_y, offspring);
+    }
+
+    /// Position of the neighbor of (x, y) in an absolute direction (0: down, 1: right, 2: up,
+    /// 3: left), or None past a bounded edge.
+    #[inline]
+    fn neighbor(&self, x: usize, y: usize, direction: u32) -> Option<(usize, usize)> {
+        let (dx, dy): (isize, isize) = match direction {
+            0 => (0, 1),
+            1 => (1, 0),
+            2 => (0, -1),
+            3.. => (-1, 0),
+        };
+        let (nx, ny) = (x as isize + dx, y as isize + dy);
+        let (size_x, size_y) = (self.size_x as isize, self.size_y as isize);
+        let inside = (0..size_x).contains(&nx) && (0..size_y).contains(&ny);
+        if self.edges == Edges::Bounded && !inside {
+            return None;
+        }
+        // on a grid of width or height 1 a cell is its own neighbor across the wrapped edge
+        Some((
+            nx.rem_euclid(size_x) as usize,
+            ny.rem_euclid(size_y) as usize,
+        ))
+    }
+
+    /// Positions of the neighbors of (x, y), in the order of their directions.
+    #[inline]
+    fn neighbors(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..4).filter_map(move |direction| self.neighbor(x, y, direction))
+    }
+
+    /// The distinct molds with a cell or spore next to (x, y), in the order they are found.
+    fn neighbor_molds(&self, x: usize, y: usize) -> ArrayVec<Rc<Mold>, 4> {
+        let mut molds: ArrayVec<Rc<Mold>, 4> = ArrayVec::new();
+        for (nx, ny) in self.neighbors(x, y) {
+            if let Cell::MoldPart { mold, .. } | Cell::Spore { mold, .. } = &self.grid[nx][ny] {
+                if molds.iter().all(|other| !Rc::ptr_eq(other, mold)) {
+                    molds.push(mold.clone());
+                }
+            }
+        }
+        molds
+    }
+
+    /// If there is only one mold neighboring (x, y), give it the light of (x, y).
+    /// With more than one neighboring mold the energy policy decides who gets the light.
+    /// Barren cells give nothing.
+    #[inline]
+    fn distribute_energy(&mut self, x: usize, y: usize) {
+        if !self.is_fertile(x, y) {
+            return;
+        }
+        let neighbors = self.neighbor_molds(x, y);
+        let mut light = self.light_at(x, y);
+        if self.shading.is_some() {
+            light = light * self.shade[x * self.size_y + y] as i32 / FULL_LIGHT as i32;
+        }
+        match (neighbors.len(), self.energy_policy) {
+            (0, _) | (2.., EnergyPolicy::Exclusive) => (),
+            (1, _) => {
+                *neighbors[0].energy.borrow_mut() += light;
+                self.energy_ledger.light += light as i64;
+            }
+            (n, EnergyPolicy::Split) => {
+                for neighbor in neighbors.iter() {
+                    *neighbor.energy.borrow_mut() += light / n as i32;
+                }
+                self.energy_ledger.light += (light / n as i32 * n as i32) as i64;
+            }
+            (_, EnergyPolicy::Neediest) => {
+                // ties go to the first neighbor found, which keeps the outcome deterministic
+                let neediest = neighbors
+                    .iter()
+                    .min_by_key(|neighbor| *neighbor.energy.borrow())
+                    .unwrap();
+                *neediest.energy.borrow_mut() += light;
+                self.energy_ledger.light += light as i64;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ops::Range;
+
+    /// A genome of the given color that never grows.
+    fn inert_genome(color: u32) -> Genome {
+        let mut genome = Genome {
+            genes: [-2; GENOME_SIZE * 3],
+            alleles: None,
+            color,
+            hash: 0,
+            species: None,
+        };
+        genome.hash = genome.compute_hash();
+        genome
+    }
+
+    #[test]
+    fn trail_grows_on_occupied_and_fades_on_empty_cells() {
+        // the mold lives on the light of the empty cells around it
+        let mut simulation = Simulation::new(5, 3, 20);
+        simulation.set_trail(true);
+        assert!(simulation.spawn_mold(2, 1, inert_genome(0x808080)));
+        let index = |x: usize, y: usize| x * 3 + y;
+        let trail = simulation.trail.as_mut().unwrap();
+        trail[index(0, 0)] = 10;
+        trail[index(4, 2)] = 10;
+        trail[index(1, 1)] = TRAIL_MAX - 6;
+        simulation.run(3);
+        assert_eq!(simulation.trail_at(2, 1), Some(3 * TRAIL_GAIN));
+        assert_eq!(simulation.trail_at(0, 0), Some(7));
+        assert_eq!(simulation.trail_at(4, 2), Some(7));
+        assert_eq!(simulation.trail_at(1, 1), Some(TRAIL_MAX - 9));
+        assert_eq!(simulation.trail_at(3, 0), Some(0));
+
+        simulation.trail.as_mut().unwrap()[index(2, 1)] = TRAIL_MAX - 1;
+        simulation.update();
+        assert_eq!(simulation.trail_at(2, 1), Some(TRAIL_MAX));
+
+        simulation.set_trail(false);
+        assert_eq!(simulation.trail_at(2, 1), None);
+    }
+
+    /// Put walls in the given columns and molds in the first and last of the columns `inside`,
+    /// then check every tick of a run that nothing grows outside of them.
+    fn check_walled_in(mut simulation: Simulation, walls: &[usize], inside: Range<usize>) {
+        let (size_x, size_y) = simulation.size();
+        for &x in walls {
+            for y in 0..size_y {
+                simulation.set_wall(x, y, true);
+            }
+        }
+        for y in 0..size_y {
+            assert!(simulation.generate_mold(inside.start, y));
+            assert!(simulation.generate_mold(inside.end - 1, y));
+        }
+        let mut reached = (false, false);
+        for _ in 0..1000 {
+            simulation.update();
+            for x in 0..size_x {
+                for y in 0..size_y {
+                    let cell = &simulation.grid[x][y];
+                    if walls.contains(&x) {
+                        assert!(
+                            matches!(cell, Cell::Wall),
+                            "wall at ({x}, {y}) was replaced"
+                        );
+                    } else if !inside.contains(&x) {
+                        assert!(matches!(cell, Cell::Empty), "({x}, {y}) is occupied");
                     }
-                    Cell::Spore { mold, age, .. } if *age >= SPORE_RIPING_AGE => {
-                        // invert color with boolean NOT to distinguish spores from normal cells
-                        buffer[buffer_index] = !mold.genome.color;
+                }
+            }
+            let is_occupied =
+                |x: usize| (0..size_y).any(|y| !matches!(simulation.grid[x][y], Cell::Empty));
+            reached.0 |= is_occupied(inside.start);
+            reached.1 |= is_occupied(inside.end - 1);
+        }
+        // the molds did push against both sides
+        assert_eq!(reached, (true, true));
+    }
+
+    /// The numbers of gene `index`.
+    fn gene(genes: &Genes, index: usize) -> &[isize] {
+        &genes[index * GENE_DIRECTIONS..(index + 1) * GENE_DIRECTIONS]
+    }
+
+    #[test]
+    fn inserted_gene_keeps_growth_of_others() {
+        let mut rng = fastrand::Rng::with_seed(1);
+        for _ in 0..20 {
+            let genes = Genome::new(&mut rng).genes;
+            for index in 0..GENOME_SIZE {
+                let mut inserted = genes;
+                insert_gene(&mut inserted, index, &mut rng);
+                // where a gene ended up, with the last one dropped and references to it reset
+                let moved = |gene: usize| match gene {
+                    _ if gene < index => Some(gene),
+                    _ if gene + 1 < GENOME_SIZE => Some(gene + 1),
+                    _ => None,
+                };
+                for old in 0..GENOME_SIZE {
+                    let Some(new) = moved(old) else {
+                        continue;
+                    };
+                    let expected: Vec<isize> = gene(&genes, old)
+                        .iter()
+                        .map(|&value| match value {
+                            ..0 => value,
+                            _ => moved(value as usize).map_or(0, |gene| gene as isize),
+                        })
+                        .collect();
+                    assert_eq!(gene(&inserted, new), expected, "gene {old} moved to {new}");
+                }
+                assert!(inserted
+                    .iter()
+                    .all(|&value| (-2..GENOME_SIZE as isize).contains(&value)));
+            }
+        }
+    }
+
+    #[test]
+    fn deleted_gene_keeps_growth_of_others() {
+        let mut rng = fastrand::Rng::with_seed(2);
+        for _ in 0..20 {
+            let genes = Genome::new(&mut rng).genes;
+            for index in 0..GENOME_SIZE {
+                let mut deleted = genes;
+                delete_gene(&mut deleted, index, &mut rng);
+                let moved = |gene: usize| match gene {
+                    _ if gene < index => Some(gene),
+                    _ if gene > index => Some(gene - 1),
+                    _ => None,
+                };
+                for old in (0..GENOME_SIZE).filter(|&old| old != index) {
+                    let expected: Vec<isize> = gene(&genes, old)
+                        .iter()
+                        .map(|&value| match value {
+                            ..0 => value,
+                            _ => moved(value as usize).map_or(0, |gene| gene as isize),
+                        })
+                        .collect();
+                    assert_eq!(gene(&deleted, moved(old).unwrap()), expected, "gene {old}");
+                }
+                assert!(deleted
+                    .iter()
+                    .all(|&value| (-2..GENOME_SIZE as isize).contains(&value)));
+            }
+        }
+    }
+
+    #[test]
+    fn inserted_gene_keeps_growth_of_mold() {
+        let mut rng = fastrand::Rng::with_seed(4);
+        let mut genes = Genome::new(&mut rng).genes;
+        for value in genes.iter_mut() {
+            if *value == GENOME_SIZE as isize - 1 {
+                *value = -2;
+            }
+        }
+        let index = 7;
+        let mut inserted = genes;
+        insert_gene(&mut inserted, index, &mut rng);
+        // what each cell grows next, in terms of the genes before the insertion
+        let grown = |genes: &Genes, shifted: bool| {
+            let mut simulation = Simulation::builder(40, 40).seed(1).build();
+            let genome = Genome::from_genes(genes, 0x808080).unwrap();
+            assert!(simulation.spawn_mold(20, 20, genome));
+            simulation.run(150);
+            let cells: Vec<Option<isize>> = simulation
+                .grid
+                .iter()
+                .flatten()
+                .map(|cell| match cell {
+                    Cell::MoldPart { active_gene, .. } => {
+                        let gene = *active_gene as usize;
+                        Some((gene - usize::from(shifted && gene > index)) as isize)
                     }
-                    Cell::MoldPart { mold, .. } | Cell::Spore { mold, .. } => {
-                        buffer[buffer_index] = mold.genome.color;
+                    Cell::Spore { .. } => Some(-1),
+                    _ => None,
+                })
+                .collect();
+            cells
+        };
+        let cells = grown(&genes, false);
+        assert!(cells.iter().flatten().count() > 1);
+        assert_eq!(grown(&inserted, true), cells);
+    }
+
+    /// Weights that only allow the given kinds of change.
+    fn only(point: u32, duplication: u32) -> MutationWeights {
+        MutationWeights {
+            point,
+            duplication,
+            insertion: 0,
+            deletion: 0,
+        }
+    }
+
+    #[test]
+    fn duplication_copies_a_gene() {
+        let mut rng = fastrand::Rng::with_seed(5);
+        let mut copies = 0;
+        for _ in 0..50 {
+            let genome = Genome::new(&mut rng);
+            let mutated = genome.make_mutation(ColorMutation::Jump, only(0, 1), 1, &mut rng);
+            let changed: Vec<usize> = (0..GENOME_SIZE)
+                .filter(|&index| gene(&mutated.genes, index) != gene(&genome.genes, index))
+                .collect();
+            // a gene copied over itself or an equal gene changes nothing
+            match changed[..] {
+                [] => {}
+                [target] => {
+                    assert!(
+                        (0..GENOME_SIZE).any(|source| source != target
+                            && gene(&genome.genes, source) == gene(&mutated.genes, target)),
+                        "gene {target} is not a copy of another gene"
+                    );
+                    copies += 1;
+                }
+                _ => panic!("genes {changed:?} changed"),
+            }
+        }
+        assert!(copies > 40);
+    }
+
+    #[test]
+    fn zero_weights_keep_genes() {
+        let mut rng = fastrand::Rng::with_seed(6);
+        for _ in 0..20 {
+            let genome = Genome::new(&mut rng);
+            let mutated = genome.make_mutation(ColorMutation::Jump, only(0, 0), 1, &mut rng);
+            assert_eq!(mutated.genes, genome.genes);
+        }
+    }
+
+    #[test]
+    fn deleting_inserted_gene_restores_genes() {
+        let mut rng = fastrand::Rng::with_seed(3);
+        let mut genes = Genome::new(&mut rng).genes;
+        // nothing refers to the last gene, which insertion drops
+        for value in genes.iter_mut() {
+            if *value == GENOME_SIZE as isize - 1 {
+                *value = -2;
+            }
+        }
+        for index in 0..GENOME_SIZE {
+            let mut changed = genes;
+            insert_gene(&mut changed, index, &mut rng);
+            delete_gene(&mut changed, index, &mut rng);
+            let last = (GENOME_SIZE - 1) * GENE_DIRECTIONS;
+            assert_eq!(changed[..last], genes[..last], "at {index}");
+        }
+    }
+
+    /// Bounds of a mold grown to cover the given cells on a 20x10 grid.
+    fn mold_bounds(edges: Edges, cells: &[(usize, usize)]) -> Rect {
+        let mut simulation = Simulation::builder(20, 10).edges(edges).build();
+        let (x, y) = cells[0];
+        assert!(simulation.generate_mold(x, y));
+        let part = simulation.grid[x][y].clone();
+        for &(x, y) in &cells[1..] {
+            simulation.set_cell(x, y, part.clone());
+        }
+        simulation.mold_at(x, y).unwrap().bounds
+    }
+
+    #[test]
+    fn bounds_of_mold_across_edge() {
+        let cells = [(0, 5), (19, 5), (18, 6), (1, 4), (1, 0), (1, 9)];
+        assert_eq!(
+            mold_bounds(Edges::Wrap, &cells),
+            Rect {
+                x: 18,
+                y: 4,
+                width: 4,
+                height: 7
+            }
+        );
+        assert_eq!(
+            mold_bounds(Edges::Bounded, &cells),
+            Rect {
+                x: 0,
+                y: 0,
+                width: 20,
+                height: 10
+            }
+        );
+        let cells = [(3, 2), (4, 2), (4, 3)];
+        for edges in [Edges::Wrap, Edges::Bounded] {
+            assert_eq!(
+                mold_bounds(edges, &cells),
+                Rect {
+                    x: 3,
+                    y: 2,
+                    width: 2,
+                    height: 2
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn covering_spans() {
+        let span = |occupied: &str, wraps| {
+            let occupied: Vec<bool> = occupied.chars().map(|c| c == '#').collect();
+            covering_span(&occupied, wraps)
+        };
+        assert_eq!(span("....", true), (0, 0));
+        assert_eq!(span("####", true), (0, 4));
+        assert_eq!(span("..#.", true), (2, 1));
+        assert_eq!(span("#..#", true), (3, 2));
+        assert_eq!(span("#..#", false), (0, 4));
+        assert_eq!(span("#.#....#", true), (7, 4));
+        // of equally long gaps the one across the end is left out
+        assert_eq!(span("#..#..", true), (0, 4));
+        assert_eq!(span(".#.#.", false), (1, 3));
+    }
+
+    #[test]
+    fn walls_block_growth() {
+        let simulation = Simulation::builder(20, 10).seed(1).build();
+        check_walled_in(simulation, &[5, 15], 6..15);
+    }
+
+    #[test]
+    fn bounded_edges_block_growth() {
+        let simulation = Simulation::builder(20, 10)
+            .edges(Edges::Bounded)
+            .seed(1)
+            .build();
+        check_walled_in(simulation, &[10], 0..10);
+    }
+
+    #[test]
+    fn update_runs_energy_then_growth_passes() {
+        for substeps in [1, 3] {
+            let build = || {
+                let mut simulation = Simulation::builder(40, 30)
+                    .seed(4)
+                    .substeps(substeps)
+                    .build();
+                simulation.generate_random_molds(20);
+                simulation
+            };
+            let mut whole = build();
+            let mut phased = build();
+            for _ in 0..300 {
+                whole.update();
+                for _ in 0..substeps {
+                    phased.update_energy();
+                }
+                phased.update_growth();
+                phased.finish_tick();
+            }
+            assert!(whole.stats().molds > 0);
+            assert_eq!(phased.stats(), whole.stats(), "{substeps} substeps");
+            assert!(phased.render_to_rgba() == whole.render_to_rgba());
+            assert_eq!((whole.tick(), phased.tick()), (300, 300));
+        }
+    }
+
+    #[test]
+    fn substeps_hand_out_light_several_times_per_update() {
+        assert_eq!(
+            Simulation::builder(10, 10).substeps(0).build().substeps(),
+            1
+        );
+        let build = || {
+            let mut simulation = Simulation::builder(40, 30).seed(6).build();
+            simulation.generate_random_molds(20);
+            simulation.run(20);
+            simulation
+        };
+        let mut single = build();
+        let mut triple = build();
+        triple.set_substeps(0);
+        assert_eq!(triple.substeps(), 1);
+        triple.set_substeps(3);
+        assert_eq!(triple.substeps(), 3);
+
+        // the first energy pass of both is the same, the other two add more light
+        let light = single.energy_ledger().light;
+        single.update();
+        triple.update();
+        let gained = |simulation: &Simulation| simulation.energy_ledger().light - light;
+        assert!(gained(&single) > 0);
+        assert!(gained(&triple) > 2 * gained(&single), "light of 3 substeps");
+        assert_eq!(triple.tick(), single.tick());
+        assert_eq!(
+            triple.energy_ledger().balance(),
+            triple.stats().total_energy
+        );
+    }
+
+    #[test]
+    fn finishing_a_tick_advances_the_tick_and_history() {
+        let build = || {
+            let mut simulation = Simulation::builder(30, 20).seed(2).build();
+            simulation.generate_random_molds(10);
+            simulation.set_population_history(10);
+            simulation
+        };
+        let mut whole = build();
+        let mut phased = build();
+        for tick in 1..=5 {
+            phased.update_energy();
+            phased.update_growth();
+            assert_eq!(
+                phased.energy_ledger().balance(),
+                phased.stats().total_energy
+            );
+            // the tick only closes when it is finished
+            assert_eq!(phased.tick(), tick - 1);
+            assert_eq!(phased.population_history().len(), tick as usize - 1);
+            phased.finish_tick();
+            whole.update();
+            assert_eq!(phased.tick(), tick);
+            assert_eq!(phased.population_history(), whole.population_history());
+        }
+        assert!(phased.energy_ledger().light > 0);
+    }
+
+    #[test]
+    fn occupied_cells_shade_the_cells_behind_them() {
+        let attenuation = 64;
+        let kept = (FULL_LIGHT - attenuation) as u32;
+        let once = (FULL_LIGHT as u32 * kept / FULL_LIGHT as u32) as u8;
+        let twice = (once as u32 * kept / FULL_LIGHT as u32) as u8;
+        for direction in [
+            LightDirection::Top,
+            LightDirection::Bottom,
+            LightDirection::Left,
+            LightDirection::Right,
+        ] {
+            let mut simulation = Simulation::builder(6, 6)
+                .shading(Shading {
+                    direction,
+                    attenuation,
+                })
+                .build();
+            // the cell of line 2 at a distance from the edge the light shines in from
+            let at = |step: usize| match direction {
+                LightDirection::Top => (2, step),
+                LightDirection::Bottom => (2, 5 - step),
+                LightDirection::Left => (step, 2),
+                LightDirection::Right => (5 - step, 2),
+            };
+            for step in [1, 3] {
+                let (x, y) = at(step);
+                simulation.set_wall(x, y, true);
+            }
+            simulation.update_shade();
+            let shade = |(x, y): (usize, usize)| simulation.shade[x * 6 + y];
+            let line: Vec<u8> = (0..6).map(|step| shade(at(step))).collect();
+            let full = FULL_LIGHT;
+            assert_eq!(
+                line,
+                [full, full, once, once, twice, twice],
+                "{direction:?}"
+            );
+            // the other lines get all the light
+            let mut lit = (0..6)
+                .flat_map(|x| (0..6).map(move |y| (x, y)))
+                .filter(|&(x, y)| !(0..6).any(|step| at(step) == (x, y)));
+            assert!(lit.all(|cell| shade(cell) == full), "{direction:?}");
+        }
+    }
+
+    #[test]
+    fn empty_cells_behind_a_colony_get_less_light() {
+        let attenuation = 128;
+        let gained = |colony: bool| {
+            let mut simulation = Simulation::builder(3, 6)
+                .edges(Edges::Bounded)
+                .shading(Shading {
+                    direction: LightDirection::Top,
+                    attenuation,
+                })
+                .build();
+            assert!(simulation.spawn_mold(1, 5, inert_genome(0x808080)));
+            if colony {
+                assert!(simulation.spawn_mold(1, 1, inert_genome(0x404040)));
+            }
+            simulation.update_energy();
+            let energy = simulation.mold_energy_at(1, 5).unwrap();
+            (energy, simulation.light_at(1, 4))
+        };
+        let (lit, light) = gained(false);
+        let (shaded, _) = gained(true);
+        // only the empty cell above the mold is behind the colony
+        let kept = light * (FULL_LIGHT - attenuation) as i32 / FULL_LIGHT as i32;
+        assert!(kept < light);
+        assert_eq!(lit - shaded, light - kept);
+    }
+
+    /// Every grid from 1x1 to 3x3 with both kinds of edges.
+    fn small_grids() -> impl Iterator<Item = (usize, usize, Edges)> {
+        (1..=3).flat_map(|size_x| {
+            (1..=3).flat_map(move |size_y| {
+                [Edges::Wrap, Edges::Bounded].map(|edges| (size_x, size_y, edges))
+            })
+        })
+    }
+
+    /// The neighbor of (x, y) in a direction, computed independently of Simulation::neighbor.
+    fn expected_neighbor(
+        (x, y): (usize, usize),
+        (size_x, size_y): (usize, usize),
+        edges: Edges,
+        direction: u32,
+    ) -> Option<(usize, usize)> {
+        let (dx, dy) = [(0, 1), (1, 0), (0, size_y - 1), (size_x - 1, 0)][direction as usize];
+        let crosses_edge = match direction {
+            0 => y == size_y - 1,
+            1 => x == size_x - 1,
+            2 => y == 0,
+            _ => x == 0,
+        };
+        if crosses_edge && edges == Edges::Bounded {
+            return None;
+        }
+        Some(((x + dx) % size_x, (y + dy) % size_y))
+    }
+
+    #[test]
+    fn neighbors_on_small_grids() {
+        for (size_x, size_y, edges) in small_grids() {
+            let simulation = Simulation::builder(size_x, size_y).edges(edges).build();
+            for x in 0..size_x {
+                for y in 0..size_y {
+                    for direction in 0..4 {
+                        assert_eq!(
+                            simulation.neighbor(x, y, direction),
+                            expected_neighbor((x, y), (size_x, size_y), edges, direction),
+                            "({x}, {y}) in direction {direction} on {size_x}x{size_y} {edges:?}"
+                        );
                     }
                 }
+            }
+        }
+    }
+
+    #[test]
+    fn occupied_neighbors_at_edges() {
+        for edges in [Edges::Wrap, Edges::Bounded] {
+            let mut simulation = Simulation::builder(4, 3).edges(edges).build();
+            for x in 0..4 {
+                for y in 0..3 {
+                    simulation.set_wall(x, y, true);
+                }
+            }
+            for x in 0..4 {
+                for y in 0..3 {
+                    let edges_touched = usize::from(x == 0)
+                        + usize::from(x == 3)
+                        + usize::from(y == 0)
+                        + usize::from(y == 2);
+                    let expected = match edges {
+                        Edges::Wrap => 4,
+                        Edges::Bounded => 4 - edges_touched as u8,
+                    };
+                    assert_eq!(simulation.occupied_neighbors(x, y), expected, "({x}, {y})");
+                }
+            }
 
-                buffer_index += 1;
+            // a single occupied corner is next to cells across both edges only when they wrap
+            let mut simulation = Simulation::builder(4, 3).edges(edges).build();
+            assert!(simulation.generate_mold(0, 0));
+            let wraps = u8::from(edges == Edges::Wrap);
+            for x in 0..4 {
+                for y in 0..3 {
+                    let expected = match (x, y) {
+                        (1, 0) | (0, 1) => 1,
+                        (3, 0) | (0, 2) => wraps,
+                        _ => 0,
+                    };
+                    assert_eq!(simulation.occupied_neighbors(x, y), expected, "({x}, {y})");
+                }
             }
         }
     }
 
-    /// convert a pixel location of the screen buffer to grid coordinates
-    pub fn pixel_to_grid_coords(
-        &self,
-        x: usize,
-        y: usize,
-        camera_offset: (usize, usize),
-        zoom: usize,
-    ) -> (usize, usize) {
-        (
-            ((x + camera_offset.0) / zoom) % self.size_x,
-            ((y + camera_offset.1) / zoom) % self.size_y,
-        )
+    #[test]
+    fn occupied_neighbors_on_narrow_grid() {
+        // a cell is its own left and right neighbor on a grid 1 cell wide that wraps
+        let mut simulation = Simulation::builder(1, 3).build();
+        assert!(simulation.generate_mold(0, 1));
+        assert_eq!(simulation.occupied_neighbors(0, 1), 2);
+        assert_eq!(simulation.occupied_neighbors(0, 0), 1);
+        let mut simulation = Simulation::builder(1, 3).edges(Edges::Bounded).build();
+        assert!(simulation.generate_mold(0, 1));
+        assert_eq!(simulation.occupied_neighbors(0, 1), 0);
+        assert_eq!(simulation.occupied_neighbors(0, 2), 1);
+    }
+
+    #[test]
+    fn energy_goes_to_neighbors_on_small_grids() {
+        for (size_x, size_y, edges) in small_grids() {
+            let cells = (0..size_x).flat_map(|x| (0..size_y).map(move |y| (x, y)));
+            for mold in cells.clone() {
+                for cell in cells.clone().filter(|&cell| cell != mold) {
+                    let mut simulation = Simulation::builder(size_x, size_y)
+                        .edges(edges)
+                        .seed(1)
+                        .build();
+                    assert!(simulation.generate_mold(mold.0, mold.1));
+                    let energy = simulation.mold_energy_at(mold.0, mold.1).unwrap();
+                    simulation.distribute_energy(cell.0, cell.1);
+                    let is_neighbor = (0..4).any(|direction| {
+                        expected_neighbor(cell, (size_x, size_y), edges, direction) == Some(mold)
+                    });
+                    let light = if is_neighbor {
+                        simulation.light_at(cell.0, cell.1)
+                    } else {
+                        0
+                    };
+                    assert_eq!(
+                        simulation.mold_energy_at(mold.0, mold.1),
+                        Some(energy + light),
+                        "{cell:?} next to {mold:?} on {size_x}x{size_y} {edges:?}"
+                    );
+                    assert_eq!(simulation.energy_ledger().light, light as i64);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn added_energy_is_booked() {
+        let mut simulation = Simulation::builder(20, 20).seed(1).build();
+        assert!(simulation.generate_mold(10, 10));
+        assert!(simulation.add_energy(10, 10, 1000));
+        simulation.update();
+        assert_eq!(
+            simulation.energy_ledger().balance(),
+            simulation.stats().total_energy
+        );
+    }
+
+    #[test]
+    fn saturated_energy_is_booked_as_added() {
+        let mut simulation = Simulation::builder(20, 20).seed(1).build();
+        assert!(simulation.generate_mold(10, 10));
+        assert!(simulation.add_energy(10, 10, 1000));
+        assert!(simulation.add_energy(10, 10, i32::MAX));
+        assert_eq!(simulation.mold_energy_at(10, 10), Some(i32::MAX));
+        assert_eq!(simulation.energy_ledger().spawned, i32::MAX as i64);
+        assert!(!simulation.add_energy(0, 0, 1000));
+    }
+
+    /// Energy and age of the predator at (x, y).
+    fn predator_at(simulation: &Simulation, x: usize, y: usize) -> Option<(i32, u32)> {
+        match simulation.grid[x][y] {
+            Cell::Predator { energy, age } => Some((energy, age)),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn predator_drains_neighbors_and_pays_upkeep() {
+        let mut simulation = Simulation::builder(5, 5).edges(Edges::Bounded).build();
+        assert!(simulation.spawn_predator(2, 2));
+        // a mold with plenty of energy, one with less than a drain and one with none
+        for (x, y, energy) in [(1, 2, 100), (3, 2, 5), (2, 1, 0)] {
+            assert!(simulation.spawn_mold(x, y, inert_genome(0x808080)));
+            if energy > 0 {
+                assert!(simulation.add_energy(x, y, energy));
+            }
+        }
+        let upkeep = simulation.energy_ledger().upkeep;
+        simulation.feed_predator(2, 2);
+        assert_eq!(simulation.mold_energy_at(1, 2), Some(100 - PREDATOR_DRAIN));
+        assert_eq!(simulation.mold_energy_at(3, 2), Some(0));
+        assert_eq!(simulation.mold_energy_at(2, 1), Some(0));
+        assert_eq!(
+            predator_at(&simulation, 2, 2),
+            Some((
+                PREDATOR_START_ENERGY + PREDATOR_DRAIN + 5 - PREDATOR_UPKEEP,
+                1
+            ))
+        );
+        assert_eq!(
+            simulation.energy_ledger().upkeep,
+            upkeep + PREDATOR_UPKEEP as i64
+        );
+        assert_eq!(
+            simulation.energy_ledger().balance(),
+            simulation.stats().total_energy
+        );
+
+        // without molds around, a predator only pays its upkeep
+        assert!(simulation.spawn_predator(0, 4));
+        simulation.feed_predator(0, 4);
+        assert_eq!(
+            predator_at(&simulation, 0, 4),
+            Some((PREDATOR_START_ENERGY - PREDATOR_UPKEEP, 1))
+        );
+    }
+
+    #[test]
+    fn predator_splits_into_empty_neighbor() {
+        let mut simulation = Simulation::builder(5, 5).edges(Edges::Bounded).build();
+        // the only empty neighbor is below the predator
+        for (x, y) in [(2, 1), (1, 2), (3, 2)] {
+            simulation.set_wall(x, y, true);
+        }
+        assert!(simulation.spawn_predator(2, 2));
+        let energy = PREDATOR_BIRTH_ENERGY + 101;
+        if let Cell::Predator {
+            energy: current, ..
+        } = &mut simulation.grid[2][2]
+        {
+            *current = energy;
+        }
+        simulation.energy_ledger.spawned += (energy - PREDATOR_START_ENERGY) as i64;
+        simulation.update();
+        let left = energy - PREDATOR_UPKEEP;
+        assert_eq!(predator_at(&simulation, 2, 2), Some((left - left / 2, 1)));
+        assert_eq!(predator_at(&simulation, 2, 3), Some((left / 2, 0)));
+        assert_eq!(
+            simulation.energy_ledger().balance(),
+            simulation.stats().total_energy
+        );
+    }
+
+    #[test]
+    fn starved_predator_dies() {
+        let mut simulation = Simulation::builder(5, 5).build();
+        assert!(simulation.spawn_predator(2, 2));
+        let lifetime = PREDATOR_START_ENERGY / PREDATOR_UPKEEP;
+        for tick in 1..=lifetime {
+            simulation.update();
+            let expected =
+                (tick < lifetime).then_some(PREDATOR_START_ENERGY - tick * PREDATOR_UPKEEP);
+            assert_eq!(
+                predator_at(&simulation, 2, 2).map(|(energy, _)| energy),
+                expected,
+                "tick {tick}"
+            );
+            assert_eq!(
+                simulation.energy_ledger().balance(),
+                simulation.stats().total_energy,
+                "tick {tick}"
+            );
+        }
+        assert!(matches!(simulation.grid[2][2], Cell::Empty));
+    }
+
+    #[test]
+    fn reset_light_gives_full_light() {
+        let mut simulation = Simulation::builder(6, 4).build();
+        simulation.set_light(0, 0, 0);
+        simulation.set_light(5, 3, 100);
+        let revision = simulation.revision;
+        simulation.reset_light();
+        for x in 0..6 {
+            for y in 0..4 {
+                assert_eq!(simulation.light_intensity(x, y), FULL_LIGHT);
+            }
+        }
+        // every cell has to be drawn again for the light overlay
+        assert!(simulation.revision > revision);
+        assert!(simulation
+            .changed_at
+            .iter()
+            .all(|&changed| changed == simulation.revision));
+    }
+
+    /// Hash of the kind, mold and age of every cell.
+    fn grid_fingerprint(simulation: &Simulation) -> u64 {
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for cell in simulation.grid.iter().flatten() {
+            let value = match cell {
+                Cell::Empty => 1,
+                Cell::Wall => 2,
+                Cell::MoldPart { mold, age, .. } => 3 + mold.id.0 * 7 + *age as u64 * 1000003,
+                Cell::Spore { mold, age, .. } => 5 + mold.id.0 * 11 + *age as u64 * 1000033,
+                Cell::Predator { energy, .. } => 9 + *energy as u64,
+            };
+            hash = (hash ^ value).wrapping_mul(0x100000001b3);
+        }
+        hash
+    }
+
+    #[test]
+    fn same_seed_gives_same_run() {
+        let run = |seed| {
+            let mut simulation = Simulation::builder(100, 60).seed(seed).build();
+            simulation.generate_random_molds(30);
+            simulation.run(500);
+            simulation
+        };
+        let (first, second) = (run(5), run(5));
+        assert!(first.stats().molds > 0);
+        assert_eq!(first.stats(), second.stats());
+        assert_eq!(first.rng_state(), second.rng_state());
+        assert_eq!(grid_fingerprint(&first), grid_fingerprint(&second));
+        assert_ne!(grid_fingerprint(&run(6)), grid_fingerprint(&first));
+    }
+
+    #[test]
+    fn impact_empties_disk_and_scorches_light() {
+        for edges in [Edges::Wrap, Edges::Bounded] {
+            let mut simulation = Simulation::builder(9, 9).edges(edges).build();
+            for x in 0..9 {
+                for y in 0..9 {
+                    simulation.set_wall(x, y, true);
+                }
+            }
+            simulation.set_wall(1, 1, false);
+            simulation.set_wall(5, 5, false);
+            assert!(simulation.generate_mold(1, 1));
+            assert!(simulation.generate_mold(5, 5));
+            simulation.impact(0, 0, 2);
+
+            let inside = |x: isize, y: isize| x * x + y * y <= 6;
+            for x in 0..9 {
+                for y in 0..9 {
+                    // offset from the center, the shorter way around when the edges wrap
+                    let offset = |position: usize| match edges {
+                        Edges::Wrap if position > 4 => position as isize - 9,
+                        _ => position as isize,
+                    };
+                    let empty = matches!(simulation.grid[x][y], Cell::Empty);
+                    let hit = inside(offset(x), offset(y));
+                    assert_eq!(empty, hit, "{edges:?} ({x}, {y})");
+                    if !hit {
+                        assert_eq!(simulation.light_intensity(x, y), FULL_LIGHT);
+                    }
+                }
+            }
+            assert_eq!(simulation.stats().molds, 1);
+            assert!(simulation.mold_at(5, 5).is_some());
+            // a cell keeps its distance over radius + 1 of its light
+            let light = |x, y| simulation.light_intensity(x, y);
+            assert_eq!(
+                [light(0, 0), light(1, 0), light(1, 1), light(2, 0)],
+                [0, 85, 120, 170]
+            );
+            let wrapped = [light(8, 0), light(0, 7), light(7, 8)];
+            match edges {
+                Edges::Wrap => assert_eq!(wrapped, [85, 170, 190]),
+                Edges::Bounded => assert_eq!(wrapped, [FULL_LIGHT; 3]),
+            }
+        }
+    }
+
+    #[test]
+    fn growth_cost_is_paid_and_booked() {
+        let straight = |simulation: &mut Simulation, x: usize, energy: i32| {
+            let mut genes = [-2; GENOME_SIZE * GENE_DIRECTIONS];
+            genes[1] = 0;
+            let genome = Genome::from_genes(&genes, 0x808080).unwrap();
+            assert!(simulation.spawn_mold(x, 0, genome));
+            assert!(simulation.add_energy(x, 0, energy));
+        };
+        let mut simulation = Simulation::builder(4, 12)
+            .edges(Edges::Bounded)
+            .energy_light(0)
+            .growth_cost(20)
+            .build();
+        // has 19 left after the upkeep of the first tick, too little to grow
+        straight(&mut simulation, 0, ENERGY_LOSS + 19);
+        straight(&mut simulation, 2, 1_000_000);
+        simulation.run(1);
+        assert_eq!(simulation.energy_ledger().growth, 20);
+        assert_eq!(
+            simulation.mold_energy_at(2, 1),
+            Some(1_000_000 - ENERGY_LOSS - 20)
+        );
+        simulation.run(3);
+
+        assert_eq!(simulation.growth_by_direction(), [4, 0, 0, 0]);
+        assert_eq!(simulation.energy_ledger().growth, 80);
+        assert_eq!(
+            simulation.energy_ledger().balance(),
+            simulation.stats().total_energy
+        );
+        assert!(simulation.mold_at(0, 0).is_some());
+        assert!(simulation.mold_at(0, 1).is_none());
+        assert!(simulation.mold_at(2, 4).is_some());
+
+        simulation.set_growth_cost(-5);
+        assert_eq!(simulation.growth_cost(), 0);
+    }
+
+    #[test]
+    fn growing_alleles_are_expressed_over_no_growth() {
+        let mut first = [-2; GENOME_SIZE * GENE_DIRECTIONS];
+        let mut second = [-2; GENOME_SIZE * GENE_DIRECTIONS];
+        first[..4].copy_from_slice(&[4, -2, -2, 7]);
+        second[..4].copy_from_slice(&[9, 6, -1, -2]);
+        let expressed = express(&[first, second]);
+        assert_eq!(expressed[..4], [4, 6, -1, 7]);
+        assert!(expressed[4..].iter().all(|&gene| gene == -2));
+    }
+
+    #[test]
+    fn gametes_cross_over_once() {
+        let mut rng = fastrand::Rng::with_seed(2);
+        let haploid = Genome::new(&mut rng);
+        assert_eq!(haploid.gamete(&mut rng), haploid.genes);
+
+        let diploid = Genome::from_alleles(
+            [
+                [0; GENOME_SIZE * GENE_DIRECTIONS],
+                [1; GENOME_SIZE * GENE_DIRECTIONS],
+            ],
+            0x808080,
+        );
+        let mut starts = [false; 2];
+        for _ in 0..100 {
+            let genes = diploid.gamete(&mut rng);
+            starts[genes[0] as usize] = true;
+            let switches: Vec<usize> = (1..genes.len())
+                .filter(|&index| genes[index] != genes[index - 1])
+                .collect();
+            assert!(switches.len() <= 1, "{switches:?}");
+            // the crossover falls between two genes
+            assert!(switches
+                .iter()
+                .all(|&index| index.is_multiple_of(GENE_DIRECTIONS)));
+        }
+        // either set can come first
+        assert_eq!(starts, [true, true]);
+    }
+
+    #[test]
+    fn spores_mate_with_a_neighbor_or_themselves() {
+        let genome = |value: isize, color: u32| {
+            Genome::from_alleles([[value; GENOME_SIZE * GENE_DIRECTIONS]; 2], color)
+        };
+        let mold_at = |simulation: &Simulation, x: usize, y: usize| match &simulation.grid[x][y] {
+            Cell::MoldPart { mold, .. } => mold.clone(),
+            _ => panic!("no mold at ({x}, {y})"),
+        };
+        let mut simulation = Simulation::builder(5, 5)
+            .edges(Edges::Bounded)
+            .ploidy(Ploidy::Diploid)
+            .build();
+        assert!(simulation.spawn_mold(1, 1, genome(0, 0x102030)));
+        let parent = mold_at(&simulation, 1, 1);
+        let selfed = simulation.cross(1, 1, &parent);
+        assert_eq!(selfed.alleles(), parent.genome.alleles());
+
+        assert!(simulation.spawn_mold(2, 1, genome(1, 0x405060)));
+        let crossed = simulation.cross(1, 1, &parent);
+        let (first, second) = crossed.alleles().unwrap();
+        assert!(first.iter().all(|&gene| gene == 0));
+        assert!(second.iter().all(|&gene| gene == 1));
+        // the offspring looks like the parent the spore came from
+        assert_eq!(crossed.color(), 0x102030);
+    }
+
+    #[test]
+    fn energy_policy_decides_who_gets_contested_light() {
+        // the empty cell in the middle neighbors a mold on either side
+        let contest = |energy_policy: EnergyPolicy, energies: [i32; 2]| {
+            let mut simulation = Simulation::builder(3, 1)
+                .edges(Edges::Bounded)
+                .energy_light(7)
+                .energy_policy(energy_policy)
+                .build();
+            assert_eq!(simulation.energy_policy(), energy_policy);
+            for (x, energy) in [0, 2].into_iter().zip(energies) {
+                assert!(simulation.spawn_mold(x, 0, inert_genome(0x808080)));
+                assert!(simulation.add_energy(x, 0, energy));
+            }
+            let light = simulation.energy_ledger().light;
+            simulation.distribute_energy(1, 0);
+            let gained = [
+                simulation.mold_energy_at(0, 0).unwrap() - energies[0],
+                simulation.mold_energy_at(2, 0).unwrap() - energies[1],
+            ];
+            (gained, simulation.energy_ledger().light - light)
+        };
+        assert_eq!(contest(EnergyPolicy::Exclusive, [50, 100]), ([0, 0], 0));
+        // the remainder of the division is lost
+        assert_eq!(contest(EnergyPolicy::Split, [50, 100]), ([3, 3], 6));
+        assert_eq!(contest(EnergyPolicy::Neediest, [50, 100]), ([7, 0], 7));
+        assert_eq!(contest(EnergyPolicy::Neediest, [100, 50]), ([0, 7], 7));
+        // a tie goes to the neighbor found first, the one to the right
+        assert_eq!(contest(EnergyPolicy::Neediest, [80, 80]), ([0, 7], 7));
+    }
+
+    #[test]
+    fn population_limit_stops_growth_until_cells_go() {
+        let mut simulation = Simulation::builder(20, 20)
+            .edges(Edges::Bounded)
+            .max_population(30)
+            .build();
+        assert_eq!(simulation.max_population(), Some(30));
+        // grows in every direction and never makes spores
+        let genome = Genome::from_genes(&[0; GENOME_SIZE * GENE_DIRECTIONS], 0x808080).unwrap();
+        assert!(simulation.spawn_mold(10, 10, genome));
+        assert!(simulation.add_energy(10, 10, 100_000_000));
+        let mut reached = None;
+        for tick in 0..200 {
+            simulation.update();
+            let cells = simulation.stats().mold_cells;
+            assert!(cells <= 30, "tick {tick}: {cells} cells");
+            if cells == 30 {
+                reached.get_or_insert(tick);
+            }
+        }
+        // the limit was reached early and held from then on
+        assert!(reached.is_some_and(|tick| tick < 20), "{reached:?}");
+        assert_eq!(simulation.stats().mold_cells, 30);
+
+        // freed cells are grown again, up to the limit
+        simulation.clear_region(Rect {
+            x: 0,
+            y: 0,
+            width: 20,
+            height: 10,
+        });
+        let cleared = simulation.stats().mold_cells;
+        assert!(cleared < 30);
+        simulation.run(20);
+        assert_eq!(simulation.stats().mold_cells, 30);
+
+        simulation.set_max_population(None);
+        simulation.run(5);
+        assert!(simulation.stats().mold_cells > 30);
+    }
+
+    #[test]
+    fn patterns_place_molds_by_character() {
+        let simulation = Simulation::from_pattern("ab a\n\n  b\tcab", 10);
+        assert_eq!(simulation.size(), (7, 3));
+        let genome = |x: usize, y: usize| simulation.mold_at(x, y).unwrap().genome;
+        let code = |x: usize, y: usize| (genome(x, y).genes().to_vec(), genome(x, y).color());
+        // equal characters share their genes and color, but not their mold
+        assert_eq!(code(0, 0), code(3, 0));
+        assert_eq!(code(0, 0), code(5, 2));
+        assert_eq!(code(1, 0), code(2, 2));
+        assert_ne!(
+            simulation.mold_at(0, 0).unwrap().id,
+            simulation.mold_at(3, 0).unwrap().id
+        );
+        for (a, b) in [((0, 0), (1, 0)), ((0, 0), (4, 2)), ((1, 0), (4, 2))] {
+            assert_ne!(
+                genome(a.0, a.1).genes(),
+                genome(b.0, b.1).genes(),
+                "{a:?} {b:?}"
+            );
+            assert_ne!(
+                genome(a.0, a.1).color(),
+                genome(b.0, b.1).color(),
+                "{a:?} {b:?}"
+            );
+        }
+        // spaces, tabs, the empty line and what lies past the end of a line stay empty
+        for (x, y) in [(2, 0), (4, 0), (6, 0), (0, 2), (1, 2), (3, 2)]
+            .into_iter()
+            .chain((0..7).map(|x| (x, 1)))
+        {
+            assert!(matches!(simulation.grid[x][y], Cell::Empty), "({x}, {y})");
+        }
+        assert_eq!(simulation.stats().molds, 7);
+
+        assert_eq!(Simulation::from_pattern("", 10).size(), (1, 1));
+        assert_eq!(Simulation::from_pattern("", 10).stats().molds, 0);
+    }
+
+    #[test]
+    fn old_cells_turn_into_spores_or_die() {
+        let mut simulation = Simulation::builder(4, 1)
+            .edges(Edges::Bounded)
+            .energy_light(0)
+            .max_age(2)
+            .build();
+        assert_eq!(simulation.max_age(), Some(2));
+        // grows straight ahead, but the edge is in the way
+        let mut genes = [-2; GENOME_SIZE * GENE_DIRECTIONS];
+        genes[1] = 0;
+        let straight = Genome::from_genes(&genes, 0x808080).unwrap();
+        assert!(simulation.spawn_mold(0, 0, straight));
+        assert!(simulation.spawn_mold(2, 0, inert_genome(0x808080)));
+        for x in [0, 2] {
+            assert!(simulation.add_energy(x, 0, 1000));
+        }
+        let inert = simulation.mold_at(2, 0).unwrap().id;
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let collected = events.clone();
+        simulation.subscribe(Box::new(move |event| collected.borrow_mut().push(*event)));
+
+        simulation.update();
+        // a younger second cell of the inert mold
+        let Cell::MoldPart { mold, .. } = simulation.grid[2][0].clone() else {
+            unreachable!();
+        };
+        let cell = Cell::MoldPart {
+            mold,
+            age: 0,
+            active_gene: 0,
+            direction: 0,
+        };
+        simulation.set_cell(3, 0, cell);
+        simulation.update();
+        assert!(simulation
+            .mold_at(0, 0)
+            .is_some_and(|info| info.active_gene.is_some()));
+        assert!(simulation.mold_at(2, 0).is_some());
+
+        simulation.update();
+        assert!(simulation.is_ripe_spore(0, 0));
+        assert!(matches!(simulation.grid[2][0], Cell::Empty));
+        assert!(simulation.mold_at(3, 0).is_some());
+        assert!(events.borrow().is_empty());
+
+        simulation.update();
+        assert!(matches!(simulation.grid[3][0], Cell::Empty));
+        assert_eq!(
+            *events.borrow(),
+            [Event::Died {
+                x: 3,
+                y: 0,
+                mold: inert
+            }]
+        );
+        assert_eq!(
+            simulation.energy_ledger().balance(),
+            simulation.stats().total_energy
+        );
+    }
+
+    #[test]
+    fn blooms_keep_their_species_unless_mutated() {
+        // species of the molds sprouted from ripe spores at (0, 0) and (2, 0) of the same mold
+        let bloom = |mutation_odds: u32| {
+            let mut simulation = Simulation::builder(3, 1)
+                .edges(Edges::Bounded)
+                .energy_light(0)
+                .mutation_odds(mutation_odds)
+                .seed(1)
+                .build();
+            assert!(simulation.spawn_mold(0, 0, inert_genome(0x808080)));
+            let parent = simulation.species_id_at(0, 0).unwrap();
+            let Cell::MoldPart { mold, .. } = simulation.grid[0][0].clone() else {
+                unreachable!();
+            };
+            for x in [0, 2] {
+                let spore = Cell::Spore {
+                    mold: mold.clone(),
+                    age: SPORE_RIPING_AGE,
+                    direction: 0,
+                };
+                simulation.set_cell(x, 0, spore);
+            }
+            simulation.update();
+            let sprouted = [0, 2].map(|x| simulation.species_id_at(x, 0).unwrap());
+            (parent, sprouted)
+        };
+        let (parent, sprouted) = bloom(u32::MAX);
+        assert_eq!(sprouted, [parent; 2]);
+        // every mutated offspring founds a species of its own
+        let (parent, [first, second]) = bloom(1);
+        assert_ne!(first, parent);
+        assert_ne!(second, parent);
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn exported_genomes_import_into_another_simulation() {
+        let mut source = Simulation::builder(30, 20).seed(3).build();
+        source.generate_random_molds(12);
+        source.run(50);
+        let exported = source.export_genomes();
+        assert!(exported.len() > 1);
+        let codes = |genomes: &[Genome]| {
+            let mut codes: Vec<(Vec<isize>, u32)> = genomes
+                .iter()
+                .map(|genome| (genome.genes().to_vec(), genome.color()))
+                .collect();
+            codes.sort();
+            codes
+        };
+
+        let mut target = Simulation::builder(30, 20).seed(4).build();
+        let created = target.import_genomes(exported.clone());
+        assert_eq!(created, exported.len());
+        assert_eq!(target.stats().mold_cells, created);
+        assert_eq!(codes(&target.export_genomes()), codes(&exported));
+
+        // only as many as there are empty cells
+        let mut full = Simulation::new(1, 1, 10);
+        assert_eq!(full.import_genomes(exported), 1);
+        assert_eq!(full.stats().mold_cells, 1);
+    }
+
+    #[test]
+    fn barren_cells_give_no_light() {
+        let mut simulation = Simulation::builder(2, 1)
+            .edges(Edges::Bounded)
+            .energy_light(10)
+            .build();
+        assert!(simulation.spawn_mold(0, 0, inert_genome(0x808080)));
+        simulation.set_fertility(1, 0, false);
+        assert!(!simulation.is_fertile(1, 0));
+        simulation.update_energy();
+        assert_eq!(simulation.mold_energy_at(0, 0), Some(-ENERGY_LOSS));
+        assert_eq!(simulation.energy_ledger().light, 0);
+
+        simulation.set_fertility(1, 0, true);
+        simulation.update_energy();
+        assert_eq!(simulation.mold_energy_at(0, 0), Some(10 - 2 * ENERGY_LOSS));
+        assert_eq!(simulation.energy_ledger().light, 10);
     }
 }
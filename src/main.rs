@@ -2,160 +2,2111 @@ use std::time::{Duration, Instant};
 
 use minifb::{Key, MouseMode, Window, WindowOptions};
 
+mod brush;
+mod capture;
+mod cli;
+mod config;
+mod graph;
+mod hud;
+mod interrupt;
+mod layout;
+mod pane;
+#[cfg(feature = "recording")]
+mod recording;
+mod reseed;
+mod saves;
+mod stats_log;
+mod timelapse;
+mod timestep;
+mod view;
+
+use brush::{Stroke, Tool};
+use cli::{Command, Options};
+use config::{Settings, WindowSettings};
+use layout::Layout;
+use pane::{Follow, Origin, Pane};
+use rustymold::{
+    screen_to_buffer, BufferRegion, Camera, Edges, Genome, MoldId, MoldInfo, Rect, Simulation,
+    SporeStyle, Stamp, Stats, FULL_LIGHT, GENE_DIRECTIONS,
+};
+use saves::Pick;
+use stats_log::StatsLog;
+use timestep::{FixedTimestep, SlowMotion, Speed};
+use view::ViewState;
+
 const GRID_X: usize = 630;
 const GRID_Y: usize = 330;
 
 const DEFAULT_ENERGY_LIGHT: i32 = 16;
+/// number of molds created at once with the G key or at the start of a headless run
+const GENERATED_MOLDS: usize = 300;
+/// time between two progress messages of a headless run
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(5);
+/// number of ticks between color changes of blinking spores
+const SPORE_BLINK_PERIOD: u64 = 15;
+/// color of all ripe spores with the fixed spore style
+const FIXED_SPORE_COLOR: u32 = 0xFFFF00;
+/// background color used instead of the default black one when the J key is pressed
+const LIGHT_BACKGROUND: u32 = 0xE0E0E0;
 
 // window defaults
 const TARGET_FPS: u64 = 60;
-const BUFFER_X: usize = GRID_X; // initial size of screen buffer - half the size of the window in pixels
+/// simulation updates per second of wall clock time, independent of the frame rate
+const TICKS_PER_SECOND: u32 = 60;
+/// highest rate reached by doubling from 1 with the +/- keys
+const MAX_TICKS_PER_SECOND: u32 = 3840;
+/// ticks waited after all molds died before new ones are scattered with autoreseed
+const RESEED_DELAY: u64 = 300;
+/// most updates done in a single frame, so a frame that took long doesn't make the next ones
+/// try to catch up
+const MAX_STEPS_PER_FRAME: usize = 256;
+/// part of the time of a frame that may be spent updating the simulation, the rest is left for
+/// rendering so the window stays responsive
+const UPDATE_TIME_SHARE: f64 = 0.75;
+const BUFFER_X: usize = GRID_X; // largest initial size of screen buffer - half the size of the window in pixels
 const BUFFER_Y: usize = GRID_Y;
-const ZOOM: usize = 1;
-const MIN_ZOOM: usize = 1;
-const MAX_ZOOM: usize = 16;
+const ZOOM: f32 = 1.;
+const MIN_ZOOM: f32 = 1.;
+const MAX_ZOOM: f32 = 16.;
+/// energy given to a mold Alt+clicked
+const FEED_ENERGY: i32 = 1000;
+/// factor the zoom changes by for every notch the mouse wheel is scrolled
+const ZOOM_STEP: f32 = 1.1;
+/// rarest mutations reached by halving their chance with the [ key, as one in this many
+const MAX_MUTATION_ODDS: u32 = 1 << 16;
+/// the minimap is this many times smaller than the grid
+const MINIMAP_SCALE: usize = 4;
+/// distance in pixels between the minimap and the edges of the window
+const MINIMAP_MARGIN: usize = 4;
+/// the minimap is shrunk to cover at most this part of the width and height of a pane, and
+/// hidden when that makes it smaller than MIN_MINIMAP_SIZE pixels on either side
+const MAX_MINIMAP_SHARE: f32 = 0.5;
+const MIN_MINIMAP_SIZE: usize = 24;
+/// time between renders of the cells of the minimap, which take a pass over the whole grid
+const MINIMAP_REFRESH: Duration = Duration::from_millis(250);
+/// width in pixels of the population graph left of the minimap, one column per tick
+const SPARKLINE_WIDTH: usize = 120;
+/// keys for running 1, 2, 4 and so on up to 256 updates every frame, or with Shift one update
+/// every 1, 2, 4 and so on up to 256 frames
+const SPEED_KEYS: [Key; 9] = [
+    Key::Key1,
+    Key::Key2,
+    Key::Key3,
+    Key::Key4,
+    Key::Key5,
+    Key::Key6,
+    Key::Key7,
+    Key::Key8,
+    Key::Key9,
+];
+/// updates run between checks for input in turbo mode
+const TURBO_POLL_STEPS: usize = 16;
+/// time between rendered frames in turbo mode
+const TURBO_RENDER_PERIOD: Duration = Duration::from_secs(1);
+/// how long messages about saving and loading stay in the window title
+const STATUS_DURATION: Duration = Duration::from_secs(5);
+/// distance in pixels between the HUD text and the top left corner of the window
+const HUD_MARGIN: isize = 3;
+const HUD_COLOR: u32 = 0xFFFFFF;
+const HUD_SHADOW_COLOR: u32 = 0x000000;
+/// color of a setting in the HUD that was just changed with a key, and for how long
+const HUD_HIGHLIGHT_COLOR: u32 = 0xFFFF00;
+const KNOB_HIGHLIGHT_DURATION: Duration = Duration::from_millis(1500);
+/// how often the population shown in the HUD is counted again
+const HUD_STATS_PERIOD: Duration = Duration::from_millis(250);
+/// while recording, every this many frames one is added to the recording
+#[cfg(feature = "recording")]
+const RECORD_EVERY: u64 = 2;
+/// number of pixels along each side of the squares averaged into one pixel of a recording
+#[cfg(feature = "recording")]
+const RECORD_DOWNSCALE: usize = 1;
+/// color and size in pixels of the square shown in the top left corner while recording
+#[cfg(feature = "recording")]
+const RECORD_INDICATOR: (u32, usize) = (0xFF0000, 4);
+/// color of the divider between the two simulations while comparing
+const DIVIDER_COLOR: u32 = 0x808080;
+/// color of the lines between cells when they are shown
+const GRID_LINE_COLOR: u32 = 0x404040;
+/// speed in pixels per second of panning with the arrow keys, and how much faster it is with Shift
+const KEY_PAN_SPEED: f32 = 600.;
+const KEY_PAN_FAST: f32 = 4.;
+/// time in seconds in which a following camera covers about two thirds of the way to the mold
+const FOLLOW_EASING: f32 = 0.3;
+/// starting and largest radius in cells of the disk destroyed by a meteor
+const METEOR_RADIUS: usize = 12;
+const MAX_METEOR_RADIUS: usize = 64;
+/// color of the flash where a meteor struck, and for how many rendered frames it is shown
+const METEOR_FLASH_COLOR: u32 = 0xFFFFFF;
+const METEOR_FLASH_FRAMES: u32 = 3;
 
 fn main() {
-    // fastrand::seed(4);
+    let command = cli::parse(std::env::args().skip(1)).unwrap_or_else(|message| {
+        eprintln!("{message}\n\n{}", cli::USAGE);
+        std::process::exit(2);
+    });
+    let (Command::Window(options) | Command::Run(options)) = &command;
+    let (settings, warnings) = config::load(&options.config);
+    for warning in warnings {
+        eprintln!("warning: {warning}");
+    }
+    match command {
+        Command::Window(options) => run_window(options, settings),
+        Command::Run(options) => run_headless(options, settings),
+    }
+}
 
-    // create simulation instance
-    let mut simulation = rustymold::Simulation::new(GRID_X, GRID_Y, DEFAULT_ENERGY_LIGHT);
+/// Create the simulation the options describe: loaded from a save, or a new grid with the
+/// simulation settings and molds at the number of random positions given by the density or by
+/// `default_molds`. Returns it with the camera of the save and the seed, which is None when a
+/// loaded simulation keeps the random state it was saved with.
+fn start_simulation(
+    options: &Options,
+    settings: &Settings,
+    default_molds: usize,
+) -> Result<(Simulation, Option<Camera>, Option<u64>), String> {
+    let render = &settings.render;
+    if let Some(path) = &options.load {
+        let (mut simulation, camera) = saves::load_file(path)?;
+        simulation.set_palette(render.palette());
+        simulation.set_spore_style(render.spore_style());
+        simulation.set_spore_ripening(render.spore_ripening);
+        ViewState::new(render.view_mode).apply(&mut simulation);
+        if let Some(light) = options.light {
+            simulation.set_energy_light(light);
+        }
+        if let Some(seed) = options.seed {
+            simulation.seed(seed);
+        }
+        return Ok((simulation, Some(camera), options.seed));
+    }
 
-    // create window
-    let options = WindowOptions {
-        borderless: false,
-        title: true,
-        resize: true,
-        scale: minifb::Scale::X2, // x2 zoom level by default
-        scale_mode: minifb::ScaleMode::Stretch,
-        topmost: false,
-        transparency: false,
-        none: false,
-    };
-    let mut window = Window::new("rusty-mold", BUFFER_X, BUFFER_Y, options).unwrap_or_else(|e| {
-        panic!("{}", e);
+    let width = options.grid.0.unwrap_or(settings.simulation.width);
+    let height = options.grid.1.unwrap_or(settings.simulation.height);
+    let light = options.light.unwrap_or(settings.simulation.light);
+    let mut simulation = settings
+        .simulation
+        .builder(width, height)
+        .energy_light(light)
+        .build();
+    simulation.set_palette(render.palette());
+    simulation.set_spore_style(render.spore_style());
+    simulation.set_spore_ripening(render.spore_ripening);
+    ViewState::new(render.view_mode).apply(&mut simulation);
+    let seed = options.seed.unwrap_or_else(|| fastrand::u64(..));
+    simulation.seed(seed);
+    let molds = options.density.map_or(default_molds, |density| {
+        (density * (width * height) as f64).round() as usize
     });
+    simulation.generate_random_molds(molds);
+    Ok((simulation, None, Some(seed)))
+}
 
-    // Limit frame rate
-    window.limit_update_rate(Some(std::time::Duration::from_micros(
-        1_000_000 / TARGET_FPS,
-    )));
-    window.set_background_color(0, 0, 0);
+/// How a run can be reproduced: its seed, or the save it continues.
+fn describe_origin(options: &Options, seed: Option<u64>) -> String {
+    match (seed, &options.load) {
+        (Some(seed), _) => format!("seed {seed}"),
+        (None, Some(path)) => format!("the random state of {}", path.display()),
+        (None, None) => unreachable!("new simulations are always seeded"),
+    }
+}
+
+/// Run a simulation without opening a window, optionally writing its stats to a CSV file along
+/// the way and saving the final state as a PNG.
+fn run_headless(options: Options, settings: Settings) {
+    let (mut simulation, _, seed) = start_simulation(&options, &settings, GENERATED_MOLDS)
+        .unwrap_or_else(|message| {
+            eprintln!("{message}");
+            std::process::exit(1);
+        });
+    let mut stats_log = options.stats_out.as_ref().map(|path| {
+        StatsLog::create(path, options.stats_interval, &simulation).unwrap_or_else(|message| {
+            eprintln!("{message}");
+            std::process::exit(1);
+        })
+    });
+    if stats_log.is_some() {
+        interrupt::install();
+    }
+    let mut ran = 0;
+    let mut last_progress = Instant::now();
+    while ran < options.steps && !interrupt::is_interrupted() {
+        simulation.update();
+        ran += 1;
+        let captured = match &options.timelapse {
+            Some(timelapse) => timelapse.capture(&simulation),
+            None => Ok(()),
+        };
+        let logged = match &mut stats_log {
+            Some(log) => log.record(&simulation),
+            None => Ok(()),
+        };
+        if let Err(message) = captured.and(logged) {
+            eprintln!("{message}");
+            std::process::exit(1);
+        }
+        if last_progress.elapsed() >= PROGRESS_INTERVAL {
+            eprintln!("{ran} of {} steps", options.steps);
+            last_progress = Instant::now();
+        }
+    }
+    if let Some(Err(message)) = stats_log.map(|log| log.finish(&simulation, ran)) {
+        eprintln!("{message}");
+        std::process::exit(1);
+    }
+    let (width, height) = simulation.size();
+    println!(
+        "ran {ran} steps on a {width}x{height} grid with {}",
+        describe_origin(&options, seed)
+    );
+
+    if let Some(path) = options.out {
+        #[cfg(feature = "image")]
+        if let Err(e) = simulation.save_png(&path, rustymold::RenderOptions::default()) {
+            eprintln!("failed to write {}: {e}", path.display());
+            std::process::exit(1);
+        }
+        #[cfg(not(feature = "image"))]
+        {
+            eprintln!(
+                "cannot write {}: built without the image feature",
+                path.display()
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Open the interactive window and run the simulation until it is closed.
+fn run_window(start: Options, settings: Settings) {
+    // create simulation instance, the simulations are shown next to each other with a second one
+    // on the right while comparing, and keyboard input goes to the focused one
+    let (simulation, start_camera, seed) =
+        start_simulation(&start, &settings, 0).unwrap_or_else(|message| {
+            eprintln!("{message}");
+            std::process::exit(1);
+        });
+    println!("starting with {}", describe_origin(&start, seed));
+    let origin = Origin {
+        options: Options {
+            seed,
+            ..start.clone()
+        },
+        molds: 0,
+    };
+    let mut simulations = vec![simulation];
+    simulations[0].set_population_history(SPARKLINE_WIDTH);
+    let mut focus: usize = 0;
+
+    // create window, the size from the config file or one fitting the grid
+    let scale = settings.window.scale;
+    let grid_size = simulations[0].size();
+    let fitting_size = (
+        grid_size.0.min(BUFFER_X) * scale,
+        grid_size.1.min(BUFFER_Y) * scale,
+    );
+    let mut windowed = Geometry {
+        size: settings.window.size().unwrap_or(fitting_size),
+        position: None,
+    };
+    let mut is_fullscreen = false;
+    let mut window = open_window(&settings.window, windowed, is_fullscreen);
+    let mut buffer_size = buffer_size_of(&window, scale);
+    let target_fps = settings.window.target_fps;
+    let frame_time = Duration::from_micros(1_000_000 / target_fps);
 
     // current window state
-    let mut buffer: Vec<u32> = vec![0; BUFFER_X * BUFFER_Y];
-    let mut buffer_size: (usize, usize) = (BUFFER_X, BUFFER_Y);
-    let mut zoom = ZOOM;
-    // offset representing the amount of pixels that the simulation grid is panned
-    let mut camera_position: (f32, f32) = (0.0, 0.0);
+    // the rendered simulations next to each other, and the frame shown in the window with the
+    // minimap drawn over them
+    let mut buffer: Vec<u32> = vec![0; buffer_size.0 * buffer_size.1];
+    let mut frame: Vec<u32> = buffer.clone();
+    let mut minimap: Vec<u32> = Vec::new();
+    // the cells of the minimap without the viewport outline, with the pane, size and time they
+    // were rendered at, and where the minimap was drawn in the last frame
+    let mut minimap_cells: Vec<u32> = Vec::new();
+    let mut minimap_rendered: Option<(usize, (usize, usize), Instant)> = None;
+    let mut minimap_area: Option<MinimapArea> = None;
+    let mut show_minimap = true;
+    let mut sparkline: Vec<u32> = Vec::new();
+    // the view of the first pane, as saved if the simulation was loaded
+    let camera = match start_camera {
+        Some(camera) => Camera {
+            zoom: camera.zoom.clamp(MIN_ZOOM, MAX_ZOOM),
+            ..camera
+        },
+        None => Camera {
+            position: (0.0, 0.0),
+            zoom: ZOOM,
+        },
+    };
 
-    let mut is_mouse_right_down: bool = false;
-    let mut mouse_pan_start: (f32, f32) = camera_position;
+    let mut is_mouse_middle_down: bool = false;
+    let mut is_mouse_left_down: bool = false;
+    // whether the left mouse button was pressed on the minimap and is still down
+    let mut is_minimap_dragged = false;
+    // pane and position in it where the left mouse button was pressed, while dragging out an
+    // area to erase
+    let mut erase_start: Option<(usize, (f32, f32))> = None;
+    // pane being panned with the right mouse button and the position the pan started from
+    let mut pan_start: Option<(usize, (f32, f32))> = None;
+    let mut tool = Tool::View;
+    // rectangle selected by dragging with Shift and the left mouse button, and the cells copied
+    // from one with Ctrl+C
+    let mut selection: Option<Selection> = None;
+    // what is shown of every simulation and how, kept when a simulation is cleared or loaded
+    let mut panes = vec![Pane::new(
+        ViewState::new(settings.render.view_mode),
+        camera,
+        origin,
+    )];
+    let mut show_graph = false;
+    let mut autoreseed = settings.window.autoreseed;
+    // whether the grids are resized to fill their panes, and the size they are cropped to when
+    // Enter is pressed after the window shrank
+    let mut fit_grid = settings.window.fit_grid;
+    let mut pending_crop: Option<(usize, usize)> = None;
+    let mut clipboard: Option<Stamp> = None;
+    // genome picked up with the eyedropper and the mold it came from, stamped with the stamp tool
+    let mut picked: Option<(MoldId, Genome)> = None;
+    // the last watched mold that died, and when
+    let mut watch_ended: Option<(MoldId, Instant)> = None;
+    // the last followed mold that died, and when
+    let mut follow_ended: Option<(MoldId, Instant)> = None;
+    let mut brush_radius = brush::BRUSH_RADIUS;
+    // pane and unwrapped cell the brush was at in the previous frame, while painting
+    let mut brush_last: Option<(usize, (isize, isize))> = None;
+    let mut meteor_radius = METEOR_RADIUS;
+    // whether the meteor was sized since the X key was pressed, so releasing it drops none
+    let mut is_meteor_sized = false;
+    // center and radius in pixels of the last meteor strike, and the frames it is still shown
+    let mut meteor_flash: Option<((f32, f32), f32, u32)> = None;
 
     let mut last_frame_time = Instant::now();
-    let mut average_fps: f64 = TARGET_FPS as f64;
+    let mut last_render_time = Instant::now();
+    let mut average_fps: f64 = target_fps as f64;
+    let mut average_tps: f64 = settings.window.ticks_per_second as f64;
 
-    let mut is_running: bool = true;
+    let mut is_running: bool = !(start.paused || settings.window.paused);
+    // message shown in the window title until STATUS_DURATION after it was set
+    let mut status: Option<(String, Instant)> = None;
+    // whether the HUD is drawn, and the population it shows with when it was counted
+    let mut show_hud = true;
+    let mut hud_stats: Option<(Stats, Instant)> = None;
+    // setting last changed with a key and when, to highlight it in the HUD
+    let mut knob_changed: Option<(Knob, Instant)> = None;
+    // set when the S key is pressed, the screenshot is taken once the frame is complete
+    let mut screenshot_requested = false;
+    #[cfg(feature = "recording")]
+    let mut recording: Option<recording::Recording> = None;
+    #[cfg(feature = "recording")]
+    let mut frame_number: u64 = 0;
+    let mut timestep = FixedTimestep::new(
+        settings.window.ticks_per_second,
+        settings.window.max_steps_per_frame,
+    );
+    let mut speed = Speed::Paced;
+    let mut slow_motion = SlowMotion::default();
+    // set when N is pressed, a single update is run while paused
+    let mut is_step_requested = false;
+    let update_budget = Duration::from_secs_f64(UPDATE_TIME_SHARE / target_fps as f64);
 
     // main loop
-    while window.is_open() && !window.is_key_down(Key::Escape) {
-        // reshape frame buffer if the window was resized
-        let new_window_size = window.get_size();
-        let new_buffer_size = (new_window_size.0 / 2, new_window_size.1 / 2);
+    while window.is_open() {
+        // reshape frame buffer if the window was resized or opened again
+        if !is_fullscreen {
+            windowed = Geometry {
+                size: window.get_size(),
+                position: Some(window.get_position()),
+            };
+        }
+        // in turbo mode the window is polled for input between updates without waiting
+        let is_turbo = speed == Speed::Turbo && is_running;
+        window.limit_update_rate((!is_turbo).then_some(frame_time));
+        let new_buffer_size = buffer_size_of(&window, scale);
         if new_buffer_size != buffer_size {
             buffer_size = new_buffer_size;
             let new_buffer_length = buffer_size.0 * buffer_size.1;
             buffer.resize(new_buffer_length, 0)
         }
+        let layout = Layout {
+            buffer_size,
+            panes: simulations.len(),
+        };
+        // grow the grids with the window before anything is drawn, so grids and buffer agree in
+        // every frame, but only crop them once Enter confirms it, not to destroy a colony by
+        // accident
+        if fit_grid {
+            let pane_size = layout.pane_size();
+            let target = (pane_size.0.max(1), pane_size.1.max(1));
+            let mut is_resized = false;
+            for simulation in &mut simulations {
+                let size = simulation.size();
+                let grown = (size.0.max(target.0), size.1.max(target.1));
+                if grown != size {
+                    simulation.resize(grown.0, grown.1);
+                    is_resized = true;
+                }
+            }
+            let crop = simulations
+                .iter()
+                .any(|simulation| simulation.size() != target)
+                .then_some(target);
+            if crop.is_some() && crop != pending_crop {
+                let message = format!("press Enter to crop the grid to {}x{}", target.0, target.1);
+                status = Some((message, Instant::now()));
+            }
+            pending_crop = crop;
+            if pending_crop.is_some() && window.is_key_pressed(Key::Enter, minifb::KeyRepeat::No) {
+                for simulation in &mut simulations {
+                    simulation.resize(target.0, target.1);
+                }
+                pending_crop = None;
+                is_resized = true;
+                let message = format!("cropped the grid to {}x{}", target.0, target.1);
+                status = Some((message, Instant::now()));
+            }
+            if is_resized {
+                selection = None;
+                minimap_rendered = None;
+            }
+        }
 
         // handle keyboard/mouse input
-        // zoom when scroll wheel is used
+        // zoom when scroll wheel is used, or size the meteor while X is held, or size the brush
+        // while painting walls or light
         if let Some(scroll) = window.get_scroll_wheel() {
-            if let Some((x, y)) = window.get_mouse_pos(minifb::MouseMode::Discard) {
-                if scroll.1 < 0. && zoom > MIN_ZOOM {
-                    let zoom_ratio = 1. - 1. / zoom as f32;
-                    zoom -= 1;
-
-                    camera_position = (
-                        (camera_position.0 + x) * zoom_ratio - x,
-                        (camera_position.1 + y) * zoom_ratio - y,
-                    );
-                } else if scroll.1 > 0. && zoom < MAX_ZOOM {
-                    let zoom_ratio = 1. + 1. / zoom as f32;
-                    zoom += 1;
-
-                    camera_position = (
-                        (camera_position.0 + x) * zoom_ratio - x,
-                        (camera_position.1 + y) * zoom_ratio - y,
-                    );
+            if window.is_key_down(Key::X) {
+                if scroll.1 > 0. {
+                    meteor_radius = (meteor_radius + 1).min(MAX_METEOR_RADIUS);
+                } else if scroll.1 < 0. {
+                    meteor_radius = meteor_radius.saturating_sub(1);
+                }
+                is_meteor_sized = true;
+            } else if tool.is_brush() {
+                if scroll.1 > 0. {
+                    brush_radius = (brush_radius + 1).min(brush::MAX_BRUSH_RADIUS);
+                } else if scroll.1 < 0. {
+                    brush_radius = brush_radius.saturating_sub(1);
+                }
+            } else if let Some((pane, position)) =
+                cursor_in_pane(&window, layout, scale, MouseMode::Discard)
+            {
+                let camera = &mut panes[pane].camera;
+                let zoom = (camera.zoom * ZOOM_STEP.powf(scroll.1)).clamp(MIN_ZOOM, MAX_ZOOM);
+                if zoom != camera.zoom {
+                    camera.zoom_at(zoom, position, scale);
                 }
             }
         }
-        // pan while right mouse button is held
-        if window.get_mouse_down(minifb::MouseButton::Right) {
-            if let Some((x, y)) = window.get_mouse_pos(MouseMode::Pass) {
-                if is_mouse_right_down {
-                    camera_position = (mouse_pan_start.0 - x, mouse_pan_start.1 - y)
-                } else {
-                    mouse_pan_start = (camera_position.0 + x, camera_position.1 + y);
-                    is_mouse_right_down = true;
+        // center the view on the part of the grid clicked in the minimap, following the cursor
+        // until the left mouse button is released
+        let cursor = window
+            .get_unscaled_mouse_pos(MouseMode::Clamp)
+            .map(|screen| screen_to_buffer(screen, scale));
+        if window.get_mouse_down(minifb::MouseButton::Left) {
+            if !is_mouse_left_down {
+                is_minimap_dragged = minimap_area
+                    .zip(cursor)
+                    .is_some_and(|(area, cursor)| area.contains(cursor));
+            }
+        } else {
+            is_minimap_dragged = false;
+        }
+        if let (true, Some(area), Some(cursor)) = (is_minimap_dragged, minimap_area, cursor) {
+            let (x, y) = area.cell_at(cursor, simulations[area.pane].size());
+            panes[area.pane]
+                .camera
+                .recenter_on((x as f32 + 0.5, y as f32 + 0.5), layout.pane_size());
+        }
+        // paint walls with the left mouse button and erase everything with the right one while
+        // the wall tool is active, or raise and lower the light with the light tool, joining the
+        // cells under the cursor in consecutive frames
+        let stroke = match (
+            tool,
+            window.get_mouse_down(minifb::MouseButton::Left) && !is_minimap_dragged,
+            window.get_mouse_down(minifb::MouseButton::Right),
+        ) {
+            (Tool::Walls, true, _) => Some(Stroke::Wall),
+            (Tool::Walls, false, true) => Some(Stroke::Erase),
+            (Tool::Light, true, _) => Some(Stroke::Brighten),
+            (Tool::Light, false, true) => Some(Stroke::Darken),
+            _ => None,
+        };
+        let brush_cell = stroke.and(cursor_position(
+            &window,
+            layout,
+            scale,
+            &simulations,
+            &panes,
+        ));
+        match stroke.zip(brush_cell) {
+            Some((stroke, (pane, cell))) => {
+                let from = match brush_last {
+                    Some((last_pane, last)) if last_pane == pane => last,
+                    _ => cell,
+                };
+                for point in brush::line(from, cell) {
+                    brush::paint(&mut simulations[pane], point, brush_radius, stroke);
+                }
+                brush_last = Some((pane, cell));
+            }
+            None => brush_last = None,
+        }
+        // pan the pane the right mouse button was pressed on while it is held
+        if !tool.is_brush() && window.get_mouse_down(minifb::MouseButton::Right) {
+            if let Some(screen) = window.get_unscaled_mouse_pos(MouseMode::Pass) {
+                let (x, y) = screen_to_buffer(screen, scale);
+                match pan_start {
+                    Some((pane, start)) => {
+                        panes[pane].camera.position = (start.0 - x, start.1 - y);
+                        panes[pane].follow = None;
+                    }
+                    None => {
+                        pan_start = cursor_in_pane(&window, layout, scale, MouseMode::Discard).map(
+                            |(pane, _)| {
+                                let position = panes[pane].camera.position;
+                                (pane, (position.0 + x, position.1 + y))
+                            },
+                        );
+                    }
                 }
             }
         } else {
-            is_mouse_right_down = false;
+            pan_start = None;
+        }
+        // inspect the mold under the cursor when the middle mouse button is clicked, and highlight
+        // and watch it until another cell is clicked; clicking an empty cell stops watching
+        if window.get_mouse_down(minifb::MouseButton::Middle) {
+            if !is_mouse_middle_down {
+                if let Some((pane, (x, y))) =
+                    cursor_cell(&window, layout, scale, &simulations, &panes)
+                {
+                    let simulation = &mut simulations[pane];
+                    let info = simulation.mold_at(x, y);
+                    if let Some(info) = &info {
+                        println!("{}", inspection_report(info, x, y));
+                    }
+                    let id = info.map(|info| info.id);
+                    simulation.set_highlight(id);
+                    simulation.watch(id);
+                }
+            }
+            is_mouse_middle_down = true;
+        } else {
+            is_mouse_middle_down = false;
+        }
+        // pick up the genome of the mold clicked with the eyedropper and switch to stamping it,
+        // or drop the picked genome when clicking an empty cell; then create new molds with it
+        // on the empty cells clicked. Feed the mold clicked while Alt is held instead.
+        let is_feeding = tool == Tool::View
+            && (window.is_key_down(Key::LeftAlt) || window.is_key_down(Key::RightAlt));
+        if window.get_mouse_down(minifb::MouseButton::Left) {
+            if !is_mouse_left_down && !is_minimap_dragged {
+                let target = cursor_cell(&window, layout, scale, &simulations, &panes);
+                match (tool, target) {
+                    (_, Some((pane, (x, y)))) if is_feeding => {
+                        let message = if simulations[pane].add_energy(x, y, FEED_ENERGY) {
+                            format!("fed the mold at ({x}, {y})")
+                        } else {
+                            "no mold to feed".to_string()
+                        };
+                        status = Some((message, Instant::now()));
+                    }
+                    (Tool::Eyedropper, Some((pane, (x, y)))) => {
+                        picked = simulations[pane]
+                            .mold_at(x, y)
+                            .map(|info| (info.id, info.genome));
+                        if picked.is_some() {
+                            tool = Tool::Stamp;
+                        }
+                    }
+                    (Tool::Stamp, Some((pane, (x, y)))) => {
+                        if let Some((_, genome)) = &picked {
+                            simulations[pane].spawn_mold(x, y, genome.clone());
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            is_mouse_left_down = true;
+        } else {
+            is_mouse_left_down = false;
+        }
+        // select the cells in the area dragged out with the left mouse button while Shift is
+        // held, and otherwise erase everything in it when the button is released, unless the
+        // cursor stayed on the cell it was pressed on
+        let is_shift_down =
+            window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift);
+        let is_ctrl_down = window.is_key_down(Key::LeftCtrl) || window.is_key_down(Key::RightCtrl);
+        let is_left_down = tool == Tool::View
+            && window.get_mouse_down(minifb::MouseButton::Left)
+            && !is_minimap_dragged
+            && !is_feeding;
+        let is_selecting = selection.is_some_and(|selection| selection.dragging);
+        if is_left_down && (is_selecting || (is_shift_down && erase_start.is_none())) {
+            if let Some((pane, cell)) =
+                cursor_position(&window, layout, scale, &simulations, &panes)
+            {
+                selection = match selection {
+                    Some(dragged) if dragged.dragging && dragged.pane == pane => Some(Selection {
+                        end: cell,
+                        ..dragged
+                    }),
+                    Some(dragged) if dragged.dragging => Some(dragged),
+                    _ => Some(Selection {
+                        pane,
+                        start: cell,
+                        end: cell,
+                        dragging: true,
+                    }),
+                };
+            }
+        } else if is_left_down {
+            if erase_start.is_none() {
+                erase_start = cursor_in_pane(&window, layout, scale, MouseMode::Discard);
+            }
+        } else {
+            if let Some(selection) = &mut selection {
+                selection.dragging = false;
+            }
+            if let Some((pane, start)) = erase_start.take() {
+                if let Some(end) = window.get_unscaled_mouse_pos(MouseMode::Clamp) {
+                    let end = position_in_pane(layout, scale, pane, end);
+                    let simulation = &mut simulations[pane];
+                    let camera = &panes[pane].camera;
+                    if let Some(region) =
+                        dragged_region(start, end, camera, scale, simulation.size())
+                    {
+                        simulation.clear_region(region);
+                    }
+                }
+            }
+        }
+        // clear the selected cells when Delete is pressed, copy them with Ctrl+C and paste the
+        // copy with its top left corner under the cursor with Ctrl+V
+        if let Some(selected) = selection.filter(|selection| !selection.dragging) {
+            let simulation = &mut simulations[selected.pane];
+            let rect = selected.rect(simulation.size());
+            if window.is_key_pressed(Key::Delete, minifb::KeyRepeat::No) {
+                simulation.clear_region(rect);
+            }
+            if is_ctrl_down && window.is_key_pressed(Key::C, minifb::KeyRepeat::No) {
+                let copy = simulation.copy_region(rect);
+                let (width, height) = copy.size();
+                status = Some((format!("copied {width}x{height} cells"), Instant::now()));
+                clipboard = Some(copy);
+            }
         }
+        if is_ctrl_down && window.is_key_pressed(Key::V, minifb::KeyRepeat::No) {
+            let target = cursor_cell(&window, layout, scale, &simulations, &panes);
+            if let (Some(copy), Some((pane, (x, y)))) = (&clipboard, target) {
+                simulations[pane].paste(copy, x, y);
+            }
+        }
+        // cancel the selection when Escape is pressed, or else drop the picked genome and stop
+        // using the eyedropper, or else stop watching molds, or close the window if there is
+        // nothing to cancel
+        if window.is_key_pressed(Key::Escape, minifb::KeyRepeat::No) && selection.take().is_none() {
+            let is_picking = matches!(tool, Tool::Eyedropper | Tool::Stamp);
+            let is_watching = simulations
+                .iter()
+                .any(|simulation| simulation.watched().is_some());
+            if picked.is_some() || is_picking {
+                picked = None;
+                if is_picking {
+                    tool = Tool::View;
+                }
+            } else if is_watching {
+                for simulation in &mut simulations {
+                    simulation.watch(None);
+                    simulation.set_highlight(None);
+                }
+            } else {
+                break;
+            }
+        }
+        // compare with a copy of the focused simulation shown on the right when C is pressed, or
+        // with a new one of the same size with random molds when Shift+C is pressed, or stop
+        // comparing and keep only the focused one
+        if !is_ctrl_down && window.is_key_pressed(Key::C, minifb::KeyRepeat::No) {
+            if simulations.len() == 1 {
+                let other = if is_shift_down {
+                    fresh(&simulations[0], &start, &settings).map(|(simulation, origin)| {
+                        let message = format!(
+                            "comparing with {}",
+                            describe_origin(&origin.options, origin.options.seed)
+                        );
+                        // a new simulation starts a new graph and epoch
+                        let pane = Pane::new(panes[0].view, panes[0].camera, origin);
+                        (simulation, pane, message)
+                    })
+                } else {
+                    duplicate(&simulations[0])
+                        .map(|copy| (copy, panes[0].clone(), "comparing with a copy".to_string()))
+                        .map_err(|e| format!("cannot copy the simulation: {e}"))
+                };
+                let message = match other {
+                    Ok((other, pane, message)) => {
+                        simulations.push(other);
+                        panes.push(pane);
+                        focus = 1;
+                        format!("{message}, Tab switches focus")
+                    }
+                    Err(message) => message,
+                };
+                status = Some((message, Instant::now()));
+            } else {
+                simulations.swap(0, focus);
+                simulations.truncate(1);
+                panes.swap(0, focus);
+                panes.truncate(1);
+                selection = None;
+                focus = 0;
+            }
+        }
+        // cycle between viewing, painting walls and painting light when T is pressed, showing the
+        // light overlay of every pane while painting light
+        if window.is_key_pressed(Key::T, minifb::KeyRepeat::No) {
+            tool = tool.next();
+            if tool == Tool::Light {
+                for pane in &mut panes {
+                    pane.view.light_overlay = true;
+                }
+            }
+        }
+        // drop a meteor on the cell under the cursor when X is released, unless it was sized by
+        // scrolling while X was held
+        if window.is_key_pressed(Key::X, minifb::KeyRepeat::No) {
+            is_meteor_sized = false;
+        }
+        if window.is_key_released(Key::X) && !is_meteor_sized {
+            let target = cursor_cell(&window, layout, scale, &simulations, &panes)
+                .zip(cursor_in_pane(&window, layout, scale, MouseMode::Discard));
+            if let Some(((pane, (x, y)), (_, position))) = target {
+                simulations[pane].impact(x, y, meteor_radius);
+                let (px, py) = screen_to_buffer(position, scale);
+                let center = (px + layout.pane_x(pane) as f32, py);
+                let radius = (meteor_radius as f32 + 0.5) * panes[pane].camera.zoom.max(1.);
+                meteor_flash = Some((center, radius, METEOR_FLASH_FRAMES));
+            }
+        }
+        // pick up a genome with the eyedropper when K is pressed, or go back to viewing
+        if window.is_key_pressed(Key::K, minifb::KeyRepeat::No) {
+            tool = match tool {
+                Tool::Eyedropper | Tool::Stamp => Tool::View,
+                Tool::View | Tool::Walls | Tool::Light => Tool::Eyedropper,
+            };
+        }
+        // move the focus to the other simulation when Tab is pressed
+        if window.is_key_pressed(Key::Tab, minifb::KeyRepeat::No) {
+            focus = (focus + 1) % simulations.len();
+        }
+        // go back to the starting view when Home is pressed or to the center of the grid with
+        // Shift+Home, or center the view on the molds of the focused simulation when End is
+        // pressed, all of which stop following a mold
+        if window.is_key_pressed(Key::Home, minifb::KeyRepeat::No) {
+            panes[focus].follow = None;
+            let camera = &mut panes[focus].camera;
+            if is_shift_down {
+                let (width, height) = simulations[focus].size();
+                let center = (width as f32 / 2., height as f32 / 2.);
+                camera.recenter_on(center, layout.pane_size());
+            } else {
+                *camera = Camera {
+                    position: (0.0, 0.0),
+                    zoom: ZOOM,
+                };
+            }
+        }
+        if window.is_key_pressed(Key::End, minifb::KeyRepeat::No) {
+            let pane = &mut panes[focus];
+            pane.follow = None;
+            match simulations[focus].mold_centroid() {
+                Some(centroid) => pane.camera.recenter_on(centroid, layout.pane_size()),
+                None => status = Some(("no molds to center on".to_string(), Instant::now())),
+            }
+        }
+        // start/stop the camera following the watched mold when F is pressed
+        if window.is_key_pressed(Key::F, minifb::KeyRepeat::No) {
+            let simulation = &simulations[focus];
+            let pane = &mut panes[focus];
+            pane.follow = match (pane.follow, simulation.watched()) {
+                (Some(_), _) => None,
+                (None, Some(_)) => Follow::start(simulation),
+                (None, None) => {
+                    let message = "middle-click a mold to follow it".to_string();
+                    status = Some((message, Instant::now()));
+                    None
+                }
+            };
+        }
+        let simulation = &mut simulations[focus];
         // create new molds when G key is pressed
         if window.is_key_pressed(Key::G, minifb::KeyRepeat::No) {
-            for _ in 0..300 {
-                let x = fastrand::usize(..GRID_X);
-                let y = fastrand::usize(..GRID_Y);
-                simulation.generate_mold(x, y);
-            }
+            simulation.generate_random_molds(GENERATED_MOLDS);
         }
         // delete everything when D key is pressed
         if window.is_key_pressed(Key::D, minifb::KeyRepeat::No) {
             simulation.clear();
+            panes[focus].graph.mark_gap(simulation.tick());
         }
         // start/pause when P key is pressed
         if window.is_key_pressed(Key::P, minifb::KeyRepeat::No) {
             is_running = !is_running;
         }
+        // pause and run a single update when N key is pressed, repeating while it is held
+        if window.is_key_pressed(Key::N, minifb::KeyRepeat::Yes) {
+            is_running = false;
+            is_step_requested = true;
+        }
+        // save to a new file when F5 is pressed, load the newest save with F9 or the oldest with
+        // Shift+F9 and pause to look around
+        if window.is_key_pressed(Key::F5, minifb::KeyRepeat::No) {
+            let message = match saves::save(simulation, &panes[focus].camera) {
+                Ok(path) => format!("saved {}", path.display()),
+                Err(message) => message,
+            };
+            status = Some((message, Instant::now()));
+        }
+        if window.is_key_pressed(Key::F9, minifb::KeyRepeat::No) {
+            let pick = if window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift)
+            {
+                Pick::Oldest
+            } else {
+                Pick::Newest
+            };
+            let message = match saves::load(pick) {
+                Ok((mut loaded, loaded_camera, path)) => {
+                    carry_view_settings(simulation, &mut loaded);
+                    *simulation = loaded;
+                    panes[focus].graph.mark_gap(simulation.tick());
+                    panes[focus].camera = Camera {
+                        zoom: loaded_camera.zoom.clamp(MIN_ZOOM, MAX_ZOOM),
+                        ..loaded_camera
+                    };
+                    is_running = false;
+                    format!("loaded {}, paused", path.display())
+                }
+                Err(message) => message,
+            };
+            status = Some((message, Instant::now()));
+        }
+        // take a screenshot of the window when S is pressed, or of the whole grid with Shift+S
+        if window.is_key_pressed(Key::S, minifb::KeyRepeat::No) {
+            if window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift) {
+                let message = match capture::save_grid_screenshot(simulation) {
+                    Ok(path) => format!("saved {}", path.display()),
+                    Err(message) => message,
+                };
+                status = Some((message, Instant::now()));
+            } else {
+                screenshot_requested = true;
+            }
+        }
+        // start/stop recording the window when R is pressed
+        if !is_ctrl_down && !is_shift_down && window.is_key_pressed(Key::R, minifb::KeyRepeat::No) {
+            #[cfg(feature = "recording")]
+            let message = match recording.take() {
+                Some(finished) => match finished.finish() {
+                    Ok(path) => format!("saved {}", path.display()),
+                    Err(message) => message,
+                },
+                None => {
+                    let delay = (RECORD_EVERY as u16, target_fps as u16);
+                    match recording::Recording::start(buffer_size, RECORD_DOWNSCALE, delay) {
+                        Ok(started) => {
+                            recording = Some(started);
+                            "recording started".to_string()
+                        }
+                        Err(message) => message,
+                    }
+                }
+            };
+            #[cfg(not(feature = "recording"))]
+            let message = "cannot record: built without the recording feature".to_string();
+            status = Some((message, Instant::now()));
+        }
+        // start the focused simulation over with a new random seed when Ctrl+R is pressed, or
+        // exactly the way it started with Shift+R
+        if (is_ctrl_down || is_shift_down) && window.is_key_pressed(Key::R, minifb::KeyRepeat::No) {
+            let pane = &mut panes[focus];
+            let mut origin = pane.origin.clone();
+            if is_ctrl_down {
+                origin.options.seed = Some(fastrand::u64(..));
+            }
+            let message = match restart(&origin, simulation, &settings) {
+                Ok(restarted) => {
+                    *simulation = restarted;
+                    let described = describe_origin(&origin.options, origin.options.seed);
+                    // the new run starts a new graph and epoch
+                    *pane = Pane::new(pane.view, pane.camera, origin);
+                    format!("started over with {described}")
+                }
+                Err(message) => message,
+            };
+            status = Some((message, Instant::now()));
+        }
+        // cycle through spore styles when U key is pressed, or fade spores in as they ripen with
+        // Shift
+        if window.is_key_pressed(Key::U, minifb::KeyRepeat::No) {
+            if is_shift_down {
+                simulation.set_spore_ripening(!simulation.spore_ripening());
+            } else {
+                simulation.set_spore_style(next_spore_style(simulation.spore_style()));
+            }
+        }
+        // switch between dark and light background when J key is pressed
+        if window.is_key_pressed(Key::J, minifb::KeyRepeat::No) {
+            let mut palette = *simulation.palette();
+            palette.background = if palette.background == LIGHT_BACKGROUND {
+                settings.render.background
+            } else {
+                LIGHT_BACKGROUND
+            };
+            simulation.set_palette(palette);
+        }
+        // show/hide the HUD when H key is pressed
+        if window.is_key_pressed(Key::H, minifb::KeyRepeat::No) {
+            show_hud = !show_hud;
+        }
+        // turn autoreseed on/off when A key is pressed
+        if window.is_key_pressed(Key::A, minifb::KeyRepeat::No) {
+            autoreseed = !autoreseed;
+            knob_changed = Some((Knob::Autoreseed, Instant::now()));
+        }
+        // turn resizing the grids with the window on/off when E key is pressed, going back to
+        // the starting view which the grids fill once they are resized
+        if window.is_key_pressed(Key::E, minifb::KeyRepeat::No) {
+            fit_grid = !fit_grid;
+            pending_crop = None;
+            if fit_grid {
+                for pane in &mut panes {
+                    pane.camera = Camera {
+                        position: (0.0, 0.0),
+                        zoom: ZOOM,
+                    };
+                }
+            }
+            let message = format!("fit grid to window {}", if fit_grid { "on" } else { "off" });
+            status = Some((message, Instant::now()));
+        }
+        // show/hide the population graph when Y key is pressed
+        if window.is_key_pressed(Key::Y, minifb::KeyRepeat::No) {
+            show_graph = !show_graph;
+        }
+        // show/hide the minimap when M key is pressed
+        if window.is_key_pressed(Key::M, minifb::KeyRepeat::No) {
+            show_minimap = !show_minimap;
+        }
+        let view = &mut panes[focus].view;
+        // cycle through the view modes when V is pressed without Ctrl
+        if !is_ctrl_down && window.is_key_pressed(Key::V, minifb::KeyRepeat::No) {
+            view.cycle_view_mode();
+        }
+        // show/hide the light overlay when O key is pressed, or give every cell full light again
+        // with Shift
+        if window.is_key_pressed(Key::O, minifb::KeyRepeat::No) {
+            if is_shift_down {
+                simulation.reset_light();
+                status = Some(("light reset".to_string(), Instant::now()));
+            } else {
+                view.light_overlay = !view.light_overlay;
+            }
+        }
+        // show/hide where molds have been when B key is pressed
+        if window.is_key_pressed(Key::B, minifb::KeyRepeat::No) {
+            view.trail = !view.trail;
+        }
+        // show/hide lines between the cells when L is pressed
+        if window.is_key_pressed(Key::L, minifb::KeyRepeat::No) {
+            view.grid_lines = !view.grid_lines;
+        }
+        // switch to the activity view when Z is pressed, or back to the view mode from the config
+        if window.is_key_pressed(Key::Z, minifb::KeyRepeat::No) {
+            view.toggle_activity(settings.render.view_mode);
+        }
+        // halve/double the number of updates per second when -/+ is pressed, down to 0 which
+        // keeps rendering without updating
+        if window.is_key_pressed(Key::Minus, minifb::KeyRepeat::Yes)
+            || window.is_key_pressed(Key::NumPadMinus, minifb::KeyRepeat::Yes)
+        {
+            speed = Speed::Paced;
+            timestep.set_ticks_per_second(timestep.ticks_per_second() / 2);
+        }
+        if window.is_key_pressed(Key::Equal, minifb::KeyRepeat::Yes)
+            || window.is_key_pressed(Key::NumPadPlus, minifb::KeyRepeat::Yes)
+        {
+            speed = Speed::Paced;
+            let ticks_per_second = (timestep.ticks_per_second() * 2).clamp(1, MAX_TICKS_PER_SECOND);
+            timestep.set_ticks_per_second(ticks_per_second);
+        }
+        // run 1, 2, 4 and so on up to 256 updates every frame when 1 to 9 is pressed, or one
+        // update every 1, 2, 4 and so on up to 256 frames with Shift, or as many as possible while
+        // rendering once a second when 0 is pressed
+        for (preset, key) in SPEED_KEYS.into_iter().enumerate() {
+            if window.is_key_pressed(key, minifb::KeyRepeat::No) {
+                speed = Speed::preset(preset, is_shift_down);
+                slow_motion.reset();
+            }
+        }
+        if window.is_key_pressed(Key::Key0, minifb::KeyRepeat::No) {
+            speed = Speed::Turbo;
+        }
+        // switch between fullscreen and the windowed geometry from before when F11 is pressed,
+        // by opening the window again as minifb can't change an open one
+        if window.is_key_pressed(Key::F11, minifb::KeyRepeat::No) {
+            is_fullscreen = !is_fullscreen;
+            window = open_window(&settings.window, windowed, is_fullscreen);
+        }
         // decrease/increase light level when Q/W is pressed
         if window.is_key_pressed(Key::Q, minifb::KeyRepeat::No) {
-            simulation.energy_light = 0.max(simulation.energy_light - 1)
+            simulation.set_energy_light(simulation.energy_light() - 1);
+            knob_changed = Some((Knob::Light, Instant::now()));
         }
         if window.is_key_pressed(Key::W, minifb::KeyRepeat::No) {
-            simulation.energy_light = 20.min(simulation.energy_light + 1)
+            simulation.set_energy_light(simulation.energy_light() + 1);
+            knob_changed = Some((Knob::Light, Instant::now()));
         }
-
-        // update simulation state
-        if is_running {
-            for _ in 0..1 {
-                simulation.update();
-            }
+        // halve/double the chance that sprouting spores mutate when [/] is pressed
+        if window.is_key_pressed(Key::LeftBracket, minifb::KeyRepeat::No) {
+            let odds = simulation.mutation_odds().saturating_mul(2);
+            simulation.set_mutation_odds(odds.min(MAX_MUTATION_ODDS));
+            knob_changed = Some((Knob::Mutation, Instant::now()));
+        }
+        if window.is_key_pressed(Key::RightBracket, minifb::KeyRepeat::No) {
+            simulation.set_mutation_odds(simulation.mutation_odds() / 2);
+            knob_changed = Some((Knob::Mutation, Instant::now()));
         }
+        let layout = Layout {
+            buffer_size,
+            panes: simulations.len(),
+        };
 
-        // update fps in window title
         let elapsed: Duration = last_frame_time.elapsed();
         last_frame_time = Instant::now();
-        average_fps = 0.95 * average_fps + 0.05 / elapsed.as_secs_f64();
 
+        // update simulation state by the updates that are due since the last frame, stopping
+        // early when paced updates take longer than the budget so the frame rate holds up, or by
+        // the single update requested while paused
+        let mut steps_done = 0;
+        if is_running || is_step_requested {
+            let due = match speed {
+                _ if !is_running => 1,
+                Speed::Paced => timestep.advance(elapsed),
+                Speed::PerFrame(steps) => steps,
+                Speed::Slow(frames) => slow_motion.advance(frames),
+                Speed::Turbo => TURBO_POLL_STEPS,
+            };
+            is_step_requested = false;
+            let update_start = Instant::now();
+            while steps_done < due
+                && (speed != Speed::Paced || update_start.elapsed() < update_budget)
+            {
+                for simulation in &mut simulations {
+                    simulation.update();
+                }
+                steps_done += 1;
+                // pause rather than skip frames of the time-lapse when they can't be written
+                if let Some(timelapse) = &start.timelapse {
+                    if let Err(message) = timelapse.capture(&simulations[0]) {
+                        is_running = false;
+                        status = Some((format!("{message}, paused"), Instant::now()));
+                        break;
+                    }
+                }
+            }
+        }
+        average_tps = 0.95 * average_tps + 0.05 * steps_done as f64 / elapsed.as_secs_f64();
+        // start over with new molds where all molds died a while ago
+        if autoreseed {
+            for (index, (pane, simulation)) in panes.iter_mut().zip(&mut simulations).enumerate() {
+                let ended = simulation.tick();
+                let reseed = &mut pane.reseed;
+                if reseed.update(simulation, settings.window.reseed_delay, GENERATED_MOLDS) {
+                    println!(
+                        "epoch {} of simulation {} ended at tick {ended}, scattered new molds",
+                        reseed.epoch - 1,
+                        index + 1
+                    );
+                }
+            }
+        }
+        for (pane, simulation) in panes.iter_mut().zip(&simulations) {
+            pane.graph.record(simulation);
+        }
+        // stop watching and following molds that died
+        for (pane, simulation) in panes.iter_mut().zip(&mut simulations) {
+            if let Some(watched) = simulation.watched().filter(|watched| !watched.alive) {
+                simulation.watch(None);
+                simulation.set_highlight(None);
+                watch_ended = Some((watched.id, Instant::now()));
+                status = Some((format!("mold {} died", watched.id.0), Instant::now()));
+                if pane.follow.take().is_some() {
+                    follow_ended = Some((watched.id, Instant::now()));
+                }
+            }
+        }
+        // pan the focused pane with the arrow keys, faster with Shift, at the same speed on screen
+        // whatever the zoom, which stops following a mold
+        let axis = |negative, positive| {
+            window.is_key_down(positive) as i32 as f32 - window.is_key_down(negative) as i32 as f32
+        };
+        let direction = (axis(Key::Left, Key::Right), axis(Key::Up, Key::Down));
+        if direction != (0., 0.) {
+            let fast = if is_shift_down { KEY_PAN_FAST } else { 1. };
+            let distance = KEY_PAN_SPEED * fast * elapsed.as_secs_f32();
+            let pane = &mut panes[focus];
+            pane.camera.position.0 += direction.0 * distance;
+            pane.camera.position.1 += direction.1 * distance;
+            pane.follow = None;
+        }
+        // ease the cameras following a mold toward its center, found again every few ticks, and
+        // stop following once the mold isn't watched anymore
+        for (pane, simulation) in panes.iter_mut().zip(&simulations) {
+            let Some(follow) = &mut pane.follow else {
+                continue;
+            };
+            if !follow.retarget(simulation) {
+                pane.follow = None;
+                continue;
+            }
+            let fraction = 1. - (-elapsed.as_secs_f32() / FOLLOW_EASING).exp();
+            let (target, size) = (follow.target, simulation.size());
+            pane.camera
+                .ease_toward(target, layout.pane_size(), size, fraction);
+        }
+
+        // keep the cameras within one copy of their grid, however far they were panned, or on
+        // the grid if it doesn't wrap and clamp_to_grid is set
+        for (pane, simulation) in panes.iter_mut().zip(&simulations) {
+            if settings.window.clamp_to_grid && simulation.edges() == Edges::Bounded {
+                pane.camera
+                    .clamp_to_grid(simulation.size(), layout.pane_size());
+            }
+            pane.camera.normalize(simulation.size());
+        }
+
+        // in turbo mode only check for input until the next frame is due
+        if is_turbo && last_render_time.elapsed() < TURBO_RENDER_PERIOD {
+            window.update();
+            continue;
+        }
+        average_fps = 0.95 * average_fps + 0.05 / last_render_time.elapsed().as_secs_f64();
+        last_render_time = Instant::now();
+        let target_speed = speed.label(timestep.ticks_per_second());
+
+        // describe the cell under the mouse cursor
+        let hover = cursor_cell(&window, layout, scale, &simulations, &panes)
+            .map(|(pane, (x, y))| describe_cell(&simulations[pane], x, y))
+            .unwrap_or_default();
+        let seed_text = panes[focus]
+            .origin
+            .options
+            .seed
+            .map(|seed| format!(" - seed: {seed}"))
+            .unwrap_or_default();
+        let selection_text = selection
+            .map(|selected| {
+                let rect = selected.rect(simulations[selected.pane].size());
+                format!(" - selected {}x{}", rect.width, rect.height)
+            })
+            .unwrap_or_default();
+        let tool_text = match tool {
+            Tool::View => String::new(),
+            _ => format!(" - tool: {}", describe_tool(tool, brush_radius, &picked)),
+        };
+        let comparing = match (simulations.len(), focus) {
+            (1, _) => "",
+            (_, 0) => " - left focused",
+            _ => " - right focused",
+        };
+
+        status = status.filter(|(_, since)| since.elapsed() < STATUS_DURATION);
+        let status_text = status
+            .as_ref()
+            .map(|(message, _)| format!(" - {message}"))
+            .unwrap_or_default();
+        #[cfg(feature = "recording")]
+        let status_text = match &recording {
+            Some(recording) => format!(" - REC {} frames{status_text}", recording.frames()),
+            None => status_text,
+        };
+
+        // the rates and light level are in the HUD while it is shown
+        let rates = if show_hud {
+            String::new()
+        } else {
+            format!(
+                " - fps: {average_fps:.0} - tps: {average_tps:.0}{target_speed} - light level: {} - view: {}",
+                simulations[focus].energy_light(),
+                config::view_mode_name(panes[focus].view.view_mode)
+            )
+        };
         window.set_title(
             format!(
-                "rusty-mold - fps: {average_fps:.0} - light level: {0}",
-                simulation.energy_light
+                "rusty-mold{rates}{seed_text}{tool_text}{selection_text}{comparing}{hover}{status_text}"
             )
             .as_str(),
         );
 
-        // render new state
-        let camera_offset = (
-            (camera_position.0).rem_euclid((GRID_X * zoom) as f32) as usize,
-            (camera_position.1).rem_euclid((GRID_Y * zoom) as f32) as usize,
+        // render the cells that changed in the pane of every simulation, then draw the minimap of
+        // the focused simulation in the bottom right corner of its pane with the population graph
+        // next to it
+        layout::draw_dividers(&mut buffer, layout, DIVIDER_COLOR);
+        for (index, (simulation, pane)) in simulations.iter_mut().zip(&panes).enumerate() {
+            pane.view.apply(simulation);
+            simulation.render_dirty_into(&mut buffer, layout.pane_region(index), &pane.camera);
+        }
+        frame.clear();
+        frame.extend_from_slice(&buffer);
+        for (index, (simulation, pane)) in simulations.iter().zip(&panes).enumerate() {
+            if pane.view.grid_lines {
+                let region = layout.pane_region(index);
+                let size = simulation.size();
+                view::draw_grid_lines(&mut frame, region, &pane.camera, size, GRID_LINE_COLOR);
+            }
+        }
+        let pane_size = layout.pane_size();
+        let simulation = &simulations[focus];
+        let pane_x = layout.pane_x(focus);
+        minimap_area = None;
+        if let Some(minimap_size) =
+            fit_minimap(simulation.size(), pane_size).filter(|_| show_minimap)
+        {
+            let is_stale = minimap_rendered.is_none_or(|(pane, size, rendered)| {
+                pane != focus || size != minimap_size || rendered.elapsed() >= MINIMAP_REFRESH
+            });
+            if is_stale {
+                simulation.render_minimap_cells(&mut minimap_cells, minimap_size.0, minimap_size.1);
+                minimap_rendered = Some((focus, minimap_size, Instant::now()));
+            }
+            minimap.clear();
+            minimap.extend_from_slice(&minimap_cells);
+            let viewport = panes[focus].camera.viewport(pane_size, simulation.size());
+            simulation.draw_minimap_viewport(
+                &mut minimap,
+                minimap_size.0,
+                minimap_size.1,
+                viewport,
+            );
+            let origin = (
+                pane_x + pane_size.0 - minimap_size.0 - MINIMAP_MARGIN,
+                pane_size.1 - minimap_size.1 - MINIMAP_MARGIN,
+            );
+            blit(&mut frame, buffer_size.0, &minimap, minimap_size, origin);
+            minimap_area = Some(MinimapArea {
+                pane: focus,
+                origin,
+                size: minimap_size,
+            });
+
+            let sparkline_size = (SPARKLINE_WIDTH, minimap_size.1);
+            if sparkline_size.0 + MINIMAP_MARGIN <= origin.0 - pane_x {
+                sparkline.resize(sparkline_size.0 * sparkline_size.1, 0);
+                simulation.render_sparkline(&mut sparkline, sparkline_size.0, sparkline_size.1);
+                let origin = (origin.0 - sparkline_size.0 - MINIMAP_MARGIN, origin.1);
+                blit(
+                    &mut frame,
+                    buffer_size.0,
+                    &sparkline,
+                    sparkline_size,
+                    origin,
+                );
+            }
+        }
+        // the population graph in the top right corner of the focused pane, if there is room
+        let graph_size = (
+            graph::GRAPH_SIZE
+                .0
+                .min(pane_size.0.saturating_sub(2 * MINIMAP_MARGIN)),
+            graph::GRAPH_SIZE
+                .1
+                .min(pane_size.1.saturating_sub(2 * MINIMAP_MARGIN)),
         );
-        simulation.render(&mut buffer, buffer_size, camera_offset, zoom);
+        if show_graph
+            && graph_size.0 >= graph::MIN_GRAPH_SIZE.0
+            && graph_size.1 >= graph::MIN_GRAPH_SIZE.1
+        {
+            let region = BufferRegion {
+                origin: (
+                    pane_x + pane_size.0 - graph_size.0 - MINIMAP_MARGIN,
+                    MINIMAP_MARGIN,
+                ),
+                size: graph_size,
+                stride: buffer_size.0,
+            };
+            panes[focus].graph.draw(&mut frame, region);
+        }
+        if let Some(selected) = selection {
+            let pane_x = layout.pane_x(selected.pane) as f32;
+            let simulation = &simulations[selected.pane];
+            let camera = &panes[selected.pane].camera;
+            let (left, right) = sorted(selected.start.0, selected.end.0);
+            let (top, bottom) = sorted(selected.start.1, selected.end.1);
+            // pixels of the outer edge of the selected cells, relative to the window buffer
+            let corner = |x: isize, y: isize| {
+                let cell = (x.max(0) as usize, y.max(0) as usize);
+                camera.grid_to_screen(cell, 1, simulation.size())
+            };
+            let start = corner(left, top);
+            let start = (start.0 + pane_x, start.1);
+            let end = corner(right + 1, bottom + 1);
+            let end = (end.0 - 1. + pane_x, end.1 - 1.);
+            let start = position_in_pane(layout, 1, selected.pane, start);
+            let end = position_in_pane(layout, 1, selected.pane, end);
+            let (start, end) = ((start.0 + pane_x, start.1), (end.0 + pane_x, end.1));
+            let color = simulation.palette().viewport;
+            draw_outline(&mut frame, buffer_size, start, end, color);
+        }
+        if let (Some((pane, start)), Some(end)) =
+            (erase_start, window.get_unscaled_mouse_pos(MouseMode::Clamp))
+        {
+            let start = screen_to_buffer(start, scale);
+            let end = screen_to_buffer(position_in_pane(layout, scale, pane, end), scale);
+            let pane_x = layout.pane_x(pane) as f32;
+            let color = simulations[pane].palette().viewport;
+            let (start, end) = ((start.0 + pane_x, start.1), (end.0 + pane_x, end.1));
+            draw_outline(&mut frame, buffer_size, start, end, color);
+        }
+        // the cells the meteor or the brush covers around the mouse cursor
+        let cursor_radius = if window.is_key_down(Key::X) {
+            Some(meteor_radius)
+        } else {
+            tool.is_brush().then_some(brush_radius)
+        };
+        if let (Some(cursor_radius), Some((pane, position))) = (
+            cursor_radius,
+            cursor_in_pane(&window, layout, scale, MouseMode::Discard),
+        ) {
+            let (x, y) = screen_to_buffer(position, scale);
+            let center = (x + layout.pane_x(pane) as f32, y);
+            let radius = (cursor_radius as f32 + 0.5) * panes[pane].camera.zoom.max(1.);
+            let color = simulations[pane].palette().viewport;
+            draw_circle(&mut frame, buffer_size, center, radius, color);
+        }
+        // flash where a meteor just struck
+        if let Some((center, radius, frames)) = meteor_flash {
+            fill_circle(&mut frame, buffer_size, center, radius, METEOR_FLASH_COLOR);
+            meteor_flash = (frames > 1).then_some((center, radius, frames - 1));
+        }
+        if show_hud {
+            let simulation = &simulations[focus];
+            let stats = match hud_stats {
+                Some((stats, counted)) if counted.elapsed() < HUD_STATS_PERIOD => stats,
+                _ => {
+                    let stats = simulation.stats();
+                    hud_stats = Some((stats, Instant::now()));
+                    stats
+                }
+            };
+            let paused = if is_running { "" } else { "  paused" };
+            let tool_text = describe_tool(tool, brush_radius, &picked);
+            let mut lines = vec![
+                format!(
+                    "fps {average_fps:.0}  tps {average_tps:.0}{target_speed}  tick {}{paused}",
+                    simulation.tick()
+                ),
+                format!(
+                    "molds {}  cells {}  spores {}  view {}  tool {tool_text}",
+                    stats.molds,
+                    stats.mold_cells,
+                    stats.spores,
+                    config::view_mode_name(panes[focus].view.view_mode)
+                ),
+            ];
+            // the numbers of the watched mold, or that it died a moment ago
+            watch_ended = watch_ended.filter(|(_, since)| since.elapsed() < STATUS_DURATION);
+            match (simulation.watched(), watch_ended) {
+                (Some(watched), _) => lines.push(format!(
+                    "mold {}  energy {}  cells {}  oldest {}  generation {}",
+                    watched.id.0,
+                    watched.energy,
+                    watched.cells,
+                    watched.oldest_age,
+                    watched.generation
+                )),
+                (None, Some((id, _))) => lines.push(format!("mold {} died", id.0)),
+                (None, None) => {}
+            }
+            follow_ended = follow_ended.filter(|(_, since)| since.elapsed() < STATUS_DURATION);
+            match (&panes[focus].follow, follow_ended) {
+                (Some(follow), _) => {
+                    lines.push(format!("following mold {}, F to stop", follow.mold.0))
+                }
+                (None, Some((id, _))) => {
+                    lines.push(format!("stopped following mold {}, camera free", id.0))
+                }
+                (None, None) => {}
+            }
+            // the light of the cell under the cursor while painting light
+            if let (Tool::Light, Some((pane, (x, y)))) = (
+                tool,
+                cursor_cell(&window, layout, scale, &simulations, &panes),
+            ) {
+                let light = simulations[pane].light_intensity(x, y);
+                lines.push(format!("light at ({x}, {y}): {light}/{FULL_LIGHT}"));
+            }
+            let origin = (layout.pane_x(focus) as isize + HUD_MARGIN, HUD_MARGIN);
+            hud::draw_lines(
+                &mut frame,
+                buffer_size.0,
+                origin,
+                &lines,
+                HUD_COLOR,
+                HUD_SHADOW_COLOR,
+            );
+            // the settings changed with keys, with the one changed last highlighted for a moment
+            knob_changed =
+                knob_changed.filter(|(_, since)| since.elapsed() < KNOB_HIGHLIGHT_DURATION);
+            let knobs = [
+                (Knob::Light, format!("light {}", simulation.energy_light())),
+                (
+                    Knob::Mutation,
+                    format!("mutation 1/{}", simulation.mutation_odds()),
+                ),
+                (
+                    Knob::Autoreseed,
+                    format!("autoreseed {}", if autoreseed { "on" } else { "off" }),
+                ),
+            ];
+            let mut spans: Vec<(String, u32)> = knobs
+                .into_iter()
+                .map(|(knob, text)| {
+                    let is_changed = knob_changed.is_some_and(|(changed, _)| changed == knob);
+                    let color = if is_changed {
+                        HUD_HIGHLIGHT_COLOR
+                    } else {
+                        HUD_COLOR
+                    };
+                    (format!("{text}  "), color)
+                })
+                .collect();
+            spans.push((format!("epoch {}", panes[focus].reseed.epoch), HUD_COLOR));
+            let top = origin.1 + (lines.len() * hud::LINE_HEIGHT) as isize;
+            hud::draw_spans(
+                &mut frame,
+                buffer_size.0,
+                (origin.0, top),
+                &spans,
+                HUD_SHADOW_COLOR,
+            );
+        }
+        if screenshot_requested {
+            screenshot_requested = false;
+            let message = match capture::save_screenshot(&frame, buffer_size) {
+                Ok(path) => format!("saved {}", path.display()),
+                Err(message) => message,
+            };
+            status = Some((message, Instant::now()));
+        }
+        // add the frame to the recording before drawing the indicator, and stop recording when
+        // the window was resized since frames of a recording all have the same size
+        #[cfg(feature = "recording")]
+        if let Some(active) = &mut recording {
+            let result = if active.buffer_size() != buffer_size {
+                Err("recording stopped: the window was resized".to_string())
+            } else if frame_number.is_multiple_of(RECORD_EVERY) {
+                active.add_frame(&frame)
+            } else {
+                Ok(())
+            };
+            if let Err(message) = result {
+                let saved = recording.take().map(|stopped| stopped.finish());
+                let message = match saved {
+                    Some(Ok(path)) => format!("{message}, saved {}", path.display()),
+                    _ => message,
+                };
+                status = Some((message, Instant::now()));
+            } else {
+                let (color, size) = RECORD_INDICATOR;
+                for y in 0..size.min(buffer_size.1) {
+                    let row = y * buffer_size.0;
+                    frame[row..row + size.min(buffer_size.0)].fill(color);
+                }
+            }
+            frame_number += 1;
+        }
         window
-            .update_with_buffer(&buffer, buffer_size.0, buffer_size.1)
+            .update_with_buffer(&frame, buffer_size.0, buffer_size.1)
             .unwrap();
     }
+
+    if let Err(e) = config::save_window_state(&start.config, windowed.size, autoreseed) {
+        eprintln!(
+            "cannot save the window size to {}: {e}",
+            start.config.display()
+        );
+    }
+
+    #[cfg(feature = "recording")]
+    if let Some(Err(message)) = recording.map(|recording| recording.finish()) {
+        eprintln!("{message}");
+    }
+}
+
+/// Size and position of the window on the screen in pixels, while it is not fullscreen.
+#[derive(Clone, Copy, Debug)]
+struct Geometry {
+    size: (usize, usize),
+    /// None to let the system place the window
+    position: Option<(isize, isize)>,
+}
+
+/// Open the window: borderless in the top left corner of the screen at the fullscreen size from
+/// the settings, or with the given geometry.
+fn open_window(settings: &WindowSettings, windowed: Geometry, fullscreen: bool) -> Window {
+    let options = WindowOptions {
+        borderless: fullscreen,
+        title: !fullscreen,
+        resize: !fullscreen,
+        scale: settings.minifb_scale(),
+        scale_mode: minifb::ScaleMode::Stretch,
+        topmost: fullscreen,
+        transparency: false,
+        none: false,
+    };
+    let size = if fullscreen {
+        (settings.fullscreen_width, settings.fullscreen_height)
+    } else {
+        windowed.size
+    };
+    // the size of the window is given in pixels of the buffer, which minifb scales up
+    let (width, height) = (
+        (size.0 / settings.scale).max(1),
+        (size.1 / settings.scale).max(1),
+    );
+    let mut window = Window::new("rusty-mold", width, height, options).unwrap_or_else(|e| {
+        panic!("{}", e);
+    });
+    match (fullscreen, windowed.position) {
+        (true, _) => window.set_position(0, 0),
+        (false, Some((x, y))) => window.set_position(x, y),
+        (false, None) => (),
+    }
+
+    window.set_background_color(0, 0, 0);
+    window
+}
+
+/// Size of the buffer shown in a window: its size on the screen divided by the scale.
+fn buffer_size_of(window: &Window, scale: usize) -> (usize, usize) {
+    let (width, height) = window.get_size();
+    (width / scale, height / scale)
+}
+
+/// Give a simulation loaded from a save the render settings of the one it replaces that are not
+/// part of its ViewState, which saves don't include.
+fn carry_view_settings(from: &Simulation, to: &mut Simulation) {
+    to.set_population_history(SPARKLINE_WIDTH);
+    to.set_spore_style(from.spore_style());
+    to.set_spore_ripening(from.spore_ripening());
+    to.set_palette(*from.palette());
+    to.set_colormap(from.colormap().clone());
+}
+
+/// A setting changed with keys while the window is open, shown in the settings row of the HUD.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Knob {
+    Light,
+    Mutation,
+    Autoreseed,
+}
+
+/// Rectangle of cells selected in a pane, with corners counted like in cursor_position.
+#[derive(Clone, Copy, Debug)]
+struct Selection {
+    pane: usize,
+    start: (isize, isize),
+    end: (isize, isize),
+    /// whether the left mouse button is still held to drag out the rectangle
+    dragging: bool,
+}
+
+impl Selection {
+    /// The selected cells. A selection dragged across the right or bottom edge of the grid
+    /// wraps around like the grid repeated in the window; clearing and copying it then wraps
+    /// around too, or stops at the edge when the edges of the grid are bounded.
+    fn rect(&self, grid_size: (usize, usize)) -> Rect {
+        let (left, right) = sorted(self.start.0, self.end.0);
+        let (top, bottom) = sorted(self.start.1, self.end.1);
+        Rect {
+            x: left.rem_euclid(grid_size.0 as isize) as usize,
+            y: top.rem_euclid(grid_size.1 as isize) as usize,
+            width: ((right - left + 1) as usize).min(grid_size.0),
+            height: ((bottom - top + 1) as usize).min(grid_size.1),
+        }
+    }
+}
+
+/// Two numbers, smallest first.
+fn sorted(a: isize, b: isize) -> (isize, isize) {
+    (a.min(b), a.max(b))
+}
+
+/// An exact copy of a simulation with the same render settings, which makes the same updates as
+/// the original until either of them is changed.
+fn duplicate(simulation: &Simulation) -> Result<Simulation, rustymold::SnapshotError> {
+    let mut snapshot = Vec::new();
+    simulation.write_snapshot(&mut snapshot)?;
+    let mut copy = Simulation::read_snapshot(snapshot.as_slice())?;
+    carry_view_settings(simulation, &mut copy);
+    Ok(copy)
+}
+
+/// A new simulation with the size, light and render settings of another one and GENERATED_MOLDS
+/// random molds with a random seed, and how it was set up. The other options are those of
+/// `start`.
+fn fresh(
+    simulation: &Simulation,
+    start: &Options,
+    settings: &Settings,
+) -> Result<(Simulation, Origin), String> {
+    let (width, height) = simulation.size();
+    let origin = Origin {
+        options: Options {
+            grid: (Some(width), Some(height)),
+            light: Some(simulation.energy_light()),
+            seed: Some(fastrand::u64(..)),
+            density: None,
+            load: None,
+            ..start.clone()
+        },
+        molds: GENERATED_MOLDS,
+    };
+    let other = restart(&origin, simulation, settings)?;
+    Ok((other, origin))
+}
+
+/// Set up a simulation the way `origin` describes, with the render settings of the one it
+/// replaces that are not part of its ViewState.
+fn restart(
+    origin: &Origin,
+    simulation: &Simulation,
+    settings: &Settings,
+) -> Result<Simulation, String> {
+    let (mut restarted, _, _) = start_simulation(&origin.options, settings, origin.molds)?;
+    carry_view_settings(simulation, &mut restarted);
+    Ok(restarted)
+}
+
+/// Position in a pane of a point of a window showing every pixel of the buffer as `scale` x
+/// `scale` pixels, clamped to the pane. Both are in pixels of the window, or of the buffer with a
+/// scale of 1.
+fn position_in_pane(layout: Layout, scale: usize, pane: usize, screen: (f32, f32)) -> (f32, f32) {
+    let (width, height) = layout.pane_size();
+    let (x, y) = screen_to_buffer(screen, scale);
+    let x = x - layout.pane_x(pane) as f32;
+    let scale = scale.max(1) as f32;
+    (
+        x.clamp(0., width.saturating_sub(1) as f32) * scale,
+        y.clamp(0., height.saturating_sub(1) as f32) * scale,
+    )
+}
+
+/// Pane under the mouse cursor and the position of the cursor relative to it, in pixels of the
+/// window rather than of the buffer.
+fn cursor_in_pane(
+    window: &Window,
+    layout: Layout,
+    scale: usize,
+    mode: MouseMode,
+) -> Option<(usize, (f32, f32))> {
+    let (pane, position) = layout.pane_at(screen_to_buffer(
+        window.get_unscaled_mouse_pos(mode)?,
+        scale,
+    ))?;
+    let scale = scale.max(1) as f32;
+    Some((pane, (position.0 * scale, position.1 * scale)))
+}
+
+/// Copy an image of `size` pixels into a buffer with rows of `buffer_width` pixels, with its top
+/// left corner at `origin`. The image must fit in the buffer.
+fn blit(
+    buffer: &mut [u32],
+    buffer_width: usize,
+    image: &[u32],
+    size: (usize, usize),
+    origin: (usize, usize),
+) {
+    for (y, row) in image.chunks_exact(size.0).enumerate() {
+        let start = (origin.1 + y) * buffer_width + origin.0;
+        buffer[start..start + size.0].copy_from_slice(row);
+    }
+}
+
+/// Size of the minimap of a grid shown in a pane, MINIMAP_SCALE times smaller than the grid but
+/// shrunk to cover at most MAX_MINIMAP_SHARE of the pane. None if it would be smaller than
+/// MIN_MINIMAP_SIZE, or wouldn't fit with its margin.
+fn fit_minimap(grid_size: (usize, usize), pane_size: (usize, usize)) -> Option<(usize, usize)> {
+    let size = (grid_size.0 / MINIMAP_SCALE, grid_size.1 / MINIMAP_SCALE);
+    let share = |size: usize, pane: usize| pane as f32 * MAX_MINIMAP_SHARE / size.max(1) as f32;
+    let shrink = share(size.0, pane_size.0)
+        .min(share(size.1, pane_size.1))
+        .min(1.0);
+    let size = (
+        (size.0 as f32 * shrink) as usize,
+        (size.1 as f32 * shrink) as usize,
+    );
+    let fits = size.0 + MINIMAP_MARGIN <= pane_size.0 && size.1 + MINIMAP_MARGIN <= pane_size.1;
+    (fits && size.0 >= MIN_MINIMAP_SIZE && size.1 >= MIN_MINIMAP_SIZE).then_some(size)
+}
+
+/// Where the minimap of the simulation in a pane was drawn, in pixels of the window buffer.
+#[derive(Clone, Copy, Debug)]
+struct MinimapArea {
+    pane: usize,
+    origin: (usize, usize),
+    size: (usize, usize),
+}
+
+impl MinimapArea {
+    fn contains(&self, position: (f32, f32)) -> bool {
+        let inside = |position: f32, origin: usize, size: usize| {
+            position >= origin as f32 && position < (origin + size) as f32
+        };
+        inside(position.0, self.origin.0, self.size.0)
+            && inside(position.1, self.origin.1, self.size.1)
+    }
+
+    /// Cell of a grid of the given size shown by the pixel of the minimap nearest to a position
+    /// in the buffer.
+    fn cell_at(&self, position: (f32, f32), grid_size: (usize, usize)) -> (usize, usize) {
+        let cell = |position: f32, origin: usize, size: usize, grid_len: usize| {
+            let pixel = (position - origin as f32).clamp(0.0, size as f32 - 1.0) as usize;
+            // the middle of the cells covered by the pixel
+            ((2 * pixel + 1) * grid_len / (2 * size.max(1))).min(grid_len.saturating_sub(1))
+        };
+        (
+            cell(position.0, self.origin.0, self.size.0, grid_size.0),
+            cell(position.1, self.origin.1, self.size.1, grid_size.1),
+        )
+    }
+}
+
+/// Draw the outline of the rectangle between two corners given in pixels, clamped to the buffer.
+fn draw_outline(
+    buffer: &mut [u32],
+    buffer_size: (usize, usize),
+    start: (f32, f32),
+    end: (f32, f32),
+    color: u32,
+) {
+    if buffer_size.0 == 0 || buffer_size.1 == 0 {
+        return;
+    }
+    let clamp = |position: f32, len: usize| (position.max(0.) as usize).min(len - 1);
+    let (left, right) = (
+        clamp(start.0.min(end.0), buffer_size.0),
+        clamp(start.0.max(end.0), buffer_size.0),
+    );
+    let (top, bottom) = (
+        clamp(start.1.min(end.1), buffer_size.1),
+        clamp(start.1.max(end.1), buffer_size.1),
+    );
+    for x in left..=right {
+        buffer[top * buffer_size.0 + x] = color;
+        buffer[bottom * buffer_size.0 + x] = color;
+    }
+    for y in top..=bottom {
+        buffer[y * buffer_size.0 + left] = color;
+        buffer[y * buffer_size.0 + right] = color;
+    }
+}
+
+/// Draw the outline of the circle around a center given in pixels, clipped to the buffer.
+fn draw_circle(
+    buffer: &mut [u32],
+    buffer_size: (usize, usize),
+    center: (f32, f32),
+    radius: f32,
+    color: u32,
+) {
+    // enough points along the circle for neighbouring ones to be at most a pixel apart
+    let steps = ((std::f32::consts::TAU * radius).ceil() as usize).max(8);
+    for step in 0..steps {
+        let angle = std::f32::consts::TAU * step as f32 / steps as f32;
+        let (x, y) = (
+            center.0 + radius * angle.cos(),
+            center.1 + radius * angle.sin(),
+        );
+        if x >= 0. && y >= 0. && (x as usize) < buffer_size.0 && (y as usize) < buffer_size.1 {
+            buffer[y as usize * buffer_size.0 + x as usize] = color;
+        }
+    }
+}
+
+/// Fill the circle around a center given in pixels, clipped to the buffer.
+fn fill_circle(
+    buffer: &mut [u32],
+    buffer_size: (usize, usize),
+    center: (f32, f32),
+    radius: f32,
+    color: u32,
+) {
+    let clamp = |position: f32, len: usize| (position.max(0.) as usize).min(len);
+    let (left, right) = (
+        clamp(center.0 - radius, buffer_size.0),
+        clamp(center.0 + radius + 1., buffer_size.0),
+    );
+    let (top, bottom) = (
+        clamp(center.1 - radius, buffer_size.1),
+        clamp(center.1 + radius + 1., buffer_size.1),
+    );
+    for y in top..bottom {
+        for x in left..right {
+            let (dx, dy) = (x as f32 + 0.5 - center.0, y as f32 + 0.5 - center.1);
+            if dx * dx + dy * dy <= radius * radius {
+                buffer[y * buffer_size.0 + x] = color;
+            }
+        }
+    }
+}
+
+/// Cells covered by the rectangle between two corners given in pixels of the window relative to a
+/// pane, or None when both corners are on the same cell, so a click covers nothing. The rectangle
+/// may extend past the right and bottom edges of the grid.
+fn dragged_region(
+    start: (f32, f32),
+    end: (f32, f32),
+    camera: &Camera,
+    scale: usize,
+    grid_size: (usize, usize),
+) -> Option<Rect> {
+    let cell = |screen: (f32, f32)| {
+        let screen = (screen.0.max(0.), screen.1.max(0.));
+        camera
+            .screen_to_cell(screen, scale, grid_size)
+            .unwrap_or_default()
+    };
+    let (start, end) = (cell(start), cell(end));
+    if start == end {
+        return None;
+    }
+    // first cell and number of cells covered along one axis
+    let span = |a: usize, b: usize, grid_len: usize| (a.min(b) % grid_len, a.abs_diff(b) + 1);
+    let (x, width) = span(start.0, end.0, grid_size.0);
+    let (y, height) = span(start.1, end.1, grid_size.1);
+    Some(Rect {
+        x,
+        y,
+        width,
+        height,
+    })
+}
+
+/// Pane under the mouse cursor and the grid coordinates of the cell under it in the simulation
+/// shown there, if the cursor is on a pane.
+fn cursor_cell(
+    window: &Window,
+    layout: Layout,
+    scale: usize,
+    simulations: &[Simulation],
+    panes: &[Pane],
+) -> Option<(usize, (usize, usize))> {
+    let (pane, position) = cursor_in_pane(window, layout, scale, MouseMode::Discard)?;
+    let camera = &panes[pane].camera;
+    let cell = camera.screen_to_grid(position, scale, simulations[pane].size())?;
+    Some((pane, cell))
+}
+
+/// Pane under the mouse cursor and the position of the cell under it in the simulation shown
+/// there, counting cells of the repeated grid past its right and bottom edges instead of wrapping
+/// around, so positions in consecutive frames can be joined by a line.
+fn cursor_position(
+    window: &Window,
+    layout: Layout,
+    scale: usize,
+    simulations: &[Simulation],
+    panes: &[Pane],
+) -> Option<(usize, (isize, isize))> {
+    let (pane, position) = cursor_in_pane(window, layout, scale, MouseMode::Discard)?;
+    let camera = &panes[pane].camera;
+    let (x, y) = camera.screen_to_cell(position, scale, simulations[pane].size())?;
+    Some((pane, (x as isize, y as isize)))
+}
+
+/// Multi-line description of a mold looked up at (x, y), with the genes it can reach from the
+/// gene every new mold starts with, one per line.
+fn inspection_report(info: &MoldInfo, x: usize, y: usize) -> String {
+    let kind = match info.active_gene {
+        Some(gene) => format!("cell with active gene {gene}"),
+        None => "spore".to_string(),
+    };
+    let mut report = format!(
+        "mold {} at ({x}, {y}), {kind} of age {}\n\
+         energy: {}, cells: {}, generation: {} (founder: mold {})\n\
+         bounds: {}x{} from ({}, {})\n\
+         color: #{:06X}, genes reachable from gene 0:",
+        info.id.0,
+        info.age,
+        info.energy,
+        info.cells,
+        info.generation,
+        info.founder.0,
+        info.bounds.width,
+        info.bounds.height,
+        info.bounds.x,
+        info.bounds.y,
+        info.genome.color(),
+    );
+    for (index, gene) in reachable_genes(info.genome.genes()) {
+        let [left, straight, right] = gene.map(describe_gene_value);
+        report.push_str(&format!(
+            "\n  {index:>3}: left {left}, straight {straight}, right {right}"
+        ));
+    }
+    report
+}
+
+/// Genes that can become active starting from gene 0, in the order they are first reached.
+fn reachable_genes(genes: &[isize]) -> Vec<(usize, [isize; GENE_DIRECTIONS])> {
+    let gene_count = genes.len() / GENE_DIRECTIONS;
+    let mut reached = vec![false; gene_count];
+    let mut queue = std::collections::VecDeque::from([0]);
+    reached[0] = true;
+    let mut reachable = Vec::new();
+    while let Some(index) = queue.pop_front() {
+        let start = index * GENE_DIRECTIONS;
+        let gene: [isize; GENE_DIRECTIONS] =
+            genes[start..start + GENE_DIRECTIONS].try_into().unwrap();
+        for &next in &gene {
+            if next >= 0 && !reached[next as usize] {
+                reached[next as usize] = true;
+                queue.push_back(next as usize);
+            }
+        }
+        reachable.push((index, gene));
+    }
+    reachable
+}
+
+/// What a single value of a gene does, see Genome::genes.
+fn describe_gene_value(value: isize) -> String {
+    match value {
+        -2 => "stop".to_string(),
+        -1 => "spore".to_string(),
+        next => format!("-> {next}"),
+    }
+}
+
+/// Title bar description of the cell at (x, y): its coordinates and the mold occupying it.
+fn describe_cell(simulation: &rustymold::Simulation, x: usize, y: usize) -> String {
+    match (
+        simulation.mold_id_at(x, y),
+        simulation.mold_color_at(x, y),
+        simulation.mold_energy_at(x, y),
+    ) {
+        (Some(id), Some(color), Some(energy)) => {
+            let ripe = if simulation.is_ripe_spore(x, y) {
+                ", ripe spore"
+            } else {
+                ""
+            };
+            format!(
+                " - ({x}, {y}): mold {} #{color:06X}, energy {energy}{ripe}",
+                id.0
+            )
+        }
+        _ => format!(" - ({x}, {y}): empty"),
+    }
+}
+
+/// Name of a tool with its settings: the brush size, or the genome picked with the eyedropper.
+fn describe_tool(tool: Tool, brush_radius: usize, picked: &Option<(MoldId, Genome)>) -> String {
+    match (tool, picked) {
+        (Tool::Walls | Tool::Light, _) => format!("{}, brush {brush_radius}", tool.name()),
+        (Tool::Stamp, Some((id, genome))) => {
+            format!("{} mold {} #{:06X}", tool.name(), id.0, genome.color())
+        }
+        _ => tool.name().to_string(),
+    }
+}
+
+/// The spore style following `style` when cycling through them with the U key.
+fn next_spore_style(style: SporeStyle) -> SporeStyle {
+    match style {
+        SporeStyle::Invert => SporeStyle::Blink {
+            period: SPORE_BLINK_PERIOD,
+        },
+        SporeStyle::Blink { .. } => SporeStyle::Ring,
+        SporeStyle::Ring => SporeStyle::Plain,
+        SporeStyle::Plain => SporeStyle::Fixed(FIXED_SPORE_COLOR),
+        SporeStyle::Fixed(_) => SporeStyle::Brighten,
+        SporeStyle::Brighten => SporeStyle::Invert,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dragged_region_wraps_across_the_seam() {
+        let grid_size = (10, 8);
+        // the first column of the view shows cell 7, with cells 2 pixels wide
+        let camera = Camera {
+            position: (14., 0.),
+            zoom: 2.,
+        };
+        let region = Rect {
+            x: 7,
+            y: 0,
+            width: 5,
+            height: 3,
+        };
+        let drag = |start, end| dragged_region(start, end, &camera, 1, grid_size);
+        assert_eq!(drag((0., 0.), (9., 5.)), Some(region));
+        assert_eq!(drag((9., 5.), (0., 0.)), Some(region));
+        // corners left of and above the view are on its first cells
+        assert_eq!(drag((-30., -4.), (9., 5.)), Some(region));
+        assert_eq!(drag((6., 2.), (7.5, 3.)), None);
+    }
+
+    #[test]
+    fn digit_keys_set_steps_per_frame() {
+        let presets: Vec<_> = SPEED_KEYS
+            .into_iter()
+            .enumerate()
+            .map(|(preset, key)| (key, Speed::preset(preset, false)))
+            .collect();
+        let digits = [
+            Key::Key1,
+            Key::Key2,
+            Key::Key3,
+            Key::Key4,
+            Key::Key5,
+            Key::Key6,
+            Key::Key7,
+            Key::Key8,
+            Key::Key9,
+        ];
+        let steps = [1, 2, 4, 8, 16, 32, 64, 128, 256].map(Speed::PerFrame);
+        assert_eq!(presets, digits.into_iter().zip(steps).collect::<Vec<_>>());
+        // with shift the same keys slow down instead, except for 1
+        assert_eq!(Speed::preset(0, true), Speed::PerFrame(1));
+        assert_eq!(Speed::preset(3, true), Speed::Slow(8));
+    }
+
+    #[test]
+    fn selection_wraps_across_the_seam() {
+        let selection = |start, end| Selection {
+            pane: 0,
+            start,
+            end,
+            dragging: false,
+        };
+        let rect = |x, y, width, height| Rect {
+            x,
+            y,
+            width,
+            height,
+        };
+        let grid_size = (10, 8);
+        assert_eq!(selection((12, 3), (8, 1)).rect(grid_size), rect(8, 1, 5, 3));
+        assert_eq!(selection((-2, 9), (1, 9)).rect(grid_size), rect(8, 1, 4, 1));
+        // a selection larger than the grid covers all of it once
+        assert_eq!(
+            selection((3, 0), (25, 20)).rect(grid_size),
+            rect(3, 0, 10, 8)
+        );
+    }
 }
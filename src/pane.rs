@@ -0,0 +1,151 @@
+//! Everything about a simulation shown in one pane of the window apart from the simulation
+//! itself, so the simulations compared side by side can be looked at separately.
+
+use rustymold::{Camera, MoldId, Simulation};
+
+use crate::cli::Options;
+use crate::graph::PopulationGraph;
+use crate::reseed::Reseed;
+use crate::view::ViewState;
+
+/// ticks between finding the center of a followed mold again
+const FOLLOW_INTERVAL: u64 = 8;
+
+/// State of one pane, kept when its simulation is cleared or replaced.
+#[derive(Clone, Debug)]
+pub struct Pane {
+    /// What is shown of the simulation.
+    pub view: ViewState,
+    /// The part of the grid shown and how far it is zoomed in.
+    pub camera: Camera,
+    /// Population of the simulation over time, recorded while the graph is hidden too.
+    pub graph: PopulationGraph,
+    /// Epochs of the simulation, started over with new molds when they died out while
+    /// autoreseed is on.
+    pub reseed: Reseed,
+    /// How the simulation was set up, to start it over.
+    pub origin: Origin,
+    /// The camera following the watched mold, if it does.
+    pub follow: Option<Follow>,
+}
+
+impl Pane {
+    pub fn new(view: ViewState, camera: Camera, origin: Origin) -> Self {
+        Self {
+            view,
+            camera,
+            graph: PopulationGraph::default(),
+            reseed: Reseed::new(),
+            origin,
+            follow: None,
+        }
+    }
+}
+
+/// Where the camera of a pane following the watched mold is heading.
+#[derive(Clone, Copy, Debug)]
+pub struct Follow {
+    /// The mold the target is the center of.
+    pub mold: MoldId,
+    /// Center of the mold in cells, which the camera eases toward.
+    pub target: (f32, f32),
+    /// Tick of the simulation when the target was found.
+    pub found_at: u64,
+}
+
+impl Follow {
+    /// Start following the mold watched in `simulation`, or None if no mold with cells is
+    /// watched.
+    pub fn start(simulation: &Simulation) -> Option<Self> {
+        let watched = simulation.watched()?;
+        let target = simulation.mold_centroid_of(watched.id)?;
+        Some(Self {
+            mold: watched.id,
+            target,
+            found_at: simulation.tick(),
+        })
+    }
+
+    /// Find the center of the watched mold again when another mold is watched or the target is
+    /// FOLLOW_INTERVAL ticks old. Returns false once no mold is watched, so following stops.
+    pub fn retarget(&mut self, simulation: &Simulation) -> bool {
+        let Some(watched) = simulation.watched() else {
+            return false;
+        };
+        let tick = simulation.tick();
+        if watched.id != self.mold || tick.abs_diff(self.found_at) >= FOLLOW_INTERVAL {
+            if let Some(target) = simulation.mold_centroid_of(watched.id) {
+                self.target = target;
+            }
+            self.mold = watched.id;
+            self.found_at = tick;
+        }
+        true
+    }
+}
+
+/// Everything done to set up a simulation before its first update, so it can be set up again
+/// exactly the same way.
+#[derive(Clone, Debug)]
+pub struct Origin {
+    /// Options the simulation was started with, with the seed it was given. The seed is None
+    /// only for a loaded save, which continues with the random state it was saved with.
+    pub options: Options,
+    /// Number of random molds scattered on a new grid when the options don't give a density.
+    pub molds: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustymold::Edges;
+
+    /// A grid with two single cell molds that don't grow, at (3, 3) and (12, 6).
+    fn two_molds() -> (Simulation, MoldId, MoldId) {
+        let mut simulation = Simulation::builder(20, 10).edges(Edges::Bounded).build();
+        simulation.set_max_population(Some(0));
+        for (x, y) in [(3, 3), (12, 6)] {
+            assert!(simulation.generate_mold(x, y));
+            assert!(simulation.add_energy(x, y, 1_000_000));
+        }
+        let id = |x, y| simulation.mold_at(x, y).unwrap().id;
+        let (first, second) = (id(3, 3), id(12, 6));
+        (simulation, first, second)
+    }
+
+    #[test]
+    fn follow_needs_watched_mold() {
+        let (mut simulation, first, _) = two_molds();
+        assert!(Follow::start(&simulation).is_none());
+        simulation.watch(Some(first));
+        let follow = Follow::start(&simulation).unwrap();
+        assert_eq!(follow.mold, first);
+        assert_eq!(follow.target, (3.5, 3.5));
+        assert_eq!(follow.found_at, 0);
+    }
+
+    #[test]
+    fn follow_retargets_every_interval_and_on_new_mold() {
+        let (mut simulation, first, second) = two_molds();
+        simulation.watch(Some(first));
+        let mut follow = Follow::start(&simulation).unwrap();
+        follow.target = (0., 0.);
+        simulation.run(FOLLOW_INTERVAL as usize - 1);
+        assert!(follow.retarget(&simulation));
+        assert_eq!((follow.target, follow.found_at), ((0., 0.), 0));
+        simulation.run(1);
+        assert!(follow.retarget(&simulation));
+        assert_eq!(
+            (follow.target, follow.found_at),
+            ((3.5, 3.5), FOLLOW_INTERVAL)
+        );
+
+        simulation.watch(Some(second));
+        assert!(follow.retarget(&simulation));
+        assert_eq!(follow.mold, second);
+        assert_eq!(follow.target, (12.5, 6.5));
+
+        simulation.watch(None);
+        assert!(!follow.retarget(&simulation));
+    }
+}
@@ -0,0 +1,156 @@
+//! Recording the window to an animated PNG, one frame at a time.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::capture::xrgb_to_rgb8;
+use crate::saves::timestamped_path;
+
+/// directory recordings are written to, relative to the working directory
+pub const RECORDING_DIR: &str = "recordings";
+
+/// An animated PNG being written. Every frame is compressed and written to the file as soon as it
+/// is added, so memory use doesn't grow with the length of the recording.
+pub struct Recording {
+    writer: png::Writer<BufWriter<File>>,
+    path: PathBuf,
+    /// size of the buffers frames are taken from
+    buffer_size: (usize, usize),
+    /// number of pixels along each side of the square averaged into one pixel of a frame
+    downscale: usize,
+    frames: u32,
+}
+
+impl Recording {
+    /// Start a recording of buffers of buffer_size pixels in a new file in RECORDING_DIR, with
+    /// every frame shown for delay.0 / delay.1 seconds.
+    pub fn start(
+        buffer_size: (usize, usize),
+        downscale: usize,
+        delay: (u16, u16),
+    ) -> Result<Self, String> {
+        let downscale = downscale.max(1);
+        let size = (
+            buffer_size.0.div_ceil(downscale),
+            buffer_size.1.div_ceil(downscale),
+        );
+        if size.0 == 0 || size.1 == 0 {
+            return Err("cannot record an empty window".to_string());
+        }
+        let path = timestamped_path(RECORDING_DIR, "recording", "png")?;
+        let error = |e: &dyn std::fmt::Display| format!("cannot write {}: {e}", path.display());
+
+        let file = File::create(&path).map_err(|e| error(&e))?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), size.0 as u32, size.1 as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        // the number of frames isn't known yet, it is filled in by finish
+        encoder
+            .set_animated(u32::MAX, 0)
+            .and_then(|()| encoder.set_frame_delay(delay.0, delay.1))
+            .map_err(|e| error(&e))?;
+        let writer = encoder.write_header().map_err(|e| error(&e))?;
+        Ok(Self {
+            writer,
+            path,
+            buffer_size,
+            downscale,
+            frames: 0,
+        })
+    }
+
+    /// Size of the buffers this recording takes frames from.
+    pub fn buffer_size(&self) -> (usize, usize) {
+        self.buffer_size
+    }
+
+    /// Number of frames recorded so far.
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    /// Append a buffer of 0RGB pixels with the size given to start as the next frame.
+    pub fn add_frame(&mut self, pixels: &[u32]) -> Result<(), String> {
+        let (width, height) = self.buffer_size;
+        let pixels = &pixels[..width * height];
+        let rgb = if self.downscale > 1 {
+            xrgb_to_rgb8(&downscale(pixels, self.buffer_size, self.downscale))
+        } else {
+            xrgb_to_rgb8(pixels)
+        };
+        self.writer
+            .write_image_data(&rgb)
+            .map_err(|e| format!("cannot write {}: {e}", self.path.display()))?;
+        self.frames += 1;
+        Ok(())
+    }
+
+    /// Complete the file and return its path. A recording without frames is deleted.
+    pub fn finish(self) -> Result<PathBuf, String> {
+        let error =
+            |e: &dyn std::fmt::Display| format!("cannot write {}: {e}", self.path.display());
+        if self.frames == 0 {
+            drop(self.writer);
+            let _ = fs::remove_file(&self.path);
+            return Err("nothing was recorded".to_string());
+        }
+        self.writer.finish().map_err(|e| error(&e))?;
+        set_frame_count(&self.path, self.frames).map_err(|e| error(&e))?;
+        Ok(self.path)
+    }
+}
+
+/// Average every square of factor x factor pixels of a 0RGB buffer into one pixel. Squares on the
+/// right and bottom edges average the pixels that are left.
+fn downscale(pixels: &[u32], size: (usize, usize), factor: usize) -> Vec<u32> {
+    let (width, height) = (size.0.div_ceil(factor), size.1.div_ceil(factor));
+    let mut scaled = Vec::with_capacity(width * height);
+    for y in 0..height {
+        let rows = y * factor..((y + 1) * factor).min(size.1);
+        for x in 0..width {
+            let columns = x * factor..((x + 1) * factor).min(size.0);
+            let mut sum = [0u32; 3];
+            for row in rows.clone() {
+                for &pixel in &pixels[row * size.0 + columns.start..row * size.0 + columns.end] {
+                    sum[0] += (pixel >> 16) & 0xFF;
+                    sum[1] += (pixel >> 8) & 0xFF;
+                    sum[2] += pixel & 0xFF;
+                }
+            }
+            let count = (rows.len() * columns.len()) as u32;
+            let [r, g, b] = sum.map(|channel| (channel + count / 2) / count);
+            scaled.push((r << 16) | (g << 8) | b);
+        }
+    }
+    scaled
+}
+
+/// Overwrite the number of frames in the animation control chunk of a finished animated PNG.
+fn set_frame_count(path: &Path, frames: u32) -> io::Result<()> {
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+    // chunks start after the 8 byte signature, each with its length, type, data and checksum
+    let mut position = 8;
+    loop {
+        file.seek(SeekFrom::Start(position))?;
+        let mut header = [0; 8];
+        file.read_exact(&mut header)?;
+        let length = u32::from_be_bytes(header[..4].try_into().unwrap()) as u64;
+        match &header[4..] {
+            b"acTL" => break,
+            b"IDAT" | b"IEND" => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "no animation control chunk",
+                ))
+            }
+            _ => position += 12 + length,
+        }
+    }
+    let mut chunk = *b"acTL\0\0\0\0\0\0\0\0";
+    file.read_exact(&mut chunk[4..])?;
+    chunk[4..8].copy_from_slice(&frames.to_be_bytes());
+    file.seek(SeekFrom::Start(position + 8))?;
+    file.write_all(&chunk[4..])?;
+    file.write_all(&crc32fast::hash(&chunk).to_be_bytes())
+}
@@ -0,0 +1,2333 @@
+use std::collections::HashSet;
+use std::ops::Range;
+
+use crate::color::{add_colors, blend, brighten, hsv_to_rgb, scale_color, PixelFormat};
+use crate::{Cell, Colormap, Genome, Mold, MoldId, Rect, Simulation, SpeciesId, SPORE_RIPING_AGE};
+
+mod constraint;
+mod overlay;
+#[cfg(feature = "parallel")]
+mod parallel;
+
+pub use constraint::PaletteConstraint;
+pub(crate) use constraint::PaletteRemap;
+pub use overlay::Overlay;
+
+/// brightness (out of 256) of spores that are not ripe yet
+const UNRIPE_SPORE_BRIGHTNESS: u32 = 150;
+/// brightness (out of 256) of molds in the activity view
+const ACTIVITY_MOLD_BRIGHTNESS: u32 = 96;
+/// minimum zoom level at which ripe spores are drawn as rings
+const MIN_RING_ZOOM: usize = 4;
+const WHITE: u32 = 0xFFFFFF;
+/// difference in hue in degrees between lineages of consecutive founders
+const LINEAGE_HUE_STEP: f64 = 137.507_764;
+/// number of generations after which the brightness of a lineage repeats
+const LINEAGE_SHADES: u32 = 6;
+/// difference in value between the brightest and darkest generation of a lineage
+const LINEAGE_VALUE_RANGE: f32 = 0.5;
+
+/// energy shown with the last color of the colormap in the energy view
+const ENERGY_VIEW_MAX: f32 = 1_000_000.;
+
+/// saturation and value used for the colors of the species and lineage views
+const SPECIES_SATURATION: f32 = 0.8;
+const SPECIES_VALUE: f32 = 0.95;
+
+/// What the color of a mold cell represents.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ViewMode {
+    /// The color stored in the genome.
+    #[default]
+    Color,
+    /// A hue derived from a hash of the genes, so identical genomes always look identical and
+    /// any mutation gives a visibly different color.
+    SpeciesHash,
+    /// A hue derived from the founder of the lineage, so all descendants of a mold share its hue
+    /// however much their genes changed. The brightness varies with the number of generations.
+    Lineage,
+    /// The energy of the mold, through the colormap on a logarithmic scale.
+    Energy,
+    /// A glow with the activity color of the palette where molds recently grew or sprouted, over
+    /// the molds in their genome colors dimmed. Shows no glow unless activity is tracked, see
+    /// Simulation::set_activity_trail.
+    Activity,
+}
+
+/// Settings for rendering the whole grid to an image, independent of the view mode and camera
+/// used for the window.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RenderOptions {
+    pub view_mode: ViewMode,
+    /// Size of a cell in pixels. A scale of 0 is treated as 1.
+    pub scale: usize,
+    /// Number of cells along each side of a pixel, for overviews of grids larger than the image.
+    /// The colors of the cells covered by a pixel are averaged. Used instead of scale when
+    /// greater than 1.
+    pub downscale: usize,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            view_mode: ViewMode::Color,
+            scale: 1,
+            downscale: 1,
+        }
+    }
+}
+
+/// View on the grid: which part of it is visible in a buffer and how far it is zoomed in.
+///
+/// Pixel (x, y) of the buffer shows the cell containing the point (x + 0.5, y + 0.5) +
+/// position of the zoomed grid, so every pixel shows the cell under its center.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    /// Position of the top left corner of the buffer in zoomed pixels. It may lie anywhere, the
+    /// grid wraps around.
+    pub position: (f32, f32),
+    /// Size of a cell in pixels, at least 1. It may be fractional, whole zooms render faster.
+    pub zoom: f32,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            position: (0., 0.),
+            zoom: 1.,
+        }
+    }
+}
+
+impl Camera {
+    /// Area of the grid visible in a buffer of the given size, in grid coordinates. The area may
+    /// extend past the right and bottom edge of the grid, in which case it wraps around.
+    pub fn viewport(&self, buffer_size: (usize, usize), grid_size: (usize, usize)) -> Rect {
+        let (x, y) = self.pixel_to_cell((0, 0), grid_size);
+        let zoom = self.clamped_zoom();
+        let cells = |buffer_len: usize| (buffer_len as f64 / zoom).ceil() as usize;
+        Rect {
+            x: x % grid_size.0.max(1),
+            y: y % grid_size.1.max(1),
+            width: cells(buffer_size.0).min(grid_size.0),
+            height: cells(buffer_size.1).min(grid_size.1),
+        }
+    }
+
+    /// The zoom as a whole number of pixels per cell, or None if it is fractional.
+    pub fn whole_zoom(&self) -> Option<usize> {
+        let zoom = self.clamped_zoom();
+        (zoom.fract() == 0.).then_some(zoom as usize)
+    }
+
+    /// Offset of the buffer in pixels for a grid of the given size, wrapped into the zoomed grid.
+    /// Pixel (x, y) shows cell ((x + offset.0) / zoom, (y + offset.1) / zoom) when the zoom is
+    /// whole, otherwise the offset is only rounded to the nearest pixel.
+    pub fn offset(&self, grid_size: (usize, usize)) -> (usize, usize) {
+        let zoom = self.clamped_zoom();
+        let (x, y) = self.wrapped_position(grid_size);
+        let wrap = |position: f64, grid_len: usize| {
+            let period = (grid_len as f64 * zoom).ceil() as usize;
+            (position + 0.5) as usize % period.max(1)
+        };
+        (wrap(x, grid_size.0), wrap(y, grid_size.1))
+    }
+
+    /// Cell of a grid of the given size shown by a pixel of the buffer, counting cells of the
+    /// grid repeated past its right and bottom edges instead of wrapping around.
+    pub fn pixel_to_cell(
+        &self,
+        pixel: (usize, usize),
+        grid_size: (usize, usize),
+    ) -> (usize, usize) {
+        let zoom = self.clamped_zoom();
+        let (x, y) = self.wrapped_position(grid_size);
+        (
+            cell_along(pixel.0 as f64, x, zoom) as usize,
+            cell_along(pixel.1 as f64, y, zoom) as usize,
+        )
+    }
+
+    /// Whether a pixel of the buffer shows another cell than the pixel left of it, and than the
+    /// pixel above it, which makes it the first pixel of a column or row of cells. With a
+    /// fractional zoom cells do not all cover the same number of pixels.
+    pub fn is_cell_edge(&self, pixel: (usize, usize), grid_size: (usize, usize)) -> (bool, bool) {
+        let zoom = self.clamped_zoom();
+        let (x, y) = self.wrapped_position(grid_size);
+        let is_edge = |pixel: usize, position: f64| {
+            cell_along(pixel as f64, position, zoom)
+                != cell_along(pixel as f64 - 1., position, zoom)
+        };
+        (is_edge(pixel.0, x), is_edge(pixel.1, y))
+    }
+
+    /// Cell of a grid of the given size under a point of a window showing it, counting cells of
+    /// the grid repeated past its right and bottom edges instead of wrapping around. The point is
+    /// in pixels of the window relative to the top left corner of the view, which shows every
+    /// pixel of the buffer as `scale` x `scale` pixels. None for points left of or above the view.
+    pub fn screen_to_cell(
+        &self,
+        screen: (f32, f32),
+        scale: usize,
+        grid_size: (usize, usize),
+    ) -> Option<(usize, usize)> {
+        let (x, y) = screen_to_buffer(screen, scale);
+        // also rejects NaN
+        if !(x >= 0. && y >= 0.) {
+            return None;
+        }
+        Some(self.pixel_to_cell((x as usize, y as usize), grid_size))
+    }
+
+    /// Cell of a grid of the given size under a point of a window showing it, like
+    /// screen_to_cell but wrapped into the grid.
+    pub fn screen_to_grid(
+        &self,
+        screen: (f32, f32),
+        scale: usize,
+        grid_size: (usize, usize),
+    ) -> Option<(usize, usize)> {
+        let (x, y) = self.screen_to_cell(screen, scale, grid_size)?;
+        Some((x % grid_size.0, y % grid_size.1))
+    }
+
+    /// Point of a window showing a grid of the given size at the top left corner of the first
+    /// pixel showing a cell, the inverse of screen_to_cell. The cell may lie past the edges of
+    /// the grid as with screen_to_cell, and the point outside the view. With a scale of 1 the
+    /// point is in pixels of the buffer.
+    pub fn grid_to_screen(
+        &self,
+        cell: (usize, usize),
+        scale: usize,
+        grid_size: (usize, usize),
+    ) -> (f32, f32) {
+        let zoom = self.clamped_zoom();
+        let (x, y) = self.wrapped_position(grid_size);
+        let scale = scale.max(1) as f32;
+        let pixel = |cell: usize, position: f64| {
+            (cell as f64 * zoom - position - 0.5).ceil() as f32 * scale
+        };
+        (pixel(cell.0, x), pixel(cell.1, y))
+    }
+
+    /// Center the view in a buffer of the given size on a point of the grid, in cells.
+    pub fn recenter_on(&mut self, point: (f32, f32), buffer_size: (usize, usize)) {
+        let zoom = self.clamped_zoom() as f32;
+        self.position = (
+            point.0 * zoom - buffer_size.0 as f32 / 2.,
+            point.1 * zoom - buffer_size.1 as f32 / 2.,
+        );
+    }
+
+    /// Move the view in a buffer of the given size part of the way toward centering it on a point
+    /// of the grid like recenter_on, by a fraction from 0 (not at all) to 1 (all the way). The
+    /// view takes the shortest way around a grid of the given size, so a point moving across an
+    /// edge doesn't make it sweep across the whole grid.
+    pub fn ease_toward(
+        &mut self,
+        point: (f32, f32),
+        buffer_size: (usize, usize),
+        grid_size: (usize, usize),
+        fraction: f32,
+    ) {
+        let zoom = self.clamped_zoom() as f32;
+        let ease = |position: f32, point: f32, buffer_len: usize, grid_len: usize| {
+            let period = (grid_len as f32 * zoom).max(1.);
+            let target = point * zoom - buffer_len as f32 / 2.;
+            let distance = (target - position + period / 2.).rem_euclid(period) - period / 2.;
+            position + distance * fraction.clamp(0., 1.)
+        };
+        self.position = (
+            ease(self.position.0, point.0, buffer_size.0, grid_size.0),
+            ease(self.position.1, point.1, buffer_size.1, grid_size.1),
+        );
+    }
+
+    /// Keep the view in a buffer of the given size from going past the edges of a grid of the
+    /// given size, as the grid does when its edges are bounded: the top left corner of the buffer
+    /// stays on the grid, and the bottom right one too unless the zoomed grid is smaller than the
+    /// buffer, which then shows the grid from its top left corner.
+    pub fn clamp_to_grid(&mut self, grid_size: (usize, usize), buffer_size: (usize, usize)) {
+        let zoom = self.clamped_zoom();
+        let clamp = |position: f32, grid_len: usize, buffer_len: usize| {
+            let max = (grid_len as f64 * zoom - buffer_len as f64).max(0.);
+            f64::from(position).clamp(0., max) as f32
+        };
+        self.position = (
+            clamp(self.position.0, grid_size.0, buffer_size.0),
+            clamp(self.position.1, grid_size.1, buffer_size.1),
+        );
+    }
+
+    /// Wrap the position around into the pixels of a single copy of a grid of the given size,
+    /// which shows the same part of the grid, so it stays small and precise however far the view
+    /// is panned.
+    pub fn normalize(&mut self, grid_size: (usize, usize)) {
+        let (x, y) = self.wrapped_position(grid_size);
+        self.position = (x as f32, y as f32);
+    }
+
+    /// Change the zoom, keeping the cell under a point of the window in place. The point is
+    /// given as for screen_to_cell.
+    pub fn zoom_at(&mut self, zoom: f32, screen: (f32, f32), scale: usize) {
+        let (x, y) = screen_to_buffer(screen, scale);
+        let previous = self.clamped_zoom() as f32;
+        self.zoom = zoom.max(1.);
+        let ratio = self.zoom / previous;
+        // the center of the pixel under the point shows the same spot of the grid afterwards
+        let anchor = |position: f32, screen: f32| {
+            let center = screen.floor() + 0.5;
+            (position + center) * ratio - center
+        };
+        self.position = (anchor(self.position.0, x), anchor(self.position.1, y));
+    }
+
+    /// The zoom, at least 1.
+    fn clamped_zoom(&self) -> f64 {
+        // also replaces NaN
+        f64::from(self.zoom.max(1.))
+    }
+
+    /// The position wrapped into the zoomed pixels of a single copy of a grid of the given size.
+    fn wrapped_position(&self, grid_size: (usize, usize)) -> (f64, f64) {
+        let zoom = self.clamped_zoom();
+        let wrap = |position: f32, grid_len: usize| {
+            let period = grid_len.max(1) as f64 * zoom;
+            // rem_euclid of a tiny negative position rounds up to the period itself
+            let wrapped = f64::from(position).rem_euclid(period);
+            if wrapped >= period {
+                0.
+            } else {
+                wrapped
+            }
+        };
+        (
+            wrap(self.position.0, grid_size.0),
+            wrap(self.position.1, grid_size.1),
+        )
+    }
+}
+
+/// Cell along one axis shown by a pixel, for a wrapped position and a zoom of at least 1. The
+/// cell before the first may be -1.
+fn cell_along(pixel: f64, position: f64, zoom: f64) -> f64 {
+    ((pixel + 0.5 + position) / zoom).floor()
+}
+
+/// Position in the buffer of a point of a window showing every pixel of the buffer as `scale` x
+/// `scale` pixels.
+pub fn screen_to_buffer(screen: (f32, f32), scale: usize) -> (f32, f32) {
+    let scale = scale.max(1) as f32;
+    (screen.0 / scale, screen.1 / scale)
+}
+
+/// Rectangle of a larger buffer to render into, so several images can be composed in one buffer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BufferRegion {
+    /// Position in the buffer of the top left pixel of the region.
+    pub origin: (usize, usize),
+    /// Size of the region in pixels.
+    pub size: (usize, usize),
+    /// Number of pixels in a row of the buffer.
+    pub stride: usize,
+}
+
+impl BufferRegion {
+    /// The whole of a buffer of the given size.
+    pub fn whole(buffer_size: (usize, usize)) -> Self {
+        Self {
+            origin: (0, 0),
+            size: buffer_size,
+            stride: buffer_size.0,
+        }
+    }
+
+    /// The part of the region that lies inside a buffer of `buffer_len` pixels: within its rows,
+    /// and above its last complete row.
+    pub fn clipped(self, buffer_len: usize) -> Self {
+        let rows = buffer_len.checked_div(self.stride).unwrap_or(0);
+        Self {
+            size: (
+                self.size.0.min(self.stride.saturating_sub(self.origin.0)),
+                self.size.1.min(rows.saturating_sub(self.origin.1)),
+            ),
+            ..self
+        }
+    }
+
+    /// Index in the buffer of the pixel at (x, y) relative to the origin.
+    #[inline]
+    fn index(&self, x: usize, y: usize) -> usize {
+        (self.origin.1 + y) * self.stride + self.origin.0 + x
+    }
+}
+
+/// What the buffer passed to render_dirty looked like after the previous call.
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) struct RenderStamp {
+    camera_offset: (usize, usize),
+    zoom: (usize, usize),
+    /// The camera when its zoom is fractional and the offset is only approximate.
+    fractional: Option<Camera>,
+    region: BufferRegion,
+    revision: u64,
+    energy_light: i32,
+}
+
+/// Colors used for everything that is not drawn in the color of a mold.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RenderPalette {
+    /// Color of empty cells.
+    pub background: u32,
+    /// Color of wall cells.
+    pub wall: u32,
+    /// Tint of empty cells with the light overlay, stronger where there is more light.
+    pub light: u32,
+    /// Color of predator cells.
+    pub predator: u32,
+    /// Tint of empty cells where molds have been, when trails are tracked.
+    pub trail: u32,
+    /// Glow added where molds recently grew in the activity view.
+    pub activity: u32,
+    /// Columns of the population sparkline.
+    pub sparkline: u32,
+    /// Outline of the visible area on the minimap.
+    pub viewport: u32,
+    /// Brightness of cells that are not highlighted while a mold is highlighted, from 0 to 1.
+    pub highlight_dim: f32,
+}
+
+impl Default for RenderPalette {
+    fn default() -> Self {
+        Self {
+            background: 0x000000,
+            wall: 0x808080,
+            light: 0xFFFF00,
+            predator: 0xFF2020,
+            trail: 0x4060A0,
+            activity: 0xFF9030,
+            sparkline: 0x40C040,
+            viewport: 0xFFFFFF,
+            highlight_dim: 0.3,
+        }
+    }
+}
+
+/// How spores are drawn. Spores that are not ripe yet are always drawn dimmer than normal cells.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SporeStyle {
+    /// Ripe spores are drawn in the inverted color of their mold.
+    #[default]
+    Invert,
+    /// Ripe spores alternate between the color of their mold and white, switching every `period` ticks.
+    Blink { period: u64 },
+    /// Ripe spores are drawn as hollow squares when zoomed in far enough, and inverted otherwise.
+    Ring,
+    /// Ripe spores are drawn in the color of their mold.
+    Plain,
+    /// Ripe spores are all drawn in the same color, given as 0RGB.
+    Fixed(u32),
+    /// Ripe spores are drawn in a lighter version of the color of their mold.
+    Brighten,
+}
+
+/// Whether the ripe spore is drawn in its mold color at the given tick with SporeStyle::Blink.
+fn blink_on(tick: u64, period: u64) -> bool {
+    (tick / period.max(1)).is_multiple_of(2)
+}
+
+/// Whether a pixel at offset (x, y) within a block of zoom.0 x zoom.1 pixels lies inside the ring
+/// drawn for SporeStyle::Ring, and thus shows the background instead.
+fn inside_ring(x: usize, y: usize, zoom: (usize, usize)) -> bool {
+    x > 0 && y > 0 && x < zoom.0 - 1 && y < zoom.1 - 1
+}
+
+/// Whether cells are large enough in both directions to draw ripe spores as rings.
+fn shows_rings(zoom: (usize, usize)) -> bool {
+    zoom.0 >= MIN_RING_ZOOM && zoom.1 >= MIN_RING_ZOOM
+}
+
+/// Darkening of mold cells as they get older, to show the growth history of a colony.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AgeShading {
+    /// Age at which cells reach the darkest shade.
+    pub max_age: u32,
+    /// Brightness of cells at max_age and older, from 0 (black) to 1 (unchanged).
+    pub floor: f32,
+}
+
+impl AgeShading {
+    /// Brightness factor in 0..=256 for a cell of the given age, falling off linearly to the floor.
+    fn brightness(&self, age: u32) -> u32 {
+        let floor = (self.floor.clamp(0., 1.) * 256.) as u32;
+        if age >= self.max_age {
+            return floor;
+        }
+        256 - (256 - floor) * age / self.max_age
+    }
+}
+
+/// Color of a mold in the lineage view.
+fn lineage_color(mold: &Mold) -> u32 {
+    // golden angle steps keep the hues of consecutive founders far apart
+    let hue = (mold.founder.0 as f64 * LINEAGE_HUE_STEP) % 360.;
+    let shade = (mold.generation % LINEAGE_SHADES) as f32 / LINEAGE_SHADES as f32;
+    let value = SPECIES_VALUE - shade * LINEAGE_VALUE_RANGE;
+    hsv_to_rgb(hue as f32, SPECIES_SATURATION, value)
+}
+
+/// Index into the colormap for a mold with the given energy: 0 for no energy, 255 for
+/// ENERGY_VIEW_MAX and more, logarithmic in between.
+fn energy_level(energy: i32) -> usize {
+    if energy <= 0 {
+        return 0;
+    }
+    let level = (energy as f32).log2() * 255. / ENERGY_VIEW_MAX.log2();
+    (level as usize).min(255)
+}
+
+/// Color of a genome in the species hash view.
+fn species_color(genome: &Genome) -> u32 {
+    let hue = (genome.hash64() % 360) as f32;
+    hsv_to_rgb(hue, SPECIES_SATURATION, SPECIES_VALUE)
+}
+
+/// Color of a mold in a view mode where it depends on which mold it is rather than on its
+/// state, or None in the energy view.
+fn category_color(mold: &Mold, view_mode: ViewMode) -> Option<u32> {
+    match view_mode {
+        ViewMode::Color | ViewMode::Activity => Some(mold.genome.color),
+        ViewMode::SpeciesHash => Some(species_color(&mold.genome)),
+        ViewMode::Lineage => Some(lineage_color(mold)),
+        ViewMode::Energy => None,
+    }
+}
+
+impl Simulation {
+    /// Choose what the colors of rendered molds represent.
+    pub fn set_view_mode(&mut self, view_mode: ViewMode) {
+        self.view_mode = view_mode;
+        self.last_render.set(None);
+    }
+
+    pub fn view_mode(&self) -> ViewMode {
+        self.view_mode
+    }
+
+    /// Enable or disable darkening mold cells with age. Spores are not affected.
+    pub fn set_age_shading(&mut self, age_shading: Option<AgeShading>) {
+        self.age_shading = age_shading;
+        self.last_render.set(None);
+    }
+
+    /// Draw the cells of one mold at full brightness and dim all other occupied cells by
+    /// RenderPalette::highlight_dim, or stop highlighting with None.
+    pub fn set_highlight(&mut self, highlight: Option<MoldId>) {
+        self.highlight = highlight;
+        self.last_render.set(None);
+    }
+
+    pub fn highlight(&self) -> Option<MoldId> {
+        self.highlight
+    }
+
+    /// Choose the colors used for the background and other non-mold cells.
+    pub fn set_palette(&mut self, palette: RenderPalette) {
+        self.palette = palette;
+        self.last_render.set(None);
+    }
+
+    pub fn palette(&self) -> &RenderPalette {
+        &self.palette
+    }
+
+    /// Choose the gradient used by the energy view.
+    pub fn set_colormap(&mut self, colormap: Colormap) {
+        self.colormap_lut = colormap.bake();
+        self.colormap = colormap;
+        self.last_render.set(None);
+    }
+
+    pub fn colormap(&self) -> &Colormap {
+        &self.colormap
+    }
+
+    /// Enable or disable tinting empty cells by how much light they provide, see Overlay::Light.
+    pub fn set_light_overlay(&mut self, enabled: bool) {
+        self.set_overlay(Overlay::Light, enabled);
+    }
+
+    pub fn light_overlay(&self) -> bool {
+        self.overlays.contains(&Overlay::Light)
+    }
+
+    /// Choose how spores are drawn.
+    pub fn set_spore_style(&mut self, spore_style: SporeStyle) {
+        self.spore_style = spore_style;
+        self.last_render.set(None);
+    }
+
+    pub fn spore_style(&self) -> SporeStyle {
+        self.spore_style
+    }
+
+    /// Draw unripe spores blended from their dimmed color toward the color they get once ripe,
+    /// by how far they are into ripening, instead of dimmed until they are ripe.
+    pub fn set_spore_ripening(&mut self, enabled: bool) {
+        self.spore_ripening = enabled;
+        self.last_render.set(None);
+    }
+
+    pub fn spore_ripening(&self) -> bool {
+        self.spore_ripening
+    }
+
+    /// Render the state of the simulation into a buffer.
+    pub fn render(
+        &self,
+        buffer: &mut [u32],
+        buffer_size: (usize, usize),
+        camera_offset: (usize, usize),
+        zoom: usize,
+    ) {
+        self.render_zoomed(buffer, buffer_size, camera_offset, (zoom, zoom));
+    }
+
+    /// Render the state of the simulation into a buffer with pixels in the given format.
+    pub fn render_in_format(
+        &self,
+        buffer: &mut [u32],
+        buffer_size: (usize, usize),
+        camera_offset: (usize, usize),
+        zoom: usize,
+        format: PixelFormat,
+    ) {
+        self.render(buffer, buffer_size, camera_offset, zoom);
+        if format != PixelFormat::Xrgb {
+            for pixel in buffer[..buffer_size.0 * buffer_size.1].iter_mut() {
+                *pixel = format.from_xrgb(*pixel);
+            }
+        }
+    }
+
+    /// Render the state of the simulation into a buffer with cells of zoom.0 x zoom.1 pixels.
+    pub fn render_zoomed(
+        &self,
+        buffer: &mut [u32],
+        buffer_size: (usize, usize),
+        camera_offset: (usize, usize),
+        zoom: (usize, usize),
+    ) {
+        let (camera_offset, zoom) = self.wrap_view(camera_offset, zoom);
+        assert!(
+            buffer.len() >= buffer_size.0 * buffer_size.1,
+            "buffer is too small"
+        );
+        self.draw_zoomed(
+            buffer,
+            BufferRegion::whole(buffer_size),
+            camera_offset,
+            zoom,
+        );
+    }
+
+    /// Render the state of the simulation into a region of a larger buffer, as render would into
+    /// a buffer of the size of the region. Pixels outside the region are left alone, and so is
+    /// the part of the region that doesn't fit in the buffer.
+    pub fn render_into(
+        &self,
+        buffer: &mut [u32],
+        region: BufferRegion,
+        camera_offset: (usize, usize),
+        zoom: usize,
+    ) {
+        let (camera_offset, zoom) = self.wrap_view(camera_offset, (zoom, zoom));
+        let region = region.clipped(buffer.len());
+        self.draw_zoomed(buffer, region, camera_offset, zoom);
+    }
+
+    /// Render into a region that fits in the buffer, for a camera offset and zoom already passed
+    /// through wrap_view.
+    fn draw_zoomed(
+        &self,
+        buffer: &mut [u32],
+        region: BufferRegion,
+        camera_offset: (usize, usize),
+        zoom: (usize, usize),
+    ) {
+        // without zoom there is nothing to replicate, and rings look different in every row of a cell
+        if zoom == (1, 1) || (self.spore_style == SporeStyle::Ring && shows_rings(zoom)) {
+            self.sample_pixels(buffer, region, camera_offset, zoom);
+            return;
+        }
+
+        let width = region.size.0;
+        for y in 0..region.size.1 {
+            let row_start = region.index(0, y);
+            if y > 0 && !(y + camera_offset.1).is_multiple_of(zoom.1) {
+                // still within the same grid row as the previous line, so it looks identical
+                let previous_row = region.index(0, y - 1);
+                buffer.copy_within(previous_row..previous_row + width, row_start);
+                continue;
+            }
+
+            let y_grid = ((y + camera_offset.1) / zoom.1) % self.size_y;
+            let line = &mut buffer[row_start..row_start + width];
+            let mut x = 0;
+            while x < width {
+                let x_grid = ((x + camera_offset.0) / zoom.0) % self.size_x;
+                let block_end = (x + zoom.0 - (x + camera_offset.0) % zoom.0).min(width);
+                line[x..block_end].fill(self.grid_color(x_grid, y_grid));
+                x = block_end;
+            }
+        }
+    }
+
+    /// Render into a region that fits in the buffer for a camera with any zoom, by mapping every
+    /// pixel to the cell under its center. Rows and columns of pixels showing the same cells are
+    /// looked up once.
+    fn draw_fractional(&self, buffer: &mut [u32], region: BufferRegion, camera: &Camera) {
+        let (width, height) = region.size;
+        if width == 0 || height == 0 {
+            return;
+        }
+        let size = self.size();
+        let columns: Vec<usize> = (0..width)
+            .map(|x| camera.pixel_to_cell((x, 0), size).0 % self.size_x)
+            .collect();
+        let rows: Vec<usize> = (0..height)
+            .map(|y| camera.pixel_to_cell((0, y), size).1 % self.size_y)
+            .collect();
+        // whether each pixel and the one after the last starts a new cell, pixels between two
+        // that don't are inside the outline of ripe spores drawn as rings
+        let column_edges: Vec<bool> = (0..=width)
+            .map(|x| camera.is_cell_edge((x, 0), size).0)
+            .collect();
+        let row_edges: Vec<bool> = (0..=height)
+            .map(|y| camera.is_cell_edge((0, y), size).1)
+            .collect();
+        let inside = |edges: &[bool], index: usize| !edges[index] && !edges[index + 1];
+        let rings = self.spore_style == SporeStyle::Ring && camera.zoom >= MIN_RING_ZOOM as f32;
+
+        for y in 0..height {
+            let row_start = region.index(0, y);
+            let y_grid = rows[y];
+            let inside_row = inside(&row_edges, y);
+            if y > 0 && !row_edges[y] && (!rings || inside_row && inside(&row_edges, y - 1)) {
+                // within the same grid row as the previous line, away from the outline of rings
+                let previous_row = region.index(0, y - 1);
+                buffer.copy_within(previous_row..previous_row + width, row_start);
+                continue;
+            }
+
+            let line = &mut buffer[row_start..row_start + width];
+            let mut x = 0;
+            while x < width {
+                let x_grid = columns[x];
+                let cell = &self.grid[x_grid][y_grid];
+                if rings && cell.is_ripe_spore() {
+                    let color = if inside_row && inside(&column_edges, x) {
+                        self.palette.background
+                    } else {
+                        self.cell_color(cell)
+                    };
+                    line[x] = self.with_activity(color, x_grid, y_grid, self.view_mode);
+                    x += 1;
+                    continue;
+                }
+                let mut block_end = x + 1;
+                while block_end < width && !column_edges[block_end] {
+                    block_end += 1;
+                }
+                line[x..block_end].fill(self.grid_color(x_grid, y_grid));
+                x = block_end;
+            }
+        }
+    }
+
+    /// Render the state of the simulation into a buffer by looking up the cell of every pixel
+    /// separately. Gives the same result as render, which is faster when zoomed in.
+    pub fn render_sampled(
+        &self,
+        buffer: &mut [u32],
+        buffer_size: (usize, usize),
+        camera_offset: (usize, usize),
+        zoom: usize,
+    ) {
+        let (camera_offset, zoom) = self.wrap_view(camera_offset, (zoom, zoom));
+        assert!(
+            buffer.len() >= buffer_size.0 * buffer_size.1,
+            "buffer is too small"
+        );
+        let region = BufferRegion::whole(buffer_size);
+        self.sample_pixels(buffer, region, camera_offset, zoom);
+    }
+
+    /// Look up the color of every pixel of a region that fits in the buffer separately, for a
+    /// camera offset and zoom already passed through wrap_view.
+    fn sample_pixels(
+        &self,
+        buffer: &mut [u32],
+        region: BufferRegion,
+        camera_offset: (usize, usize),
+        zoom: (usize, usize),
+    ) {
+        for y in 0..region.size.1 {
+            let row_start = region.index(0, y);
+            let row = &mut buffer[row_start..row_start + region.size.0];
+            for (x, pixel) in row.iter_mut().enumerate() {
+                *pixel = self.pixel_color(x, y, camera_offset, zoom);
+            }
+        }
+    }
+
+    /// Update only the pixels of cells that changed since the previous call, assuming the buffer
+    /// still holds what that call left in it. Returns the area of the buffer that changed, or None
+    /// if nothing did. Everything is rendered again when the camera or buffer size changed, or when
+    /// the view changes every tick.
+    pub fn render_dirty(
+        &self,
+        buffer: &mut [u32],
+        buffer_size: (usize, usize),
+        camera: &Camera,
+    ) -> Option<Rect> {
+        assert!(
+            buffer.len() >= buffer_size.0 * buffer_size.1,
+            "buffer is too small"
+        );
+        self.render_dirty_into(buffer, BufferRegion::whole(buffer_size), camera)
+    }
+
+    /// Like render_dirty, but for a region of a larger buffer that only this simulation renders
+    /// into. The area returned is in coordinates of the buffer, not of the region.
+    pub fn render_dirty_into(
+        &self,
+        buffer: &mut [u32],
+        region: BufferRegion,
+        camera: &Camera,
+    ) -> Option<Rect> {
+        let region = region.clipped(buffer.len());
+        let whole_zoom = camera.whole_zoom();
+        let (camera_offset, zoom) = self.wrap_view(
+            camera.offset(self.size()),
+            (whole_zoom.unwrap_or(0), whole_zoom.unwrap_or(0)),
+        );
+        let fractional = whole_zoom.is_none().then_some(*camera);
+        let stamp = RenderStamp {
+            camera_offset,
+            zoom,
+            fractional,
+            region,
+            revision: self.revision,
+            energy_light: self.energy_light,
+        };
+        let previous = self.last_render.replace(Some(stamp));
+
+        let previous = match previous {
+            Some(previous)
+                if previous.camera_offset == camera_offset
+                    && previous.zoom == zoom
+                    && previous.fractional == fractional
+                    && previous.region == region
+                    && previous.energy_light == self.energy_light
+                    && !self.changes_every_tick() =>
+            {
+                previous
+            }
+            _ => {
+                match fractional {
+                    Some(camera) => self.draw_fractional(buffer, region, &camera),
+                    None => self.draw_zoomed(buffer, region, camera_offset, zoom),
+                }
+                return Some(Rect {
+                    x: region.origin.0,
+                    y: region.origin.1,
+                    width: region.size.0,
+                    height: region.size.1,
+                });
+            }
+        };
+        if previous.revision == self.revision {
+            return None;
+        }
+        if let Some(camera) = fractional {
+            // cells cover different numbers of pixels, so draw everything rather than find them
+            self.draw_fractional(buffer, region, &camera);
+            return Some(Rect {
+                x: region.origin.0,
+                y: region.origin.1,
+                width: region.size.0,
+                height: region.size.1,
+            });
+        }
+
+        // bounding box of changed pixels as (min_x, min_y, max_x, max_y), exclusive maximum
+        let mut bounds: Option<(usize, usize, usize, usize)> = None;
+        for x_grid in 0..self.size_x {
+            for y_grid in 0..self.size_y {
+                if self.changed_at[x_grid * self.size_y + y_grid] <= previous.revision {
+                    continue;
+                }
+                let x_spans =
+                    pixel_spans(x_grid, camera_offset.0, zoom.0, self.size_x, region.size.0);
+                for x_span in x_spans {
+                    let y_spans =
+                        pixel_spans(y_grid, camera_offset.1, zoom.1, self.size_y, region.size.1);
+                    for y_span in y_spans {
+                        for y in y_span.clone() {
+                            for x in x_span.clone() {
+                                buffer[region.index(x, y)] =
+                                    self.pixel_color(x, y, camera_offset, zoom);
+                            }
+                        }
+                        bounds = Some(match bounds {
+                            None => (x_span.start, y_span.start, x_span.end, y_span.end),
+                            Some((min_x, min_y, max_x, max_y)) => (
+                                min_x.min(x_span.start),
+                                min_y.min(y_span.start),
+                                max_x.max(x_span.end),
+                                max_y.max(y_span.end),
+                            ),
+                        });
+                    }
+                }
+            }
+        }
+        bounds.map(|(min_x, min_y, max_x, max_y)| Rect {
+            x: region.origin.0 + min_x,
+            y: region.origin.1 + min_y,
+            width: max_x - min_x,
+            height: max_y - min_y,
+        })
+    }
+
+    /// Render the cells inside `rect` (in grid coordinates) at an integer scale, independent of
+    /// any camera. The buffer must hold rect.width * scale by rect.height * scale pixels. The
+    /// rectangle may extend past the edges of the grid, it wraps around like the grid does.
+    pub fn render_region(&self, rect: Rect, buffer: &mut [u32], scale: usize) {
+        let scale = scale.max(1);
+        let buffer_size = (rect.width * scale, rect.height * scale);
+        self.render(buffer, buffer_size, (rect.x * scale, rect.y * scale), scale);
+    }
+
+    /// Whether the color of cells can change without the cells themselves changing.
+    fn changes_every_tick(&self) -> bool {
+        self.age_shading.is_some()
+            || self.spore_ripening
+            || self.view_mode == ViewMode::Energy
+            || matches!(self.spore_style, SporeStyle::Blink { .. })
+            || (self.trail.is_some() && self.overlays.contains(&Overlay::Trail))
+            || (self.activity.is_some() && self.view_mode == ViewMode::Activity)
+    }
+
+    /// Color of the pixel at (x, y) of a buffer.
+    fn pixel_color(
+        &self,
+        x: usize,
+        y: usize,
+        camera_offset: (usize, usize),
+        zoom: (usize, usize),
+    ) -> u32 {
+        let (x_grid, y_grid) = self.grid_coords(x, y, camera_offset, zoom);
+        if self.shows_ring(x_grid, y_grid, zoom)
+            && inside_ring(
+                (x + camera_offset.0) % zoom.0,
+                (y + camera_offset.1) % zoom.1,
+                zoom,
+            )
+        {
+            self.ring_inside_color(x_grid, y_grid)
+        } else {
+            self.grid_color(x_grid, y_grid)
+        }
+    }
+
+    /// Whether the cell at (x, y) is drawn as a ring at the given zoom, with the pixels inside
+    /// the ring in ring_inside_color.
+    fn shows_ring(&self, x: usize, y: usize, zoom: (usize, usize)) -> bool {
+        self.spore_style == SporeStyle::Ring && shows_rings(zoom) && self.grid[x][y].is_ripe_spore()
+    }
+
+    /// Color of the pixels inside the ring of the ripe spore at (x, y) drawn as a ring.
+    fn ring_inside_color(&self, x: usize, y: usize) -> u32 {
+        self.with_activity(self.palette.background, x, y, self.view_mode)
+    }
+
+    /// Render a downscaled overview of the whole grid into a buffer of width x height pixels,
+    /// with the outline of `viewport` (in grid coordinates) drawn on top. Every pixel shows the
+    /// first occupied cell in the area it covers, or the background if there is none. The buffer
+    /// is only reallocated when its size doesn't match.
+    pub fn render_minimap(
+        &self,
+        buffer: &mut Vec<u32>,
+        width: usize,
+        height: usize,
+        viewport: Rect,
+    ) {
+        self.render_minimap_cells(buffer, width, height);
+        self.draw_minimap_viewport(buffer, width, height, viewport);
+    }
+
+    /// Render the minimap without the viewport outline, see render_minimap. This scans the whole
+    /// grid, so callers redrawing every frame can keep the result and only draw the outline on a
+    /// copy of it with draw_minimap_viewport.
+    pub fn render_minimap_cells(&self, buffer: &mut Vec<u32>, width: usize, height: usize) {
+        buffer.resize(width * height, 0);
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        for y in 0..height {
+            let y_cells = cells_covered(y, height, self.size_y);
+            for x in 0..width {
+                let x_cells = cells_covered(x, width, self.size_x);
+                let occupied = x_cells
+                    .clone()
+                    .flat_map(|x_grid| y_cells.clone().map(move |y_grid| (x_grid, y_grid)))
+                    .map(|(x_grid, y_grid)| &self.grid[x_grid][y_grid])
+                    .find(|cell| !matches!(cell, Cell::Empty));
+                buffer[y * width + x] = match occupied {
+                    Some(cell) => self.cell_color(cell),
+                    None => self.palette.background,
+                };
+            }
+        }
+    }
+
+    /// Draw the outline of `viewport` (in grid coordinates) onto a minimap of width x height
+    /// pixels, wrapping around the edges like the grid does.
+    pub fn draw_minimap_viewport(
+        &self,
+        buffer: &mut [u32],
+        width: usize,
+        height: usize,
+        viewport: Rect,
+    ) {
+        if width == 0 || height == 0 {
+            return;
+        }
+        let left = viewport.x * width / self.size_x;
+        let top = viewport.y * height / self.size_y;
+        let right = ((viewport.x + viewport.width) * width)
+            .div_ceil(self.size_x)
+            .max(left + 1)
+            - 1;
+        let bottom = ((viewport.y + viewport.height) * height)
+            .div_ceil(self.size_y)
+            .max(top + 1)
+            - 1;
+        for x in left..=right {
+            buffer[top % height * width + x % width] = self.palette.viewport;
+            buffer[bottom % height * width + x % width] = self.palette.viewport;
+        }
+        for y in top..=bottom {
+            buffer[y % height * width + left % width] = self.palette.viewport;
+            buffer[y % height * width + right % width] = self.palette.viewport;
+        }
+    }
+
+    /// Draw the recorded population history as a column chart of width x height pixels, one
+    /// column per tick with the newest on the right, scaled so the highest visible sample
+    /// fills the height. See set_population_history.
+    pub fn render_sparkline(&self, buffer: &mut [u32], width: usize, height: usize) {
+        let history = &self.population_history;
+        let visible = history.iter().skip(history.len().saturating_sub(width));
+        draw_columns(
+            buffer,
+            width,
+            height,
+            visible,
+            self.palette.background,
+            self.palette.sparkline,
+        );
+    }
+
+    /// Render the whole grid at zoom 1 into a buffer of RGBA bytes, row by row.
+    pub fn render_to_rgba(&self) -> Vec<u8> {
+        self.render_to_rgba_with(RenderOptions {
+            view_mode: self.view_mode,
+            ..RenderOptions::default()
+        })
+    }
+
+    /// Size in pixels of the image rendered by render_to_rgba_with. Panics if the size doesn't fit
+    /// in a usize, see checked_image_size.
+    pub fn image_size(&self, options: RenderOptions) -> (usize, usize) {
+        self.checked_image_size(options)
+            .expect("image size overflows usize")
+    }
+
+    /// Size in pixels of the image rendered by render_to_rgba_with, or None if it doesn't fit in a
+    /// usize.
+    pub fn checked_image_size(&self, options: RenderOptions) -> Option<(usize, usize)> {
+        if options.downscale > 1 {
+            Some((
+                self.size_x.div_ceil(options.downscale),
+                self.size_y.div_ceil(options.downscale),
+            ))
+        } else {
+            let scale = options.scale.max(1);
+            Some((
+                self.size_x.checked_mul(scale)?,
+                self.size_y.checked_mul(scale)?,
+            ))
+        }
+    }
+
+    /// Render the whole grid as RGBA bytes with the given options, row by row, with every cell
+    /// taking up scale x scale pixels or every pixel covering downscale x downscale cells.
+    pub fn render_to_rgba_with(&self, options: RenderOptions) -> Vec<u8> {
+        if options.downscale > 1 {
+            return self.render_downscaled(options.downscale, options.view_mode);
+        }
+        let scale = options.scale.max(1);
+        let mut rgba = Vec::with_capacity(self.size_x * self.size_y * scale * scale * 4);
+        let mut row = Vec::with_capacity(self.size_x * scale * 4);
+        for y in 0..self.size_y {
+            row.clear();
+            for x in 0..self.size_x {
+                let color = self.grid_color_as(x, y, options.view_mode);
+                let pixel = PixelFormat::Rgba8.from_xrgb(color).to_ne_bytes();
+                for _ in 0..scale {
+                    row.extend_from_slice(&pixel);
+                }
+            }
+            for _ in 0..scale {
+                rgba.extend_from_slice(&row);
+            }
+        }
+        rgba
+    }
+
+    /// Render the whole grid as RGBA bytes with every pixel showing the average color of the
+    /// `factor` x `factor` cells it covers. Pixels on the right and bottom edges average the cells
+    /// that are left.
+    fn render_downscaled(&self, factor: usize, view_mode: ViewMode) -> Vec<u8> {
+        let (width, height) = (self.size_x.div_ceil(factor), self.size_y.div_ceil(factor));
+        let mut rgba = Vec::with_capacity(width * height * 4);
+        for y in 0..height {
+            let y_cells = y * factor..((y + 1) * factor).min(self.size_y);
+            for x in 0..width {
+                let x_cells = x * factor..((x + 1) * factor).min(self.size_x);
+                let mut sum = [0u32; 3];
+                for x_grid in x_cells.clone() {
+                    for y_grid in y_cells.clone() {
+                        let color = self.grid_color_as(x_grid, y_grid, view_mode);
+                        sum[0] += (color >> 16) & 0xFF;
+                        sum[1] += (color >> 8) & 0xFF;
+                        sum[2] += color & 0xFF;
+                    }
+                }
+                let count = (x_cells.len() * y_cells.len()) as u32;
+                let [r, g, b] = sum.map(|channel| (channel + count / 2) / count);
+                let pixel = PixelFormat::Rgba8.from_xrgb((r << 16) | (g << 8) | b);
+                rgba.extend_from_slice(&pixel.to_ne_bytes());
+            }
+        }
+        rgba
+    }
+
+    /// Color of the empty cell at (x, y), including overlays.
+    fn empty_color(&self, x: usize, y: usize) -> u32 {
+        if self.overlays.is_empty() {
+            return self.palette.background;
+        }
+        self.composite_overlays(self.palette.background, x, y)
+    }
+
+    /// Color of the cell at (x, y) in the current view mode, including overlays.
+    fn grid_color(&self, x: usize, y: usize) -> u32 {
+        self.grid_color_as(x, y, self.view_mode)
+    }
+
+    /// Color of the cell at (x, y) in the given view mode, including overlays.
+    fn grid_color_as(&self, x: usize, y: usize, view_mode: ViewMode) -> u32 {
+        let color = match &self.grid[x][y] {
+            Cell::Empty => self.empty_color(x, y),
+            cell => self.cell_color_as(cell, view_mode),
+        };
+        self.with_activity(color, x, y, view_mode)
+    }
+
+    /// Add the glow of the activity at (x, y) to its color in the activity view.
+    fn with_activity(&self, color: u32, x: usize, y: usize, view_mode: ViewMode) -> u32 {
+        if view_mode != ViewMode::Activity {
+            return color;
+        }
+        match self.activity_at(x, y) {
+            Some(activity) if activity > 0. => {
+                let glow = (activity.min(1.) * 256.) as u32;
+                add_colors(color, scale_color(self.palette.activity, glow))
+            }
+            _ => color,
+        }
+    }
+
+    /// Color of a single non-empty cell in the current view mode, without overlays.
+    fn cell_color(&self, cell: &Cell) -> u32 {
+        self.cell_color_as(cell, self.view_mode)
+    }
+
+    /// Color of a single non-empty cell in the given view mode, without overlays.
+    fn cell_color_as(&self, cell: &Cell, view_mode: ViewMode) -> u32 {
+        let color = self.undimmed_color(cell, view_mode);
+        let Some(highlight) = self.highlight else {
+            return color;
+        };
+        match cell {
+            Cell::Spore { mold, .. } | Cell::MoldPart { mold, .. } if mold.id == highlight => color,
+            _ => scale_color(
+                color,
+                (self.palette.highlight_dim.clamp(0., 1.) * 256.) as u32,
+            ),
+        }
+    }
+
+    /// Color of mold cells with the category color `color` in the given view mode, as rendered
+    /// after the palette constraint.
+    fn category_color_as(&self, color: u32, view_mode: ViewMode) -> u32 {
+        let color = self.constrained_color(color, view_mode);
+        // dimmed so the glow stands out
+        if view_mode == ViewMode::Activity {
+            scale_color(color, ACTIVITY_MOLD_BRIGHTNESS)
+        } else {
+            color
+        }
+    }
+
+    /// Every species with molds on the grid and the color its mold cells are rendered with in
+    /// the current view mode, after the palette constraint, ordered by species. In the lineage
+    /// view molds of a species can differ in color, then the color of the first one found is
+    /// used. Empty in the energy view, where colors show energy rather than species.
+    pub fn color_legend(&self) -> Vec<(SpeciesId, u32)> {
+        let mut legend: Vec<(SpeciesId, u32)> = Vec::new();
+        let mut seen = HashSet::new();
+        for cell in self.grid.iter().flatten() {
+            let (Cell::MoldPart { mold, .. } | Cell::Spore { mold, .. }) = cell else {
+                continue;
+            };
+            let Some(species) = mold.genome.species else {
+                continue;
+            };
+            if !seen.insert(species) {
+                continue;
+            }
+            let Some(color) = category_color(mold, self.view_mode) else {
+                return Vec::new();
+            };
+            legend.push((species, self.category_color_as(color, self.view_mode)));
+        }
+        legend.sort_unstable_by_key(|&(species, _)| species);
+        legend
+    }
+
+    /// Color of a single non-empty cell in the given view mode, ignoring the highlight.
+    fn undimmed_color(&self, cell: &Cell, view_mode: ViewMode) -> u32 {
+        let mold = match cell {
+            Cell::Empty => return self.palette.background,
+            Cell::Predator { .. } => return self.palette.predator,
+            Cell::Wall => return self.palette.wall,
+            Cell::Spore { mold, .. } | Cell::MoldPart { mold, .. } => mold,
+        };
+        let color = match category_color(mold, view_mode) {
+            Some(color) => self.category_color_as(color, view_mode),
+            None => self.colormap_lut[energy_level(*mold.energy.borrow())],
+        };
+        match cell {
+            Cell::Spore { age, .. } if *age < SPORE_RIPING_AGE => {
+                let unripe = scale_color(color, UNRIPE_SPORE_BRIGHTNESS);
+                if self.spore_ripening {
+                    let progress = *age * 256 / SPORE_RIPING_AGE;
+                    blend(unripe, self.ripe_spore_color(color), progress)
+                } else {
+                    unripe
+                }
+            }
+            Cell::Spore { .. } => self.ripe_spore_color(color),
+            Cell::MoldPart { age, .. } => match &self.age_shading {
+                Some(shading) => scale_color(color, shading.brightness(*age)),
+                None => color,
+            },
+            Cell::Empty | Cell::Predator { .. } | Cell::Wall => self.palette.background,
+        }
+    }
+
+    /// Color of a ripe spore of a mold drawn in `color`, in the current spore style.
+    fn ripe_spore_color(&self, color: u32) -> u32 {
+        match self.spore_style {
+            // invert color with boolean NOT to distinguish spores from normal cells
+            SporeStyle::Invert | SporeStyle::Ring => !color,
+            SporeStyle::Blink { period } if !blink_on(self.tick, period) => WHITE,
+            SporeStyle::Blink { .. } | SporeStyle::Plain => color,
+            SporeStyle::Fixed(fixed) => fixed,
+            SporeStyle::Brighten => brighten(color),
+        }
+    }
+
+    /// convert a pixel location of the screen buffer to grid coordinates
+    pub fn pixel_to_grid_coords(
+        &self,
+        x: usize,
+        y: usize,
+        camera_offset: (usize, usize),
+        zoom: usize,
+    ) -> (usize, usize) {
+        self.pixel_to_grid_coords_zoomed(x, y, camera_offset, (zoom, zoom))
+    }
+
+    /// Convert a pixel location of a buffer rendered with render_zoomed to grid coordinates.
+    pub fn pixel_to_grid_coords_zoomed(
+        &self,
+        x: usize,
+        y: usize,
+        camera_offset: (usize, usize),
+        zoom: (usize, usize),
+    ) -> (usize, usize) {
+        let (camera_offset, zoom) = self.wrap_view(camera_offset, zoom);
+        self.grid_coords(x, y, camera_offset, zoom)
+    }
+
+    /// Clamp the zoom to at least 1 and wrap the camera offset into the zoomed grid, so that
+    /// offsets from any source map pixels to valid cells without overflowing.
+    fn wrap_view(
+        &self,
+        camera_offset: (usize, usize),
+        zoom: (usize, usize),
+    ) -> ((usize, usize), (usize, usize)) {
+        let zoom = (zoom.0.max(1), zoom.1.max(1));
+        let offset = (
+            camera_offset.0 % (self.size_x * zoom.0),
+            camera_offset.1 % (self.size_y * zoom.1),
+        );
+        (offset, zoom)
+    }
+
+    /// Grid coordinates of a pixel, for a camera offset and zoom already passed through wrap_view.
+    #[inline]
+    fn grid_coords(
+        &self,
+        x: usize,
+        y: usize,
+        camera_offset: (usize, usize),
+        zoom: (usize, usize),
+    ) -> (usize, usize) {
+        (
+            ((x + camera_offset.0) / zoom.0) % self.size_x,
+            ((y + camera_offset.1) / zoom.1) % self.size_y,
+        )
+    }
+}
+
+/// Draw a column chart of `samples`, aligned to the right edge of the buffer, with the highest
+/// sample filling the height.
+fn draw_columns<'a>(
+    buffer: &mut [u32],
+    width: usize,
+    height: usize,
+    samples: impl ExactSizeIterator<Item = &'a usize> + Clone,
+    background: u32,
+    foreground: u32,
+) {
+    buffer[..width * height].fill(background);
+    let max = samples.clone().copied().max().unwrap_or(0);
+    if max == 0 {
+        return;
+    }
+    let first_column = width - samples.len();
+    for (x, &sample) in (first_column..).zip(samples) {
+        let column_height = (sample * height).div_ceil(max);
+        for y in height - column_height..height {
+            buffer[y * width + x] = foreground;
+        }
+    }
+}
+
+/// Ranges of pixels along one axis of a buffer of length buffer_len showing the cell at
+/// coordinate `cell` of a grid of length grid_len, which repeats when the buffer is larger.
+fn pixel_spans(
+    cell: usize,
+    offset: usize,
+    zoom: usize,
+    grid_len: usize,
+    buffer_len: usize,
+) -> impl Iterator<Item = Range<usize>> {
+    let period = (grid_len * zoom) as isize;
+    // start of the first block, which may be partially left of the buffer
+    let first = (cell * zoom) as isize - offset as isize;
+    let first = first.rem_euclid(period) - period;
+    (0..)
+        .map(move |i| first + i * period)
+        .take_while(move |start| *start < buffer_len as isize)
+        .map(move |start| {
+            start.max(0) as usize
+                ..((start + zoom as isize).min(buffer_len as isize)).max(0) as usize
+        })
+        .filter(|span| !span.is_empty())
+}
+
+/// Range of cells along an axis of length grid_len covered by pixel `pixel` when the axis is
+/// scaled to a length of pixels_len. Every pixel covers at least one cell.
+fn cells_covered(pixel: usize, pixels_len: usize, grid_len: usize) -> Range<usize> {
+    let start = pixel * grid_len / pixels_len;
+    let end = ((pixel + 1) * grid_len / pixels_len).max(start + 1);
+    start..end
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::{Mold, MoldId, GENOME_SIZE};
+
+    /// Copy of a genome with the same genes but a different color.
+    fn recolored(genome: &Genome) -> Genome {
+        Genome {
+            color: genome.color ^ 0xFFFFFF,
+            ..genome.clone()
+        }
+    }
+
+    /// A mold with random genes and the given color.
+    fn mold_of_color(color: u32) -> Rc<Mold> {
+        let mut rng = fastrand::Rng::with_seed(1);
+        Rc::new(Mold {
+            id: MoldId(1),
+            founder: MoldId(1),
+            generation: 0,
+            genome: Rc::new(Genome {
+                color,
+                ..Genome::new(&mut rng)
+            }),
+            energy: RefCell::new(0),
+        })
+    }
+
+    /// A young mold part of a new mold with the given color.
+    fn mold_part(color: u32) -> Cell {
+        Cell::MoldPart {
+            mold: mold_of_color(color),
+            age: 0,
+            active_gene: 0,
+            direction: 0,
+        }
+    }
+
+    fn ripe_spore(mold: Rc<Mold>) -> Cell {
+        Cell::Spore {
+            mold,
+            age: SPORE_RIPING_AGE,
+            direction: 0,
+        }
+    }
+
+    #[test]
+    fn equal_genomes_have_equal_species_colors() {
+        let mut rng = fastrand::Rng::with_seed(1);
+        for _ in 0..20 {
+            let genome = Genome::new(&mut rng);
+            // the color of a genome plays no part
+            assert_eq!(species_color(&recolored(&genome)), species_color(&genome));
+        }
+
+        let genome = Genome::new(&mut rng);
+        let mut simulation = Simulation::new(4, 1, 0);
+        simulation.set_view_mode(ViewMode::SpeciesHash);
+        for (x, genome) in [(0, genome.clone()), (2, recolored(&genome))] {
+            assert!(simulation.spawn_mold(x, 0, genome));
+        }
+        let mut buffer = vec![0; 4];
+        simulation.render(&mut buffer, (4, 1), (0, 0), 1);
+        assert_eq!(buffer[0], species_color(&genome));
+        assert_eq!(buffer[2], buffer[0]);
+    }
+
+    #[test]
+    fn changed_gene_changes_species_color() {
+        let mut rng = fastrand::Rng::with_seed(1);
+        let genome = Genome::new(&mut rng);
+        let color = species_color(&genome);
+        let mut same_color = 0;
+        let mut changes = 0;
+        for index in 0..genome.genes.len() {
+            for value in (-2..GENOME_SIZE as isize).filter(|&value| value != genome.genes[index]) {
+                let mut changed = genome.clone();
+                changed.genes[index] = value;
+                changed.hash = changed.compute_hash();
+                assert_ne!(changed.hash64(), genome.hash64());
+                changes += 1;
+                same_color += usize::from(species_color(&changed) == color);
+            }
+        }
+        // a hash spread over 360 hues gives the same hue to about one change in 360
+        assert!(same_color * 200 < changes, "{same_color} of {changes}");
+    }
+
+    #[test]
+    fn age_shading_brightness() {
+        let shading = AgeShading {
+            max_age: 100,
+            floor: 0.25,
+        };
+        assert_eq!(shading.brightness(0), 256);
+        assert_eq!(shading.brightness(50), 160);
+        assert_eq!(shading.brightness(100), 64);
+        assert_eq!(shading.brightness(1000), 64);
+        assert_eq!(scale_color(0xFF8040, 256), 0xFF8040);
+        assert_eq!(scale_color(0xFF8040, 160), 0x9F5028);
+        assert_eq!(scale_color(0xFF8040, 64), 0x3F2010);
+        let black = AgeShading {
+            max_age: 10,
+            floor: -1.,
+        };
+        assert_eq!(black.brightness(10), 0);
+        assert_eq!(scale_color(0xFFFFFF, black.brightness(10)), 0);
+    }
+
+    #[test]
+    fn age_shading_darkens_old_cells_but_not_spores() {
+        let mut simulation = Simulation::new(5, 1, 0);
+        let mold = mold_of_color(0xFF8040);
+        for (x, age) in [(0, 0), (1, 50), (2, 200)] {
+            simulation.grid[x][0] = Cell::MoldPart {
+                mold: mold.clone(),
+                age,
+                active_gene: 0,
+                direction: 0,
+            };
+        }
+        simulation.grid[3][0] = ripe_spore(mold);
+        let mut unshaded = vec![0; 5];
+        simulation.render(&mut unshaded, (5, 1), (0, 0), 1);
+        simulation.set_age_shading(Some(AgeShading {
+            max_age: 100,
+            floor: 0.25,
+        }));
+        let mut shaded = vec![0; 5];
+        simulation.render(&mut shaded, (5, 1), (0, 0), 1);
+        assert_eq!(shaded, [0xFF8040, 0x9F5028, 0x3F2010, unshaded[3], 0]);
+    }
+
+    #[test]
+    fn blink_phases() {
+        let phases: Vec<bool> = (0..8).map(|tick| blink_on(tick, 3)).collect();
+        assert_eq!(phases, [true, true, true, false, false, false, true, true]);
+        // a period of 0 blinks every tick like a period of 1
+        for period in [0, 1] {
+            let phases: Vec<bool> = (0..4).map(|tick| blink_on(tick, period)).collect();
+            assert_eq!(phases, [true, false, true, false]);
+        }
+
+        let mut simulation = Simulation::new(1, 1, 0);
+        simulation.set_spore_style(SporeStyle::Blink { period: 2 });
+        let spore = ripe_spore(mold_of_color(0x123456));
+        let colors: Vec<u32> = (0..6)
+            .map(|tick| {
+                simulation.tick = tick;
+                simulation.cell_color(&spore)
+            })
+            .collect();
+        let (on, off) = (0x123456, WHITE);
+        assert_eq!(colors, [on, on, off, off, on, on]);
+    }
+
+    /// A ripe spore on a 1x1 grid rendered as a ring at a zoom, with '#' for pixels in the color
+    /// of the spore, '.' for the background and '?' for anything else.
+    fn ring(zoom: (usize, usize)) -> Vec<String> {
+        let mut simulation = Simulation::new(1, 1, 0);
+        simulation.grid[0][0] = ripe_spore(mold_of_color(0x123456));
+        simulation.set_spore_style(SporeStyle::Ring);
+        let spore_color = simulation.cell_color(&simulation.grid[0][0]);
+        let mut buffer = vec![0; zoom.0 * zoom.1];
+        simulation.render_zoomed(&mut buffer, zoom, (0, 0), zoom);
+        buffer
+            .chunks(zoom.0)
+            .map(|row| {
+                row.iter()
+                    .map(|&pixel| match pixel {
+                        _ if pixel == spore_color => '#',
+                        0 => '.',
+                        _ => '?',
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn ring_geometry() {
+        assert_eq!(ring((4, 4)), ["####", "#..#", "#..#", "####"]);
+        assert_eq!(
+            ring((8, 8)),
+            [
+                "########", "#......#", "#......#", "#......#", "#......#", "#......#", "#......#",
+                "########"
+            ]
+        );
+        assert_eq!(ring((5, 4)), ["#####", "#...#", "#...#", "#####"]);
+        // too small for a ring in one direction, the spore is filled instead
+        assert_eq!(ring((3, 3)), ["###"; 3]);
+        assert_eq!(ring((8, 3)), ["########"; 3]);
+        assert!(!shows_rings((3, 8)) && shows_rings((4, 4)));
+    }
+
+    #[test]
+    fn zoomed_matches_sampled() {
+        let mut simulation = Simulation::new(40, 30, 16);
+        simulation.seed(2);
+        simulation.generate_random_molds(40);
+        simulation.run(200);
+        assert!(simulation.grid.iter().flatten().any(Cell::is_ripe_spore));
+        let spore_styles = [
+            SporeStyle::Invert,
+            SporeStyle::Blink { period: 3 },
+            SporeStyle::Ring,
+            SporeStyle::Plain,
+        ];
+        let buffer_size = (150, 110);
+        for view_mode in [ViewMode::Color, ViewMode::SpeciesHash] {
+            simulation.set_view_mode(view_mode);
+            for spore_style in spore_styles {
+                simulation.set_spore_style(spore_style);
+                for zoom in [1, 4, 8] {
+                    for offset in [(0, 0), (3, 5), (39 * zoom + 1, 29 * zoom + 2)] {
+                        let mut zoomed = vec![0; buffer_size.0 * buffer_size.1];
+                        let mut sampled = vec![1; buffer_size.0 * buffer_size.1];
+                        simulation.render(&mut zoomed, buffer_size, offset, zoom);
+                        simulation.render_sampled(&mut sampled, buffer_size, offset, zoom);
+                        assert!(
+                            zoomed == sampled,
+                            "{view_mode:?} {spore_style:?} {offset:?} {zoom}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn empty_grid_is_background() {
+        let mut simulation = Simulation::new(7, 5, 0);
+        simulation.set_palette(RenderPalette {
+            background: 0x123456,
+            ..RenderPalette::default()
+        });
+        for view_mode in [ViewMode::Color, ViewMode::SpeciesHash] {
+            simulation.set_view_mode(view_mode);
+            for zoom in [1, 3] {
+                let mut buffer = vec![1; 21 * 15];
+                simulation.render(&mut buffer, (21, 15), (0, 0), zoom);
+                assert!(buffer.iter().all(|&pixel| pixel == 0x123456));
+            }
+        }
+    }
+
+    #[test]
+    fn predators_in_predator_color() {
+        let mut simulation = Simulation::new(7, 5, 0);
+        simulation.set_palette(RenderPalette {
+            background: 0x123456,
+            predator: 0xFEDCBA,
+            ..RenderPalette::default()
+        });
+        assert!(simulation.spawn_predator(2, 3));
+        assert!(simulation.spawn_predator(6, 0));
+        for view_mode in [ViewMode::Color, ViewMode::SpeciesHash] {
+            simulation.set_view_mode(view_mode);
+            for zoom in [1, 3] {
+                let width = 7 * zoom;
+                let mut buffer = vec![1; width * 5 * zoom];
+                simulation.render(&mut buffer, (width, 5 * zoom), (0, 0), zoom);
+                for (index, &pixel) in buffer.iter().enumerate() {
+                    let cell = (index % width / zoom, index / width / zoom);
+                    let expected = match cell {
+                        (2, 3) | (6, 0) => 0xFEDCBA,
+                        _ => 0x123456,
+                    };
+                    assert_eq!(pixel, expected, "{view_mode:?} at {zoom} pixel {index}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn walls_in_wall_color() {
+        let mut simulation = Simulation::new(7, 5, 0);
+        simulation.set_palette(RenderPalette {
+            background: 0x123456,
+            wall: 0xABCDEF,
+            ..RenderPalette::default()
+        });
+        for x in 0..7 {
+            simulation.set_wall(x, x % 5, true);
+        }
+        for view_mode in [ViewMode::Color, ViewMode::SpeciesHash] {
+            simulation.set_view_mode(view_mode);
+            for zoom in [1, 3] {
+                let width = 7 * zoom;
+                let mut buffer = vec![1; width * 5 * zoom];
+                simulation.render(&mut buffer, (width, 5 * zoom), (0, 0), zoom);
+                for (index, &pixel) in buffer.iter().enumerate() {
+                    let (x, y) = (index % width / zoom, index / width / zoom);
+                    let expected = if y == x % 5 { 0xABCDEF } else { 0x123456 };
+                    assert_eq!(pixel, expected, "{view_mode:?} at {zoom} pixel {index}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ring_inside_is_background() {
+        let mut simulation = Simulation::new(1, 1, 0);
+        simulation.grid[0][0] = ripe_spore(mold_of_color(0x123456));
+        simulation.set_spore_style(SporeStyle::Ring);
+        simulation.set_palette(RenderPalette {
+            background: 0xABCDEF,
+            ..RenderPalette::default()
+        });
+        let mut buffer = vec![0; 16];
+        simulation.render(&mut buffer, (4, 4), (0, 0), 4);
+        assert_eq!(buffer[5], 0xABCDEF);
+        assert_eq!(buffer[0], !0x123456);
+    }
+
+    #[test]
+    fn minimap_shows_first_occupied_cell_of_each_block() {
+        let mut simulation = Simulation::new(16, 8, 0);
+        simulation.set_palette(RenderPalette {
+            background: 0x000001,
+            viewport: 0x000009,
+            ..RenderPalette::default()
+        });
+        // every pixel covers 4x4 cells, the viewport outline only the first one
+        simulation.grid[6][2] = mold_part(0x000002);
+        simulation.grid[5][3] = mold_part(0x000003);
+        simulation.grid[15][7] = mold_part(0x000002);
+        let viewport = Rect {
+            x: 0,
+            y: 0,
+            width: 1,
+            height: 1,
+        };
+        let mut minimap = Vec::new();
+        simulation.render_minimap(&mut minimap, 4, 2, viewport);
+        assert_eq!(minimap, [9, 3, 1, 1, 1, 1, 1, 2]);
+        // pixels covering less than a cell each show the cell they lie in
+        simulation.render_minimap(&mut minimap, 32, 16, viewport);
+        assert_eq!(minimap.len(), 32 * 16);
+        assert_eq!(
+            (
+                minimap[6 * 32 + 10],
+                minimap[4 * 32 + 12],
+                minimap[6 * 32 + 14]
+            ),
+            (3, 2, 1)
+        );
+    }
+
+    /// Outline of a viewport on an 8x5 minimap of a 32x20 grid, with '#' for the outline.
+    fn viewport_outline(viewport: Rect) -> Vec<String> {
+        let simulation = Simulation::new(32, 20, 0);
+        let mut minimap = Vec::new();
+        simulation.render_minimap(&mut minimap, 8, 5, viewport);
+        minimap
+            .chunks(8)
+            .map(|row| {
+                row.iter()
+                    .map(|&pixel| if pixel == 0 { '.' } else { '#' })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn minimap_viewport_outline() {
+        let viewport = |x, y, width, height| Rect {
+            x,
+            y,
+            width,
+            height,
+        };
+        assert_eq!(
+            viewport_outline(viewport(4, 4, 12, 12)),
+            ["........", ".###....", ".#.#....", ".###....", "........"]
+        );
+        assert_eq!(
+            viewport_outline(viewport(4, 4, 12, 8)),
+            ["........", ".###....", ".###....", "........", "........"]
+        );
+        assert_eq!(
+            viewport_outline(viewport(0, 0, 32, 20)),
+            ["########", "#......#", "#......#", "#......#", "########"]
+        );
+        // a viewport smaller than a pixel is still outlined
+        assert_eq!(
+            viewport_outline(viewport(9, 9, 1, 1)),
+            ["........", "........", "..#.....", "........", "........"]
+        );
+        // one across the bottom right corner wraps around to the other edges
+        assert_eq!(
+            viewport_outline(viewport(24, 12, 16, 12)),
+            ["##....##", "........", "........", "##....##", ".#....#."]
+        );
+    }
+
+    /// Small simulations with some molds.
+    fn simulations() -> Vec<Simulation> {
+        let mut simulations = Vec::new();
+        for (size_x, size_y) in [(1, 1), (7, 5), (16, 3)] {
+            let mut simulation = Simulation::new(size_x, size_y, 16);
+            simulation.seed(1);
+            simulation.generate_random_molds(4);
+            simulation.run(20);
+            simulations.push(simulation);
+        }
+        simulations
+    }
+
+    #[test]
+    fn any_offset_and_zoom_maps_to_cells() {
+        let offsets = [
+            0,
+            1,
+            15,
+            16,
+            1000,
+            usize::MAX / 2,
+            usize::MAX - 16,
+            usize::MAX,
+        ];
+        let buffer_size = (19, 11);
+        let mut buffer = vec![0; buffer_size.0 * buffer_size.1];
+        for simulation in simulations() {
+            let (size_x, size_y) = simulation.size();
+            for zoom in 0..=16 {
+                for zoom_y in [zoom, 1, 16] {
+                    for offset in offsets.iter().flat_map(|&x| offsets.map(|y| (x, y))) {
+                        simulation.render_zoomed(&mut buffer, buffer_size, offset, (zoom, zoom_y));
+                        for (x, y) in [(0, 0), (18, 10), (7, 3)] {
+                            let (x_grid, y_grid) = simulation.pixel_to_grid_coords_zoomed(
+                                x,
+                                y,
+                                offset,
+                                (zoom, zoom_y),
+                            );
+                            assert!(x_grid < size_x && y_grid < size_y);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn regions_are_clipped_to_buffer() {
+        let region = |origin, size| BufferRegion {
+            origin,
+            size,
+            stride: 12,
+        };
+        // a buffer of 12 by 9 pixels and a bit, the last row being incomplete
+        let len = 12 * 9 + 5;
+        let inside = region((3, 2), (5, 4));
+        assert_eq!(inside.clipped(len), inside);
+        assert_eq!(
+            region((0, 0), (12, 9)).clipped(len),
+            region((0, 0), (12, 9))
+        );
+        assert_eq!(region((9, 2), (5, 4)).clipped(len), region((9, 2), (3, 4)));
+        assert_eq!(region((3, 7), (5, 4)).clipped(len), region((3, 7), (5, 2)));
+        assert_eq!(region((9, 7), (5, 4)).clipped(len), region((9, 7), (3, 2)));
+        assert_eq!(
+            region((12, 9), (5, 4)).clipped(len),
+            region((12, 9), (0, 0))
+        );
+        assert_eq!(
+            region((20, 20), (5, 4)).clipped(len),
+            region((20, 20), (0, 0))
+        );
+        assert_eq!(region((3, 2), (5, 4)).clipped(0), region((3, 2), (5, 0)));
+        let empty = BufferRegion {
+            stride: 0,
+            ..inside
+        };
+        assert_eq!(empty.clipped(len).size, (0, 0));
+    }
+
+    #[test]
+    fn rendering_into_region_leaves_rest_alone() {
+        let mut simulation = Simulation::builder(20, 15).seed(4).build();
+        simulation.generate_random_molds(30);
+        simulation.run(100);
+        let (offset, zoom) = ((7, 3), 2);
+        let size = (5, 4);
+        let mut alone = vec![0; size.0 * size.1];
+        simulation.render(&mut alone, size, offset, zoom);
+        assert!(alone.iter().any(|&pixel| pixel != 0));
+        const UNTOUCHED: u32 = 0x01000000;
+        for origin in [(3, 2), (0, 0), (7, 5), (9, 7), (12, 2)] {
+            let mut buffer = vec![UNTOUCHED; 12 * 9];
+            let region = BufferRegion {
+                origin,
+                size,
+                stride: 12,
+            };
+            simulation.render_into(&mut buffer, region, offset, zoom);
+            for (index, &pixel) in buffer.iter().enumerate() {
+                let (x, y) = (index % 12, index / 12);
+                let relative = (x.wrapping_sub(origin.0), y.wrapping_sub(origin.1));
+                let expected = if relative.0 < size.0 && relative.1 < size.1 {
+                    alone[relative.1 * size.0 + relative.0]
+                } else {
+                    UNTOUCHED
+                };
+                assert_eq!(pixel, expected, "({x}, {y}) of region at {origin:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn easing_toward_point() {
+        let (buffer_size, grid_size) = ((100, 80), (50, 40));
+        let camera = |position| Camera { position, zoom: 2. };
+        let mut centered = camera((0., 0.));
+        centered.recenter_on((30., 25.), buffer_size);
+        assert_eq!(centered.position, (10., 10.));
+
+        for (fraction, expected) in [(0., (-20., 0.)), (0.5, (-5., 5.)), (1., (10., 10.))] {
+            let mut eased = camera((-20., 0.));
+            eased.ease_toward((30., 25.), buffer_size, grid_size, fraction);
+            assert_eq!(eased.position, expected, "{fraction}");
+        }
+        let mut overshot = camera((-20., 0.));
+        overshot.ease_toward((30., 25.), buffer_size, grid_size, 3.);
+        assert_eq!(overshot.position, (10., 10.));
+
+        // the point lies just across the right edge of the grid, so the view keeps moving right
+        // instead of sweeping back across the whole grid
+        let mut across = camera((40., 0.));
+        across.ease_toward((2., 20.), buffer_size, grid_size, 0.5);
+        assert_eq!(across.position, (47., 0.));
+        across.ease_toward((2., 20.), buffer_size, grid_size, 1.);
+        assert_eq!(across.position, (54., 0.));
+    }
+
+    #[test]
+    fn normalizing_keeps_view() {
+        let grid_size = (20, 10);
+        for zoom in [1., 2.5, 3., 16.] {
+            for position in [(0., 0.), (-35.5, 1234.25), (59.75, -0.5), (-1e4, 2e4)] {
+                let mut camera = Camera { position, zoom };
+                let before: Vec<_> = (0..40)
+                    .map(|pixel| camera.screen_to_grid((pixel as f32, pixel as f32), 1, grid_size))
+                    .collect();
+                camera.normalize(grid_size);
+                let (x, y) = camera.position;
+                assert!((0. ..20. * zoom).contains(&x) && (0. ..10. * zoom).contains(&y));
+                let after: Vec<_> = (0..40)
+                    .map(|pixel| camera.screen_to_grid((pixel as f32, pixel as f32), 1, grid_size))
+                    .collect();
+                assert_eq!(before, after, "{position:?} at {zoom}");
+            }
+        }
+    }
+
+    #[test]
+    fn screen_to_grid_table() {
+        let grid_size = (10, 6);
+        // zoom, scale, camera position, point of the window, cell and cell wrapped into the grid
+        #[rustfmt::skip]
+        let cases = [
+            (1, 1, (0., 0.), (0., 0.), Some(((0, 0), (0, 0)))),
+            (1, 1, (0., 0.), (9.9, 5.9), Some(((9, 5), (9, 5)))),
+            (1, 1, (0., 0.), (10., 6.), Some(((10, 6), (0, 0)))),
+            (1, 1, (0., 0.), (-1., 0.), None),
+            (1, 2, (0., 0.), (19., 11.), Some(((9, 5), (9, 5)))),
+            (1, 2, (0., 0.), (20., 12.), Some(((10, 6), (0, 0)))),
+            (1, 2, (0., 0.), (0., -0.5), None),
+            (2, 1, (0., 0.), (3., 3.), Some(((1, 1), (1, 1)))),
+            (2, 1, (0., 0.), (19., 11.), Some(((9, 5), (9, 5)))),
+            (2, 1, (0., 0.), (25., 0.), Some(((12, 0), (2, 0)))),
+            (2, 2, (0., 0.), (3., 3.), Some(((0, 0), (0, 0)))),
+            (2, 2, (0., 0.), (4., 4.), Some(((1, 1), (1, 1)))),
+            (2, 2, (0., 0.), (39., 23.), Some(((9, 5), (9, 5)))),
+            (2, 2, (0., 0.), (80., 48.), Some(((20, 12), (0, 0)))),
+            (8, 1, (5., 3.), (0., 0.), Some(((0, 0), (0, 0)))),
+            (8, 1, (5., 3.), (3., 5.), Some(((1, 1), (1, 1)))),
+            (8, 1, (5., 3.), (100., 50.), Some(((13, 6), (3, 0)))),
+            (8, 1, (-75., 3.), (100., 50.), Some(((13, 6), (3, 0)))),
+            (8, 2, (5., 3.), (6., 10.), Some(((1, 1), (1, 1)))),
+            (8, 2, (5., 3.), (-0.5, 4.), None),
+            (8, 2, (5., 3.), (f32::NAN, 4.), None),
+        ];
+        for (zoom, scale, position, screen, expected) in cases {
+            let camera = Camera {
+                position,
+                zoom: zoom as f32,
+            };
+            let case = format!("{screen:?} at zoom {zoom}, scale {scale} from {position:?}");
+            assert_eq!(
+                camera.screen_to_cell(screen, scale, grid_size),
+                expected.map(|(cell, _)| cell),
+                "{case}"
+            );
+            assert_eq!(
+                camera.screen_to_grid(screen, scale, grid_size),
+                expected.map(|(_, cell)| cell),
+                "{case}"
+            );
+        }
+    }
+
+    #[test]
+    fn grid_to_screen_inverts_screen_to_cell() {
+        let grid_size = (10, 6);
+        for zoom in [1., 2., 8., 2.5] {
+            for scale in [1, 2] {
+                for position in [(0., 0.), (5., 3.), (-13.5, 7.25)] {
+                    let camera = Camera { position, zoom };
+                    for cell in (0..14).flat_map(|x| (0..9).map(move |y| (x, y))) {
+                        let (x, y) = camera.grid_to_screen(cell, scale, grid_size);
+                        if x < 0. || y < 0. {
+                            continue;
+                        }
+                        let case = format!("{cell:?} at zoom {zoom}, scale {scale}");
+                        assert_eq!(
+                            camera.screen_to_cell((x, y), scale, grid_size),
+                            Some(cell),
+                            "{case}"
+                        );
+                        // the pixel before shows the cell before
+                        let before = (x - scale as f32, y - scale as f32);
+                        if let Some(previous) = camera.screen_to_cell(before, scale, grid_size) {
+                            assert_eq!(previous, (cell.0 - 1, cell.1 - 1), "{case}");
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn any_camera_position_maps_to_cells() {
+        let positions = [0., -1., -0.5, -1e3, -1e9, 3.25, 1e9, f32::MAX, f32::MIN];
+        let buffer_size = (19, 11);
+        let mut buffer = vec![0; buffer_size.0 * buffer_size.1];
+        for simulation in simulations() {
+            let size = simulation.size();
+            for zoom in 0..=16 {
+                for position in positions.iter().flat_map(|&x| positions.map(|y| (x, y))) {
+                    let camera = Camera {
+                        position,
+                        zoom: zoom as f32,
+                    };
+                    simulation.render_dirty(&mut buffer, buffer_size, &camera);
+                    let offset = camera.offset(size);
+                    let period = (size.0 * zoom.max(1), size.1 * zoom.max(1));
+                    assert!(offset.0 < period.0 && offset.1 < period.1);
+                    let viewport = camera.viewport(buffer_size, size);
+                    assert!(viewport.x < size.0 && viewport.y < size.1);
+                    assert!(viewport.width <= size.0 && viewport.height <= size.1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn region_at_scale() {
+        let mut simulation = Simulation::new(12, 10, 0);
+        simulation.set_palette(RenderPalette {
+            background: 0x000001,
+            ..RenderPalette::default()
+        });
+        let is_occupied = |x: usize, y: usize| (x * 3 + y * 5).is_multiple_of(7);
+        for x in 0..12 {
+            for y in 0..10 {
+                if is_occupied(x, y) {
+                    simulation.grid[x][y] = mold_part(0x000002);
+                }
+            }
+        }
+        // the second region lies across the bottom right corner and wraps around
+        for (x, y) in [(3, 2), (9, 7)] {
+            let rect = Rect {
+                x,
+                y,
+                width: 5,
+                height: 5,
+            };
+            const UNTOUCHED: u32 = 0x01000000;
+            let mut buffer = vec![UNTOUCHED; 10 * 10 + 3];
+            simulation.render_region(rect, &mut buffer, 2);
+            for (index, &pixel) in buffer[..100].iter().enumerate() {
+                let cell = ((x + index % 10 / 2) % 12, (y + index / 10 / 2) % 10);
+                let expected = if is_occupied(cell.0, cell.1) { 2 } else { 1 };
+                assert_eq!(pixel, expected, "pixel {index} of {rect:?}");
+            }
+            assert_eq!(buffer[100..], [UNTOUCHED; 3]);
+        }
+    }
+
+    /// Hue in degrees and value from 0 to 1 of a 0RGB color.
+    fn hue_and_value(color: u32) -> (f32, f32) {
+        let [_, r, g, b] = color.to_be_bytes().map(|channel| channel as f32 / 255.);
+        let max = r.max(g).max(b);
+        let delta = max - r.min(g).min(b);
+        let hue = if delta == 0. {
+            0.
+        } else if max == r {
+            60. * ((g - b) / delta).rem_euclid(6.)
+        } else if max == g {
+            60. * ((b - r) / delta + 2.)
+        } else {
+            60. * ((r - g) / delta + 4.)
+        };
+        (hue, max)
+    }
+
+    #[test]
+    fn lineage_hues_follow_founders() {
+        let mut rng = fastrand::Rng::with_seed(1);
+        let mut simulation = Simulation::new(4, 2, 0);
+        simulation.set_view_mode(ViewMode::Lineage);
+        for y in 0..2 {
+            assert!(simulation.spawn_mold(0, y, Genome::new(&mut rng)));
+            let mut parent = simulation.mold_at_cell(0, y).unwrap().clone();
+            for x in 1..4 {
+                // a mutated descendant with a color of its own
+                let genome = Genome {
+                    color: crate::random_color(&mut rng),
+                    ..(*parent.genome).clone()
+                };
+                let mold = simulation.new_mold(genome, Some(&parent));
+                let cell = Cell::MoldPart {
+                    mold: mold.clone(),
+                    age: 0,
+                    active_gene: 0,
+                    direction: 0,
+                };
+                simulation.set_cell(x, y, cell);
+                parent = mold;
+            }
+        }
+        let mut buffer = vec![0; 8];
+        simulation.render(&mut buffer, (4, 2), (0, 0), 1);
+
+        let founder_hues: Vec<f32> = (0..2).map(|y| hue_and_value(buffer[y * 4]).0).collect();
+        assert!(
+            (founder_hues[0] - founder_hues[1]).abs() > 90.,
+            "{founder_hues:?}"
+        );
+        for y in 0..2 {
+            let founder = simulation.founder_at(0, y).unwrap();
+            let mut last_value = f32::INFINITY;
+            for x in 0..4 {
+                assert_eq!(simulation.founder_at(x, y), Some(founder));
+                let (hue, value) = hue_and_value(buffer[y * 4 + x]);
+                assert!(
+                    (hue - founder_hues[y]).abs() < 2.,
+                    "({x}, {y}) has hue {hue}"
+                );
+                // later generations are darker
+                assert!(value < last_value, "({x}, {y}) has value {value}");
+                last_value = value;
+            }
+        }
+    }
+
+    /// Height of every column drawn in `foreground`, checking that columns are filled from the
+    /// bottom.
+    fn column_heights(buffer: &[u32], width: usize, height: usize, foreground: u32) -> Vec<usize> {
+        (0..width)
+            .map(|x| {
+                let column: Vec<bool> = (0..height)
+                    .map(|y| buffer[y * width + x] == foreground)
+                    .collect();
+                let filled = column.iter().filter(|&&filled| filled).count();
+                assert!(
+                    column[height - filled..].iter().all(|&filled| filled),
+                    "column {x}"
+                );
+                filled
+            })
+            .collect()
+    }
+
+    #[test]
+    fn columns_scale_to_highest_sample() {
+        let samples = [0, 1, 2, 8, 6, 5];
+        let mut buffer = vec![0; 8 * 4];
+        draw_columns(&mut buffer, 8, 4, samples.iter(), 1, 2);
+        assert!(buffer.iter().all(|&pixel| pixel == 1 || pixel == 2));
+        // right-aligned and rounded up, so a sample above 0 always shows
+        assert_eq!(column_heights(&buffer, 8, 4, 2), [0, 0, 0, 1, 1, 4, 3, 3]);
+
+        draw_columns(&mut buffer, 8, 4, [0, 0].iter(), 1, 2);
+        assert_eq!(buffer, [1; 8 * 4]);
+    }
+
+    #[test]
+    fn sparkline_shows_latest_history() {
+        let mut simulation = Simulation::new(4, 4, 0);
+        simulation.set_palette(RenderPalette {
+            background: 1,
+            sparkline: 2,
+            ..RenderPalette::default()
+        });
+        let mut buffer = vec![0; 4 * 5];
+        simulation.render_sparkline(&mut buffer, 4, 5);
+        assert_eq!(buffer, [1; 4 * 5]);
+
+        simulation.set_population_history(6);
+        simulation.population_history = [50, 10, 2, 4, 8, 10].into();
+        simulation.render_sparkline(&mut buffer, 4, 5);
+        // the first two samples are too old to fit and don't count towards the scale
+        assert_eq!(column_heights(&buffer, 4, 5, 2), [1, 2, 4, 5]);
+
+        simulation.set_population_history(3);
+        assert_eq!(simulation.population_history(), &[4, 8, 10]);
+        simulation.render_sparkline(&mut buffer, 4, 5);
+        assert_eq!(column_heights(&buffer, 4, 5, 2), [0, 2, 4, 5]);
+    }
+
+    #[test]
+    fn non_square_zoom() {
+        let mut simulation = Simulation::new(7, 5, 0);
+        simulation.set_palette(RenderPalette {
+            background: 1,
+            ..RenderPalette::default()
+        });
+        let is_occupied = |x: usize, y: usize| (x * 2 + y * 3).is_multiple_of(5);
+        for x in 0..7 {
+            for y in 0..5 {
+                if is_occupied(x, y) {
+                    simulation.grid[x][y] = mold_part(2);
+                }
+            }
+        }
+        let buffer_size = (17, 13);
+        for zoom in [(1, 2), (3, 1), (2, 5)] {
+            for offset in [(0, 0), (2, 3), (20, 9)] {
+                let mut buffer = vec![0; buffer_size.0 * buffer_size.1];
+                simulation.render_zoomed(&mut buffer, buffer_size, offset, zoom);
+                for (index, &pixel) in buffer.iter().enumerate() {
+                    let (x, y) = (index % buffer_size.0, index / buffer_size.0);
+                    let cell = ((x + offset.0) / zoom.0 % 7, (y + offset.1) / zoom.1 % 5);
+                    assert_eq!(
+                        simulation.pixel_to_grid_coords_zoomed(x, y, offset, zoom),
+                        cell
+                    );
+                    let expected = if is_occupied(cell.0, cell.1) { 2 } else { 1 };
+                    assert_eq!(pixel, expected, "({x}, {y}) at {zoom:?} {offset:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn zooming_keeps_cell_under_cursor() {
+        let grid_size = (50, 40);
+        let zooms = [0.5, 1., 1.3, 2., 3.7, 8., 16.];
+        let cursors = [(0., 0.), (10.2, 3.9), (99.5, 63.), (317., 241.7)];
+        // none of the spots under the cursors lies on the edge of a cell, where rounding may go
+        // either way
+        for position in [(0., 0.), (13.25, 7.3), (-40.7, 95.1)] {
+            for from in zooms {
+                for to in zooms {
+                    for cursor in cursors {
+                        for scale in [1, 2] {
+                            let mut camera = Camera {
+                                position,
+                                zoom: from,
+                            };
+                            let before = camera.screen_to_grid(cursor, scale, grid_size);
+                            camera.zoom_at(to, cursor, scale);
+                            assert_eq!(camera.zoom, to.max(1.));
+                            assert_eq!(
+                                camera.screen_to_grid(cursor, scale, grid_size),
+                                before,
+                                "{position:?} from {from} to {to} at {cursor:?} scaled {scale}"
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn dirty_rendering_matches_full_rendering() {
+        let mut simulation = Simulation::new(24, 16, 16);
+        simulation.seed(3);
+        simulation.generate_random_molds(12);
+        let camera = Camera {
+            position: (7., 5.),
+            zoom: 2.,
+        };
+        let buffer_size = (40, 26);
+        let mut buffer = vec![0; buffer_size.0 * buffer_size.1];
+        let mut expected = buffer.clone();
+        for tick in 0..120 {
+            simulation.update();
+            match tick {
+                10 => simulation.set_light(3, 4, 40),
+                20 => simulation.set_wall(5, 5, true),
+                25 => simulation.erase_cell(5, 5),
+                30 => simulation.set_light_overlay(true),
+                40 => simulation.set_palette(RenderPalette {
+                    background: 0x102030,
+                    ..RenderPalette::default()
+                }),
+                50 => {
+                    let id = (0..24)
+                        .flat_map(|x| (0..16).map(move |y| (x, y)))
+                        .find_map(|(x, y)| simulation.mold_at(x, y))
+                        .map(|info| info.id);
+                    simulation.set_highlight(id);
+                }
+                60 => simulation.set_highlight(None),
+                70 => simulation.set_view_mode(ViewMode::SpeciesHash),
+                80 => simulation.set_palette_constraint(Some(PaletteConstraint::Distinct)),
+                90 => simulation.set_light_overlay(false),
+                _ => {}
+            }
+            simulation.render_dirty(&mut buffer, buffer_size, &camera);
+            simulation.render(
+                &mut expected,
+                buffer_size,
+                camera.offset(simulation.size()),
+                2,
+            );
+            assert!(buffer == expected, "tick {tick}");
+        }
+    }
+
+    #[test]
+    fn legend_lists_every_species_once_in_order() {
+        let mut simulation = Simulation::new(6, 2, 10);
+        let mut rng = fastrand::Rng::with_seed(3);
+        // placed right to left, so the grid doesn't list them in the order of their species
+        for (x, color) in [(5, 0x102030), (3, 0x405060), (0, 0x708090)] {
+            let genome = Genome {
+                color,
+                ..Genome::new(&mut rng)
+            };
+            assert!(simulation.spawn_mold(x, 0, genome));
+        }
+        // a second cell of the first mold
+        let cell = simulation.grid[5][0].clone();
+        simulation.set_cell(1, 1, cell);
+        let species = |x: usize| simulation.species_id_at(x, 0).unwrap();
+        let expected = vec![
+            (species(5), 0x102030),
+            (species(3), 0x405060),
+            (species(0), 0x708090),
+        ];
+        assert!(expected.is_sorted_by_key(|&(species, _)| species));
+        assert_eq!(simulation.color_legend(), expected);
+
+        simulation.set_view_mode(ViewMode::Energy);
+        assert!(simulation.color_legend().is_empty());
+    }
+}
@@ -0,0 +1,300 @@
+//! Remapping the colors of molds at render time so the ones on the grid are easy to tell apart.
+
+use std::collections::HashMap;
+
+use super::{ViewMode, SPECIES_SATURATION, SPECIES_VALUE};
+use crate::color::{hsv_to_rgb, lab, linear_rgb, rgb_to_hsv};
+use crate::{Cell, Simulation};
+
+/// smallest distance in Lab space between the colors of molds, and between them and the
+/// background, that a constraint tries to keep
+const MIN_DISTANCE: f32 = 20.;
+/// hue changes in degrees tried when a color is too close to another one, smallest first
+const HUE_SHIFTS: [f32; 11] = [0., 30., -30., 60., -60., 90., -90., 120., -120., 150., 180.];
+/// how people with protanopia and deuteranopia see colors, as linear RGB matrices (Viénot,
+/// Brettel and Mollon, 1999)
+const PROTANOPIA: [[f32; 3]; 3] = [
+    [0.112_38, 0.887_62, 0.],
+    [0.112_38, 0.887_62, 0.],
+    [0.004_01, -0.004_01, 1.],
+];
+const DEUTERANOPIA: [[f32; 3]; 3] = [
+    [0.292_75, 0.707_25, 0.],
+    [0.292_75, 0.707_25, 0.],
+    [-0.022_34, 0.022_34, 1.],
+];
+
+/// What the colors of molds on the grid are changed into when rendering, see
+/// Simulation::set_palette_constraint. Genome colors stay as they are.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PaletteConstraint {
+    /// Keep the colors of the molds on the grid apart from each other and from the background.
+    Distinct,
+    /// Like Distinct, also for viewers with red-green color blindness (protanopia and
+    /// deuteranopia).
+    Colorblind,
+}
+
+/// Lab coordinates of a color as seen with normal vision, protanopia and deuteranopia.
+type Views = [[f32; 3]; 3];
+
+/// Replacement colors for the mold colors on the grid in one view mode.
+#[derive(Clone, Debug)]
+pub(crate) struct PaletteRemap {
+    view_mode: ViewMode,
+    /// revision of the grid and background color the colors were picked for, None before the
+    /// first time
+    revision: Option<u64>,
+    background: u32,
+    /// replacement of every mold color on the grid, and how it looks
+    colors: HashMap<u32, (u32, Views)>,
+}
+
+impl PaletteConstraint {
+    /// How a color looks to the viewers this constraint cares about. With Distinct all three
+    /// are the view with normal vision.
+    fn views(self, color: u32) -> Views {
+        let rgb = linear_rgb(color);
+        let normal = lab(rgb);
+        match self {
+            PaletteConstraint::Distinct => [normal; 3],
+            PaletteConstraint::Colorblind => [
+                normal,
+                lab(transform(PROTANOPIA, rgb)),
+                lab(transform(DEUTERANOPIA, rgb)),
+            ],
+        }
+    }
+
+    /// Replacement for `color` that is as close to it as possible while at least MIN_DISTANCE
+    /// from all `taken` colors, or the one furthest from them if no candidate is far enough.
+    fn pick<'a>(self, color: u32, taken: impl Iterator<Item = &'a Views> + Clone) -> (u32, Views) {
+        let (hue, saturation, value) = rgb_to_hsv(color);
+        let original = self.views(color);
+        let mut best: Option<(f32, u32, Views)> = None;
+        // candidates nearest to the original color first
+        let mut candidates: Vec<(f32, u32, Views)> = HUE_SHIFTS
+            .iter()
+            .flat_map(|&shift| {
+                [value, SPECIES_VALUE, 0.7, 0.45]
+                    .into_iter()
+                    .flat_map(move |value| {
+                        [saturation, SPECIES_SATURATION, 0.5]
+                            .map(|saturation| hsv_to_rgb(hue + shift, saturation, value))
+                    })
+            })
+            .map(|candidate| {
+                let views = self.views(candidate);
+                (distance(&original, &views), candidate, views)
+            })
+            .collect();
+        candidates.sort_by(|a, b| a.0.total_cmp(&b.0));
+        for (_, candidate, views) in candidates {
+            let nearest = taken
+                .clone()
+                .map(|other| distance(&views, other))
+                .fold(f32::INFINITY, f32::min);
+            if nearest >= MIN_DISTANCE {
+                return (candidate, views);
+            }
+            if best.is_none_or(|(furthest, ..)| nearest > furthest) {
+                best = Some((nearest, candidate, views));
+            }
+        }
+        best.map_or((color, original), |(_, color, views)| (color, views))
+    }
+}
+
+/// Smallest distance between how two colors look to any of the viewers.
+fn distance(a: &Views, b: &Views) -> f32 {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| a.iter().zip(b).map(|(a, b)| (a - b) * (a - b)).sum::<f32>())
+        .fold(f32::INFINITY, f32::min)
+        .sqrt()
+}
+
+fn transform(matrix: [[f32; 3]; 3], rgb: [f32; 3]) -> [f32; 3] {
+    matrix.map(|row| {
+        row.iter()
+            .zip(rgb)
+            .map(|(m, c)| m * c)
+            .sum::<f32>()
+            .clamp(0., 1.)
+    })
+}
+
+impl Simulation {
+    /// Change the colors of molds when rendering so that the ones on the grid at the same time
+    /// are easy to tell apart, or render them as they are with None. Applies to the color,
+    /// species and lineage views. A color keeps its replacement for as long as molds with it
+    /// are on the grid, colors that appear later are fitted in around the ones already there.
+    pub fn set_palette_constraint(&mut self, constraint: Option<PaletteConstraint>) {
+        self.palette_constraint = constraint;
+        self.palette_remaps.borrow_mut().clear();
+        self.last_render.set(None);
+    }
+
+    pub fn palette_constraint(&self) -> Option<PaletteConstraint> {
+        self.palette_constraint
+    }
+
+    /// The color to render instead of a mold color in a view mode.
+    pub(super) fn constrained_color(&self, color: u32, view_mode: ViewMode) -> u32 {
+        let Some(constraint) = self.palette_constraint else {
+            return color;
+        };
+        let mut remaps = self.palette_remaps.borrow_mut();
+        let index = match remaps.iter().position(|remap| remap.view_mode == view_mode) {
+            Some(index) => index,
+            None => {
+                remaps.push(PaletteRemap {
+                    view_mode,
+                    revision: None,
+                    background: self.palette.background,
+                    colors: HashMap::new(),
+                });
+                remaps.len() - 1
+            }
+        };
+        let remap = &mut remaps[index];
+        if remap.revision != Some(self.revision) || remap.background != self.palette.background {
+            self.refit(remap, constraint);
+        }
+        remap.colors.get(&color).map_or(color, |&(color, _)| color)
+    }
+
+    /// Bring a remap up to date with the mold colors on the grid: drop the colors that are gone
+    /// and pick replacements for new ones, the most common first.
+    fn refit(&self, remap: &mut PaletteRemap, constraint: PaletteConstraint) {
+        if remap.background != self.palette.background {
+            remap.colors.clear();
+        }
+        remap.revision = Some(self.revision);
+        remap.background = self.palette.background;
+        let mut counts: HashMap<u32, usize> = HashMap::new();
+        for cell in self.grid.iter().flatten() {
+            if let Cell::MoldPart { mold, .. } | Cell::Spore { mold, .. } = cell {
+                if let Some(color) = super::category_color(mold, remap.view_mode) {
+                    *counts.entry(color).or_default() += 1;
+                }
+            }
+        }
+        remap.colors.retain(|color, _| counts.contains_key(color));
+        let mut new: Vec<(u32, usize)> = counts
+            .into_iter()
+            .filter(|(color, _)| !remap.colors.contains_key(color))
+            .collect();
+        new.sort_by_key(|&(color, count)| (std::cmp::Reverse(count), color));
+        let background = constraint.views(self.palette.background);
+        for (color, _) in new {
+            let taken = remap
+                .colors
+                .values()
+                .map(|(_, views)| views)
+                .chain(std::iter::once(&background));
+            let replacement = constraint.pick(color, taken);
+            remap.colors.insert(color, replacement);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Genome, GENOME_SIZE};
+
+    /// A genome of the given color whose molds never grow.
+    fn genome(color: u32) -> Genome {
+        Genome::from_genes(&[-2; GENOME_SIZE * 3], color).unwrap()
+    }
+
+    /// A grid with a mold of each color on the first row, with enough energy to outlive the
+    /// test.
+    fn molds(colors: &[u32]) -> Simulation {
+        let mut simulation = Simulation::new(8, 4, 0);
+        for (x, &color) in colors.iter().enumerate() {
+            simulation.spawn_mold(2 * x, 0, genome(color));
+            simulation.add_energy(2 * x, 0, 100_000);
+        }
+        simulation
+    }
+
+    /// The color the mold at (x, 0) is rendered with.
+    fn rendered(simulation: &Simulation, x: usize) -> u32 {
+        let rgba = simulation.render_to_rgba();
+        let pixel = &rgba[x * 4..x * 4 + 3];
+        u32::from_be_bytes([0, pixel[0], pixel[1], pixel[2]])
+    }
+
+    #[test]
+    fn nearly_identical_colors_render_apart() {
+        let colors = [0x3070c0, 0x3171c0, 0x3070c1];
+        let mut simulation = molds(&colors);
+        let before: Vec<u32> = (0..3).map(|i| rendered(&simulation, 2 * i)).collect();
+        assert_eq!(before, colors);
+        simulation.set_palette_constraint(Some(PaletteConstraint::Distinct));
+        let views: Vec<Views> = (0..3)
+            .map(|i| PaletteConstraint::Distinct.views(rendered(&simulation, 2 * i)))
+            .collect();
+        let background = PaletteConstraint::Distinct.views(simulation.palette().background);
+        for (i, a) in views.iter().enumerate() {
+            assert!(distance(a, &background) >= MIN_DISTANCE, "mold {i}");
+            for (j, b) in views.iter().enumerate().skip(i + 1) {
+                assert!(distance(a, b) >= MIN_DISTANCE, "molds {i} and {j}");
+            }
+        }
+        for (i, color) in colors.into_iter().enumerate() {
+            assert_eq!(
+                simulation.mold_at(2 * i, 0).unwrap().genome.color(),
+                color,
+                "mold {i}"
+            );
+        }
+    }
+
+    #[test]
+    fn colors_keep_their_replacement() {
+        let mut simulation = molds(&[0x3070c0, 0x3171c0]);
+        simulation.set_palette_constraint(Some(PaletteConstraint::Distinct));
+        let picked = [rendered(&simulation, 0), rendered(&simulation, 2)];
+        for tick in 0..10 {
+            simulation.update();
+            if tick == 5 {
+                // a new color is fitted in around the ones already there
+                simulation.spawn_mold(6, 0, genome(0x3070c2));
+                simulation.add_energy(6, 0, 100_000);
+            }
+            assert_eq!(
+                [rendered(&simulation, 0), rendered(&simulation, 2)],
+                picked,
+                "tick {tick}"
+            );
+        }
+        assert!(!picked.contains(&rendered(&simulation, 6)));
+    }
+
+    #[test]
+    fn colorblind_views_are_honoured() {
+        // the same to someone with protanopia, far apart with normal vision
+        let (red, green) = (0xff957c, 0x00ae7c);
+        let colorblind = PaletteConstraint::Colorblind;
+        let normal = PaletteConstraint::Distinct;
+        assert!(distance(&normal.views(red), &normal.views(green)) >= MIN_DISTANCE);
+        assert!(distance(&colorblind.views(red), &colorblind.views(green)) < MIN_DISTANCE);
+
+        let mut simulation = molds(&[red, green]);
+        simulation.set_palette_constraint(Some(PaletteConstraint::Distinct));
+        assert_eq!(
+            [rendered(&simulation, 0), rendered(&simulation, 2)],
+            [red, green]
+        );
+        simulation.set_palette_constraint(Some(colorblind));
+        let (a, b) = (rendered(&simulation, 0), rendered(&simulation, 2));
+        assert!(distance(&colorblind.views(a), &colorblind.views(b)) >= MIN_DISTANCE);
+        let background = colorblind.views(simulation.palette().background);
+        for color in [a, b] {
+            assert!(distance(&colorblind.views(color), &background) >= MIN_DISTANCE);
+        }
+    }
+}
@@ -0,0 +1,186 @@
+//! Layers drawn over empty cells, blended in order in a single pass.
+
+use crate::color::blend;
+use crate::{Simulation, TRAIL_MAX};
+
+/// light level at which the light overlay is most opaque
+const LIGHT_OVERLAY_FULL: i32 = 20;
+/// opacity (out of 256) of the light overlay at LIGHT_OVERLAY_FULL
+const LIGHT_OVERLAY_ALPHA: i32 = 128;
+/// opacity (out of 256) of the trail of cells that were occupied for a long time
+const TRAIL_OVERLAY_ALPHA: u32 = 160;
+
+/// A layer tinting empty cells. Enabled overlays are blended in order, each on top of the ones
+/// before it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Overlay {
+    /// Tint by how much light a cell provides, with the light color of the palette.
+    Light,
+    /// Tint where molds have been, with the trail color of the palette. Shows nothing unless
+    /// trails are tracked, see Simulation::set_trail.
+    Trail,
+}
+
+impl Simulation {
+    /// Replace the enabled overlays, drawn in the given order.
+    pub fn set_overlays(&mut self, overlays: &[Overlay]) {
+        self.overlays = overlays.to_vec();
+        self.last_render.set(None);
+    }
+
+    /// Enabled overlays, from bottom to top.
+    pub fn overlays(&self) -> &[Overlay] {
+        &self.overlays
+    }
+
+    /// Enable an overlay on top of the others, or disable it.
+    pub fn set_overlay(&mut self, overlay: Overlay, enabled: bool) {
+        self.overlays.retain(|&enabled| enabled != overlay);
+        if enabled {
+            self.overlays.push(overlay);
+        }
+        self.last_render.set(None);
+    }
+
+    /// Blend the enabled overlays over `base`, the color of the empty cell at (x, y).
+    pub(super) fn composite_overlays(&self, base: u32, x: usize, y: usize) -> u32 {
+        self.overlays.iter().fold(base, |color, &overlay| {
+            let (tint, alpha) = self.overlay_layer(overlay, x, y);
+            if alpha == 0 {
+                color
+            } else {
+                blend(color, tint, alpha)
+            }
+        })
+    }
+
+    /// Color and opacity (out of 256) that an overlay adds to the empty cell at (x, y).
+    fn overlay_layer(&self, overlay: Overlay, x: usize, y: usize) -> (u32, u32) {
+        match overlay {
+            Overlay::Light => {
+                let light = self.light_at(x, y).clamp(0, LIGHT_OVERLAY_FULL);
+                let alpha = LIGHT_OVERLAY_ALPHA * light / LIGHT_OVERLAY_FULL;
+                (self.palette.light, alpha as u32)
+            }
+            Overlay::Trail => {
+                let trail = self.trail_at(x, y).unwrap_or(0);
+                let alpha = TRAIL_OVERLAY_ALPHA * trail as u32 / TRAIL_MAX as u32;
+                (self.palette.trail, alpha)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Genome, RenderPalette, Simulation, FULL_LIGHT};
+
+    /// Render a 4x1 grid lit with energy light 20 at intensities 0, 128 and FULL_LIGHT, with a
+    /// mold in the last cell, and the given overlays.
+    fn render_lit(palette: RenderPalette, overlays: &[Overlay]) -> Vec<u32> {
+        let mut simulation = Simulation::new(4, 1, 20);
+        simulation.set_palette(palette);
+        for (x, intensity) in [(0, 0), (1, 128), (2, FULL_LIGHT), (3, FULL_LIGHT)] {
+            simulation.set_light(x, 0, intensity);
+        }
+        let mut rng = fastrand::Rng::with_seed(1);
+        let genome = Genome {
+            color: 0x123456,
+            ..Genome::new(&mut rng)
+        };
+        assert!(simulation.spawn_mold(3, 0, genome));
+        simulation.set_overlays(overlays);
+        let mut buffer = vec![0; 4];
+        simulation.render(&mut buffer, (4, 1), (0, 0), 1);
+        buffer
+    }
+
+    #[test]
+    fn light_overlay_blends_light_color() {
+        let palette = RenderPalette {
+            background: 0x000040,
+            light: 0xFFFF00,
+            ..RenderPalette::default()
+        };
+        let unlit = render_lit(palette, &[]);
+        assert_eq!(unlit, [0x000040, 0x000040, 0x000040, 0x123456]);
+        // light 10 of 20 is blended at 64/256, full light at 128/256, the mold is left alone
+        let lit = render_lit(palette, &[Overlay::Light]);
+        assert_eq!(lit, [0x000040, 0x3F3F30, 0x7F7F20, 0x123456]);
+    }
+
+    /// A 3x1 grid whose first cell is unlit without a trail, and whose other cells get full light
+    /// and a full trail.
+    fn lit_with_trail() -> Simulation {
+        let mut simulation = Simulation::new(3, 1, 20);
+        simulation.set_palette(RenderPalette {
+            background: 0x000040,
+            light: 0xFFFF00,
+            trail: 0x00FF00,
+            ..RenderPalette::default()
+        });
+        simulation.set_trail(true);
+        simulation.set_light(0, 0, 0);
+        for x in 1..3 {
+            simulation.set_light(x, 0, FULL_LIGHT);
+            simulation.trail.as_mut().unwrap()[x] = TRAIL_MAX;
+        }
+        simulation
+    }
+
+    #[test]
+    fn overlays_blend_in_order() {
+        let mut simulation = lit_with_trail();
+        simulation.set_overlays(&[Overlay::Light, Overlay::Trail]);
+        let light_then_trail = simulation.composite_overlays(0x000040, 1, 0);
+        assert_eq!(
+            light_then_trail,
+            blend(
+                blend(0x000040, 0xFFFF00, 128),
+                0x00FF00,
+                TRAIL_OVERLAY_ALPHA
+            )
+        );
+
+        // enabling an overlay again moves it to the top
+        simulation.set_overlay(Overlay::Light, true);
+        assert_eq!(simulation.overlays(), [Overlay::Trail, Overlay::Light]);
+        let trail_then_light = simulation.composite_overlays(0x000040, 1, 0);
+        assert_eq!(
+            trail_then_light,
+            blend(
+                blend(0x000040, 0x00FF00, TRAIL_OVERLAY_ALPHA),
+                0xFFFF00,
+                128
+            )
+        );
+        assert_ne!(trail_then_light, light_then_trail);
+
+        let mut buffer = vec![0; 3];
+        simulation.render(&mut buffer, (3, 1), (0, 0), 1);
+        assert_eq!(buffer, [0x000040, trail_then_light, trail_then_light]);
+    }
+
+    #[test]
+    fn transparent_overlays_change_nothing() {
+        let mut simulation = lit_with_trail();
+        // tracking trails enabled the trail overlay
+        assert_eq!(simulation.overlays(), [Overlay::Trail]);
+        simulation.set_overlays(&[]);
+        let mut plain = vec![0; 3];
+        simulation.render(&mut plain, (3, 1), (0, 0), 1);
+        simulation.set_overlays(&[Overlay::Light, Overlay::Trail]);
+        assert_eq!(simulation.composite_overlays(0x000040, 0, 0), 0x000040);
+        let mut overlaid = vec![0; 3];
+        simulation.render(&mut overlaid, (3, 1), (0, 0), 1);
+        assert_eq!(overlaid[0], plain[0]);
+        assert_ne!(overlaid[1], plain[1]);
+
+        simulation.set_overlay(Overlay::Trail, false);
+        simulation.set_overlay(Overlay::Light, false);
+        assert!(simulation.overlays().is_empty());
+        simulation.render(&mut overlaid, (3, 1), (0, 0), 1);
+        assert_eq!(overlaid, plain);
+    }
+}
@@ -0,0 +1,126 @@
+//! Rendering with the rows of the buffer split across threads.
+
+use rayon::prelude::*;
+
+use super::inside_ring;
+use crate::Simulation;
+
+impl Simulation {
+    /// Render the state of the simulation into a buffer using all cores. Gives the same result as
+    /// render, which is faster for small buffers.
+    ///
+    /// The simulation can't be shared between threads, as looking up colors reads energies and
+    /// fills the cache of the palette constraint through RefCells. So the colors of the cells
+    /// visible in the buffer are looked up first, and the rows of the buffer are then filled
+    /// from those colors in parallel.
+    pub fn render_parallel(
+        &self,
+        buffer: &mut [u32],
+        buffer_size: (usize, usize),
+        camera_offset: (usize, usize),
+        zoom: usize,
+    ) {
+        let (camera_offset, zoom) = self.wrap_view(camera_offset, (zoom, zoom));
+        assert!(
+            buffer.len() >= buffer_size.0 * buffer_size.1,
+            "buffer is too small"
+        );
+        if buffer_size.0 == 0 || buffer_size.1 == 0 {
+            return;
+        }
+
+        // the visible cells along an axis, from the one in the first pixel on, at most all of them
+        let first = (camera_offset.0 / zoom.0, camera_offset.1 / zoom.1);
+        let visible = |buffer_len: usize, offset: usize, zoom: usize, grid_len: usize| {
+            ((buffer_len + offset % zoom).div_ceil(zoom)).min(grid_len)
+        };
+        let columns = visible(buffer_size.0, camera_offset.0, zoom.0, self.size_x);
+        let rows = visible(buffer_size.1, camera_offset.1, zoom.1, self.size_y);
+        // colors of the visible cells, and of the inside of the ring of those drawn as rings,
+        // indexed by column * rows + row
+        let mut colors = Vec::with_capacity(columns * rows);
+        let mut ring_insides = Vec::with_capacity(columns * rows);
+        for column in 0..columns {
+            let x = (first.0 + column) % self.size_x;
+            for row in 0..rows {
+                let y = (first.1 + row) % self.size_y;
+                colors.push(self.grid_color(x, y));
+                ring_insides.push(
+                    self.shows_ring(x, y, zoom)
+                        .then(|| self.ring_inside_color(x, y)),
+                );
+            }
+        }
+
+        buffer[..buffer_size.0 * buffer_size.1]
+            .par_chunks_mut(buffer_size.0)
+            .enumerate()
+            .for_each(|(y, line)| {
+                let y_zoomed = y + camera_offset.1;
+                let row = (y_zoomed / zoom.1 - first.1) % rows;
+                for (x, pixel) in line.iter_mut().enumerate() {
+                    let x_zoomed = x + camera_offset.0;
+                    let cell = ((x_zoomed / zoom.0 - first.0) % columns) * rows + row;
+                    *pixel = match ring_insides[cell] {
+                        Some(inside) if inside_ring(x_zoomed % zoom.0, y_zoomed % zoom.1, zoom) => {
+                            inside
+                        }
+                        _ => colors[cell],
+                    };
+                }
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Simulation, SporeStyle, ViewMode};
+
+    #[test]
+    fn parallel_matches_serial() {
+        let mut simulation = Simulation::builder(40, 30).seed(3).build();
+        simulation.set_activity_trail(Some(0.999));
+        simulation.generate_random_molds(40);
+        simulation.run(200);
+        let view_modes = [
+            ViewMode::Color,
+            ViewMode::SpeciesHash,
+            ViewMode::Lineage,
+            ViewMode::Energy,
+            ViewMode::Activity,
+        ];
+        let spore_styles = [
+            SporeStyle::Invert,
+            SporeStyle::Blink { period: 3 },
+            SporeStyle::Ring,
+            SporeStyle::Plain,
+            SporeStyle::Fixed(0x123456),
+            SporeStyle::Brighten,
+        ];
+        // buffers smaller and larger than the zoomed grid, at offsets within and past it
+        let views = [
+            ((64, 48), (0, 0), 1),
+            ((100, 70), (13, 7), 1),
+            ((64, 48), (5, 3), 2),
+            ((90, 50), (37, 101), 3),
+            ((120, 80), (999, 4), 5),
+            ((500, 300), (17, 250), 8),
+        ];
+        for view_mode in view_modes {
+            simulation.set_view_mode(view_mode);
+            for spore_style in spore_styles {
+                simulation.set_spore_style(spore_style);
+                for (buffer_size, offset, zoom) in views {
+                    let mut serial = vec![0; buffer_size.0 * buffer_size.1];
+                    let mut parallel = vec![1; buffer_size.0 * buffer_size.1];
+                    simulation.render(&mut serial, buffer_size, offset, zoom);
+                    simulation.render_parallel(&mut parallel, buffer_size, offset, zoom);
+                    assert!(
+                        serial == parallel,
+                        "{view_mode:?} {spore_style:?} {buffer_size:?} {offset:?} {zoom}"
+                    );
+                }
+            }
+        }
+    }
+}
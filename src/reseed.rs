@@ -0,0 +1,42 @@
+//! Starting over with new molds when all molds of a simulation died, so a long unattended run
+//! doesn't end with an empty window.
+
+use rustymold::Simulation;
+
+/// Extinctions of a simulation so far, see update.
+#[derive(Clone, Copy, Debug)]
+pub struct Reseed {
+    /// Number of times the simulation was started over, plus one.
+    pub epoch: u64,
+    /// tick at which all molds were found dead, until new ones are scattered
+    extinct_at: Option<u64>,
+}
+
+impl Reseed {
+    pub fn new() -> Self {
+        Self {
+            epoch: 1,
+            extinct_at: None,
+        }
+    }
+
+    /// Scatter `count` new random molds once all molds of the simulation have been dead for
+    /// `delay` ticks, starting a new epoch. Meant to be called every frame; returns whether the
+    /// simulation was reseeded.
+    pub fn update(&mut self, simulation: &mut Simulation, delay: u64, count: usize) -> bool {
+        if !simulation.is_extinct() {
+            self.extinct_at = None;
+            return false;
+        }
+        let tick = simulation.tick();
+        let extinct_at = *self.extinct_at.get_or_insert(tick);
+        // the tick goes back when an older save is loaded
+        if tick < extinct_at + delay && tick >= extinct_at {
+            return false;
+        }
+        simulation.generate_random_molds(count);
+        self.extinct_at = None;
+        self.epoch += 1;
+        true
+    }
+}
@@ -0,0 +1,183 @@
+//! The source of all randomness in a simulation, so other random number generators than fastrand
+//! can be plugged in, see Simulation::set_rng.
+
+use std::ops::Range;
+
+/// Random number generator used by a simulation for everything random in it.
+///
+/// Only the random numbers are needed to run a simulation, but snapshots save the generator as a
+/// single u64 returned by state, which seeding it with must restore. Generators with more state
+/// than that still run, but a snapshot of them continues differently after loading.
+pub trait Rng {
+    /// Random number in 0..1.
+    fn f32(&mut self) -> f32;
+
+    /// Random number in a range, which must not be empty.
+    fn u32(&mut self, range: Range<u32>) -> u32;
+
+    /// Random number in a range, which must not be empty.
+    fn usize(&mut self, range: Range<usize>) -> usize;
+
+    /// Random number in a range, which must not be empty.
+    fn isize(&mut self, range: Range<isize>) -> isize;
+
+    /// Random number in a range, which must not be empty.
+    fn i32(&mut self, range: Range<i32>) -> i32 {
+        let len = range.end.abs_diff(range.start);
+        range.start.wrapping_add_unsigned(self.u32(0..len))
+    }
+
+    /// True or false with equal chance.
+    fn bool(&mut self) -> bool {
+        self.u32(0..2) == 0
+    }
+
+    /// Start over from a seed, making the following numbers depend only on it.
+    fn seed(&mut self, seed: u64);
+
+    /// Current state, which seed restores.
+    fn state(&self) -> u64;
+}
+
+/// The default generator, whose own methods are used so runs repeat across versions.
+impl Rng for fastrand::Rng {
+    #[inline]
+    fn f32(&mut self) -> f32 {
+        fastrand::Rng::f32(self)
+    }
+
+    #[inline]
+    fn u32(&mut self, range: Range<u32>) -> u32 {
+        fastrand::Rng::u32(self, range)
+    }
+
+    #[inline]
+    fn usize(&mut self, range: Range<usize>) -> usize {
+        fastrand::Rng::usize(self, range)
+    }
+
+    #[inline]
+    fn isize(&mut self, range: Range<isize>) -> isize {
+        fastrand::Rng::isize(self, range)
+    }
+
+    #[inline]
+    fn i32(&mut self, range: Range<i32>) -> i32 {
+        fastrand::Rng::i32(self, range)
+    }
+
+    #[inline]
+    fn bool(&mut self) -> bool {
+        fastrand::Rng::bool(self)
+    }
+
+    fn seed(&mut self, seed: u64) {
+        fastrand::Rng::seed(self, seed);
+    }
+
+    fn state(&self) -> u64 {
+        self.get_seed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Simulation;
+
+    /// A generator other than fastrand, implementing only the required methods.
+    struct SplitMix(u64);
+
+    impl SplitMix {
+        fn next(&mut self) -> u64 {
+            self.0 = self.0.wrapping_add(0x9E3779B97F4A7C15);
+            let mut z = self.0;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+            z ^ (z >> 31)
+        }
+    }
+
+    impl Rng for SplitMix {
+        fn f32(&mut self) -> f32 {
+            (self.next() >> 40) as f32 / (1 << 24) as f32
+        }
+
+        fn u32(&mut self, range: Range<u32>) -> u32 {
+            range.start + (self.next() % (range.end - range.start) as u64) as u32
+        }
+
+        fn usize(&mut self, range: Range<usize>) -> usize {
+            range.start + (self.next() % (range.end - range.start) as u64) as usize
+        }
+
+        fn isize(&mut self, range: Range<isize>) -> isize {
+            let len = range.end.abs_diff(range.start) as u64;
+            range
+                .start
+                .wrapping_add_unsigned((self.next() % len) as usize)
+        }
+
+        fn seed(&mut self, seed: u64) {
+            self.0 = seed;
+        }
+
+        fn state(&self) -> u64 {
+            self.0
+        }
+    }
+
+    #[test]
+    fn default_methods_stay_in_range() {
+        let mut rng = SplitMix(1);
+        for range in [-5..5, 7..8, i32::MIN..i32::MAX, -3..-1] {
+            for _ in 0..1000 {
+                let value = rng.i32(range.clone());
+                assert!(range.contains(&value), "{value} not in {range:?}");
+            }
+        }
+        let heads = (0..1000).filter(|_| rng.bool()).count();
+        assert!((400..600).contains(&heads), "{heads} of 1000");
+    }
+
+    #[test]
+    fn fastrand_gives_its_own_numbers() {
+        let mut direct = fastrand::Rng::with_seed(7);
+        let mut through_trait: Box<dyn Rng> = Box::new(fastrand::Rng::with_seed(7));
+        for _ in 0..100 {
+            assert_eq!(through_trait.f32(), direct.f32());
+            assert_eq!(through_trait.u32(3..90), direct.u32(3..90));
+            assert_eq!(through_trait.usize(0..10), direct.usize(0..10));
+            assert_eq!(through_trait.isize(-2..100), direct.isize(-2..100));
+            assert_eq!(through_trait.i32(-50..50), direct.i32(-50..50));
+            assert_eq!(through_trait.bool(), direct.bool());
+        }
+        through_trait.seed(99);
+        let state = through_trait.state();
+        let first = through_trait.u32(0..1000);
+        through_trait.seed(state);
+        assert_eq!(through_trait.u32(0..1000), first);
+    }
+
+    #[test]
+    fn simulation_runs_on_another_generator() {
+        let run = |seed: u64| {
+            let mut simulation = Simulation::builder(40, 30).build();
+            simulation.set_rng(Box::new(SplitMix(seed)));
+            assert_eq!(simulation.config().seed, None);
+            simulation.generate_random_molds(20);
+            simulation.run(200);
+            simulation
+        };
+        let simulation = run(3);
+        assert!(simulation.stats().molds > 0);
+        assert_eq!(run(3).stats(), simulation.stats());
+        assert_eq!(run(3).rng_state(), simulation.rng_state());
+        assert_ne!(run(4).stats(), simulation.stats());
+
+        let mut simulation = run(3);
+        simulation.seed(5);
+        assert_eq!(simulation.config().seed, Some(5));
+        assert_eq!(simulation.rng_state(), 5);
+    }
+}
@@ -0,0 +1,160 @@
+//! Everything needed to start a simulation again the same way.
+
+use std::ops::RangeInclusive;
+
+use crate::{
+    ColorMutation, Edges, EnergyPolicy, MutationWeights, Ploidy, Shading, Simulation,
+    SimulationBuilder,
+};
+
+/// The grid size, settings and seed of a simulation. A simulation built from a config and given
+/// the same calls (such as generate_random_molds and update) develops exactly like the one the
+/// config was taken from, so sharing a config is enough to repeat a run. With the `serde` feature
+/// it can be serialized.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Config {
+    pub size_x: usize,
+    pub size_y: usize,
+    pub energy_light: i32,
+    pub energy_light_range: RangeInclusive<i32>,
+    pub color_mutation: ColorMutation,
+    pub mutation_weights: MutationWeights,
+    pub mutation_odds: u32,
+    pub ploidy: Ploidy,
+    pub energy_policy: EnergyPolicy,
+    pub max_age: Option<u32>,
+    pub max_population: Option<usize>,
+    pub growth_cost: i32,
+    pub energy_loss: i32,
+    pub substeps: u32,
+    pub shading: Option<Shading>,
+    pub edges: Edges,
+    /// Seed of the random number generator, or None to seed it randomly.
+    pub seed: Option<u64>,
+}
+
+impl Config {
+    /// Builder for a simulation with this config, for changing it further before building.
+    pub fn builder(&self) -> SimulationBuilder {
+        let mut builder = Simulation::builder(self.size_x, self.size_y)
+            .energy_light_range(self.energy_light_range.clone())
+            .energy_light(self.energy_light)
+            .color_mutation(self.color_mutation)
+            .mutation_weights(self.mutation_weights)
+            .mutation_odds(self.mutation_odds)
+            .growth_cost(self.growth_cost)
+            .energy_loss(self.energy_loss)
+            .ploidy(self.ploidy)
+            .energy_policy(self.energy_policy)
+            .substeps(self.substeps)
+            .edges(self.edges);
+        if let Some(max_age) = self.max_age {
+            builder = builder.max_age(max_age);
+        }
+        if let Some(max_population) = self.max_population {
+            builder = builder.max_population(max_population);
+        }
+        if let Some(shading) = self.shading {
+            builder = builder.shading(shading);
+        }
+        if let Some(seed) = self.seed {
+            builder = builder.seed(seed);
+        }
+        builder
+    }
+
+    /// Create a new simulation with this config.
+    pub fn build(&self) -> Simulation {
+        self.builder().build()
+    }
+}
+
+impl Simulation {
+    /// The grid size and current settings of the simulation, with the seed it was last seeded
+    /// with: the one given to the builder or to seed, or the random one it was built with
+    /// otherwise. None for a simulation read from a snapshot and never seeded since, as its
+    /// random number generator continues from the state in the snapshot instead.
+    pub fn config(&self) -> Config {
+        Config {
+            size_x: self.size_x,
+            size_y: self.size_y,
+            energy_light: self.energy_light,
+            energy_light_range: self.energy_light_range.clone(),
+            color_mutation: self.color_mutation,
+            mutation_weights: self.mutation_weights,
+            mutation_odds: self.mutation_odds,
+            ploidy: self.ploidy,
+            energy_policy: self.energy_policy,
+            max_age: self.max_age,
+            max_population: self.max_population,
+            growth_cost: self.growth_cost,
+            energy_loss: self.energy_loss,
+            substeps: self.substeps,
+            shading: self.shading,
+            edges: self.edges,
+            seed: self.seed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_rebuilds_settings() {
+        let simulation = Simulation::builder(30, 20)
+            .growth_cost(12)
+            .energy_loss(3)
+            .mutation_odds(40)
+            .substeps(2)
+            .max_age(300)
+            .edges(Edges::Bounded)
+            .seed(8)
+            .build();
+        let config = simulation.config();
+        assert_eq!(config.growth_cost, 12);
+        assert_eq!(config.seed, Some(8));
+        let rebuilt = config.build();
+        assert_eq!(rebuilt.growth_cost(), 12);
+        assert_eq!(rebuilt.energy_loss(), 3);
+        assert_eq!(rebuilt.config(), config);
+    }
+
+    #[test]
+    fn unseeded_run_repeats_from_config() {
+        let mut simulation = Simulation::builder(40, 30).substeps(2).build();
+        let config = simulation.config();
+        assert!(config.seed.is_some());
+        let mut rebuilt = config.build();
+        for simulation in [&mut simulation, &mut rebuilt] {
+            simulation.generate_random_molds(20);
+            simulation.run(200);
+        }
+        assert!(simulation.stats().molds > 0);
+        assert_eq!(rebuilt.stats(), simulation.stats());
+        assert_eq!(rebuilt.energy_ledger(), simulation.energy_ledger());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn config_survives_serde() {
+        let config = Simulation::builder(30, 20)
+            .mutation_weights(MutationWeights {
+                insertion: 2,
+                ..MutationWeights::default()
+            })
+            .ploidy(Ploidy::Diploid)
+            .max_population(300)
+            .shading(Shading {
+                direction: crate::LightDirection::Left,
+                attenuation: 90,
+            })
+            .seed(5)
+            .build()
+            .config();
+        let text = toml::to_string(&config).unwrap();
+        assert_eq!(toml::from_str::<Config>(&text).unwrap(), config);
+    }
+}
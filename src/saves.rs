@@ -0,0 +1,89 @@
+//! Saving the state of the window to numbered files and loading it back.
+
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rustymold::{Camera, Simulation};
+
+/// directory the window saves to, relative to the working directory
+pub const SAVE_DIR: &str = "saves";
+const EXTENSION: &str = "mold";
+
+/// Which save to load.
+pub enum Pick {
+    Newest,
+    Oldest,
+}
+
+/// Write the simulation and the camera to a new file in SAVE_DIR, named after the current time
+/// so the names sort by age, and return its path.
+pub fn save(simulation: &Simulation, camera: &Camera) -> Result<PathBuf, String> {
+    let path = timestamped_path(SAVE_DIR, "save", EXTENSION)?;
+
+    let write = || -> std::io::Result<()> {
+        let mut writer = BufWriter::new(File::create(&path)?);
+        writer.write_all(&camera.position.0.to_le_bytes())?;
+        writer.write_all(&camera.position.1.to_le_bytes())?;
+        writer.write_all(&camera.zoom.to_le_bytes())?;
+        simulation.write_snapshot(&mut writer)?;
+        writer.flush()
+    };
+    write().map_err(|e| format!("cannot write {}: {e}", path.display()))?;
+    Ok(path)
+}
+
+/// Path of a new file in `dir`, which is created if needed, named after the current time so the
+/// names of files created later sort after it.
+pub fn timestamped_path(dir: &str, prefix: &str, extension: &str) -> Result<PathBuf, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("cannot create {dir}: {e}"))?;
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_millis());
+    Ok(Path::new(dir).join(format!("{prefix}-{millis:015}.{extension}")))
+}
+
+/// Read the newest or oldest save in SAVE_DIR, with the camera it was saved with.
+pub fn load(pick: Pick) -> Result<(Simulation, Camera, PathBuf), String> {
+    let entries = fs::read_dir(SAVE_DIR).map_err(|e| format!("cannot read {SAVE_DIR}: {e}"))?;
+    let saves = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == EXTENSION)
+        });
+    let path = match pick {
+        Pick::Newest => saves.max(),
+        Pick::Oldest => saves.min(),
+    }
+    .ok_or_else(|| format!("no saves in {SAVE_DIR}"))?;
+    let (simulation, camera) = load_file(&path)?;
+    Ok((simulation, camera, path))
+}
+
+/// Read a save written by `save` from any path, with the camera it was saved with.
+pub fn load_file(path: &Path) -> Result<(Simulation, Camera), String> {
+    let read = || -> Result<(Simulation, Camera), Box<dyn std::error::Error>> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut header = [0; 12];
+        reader.read_exact(&mut header)?;
+        let [x, y, zoom] = [0, 4, 8].map(|start| header[start..start + 4].try_into().unwrap());
+        let camera = Camera {
+            position: (f32::from_le_bytes(x), f32::from_le_bytes(y)),
+            zoom: read_zoom(u32::from_le_bytes(zoom)),
+        };
+        Ok((Simulation::read_snapshot(reader)?, camera))
+    };
+    read().map_err(|e| format!("cannot load {}: {e}", path.display()))
+}
+
+/// Zoom from the bits written by `save`. Saves from before the zoom could be fractional hold it
+/// as a whole number, which as the bits of an f32 would be far below the smallest zoom of 1.
+fn read_zoom(bits: u32) -> f32 {
+    if bits < 1f32.to_bits() {
+        bits as f32
+    } else {
+        f32::from_bits(bits)
+    }
+}
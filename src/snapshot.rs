@@ -0,0 +1,812 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::rc::Rc;
+
+use crate::{
+    Cell, ColorMutation, Edges, EnergyLedger, EnergyPolicy, Genes, Genome, LightDirection, Mold,
+    MoldId, MutationWeights, Ploidy, Shading, Simulation, SpeciesId, ENERGY_LOSS, GENE_DIRECTIONS,
+    GENOME_SIZE, MUTATION_ODDS,
+};
+
+/// first bytes of every snapshot, and of every run-length encoded one
+const MAGIC: &[u8; 8] = b"RUSTMOLD";
+const RLE_MAGIC: &[u8; 8] = b"RUSTMRLE";
+/// version of the snapshot format, increased whenever it changes
+const VERSION: u32 = 9;
+/// largest number of cells accepted when reading a snapshot, to fail cleanly on corrupt sizes
+const MAX_CELLS: usize = 1 << 28;
+
+/// Error returned when reading a snapshot of a simulation fails.
+#[derive(Debug)]
+pub enum SnapshotError {
+    /// The snapshot could not be read.
+    Io(io::Error),
+    /// The data is not a snapshot written by this version of the library, or it is corrupt.
+    Invalid(&'static str),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Io(e) => write!(f, "{e}"),
+            SnapshotError::Invalid(reason) => write!(f, "invalid snapshot: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SnapshotError::Io(e) => Some(e),
+            SnapshotError::Invalid(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for SnapshotError {
+    fn from(error: io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::UnexpectedEof => SnapshotError::Invalid("unexpected end of data"),
+            _ => SnapshotError::Io(error),
+        }
+    }
+}
+
+/// How the grid and the maps of a snapshot are stored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Encoding {
+    /// Every value on its own, see write_snapshot.
+    Plain,
+    /// Runs of equal values, see to_rle.
+    RunLength,
+}
+
+impl Encoding {
+    fn magic(self) -> &'static [u8; 8] {
+        match self {
+            Encoding::Plain => MAGIC,
+            Encoding::RunLength => RLE_MAGIC,
+        }
+    }
+}
+
+/// Cell kinds as stored in a snapshot.
+const EMPTY: u8 = 0;
+const SPORE: u8 = 1;
+const MOLD_PART: u8 = 2;
+const PREDATOR: u8 = 3;
+const WALL: u8 = 4;
+
+impl Simulation {
+    /// Write everything needed to continue the simulation exactly where it is: the grid, the
+    /// molds and their genomes, the settings that affect updates, the ledger and the state of the
+    /// random number generator. Render settings, trails and the population history are not
+    /// included. Writes many small pieces, so the writer should be buffered.
+    pub fn write_snapshot(&self, mut writer: impl Write) -> io::Result<()> {
+        let w = &mut writer;
+        let indices = self.write_state(w, Encoding::Plain)?;
+        for cell in self.grid.iter().flatten() {
+            write_cell(w, cell, &indices)?;
+        }
+        Ok(())
+    }
+
+    /// Encode the simulation like write_snapshot, but with the grid stored row by row as runs of
+    /// empty cells and of walls, and every other cell on its own. Much smaller for sparse grids,
+    /// and a change to a few cells changes only a few bytes. Read it back with from_rle.
+    pub fn to_rle(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.write_rle(&mut bytes)
+            .expect("writing to a Vec doesn't fail");
+        bytes
+    }
+
+    fn write_rle(&self, w: &mut Vec<u8>) -> io::Result<()> {
+        let indices = self.write_state(w, Encoding::RunLength)?;
+        let (size_x, size_y) = (self.size_x, self.size_y);
+        let mut positions = (0..size_y)
+            .flat_map(|y| (0..size_x).map(move |x| (x, y)))
+            .peekable();
+        while let Some((x, y)) = positions.next() {
+            let cell = &self.grid[x][y];
+            write_cell(w, cell, &indices)?;
+            if matches!(cell, Cell::Empty | Cell::Wall) {
+                let mut run = 1;
+                let same = |&(x, y): &(usize, usize)| {
+                    std::mem::discriminant(&self.grid[x][y]) == std::mem::discriminant(cell)
+                };
+                while positions.next_if(same).is_some() {
+                    run += 1;
+                }
+                write_u32(w, run)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write everything but the grid, with the maps in the given encoding. Returns the index of
+    /// every mold in the mold table, by which the cells refer to it.
+    fn write_state(
+        &self,
+        w: &mut impl Write,
+        encoding: Encoding,
+    ) -> io::Result<HashMap<*const Mold, u32>> {
+        w.write_all(encoding.magic())?;
+        write_u32(w, VERSION)?;
+        write_u64(w, self.size_x as u64)?;
+        write_u64(w, self.size_y as u64)?;
+        write_i32(w, self.energy_light)?;
+        write_i32(w, *self.energy_light_range.start())?;
+        write_i32(w, *self.energy_light_range.end())?;
+        write_u8(w, self.color_mutation as u8)?;
+        write_u32(w, self.mutation_weights.point)?;
+        write_u32(w, self.mutation_weights.duplication)?;
+        write_u32(w, self.mutation_weights.insertion)?;
+        write_u32(w, self.mutation_weights.deletion)?;
+        write_u8(w, self.ploidy as u8)?;
+        write_u8(w, self.energy_policy as u8)?;
+        write_u8(w, self.edges as u8)?;
+        write_u8(w, self.max_age.is_some() as u8)?;
+        write_u32(w, self.max_age.unwrap_or(0))?;
+        write_u32(w, self.substeps)?;
+        write_u8(w, self.shading.is_some() as u8)?;
+        let shading = self.shading.unwrap_or(Shading {
+            direction: LightDirection::default(),
+            attenuation: 0,
+        });
+        write_u8(w, shading.direction as u8)?;
+        write_u8(w, shading.attenuation)?;
+        write_u8(w, self.max_population.is_some() as u8)?;
+        write_u64(w, self.max_population.unwrap_or(0) as u64)?;
+        write_u32(w, self.mutation_odds)?;
+        write_u32(w, self.growth_cost as u32)?;
+        write_u32(w, self.energy_loss as u32)?;
+        write_u64(w, self.tick)?;
+        write_u64(w, self.last_mold_id)?;
+        write_u64(w, self.last_species_id)?;
+        write_u64(w, self.rng_state())?;
+        for value in [
+            self.energy_ledger.light,
+            self.energy_ledger.spawned,
+            self.energy_ledger.upkeep,
+            self.energy_ledger.removed,
+            self.energy_ledger.growth,
+        ] {
+            write_u64(w, value as u64)?;
+        }
+        let fertility = self.fertility.iter().map(|&fertile| fertile as u8);
+        match encoding {
+            Encoding::Plain => {
+                w.write_all(&self.light_map)?;
+                for fertile in fertility {
+                    write_u8(w, fertile)?;
+                }
+            }
+            Encoding::RunLength => {
+                write_runs(w, self.light_map.iter().copied())?;
+                write_runs(w, fertility)?;
+            }
+        }
+
+        // every mold once, in the order its first cell appears on the grid
+        let mut indices: HashMap<*const Mold, u32> = HashMap::new();
+        let mut molds: Vec<&Rc<Mold>> = Vec::new();
+        for cell in self.grid.iter().flatten() {
+            if let Cell::Spore { mold, .. } | Cell::MoldPart { mold, .. } = cell {
+                indices.entry(Rc::as_ptr(mold)).or_insert_with(|| {
+                    molds.push(mold);
+                    molds.len() as u32 - 1
+                });
+            }
+        }
+        write_u32(w, molds.len() as u32)?;
+        for mold in molds {
+            write_u64(w, mold.id.0)?;
+            write_u64(w, mold.founder.0)?;
+            write_u32(w, mold.generation)?;
+            write_i32(w, *mold.energy.borrow())?;
+            write_genome(w, &mold.genome)?;
+        }
+        Ok(indices)
+    }
+
+    /// Recreate a simulation from a snapshot written by write_snapshot, with default render
+    /// settings. Reads many small pieces, so the reader should be buffered.
+    pub fn read_snapshot(mut reader: impl Read) -> Result<Simulation, SnapshotError> {
+        let r = &mut reader;
+        let (mut simulation, molds) = read_state(r, Encoding::Plain)?;
+        for x in 0..simulation.size_x {
+            for y in 0..simulation.size_y {
+                let cell = read_cell(r, &molds)?;
+                if !matches!(cell, Cell::Empty) {
+                    simulation.set_cell(x, y, cell);
+                }
+            }
+        }
+        Ok(simulation)
+    }
+
+    /// Recreate a simulation from bytes returned by to_rle, with default render settings.
+    pub fn from_rle(mut bytes: &[u8]) -> Result<Simulation, SnapshotError> {
+        let r = &mut bytes;
+        let (mut simulation, molds) = read_state(r, Encoding::RunLength)?;
+        let (size_x, size_y) = (simulation.size_x, simulation.size_y);
+        let mut positions = (0..size_y).flat_map(|y| (0..size_x).map(move |x| (x, y)));
+        while let Some(first) = positions.next() {
+            let cell = read_cell(r, &molds)?;
+            let run = match cell {
+                Cell::Empty | Cell::Wall => read_u32(r)? as usize,
+                _ => 1,
+            };
+            if run == 0 {
+                return Err(SnapshotError::Invalid("empty run"));
+            }
+            let mut covered = 0;
+            for (x, y) in std::iter::once(first).chain(positions.by_ref().take(run - 1)) {
+                if !matches!(cell, Cell::Empty) {
+                    simulation.set_cell(x, y, cell.clone());
+                }
+                covered += 1;
+            }
+            if covered < run {
+                return Err(SnapshotError::Invalid("run past the end of the grid"));
+            }
+        }
+        Ok(simulation)
+    }
+}
+
+/// Read everything but the grid of a snapshot in the given encoding: a simulation with an empty
+/// grid, and the molds its cells refer to by index.
+fn read_state(
+    r: &mut impl Read,
+    encoding: Encoding,
+) -> Result<(Simulation, Vec<Rc<Mold>>), SnapshotError> {
+    let mut magic = [0; 8];
+    r.read_exact(&mut magic)?;
+    if &magic != encoding.magic() {
+        return Err(SnapshotError::Invalid("not a snapshot"));
+    }
+    // version 1 is version 2 without substeps, which is version 3 without gene insertions and
+    // deletions, which is version 4 without shading, which is version 5 without fertility,
+    // which is version 6 without the population limit, which is version 7 without mutation odds,
+    // which is version 8 without growth cost, which is version 9 without energy loss
+    let version = read_u32(r)?;
+    if !(1..=VERSION).contains(&version) {
+        return Err(SnapshotError::Invalid("unsupported version"));
+    }
+    let size_x = read_u64(r)? as usize;
+    let size_y = read_u64(r)? as usize;
+    if size_x == 0 || size_y == 0 || size_x.saturating_mul(size_y) > MAX_CELLS {
+        return Err(SnapshotError::Invalid("grid size out of range"));
+    }
+    let energy_light = read_i32(r)?;
+    let energy_light_range = read_i32(r)?..=read_i32(r)?;
+    if energy_light_range.is_empty() {
+        return Err(SnapshotError::Invalid("empty light range"));
+    }
+    let color_mutation = match read_u8(r)? {
+        0 => ColorMutation::Jump,
+        1 => ColorMutation::Drift,
+        _ => return Err(SnapshotError::Invalid("unknown color mutation")),
+    };
+    let mut mutation_weights = MutationWeights {
+        point: read_u32(r)?,
+        duplication: read_u32(r)?,
+        ..MutationWeights::default()
+    };
+    if version >= 3 {
+        mutation_weights.insertion = read_u32(r)?;
+        mutation_weights.deletion = read_u32(r)?;
+    }
+    let ploidy = match read_u8(r)? {
+        0 => Ploidy::Haploid,
+        1 => Ploidy::Diploid,
+        _ => return Err(SnapshotError::Invalid("unknown ploidy")),
+    };
+    let energy_policy = match read_u8(r)? {
+        0 => EnergyPolicy::Exclusive,
+        1 => EnergyPolicy::Split,
+        2 => EnergyPolicy::Neediest,
+        _ => return Err(SnapshotError::Invalid("unknown energy policy")),
+    };
+    let edges = match read_u8(r)? {
+        0 => Edges::Wrap,
+        1 => Edges::Bounded,
+        _ => return Err(SnapshotError::Invalid("unknown edges")),
+    };
+    let has_max_age = read_u8(r)? != 0;
+    let max_age = read_u32(r)?;
+    let substeps = if version >= 2 { read_u32(r)? } else { 1 };
+    let shading = if version >= 4 {
+        let enabled = read_u8(r)? != 0;
+        let direction = match read_u8(r)? {
+            0 => LightDirection::Top,
+            1 => LightDirection::Bottom,
+            2 => LightDirection::Left,
+            3 => LightDirection::Right,
+            _ => return Err(SnapshotError::Invalid("unknown light direction")),
+        };
+        let attenuation = read_u8(r)?;
+        enabled.then_some(Shading {
+            direction,
+            attenuation,
+        })
+    } else {
+        None
+    };
+    let max_population = if version >= 6 {
+        let enabled = read_u8(r)? != 0;
+        let max_population = read_u64(r)? as usize;
+        enabled.then_some(max_population)
+    } else {
+        None
+    };
+    let mutation_odds = if version >= 7 {
+        read_u32(r)?
+    } else {
+        MUTATION_ODDS
+    };
+    let growth_cost = if version >= 8 { read_u32(r)? as i32 } else { 0 };
+    let energy_loss = if version >= 9 {
+        read_u32(r)? as i32
+    } else {
+        ENERGY_LOSS
+    };
+
+    let mut builder = Simulation::builder(size_x, size_y)
+        .energy_light_range(energy_light_range)
+        .energy_light(energy_light)
+        .color_mutation(color_mutation)
+        .mutation_weights(mutation_weights)
+        .mutation_odds(mutation_odds)
+        .growth_cost(growth_cost)
+        .energy_loss(energy_loss)
+        .ploidy(ploidy)
+        .energy_policy(energy_policy)
+        .substeps(substeps)
+        .edges(edges);
+    if has_max_age {
+        builder = builder.max_age(max_age);
+    }
+    if let Some(max_population) = max_population {
+        builder = builder.max_population(max_population);
+    }
+    if let Some(shading) = shading {
+        builder = builder.shading(shading);
+    }
+    let mut simulation = builder.build();
+    simulation.tick = read_u64(r)?;
+    simulation.last_mold_id = read_u64(r)?;
+    simulation.last_species_id = read_u64(r)?;
+    simulation.set_rng_state(read_u64(r)?);
+    simulation.energy_ledger = EnergyLedger {
+        light: read_u64(r)? as i64,
+        spawned: read_u64(r)? as i64,
+        upkeep: read_u64(r)? as i64,
+        removed: read_u64(r)? as i64,
+        growth: if version >= 8 { read_u64(r)? as i64 } else { 0 },
+    };
+    let mut fertility = vec![1; size_x * size_y];
+    match encoding {
+        Encoding::Plain => {
+            r.read_exact(&mut simulation.light_map)?;
+            if version >= 5 {
+                r.read_exact(&mut fertility)?;
+            }
+        }
+        Encoding::RunLength => {
+            read_runs(r, &mut simulation.light_map)?;
+            read_runs(r, &mut fertility)?;
+        }
+    }
+    for (fertile, byte) in simulation.fertility.iter_mut().zip(fertility) {
+        *fertile = byte != 0;
+    }
+
+    let mold_count = read_u32(r)? as usize;
+    if mold_count > size_x * size_y {
+        return Err(SnapshotError::Invalid("more molds than cells"));
+    }
+    let mut molds = Vec::with_capacity(mold_count);
+    for _ in 0..mold_count {
+        molds.push(Rc::new(Mold {
+            id: MoldId(read_u64(r)?),
+            founder: MoldId(read_u64(r)?),
+            generation: read_u32(r)?,
+            energy: read_i32(r)?.into(),
+            genome: Rc::new(read_genome(r)?),
+        }));
+    }
+    Ok((simulation, molds))
+}
+
+fn write_cell(
+    w: &mut impl Write,
+    cell: &Cell,
+    indices: &HashMap<*const Mold, u32>,
+) -> io::Result<()> {
+    match cell {
+        Cell::Empty => write_u8(w, EMPTY),
+        Cell::Spore {
+            mold,
+            age,
+            direction,
+        } => {
+            write_u8(w, SPORE)?;
+            write_u32(w, indices[&Rc::as_ptr(mold)])?;
+            write_u32(w, *age)?;
+            write_u8(w, *direction as u8)
+        }
+        Cell::MoldPart {
+            mold,
+            age,
+            active_gene,
+            direction,
+        } => {
+            write_u8(w, MOLD_PART)?;
+            write_u32(w, indices[&Rc::as_ptr(mold)])?;
+            write_u32(w, *age)?;
+            write_u32(w, *active_gene)?;
+            write_u8(w, *direction as u8)
+        }
+        Cell::Predator { energy, age } => {
+            write_u8(w, PREDATOR)?;
+            write_i32(w, *energy)?;
+            write_u32(w, *age)
+        }
+        Cell::Wall => write_u8(w, WALL),
+    }
+}
+
+/// Read a cell, referring to its mold by index in `molds`.
+fn read_cell(r: &mut impl Read, molds: &[Rc<Mold>]) -> Result<Cell, SnapshotError> {
+    let read_mold = |r: &mut dyn Read| {
+        molds
+            .get(read_u32(r)? as usize)
+            .cloned()
+            .ok_or(SnapshotError::Invalid("unknown mold"))
+    };
+    let read_direction = |r: &mut dyn Read| match read_u8(r)? {
+        direction @ 0..=3 => Ok(direction as u32),
+        _ => Err(SnapshotError::Invalid("unknown direction")),
+    };
+    Ok(match read_u8(r)? {
+        EMPTY => Cell::Empty,
+        SPORE => Cell::Spore {
+            mold: read_mold(r)?,
+            age: read_u32(r)?,
+            direction: read_direction(r)?,
+        },
+        MOLD_PART => Cell::MoldPart {
+            mold: read_mold(r)?,
+            age: read_u32(r)?,
+            active_gene: match read_u32(r)? {
+                gene if (gene as usize) < GENOME_SIZE => gene,
+                _ => return Err(SnapshotError::Invalid("unknown active gene")),
+            },
+            direction: read_direction(r)?,
+        },
+        PREDATOR => Cell::Predator {
+            energy: read_i32(r)?,
+            age: read_u32(r)?,
+        },
+        WALL => Cell::Wall,
+        _ => return Err(SnapshotError::Invalid("unknown cell")),
+    })
+}
+
+/// Write bytes as runs of equal bytes: the byte and the length of the run.
+fn write_runs(w: &mut impl Write, bytes: impl Iterator<Item = u8>) -> io::Result<()> {
+    let mut bytes = bytes.peekable();
+    while let Some(byte) = bytes.next() {
+        let mut run = 1;
+        while bytes.next_if_eq(&byte).is_some() {
+            run += 1;
+        }
+        write_u8(w, byte)?;
+        write_u32(w, run)?;
+    }
+    Ok(())
+}
+
+/// Fill a buffer with bytes written by write_runs.
+fn read_runs(r: &mut impl Read, buffer: &mut [u8]) -> Result<(), SnapshotError> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        let byte = read_u8(r)?;
+        let run = read_u32(r)? as usize;
+        if run == 0 || run > buffer.len() - filled {
+            return Err(SnapshotError::Invalid("run out of range"));
+        }
+        buffer[filled..filled + run].fill(byte);
+        filled += run;
+    }
+    Ok(())
+}
+
+fn write_genome(w: &mut impl Write, genome: &Genome) -> io::Result<()> {
+    write_u32(w, genome.color)?;
+    write_u64(w, genome.species.map_or(0, |species| species.0))?;
+    match &genome.alleles {
+        Some(alleles) => {
+            write_u8(w, 2)?;
+            write_genes(w, &alleles[0])?;
+            write_genes(w, &alleles[1])
+        }
+        None => {
+            write_u8(w, 1)?;
+            write_genes(w, &genome.genes)
+        }
+    }
+}
+
+fn read_genome(r: &mut impl Read) -> Result<Genome, SnapshotError> {
+    let color = read_u32(r)? & 0xFFFFFF;
+    let species = match read_u64(r)? {
+        0 => None,
+        id => Some(SpeciesId(id)),
+    };
+    let mut genome = match read_u8(r)? {
+        1 => {
+            let mut genome = Genome {
+                genes: read_genes(r)?,
+                alleles: None,
+                color,
+                hash: 0,
+                species: None,
+            };
+            genome.hash = genome.compute_hash();
+            genome
+        }
+        2 => Genome::from_alleles([read_genes(r)?, read_genes(r)?], color),
+        _ => return Err(SnapshotError::Invalid("unknown ploidy of genome")),
+    };
+    genome.species = species;
+    Ok(genome)
+}
+
+/// Genes are stored as one byte per value, they all lie in -2..GENOME_SIZE.
+fn write_genes(w: &mut impl Write, genes: &Genes) -> io::Result<()> {
+    let bytes: Vec<u8> = genes.iter().map(|&gene| gene as i8 as u8).collect();
+    w.write_all(&bytes)
+}
+
+fn read_genes(r: &mut impl Read) -> Result<Genes, SnapshotError> {
+    let mut bytes = [0; GENOME_SIZE * GENE_DIRECTIONS];
+    r.read_exact(&mut bytes)?;
+    let mut genes = [0; GENOME_SIZE * GENE_DIRECTIONS];
+    for (gene, byte) in genes.iter_mut().zip(bytes) {
+        *gene = byte as i8 as isize;
+        if !(-2..GENOME_SIZE as isize).contains(gene) {
+            return Err(SnapshotError::Invalid("gene out of range"));
+        }
+    }
+    Ok(genes)
+}
+
+fn write_u8(w: &mut impl Write, value: u8) -> io::Result<()> {
+    w.write_all(&[value])
+}
+
+fn write_u32(w: &mut impl Write, value: u32) -> io::Result<()> {
+    w.write_all(&value.to_le_bytes())
+}
+
+fn write_i32(w: &mut impl Write, value: i32) -> io::Result<()> {
+    w.write_all(&value.to_le_bytes())
+}
+
+fn write_u64(w: &mut impl Write, value: u64) -> io::Result<()> {
+    w.write_all(&value.to_le_bytes())
+}
+
+fn read_u8(r: &mut (impl Read + ?Sized)) -> io::Result<u8> {
+    let mut bytes = [0; 1];
+    r.read_exact(&mut bytes)?;
+    Ok(bytes[0])
+}
+
+fn read_u32(r: &mut (impl Read + ?Sized)) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    r.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_i32(r: &mut (impl Read + ?Sized)) -> io::Result<i32> {
+    let mut bytes = [0; 4];
+    r.read_exact(&mut bytes)?;
+    Ok(i32::from_le_bytes(bytes))
+}
+
+fn read_u64(r: &mut (impl Read + ?Sized)) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    r.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Stats;
+
+    /// A 30x20 grid written by version 1, before substeps, at tick 60 of a seeded run.
+    const SNAPSHOT_V1: &[u8] = include_bytes!("../tests/fixtures/snapshot_v1.bin");
+    /// A 30x20 grid written by version 7, before growth had a cost, at tick 60 of a seeded run.
+    const SNAPSHOT_V7: &[u8] = include_bytes!("../tests/fixtures/snapshot_v7.rle");
+
+    #[test]
+    fn version_7_loads_without_growth_cost() {
+        assert_eq!(SNAPSHOT_V7[8..12], 7u32.to_le_bytes());
+        let mut simulation = Simulation::from_rle(SNAPSHOT_V7).unwrap();
+        assert_eq!(simulation.size(), (30, 20));
+        assert_eq!(simulation.tick(), 60);
+        assert_eq!(simulation.growth_cost(), 0);
+        assert_eq!(simulation.energy_loss(), ENERGY_LOSS);
+        assert_eq!(
+            simulation.energy_ledger(),
+            EnergyLedger {
+                light: 83088,
+                spawned: 0,
+                upkeep: 55685,
+                growth: 0,
+                removed: -8,
+            }
+        );
+        assert_eq!(simulation.stats().total_energy, 27411);
+        assert_eq!(simulation.rng_state(), 18231279505616656871);
+
+        // continues like the version that wrote it did
+        simulation.run(300);
+        assert_eq!(
+            simulation.stats(),
+            Stats {
+                molds: 3,
+                mold_cells: 279,
+                spores: 40,
+                predators: 0,
+                total_energy: 84949,
+                genomes: 3,
+                generations: 0,
+            }
+        );
+        assert_eq!(simulation.rng_state(), 6299867063196109617);
+    }
+
+    #[test]
+    fn version_1_loads_with_one_substep() {
+        assert_eq!(SNAPSHOT_V1[8..12], 1u32.to_le_bytes());
+        let mut simulation = Simulation::read_snapshot(SNAPSHOT_V1).unwrap();
+        assert_eq!(simulation.size(), (30, 20));
+        assert_eq!(simulation.tick(), 60);
+        assert_eq!(simulation.substeps(), 1);
+        assert_eq!(
+            simulation.energy_ledger(),
+            EnergyLedger {
+                light: 111744,
+                spawned: 0,
+                upkeep: 98830,
+                growth: 0,
+                removed: -83,
+            }
+        );
+        assert_eq!(simulation.stats().molds, 7);
+        assert_eq!(simulation.stats().total_energy, 12997);
+
+        // continues like the version that wrote it did
+        simulation.run(300);
+        assert_eq!(
+            simulation.stats(),
+            Stats {
+                molds: 2,
+                mold_cells: 7,
+                spores: 0,
+                predators: 0,
+                total_energy: 39592,
+                genomes: 2,
+                generations: 0,
+            }
+        );
+        assert_eq!(
+            simulation.energy_ledger(),
+            EnergyLedger {
+                light: 396528,
+                spawned: 0,
+                upkeep: 357190,
+                growth: 0,
+                removed: -254,
+            }
+        );
+    }
+
+    #[test]
+    fn growth_cost_and_ledger_survive_snapshots() {
+        let mut simulation = Simulation::builder(30, 20)
+            .seed(3)
+            .growth_cost(7)
+            .energy_loss(4)
+            .build();
+        simulation.generate_random_molds(10);
+        simulation.run(50);
+        assert!(simulation.energy_ledger().growth > 0);
+
+        let mut bytes = Vec::new();
+        simulation.write_snapshot(&mut bytes).unwrap();
+        assert_eq!(bytes[8..12], VERSION.to_le_bytes());
+        let mut loaded = [
+            Simulation::read_snapshot(bytes.as_slice()).unwrap(),
+            Simulation::from_rle(&simulation.to_rle()).unwrap(),
+        ];
+        simulation.run(50);
+        for loaded in &mut loaded {
+            assert_eq!(loaded.growth_cost(), 7);
+            assert_eq!(loaded.energy_loss(), 4);
+            loaded.run(50);
+            assert_eq!(loaded.energy_ledger(), simulation.energy_ledger());
+            assert_eq!(loaded.stats(), simulation.stats());
+        }
+    }
+
+    #[test]
+    fn rle_keeps_shared_molds_and_is_smaller_on_sparse_grids() {
+        let mut simulation = Simulation::builder(120, 80).seed(8).build();
+        simulation.generate_random_molds(10);
+        simulation.run(100);
+        let stats = simulation.stats();
+        assert!(stats.mold_cells > stats.molds);
+
+        let rle = simulation.to_rle();
+        let mut bytes = Vec::new();
+        simulation.write_snapshot(&mut bytes).unwrap();
+        assert!(rle.len() < bytes.len(), "{} >= {}", rle.len(), bytes.len());
+
+        let loaded = Simulation::from_rle(&rle).unwrap();
+        assert_eq!(loaded.stats(), stats);
+        assert_eq!(loaded.energy_ledger(), simulation.energy_ledger());
+        // cells of one mold still share it, so they see all of its cells
+        for x in 0..120 {
+            for y in 0..80 {
+                let cells = |simulation: &Simulation| {
+                    simulation.mold_at(x, y).map(|info| (info.id, info.cells))
+                };
+                assert_eq!(cells(&loaded), cells(&simulation), "({x}, {y})");
+            }
+        }
+    }
+
+    #[test]
+    fn diploid_alleles_survive_snapshots() {
+        let mut simulation = Simulation::builder(40, 30)
+            .seed(5)
+            .ploidy(Ploidy::Diploid)
+            .build();
+        // molds whose gene sets differ, as after mating
+        let mut rng = fastrand::Rng::with_seed(5);
+        for i in 0..10 {
+            let alleles = [Genome::new(&mut rng).genes, Genome::new(&mut rng).genes];
+            assert!(simulation.spawn_mold(4 * i, 3 * i, Genome::from_alleles(alleles, 0x808080)));
+            assert!(simulation.add_energy(4 * i, 3 * i, 10_000));
+        }
+        simulation.generate_random_molds(20);
+        simulation.run(30);
+
+        let mut bytes = Vec::new();
+        simulation.write_snapshot(&mut bytes).unwrap();
+        let loaded = Simulation::read_snapshot(bytes.as_slice()).unwrap();
+        assert_eq!(loaded.config().ploidy, Ploidy::Diploid);
+        let alleles = |simulation: &Simulation| -> Vec<_> {
+            (0..40)
+                .flat_map(|x| (0..30).map(move |y| (x, y)))
+                .filter_map(|(x, y)| simulation.mold_at(x, y))
+                .map(|info| {
+                    let (first, second) = info.genome.alleles().unwrap();
+                    (first.to_vec(), second.to_vec())
+                })
+                .collect()
+        };
+        let alleles_before = alleles(&simulation);
+        assert!(alleles_before.iter().any(|(first, second)| first != second));
+        assert_eq!(alleles(&loaded), alleles_before);
+    }
+}
@@ -0,0 +1,183 @@
+//! Copying a rectangle of cells and placing the copy elsewhere.
+
+use std::rc::Rc;
+
+use crate::{Cell, Genome, Mold, Rect, Simulation};
+
+/// Cells copied from a rectangle of a simulation with copy_region, which paste places in the
+/// same or another simulation.
+#[derive(Clone, Debug)]
+pub struct Stamp {
+    width: usize,
+    height: usize,
+    /// genome and energy of every mold with cells in the rectangle
+    molds: Vec<(Genome, i32)>,
+    /// cells indexed by x * height + y
+    cells: Vec<StampCell>,
+}
+
+/// A copied cell, referring to molds by their index in Stamp::molds.
+#[derive(Clone, Copy, Debug)]
+enum StampCell {
+    Empty,
+    Wall,
+    Predator {
+        energy: i32,
+        age: u32,
+    },
+    Spore {
+        mold: usize,
+        age: u32,
+        direction: u32,
+    },
+    MoldPart {
+        mold: usize,
+        age: u32,
+        active_gene: u32,
+        direction: u32,
+    },
+}
+
+impl Stamp {
+    /// Width and height of the copied rectangle.
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+}
+
+impl Simulation {
+    /// Copy the cells inside `rect`, which wraps around or is cut off at the edges of the grid
+    /// like in clear_region. The copy doesn't keep anything of the simulation alive.
+    pub fn copy_region(&self, rect: Rect) -> Stamp {
+        let positions = self.region_positions(rect);
+        let width = positions
+            .iter()
+            .map(|((dx, _), _)| dx + 1)
+            .max()
+            .unwrap_or(0);
+        let height = positions
+            .iter()
+            .map(|((_, dy), _)| dy + 1)
+            .max()
+            .unwrap_or(0);
+        let mut stamp = Stamp {
+            width,
+            height,
+            molds: Vec::new(),
+            cells: vec![StampCell::Empty; width * height],
+        };
+        // molds already copied, by address, so every mold is copied once
+        let mut copied: Vec<*const Mold> = Vec::new();
+        for ((dx, dy), (x, y)) in positions {
+            let mut index_of = |mold: &Rc<Mold>| {
+                let address = Rc::as_ptr(mold);
+                copied
+                    .iter()
+                    .position(|copy| *copy == address)
+                    .unwrap_or_else(|| {
+                        copied.push(address);
+                        let genome = Genome::clone(&mold.genome);
+                        stamp.molds.push((genome, *mold.energy.borrow()));
+                        copied.len() - 1
+                    })
+            };
+            let cell = match &self.grid[x][y] {
+                Cell::Empty => StampCell::Empty,
+                Cell::Wall => StampCell::Wall,
+                Cell::Predator { energy, age } => StampCell::Predator {
+                    energy: *energy,
+                    age: *age,
+                },
+                Cell::Spore {
+                    mold,
+                    age,
+                    direction,
+                } => StampCell::Spore {
+                    mold: index_of(mold),
+                    age: *age,
+                    direction: *direction,
+                },
+                Cell::MoldPart {
+                    mold,
+                    age,
+                    active_gene,
+                    direction,
+                } => StampCell::MoldPart {
+                    mold: index_of(mold),
+                    age: *age,
+                    active_gene: *active_gene,
+                    direction: *direction,
+                },
+            };
+            stamp.cells[dx * height + dy] = cell;
+        }
+        stamp
+    }
+
+    /// Place a stamp with its top left corner at (x, y), replacing everything it covers, walls
+    /// included. Cells past the right or bottom edge wrap around when the edges wrap, and are
+    /// left out when they are bounded. Every mold of the stamp becomes a new mold with a new id,
+    /// its genome and the energy it had when it was copied, which counts as spawned energy.
+    pub fn paste(&mut self, stamp: &Stamp, x: usize, y: usize) {
+        let rect = Rect {
+            x,
+            y,
+            width: stamp.width,
+            height: stamp.height,
+        };
+        let molds: Vec<_> = stamp
+            .molds
+            .iter()
+            .map(|(genome, energy)| {
+                let mold = self.new_mold(genome.clone(), None);
+                *mold.energy.borrow_mut() = *energy;
+                mold
+            })
+            .collect();
+        let mut placed = vec![false; molds.len()];
+        for ((dx, dy), (x, y)) in self.region_positions(rect) {
+            self.remove_cell(x, y);
+            let cell = match stamp.cells[dx * stamp.height + dy] {
+                StampCell::Empty => Cell::Empty,
+                StampCell::Wall => Cell::Wall,
+                StampCell::Predator { energy, age } => {
+                    self.energy_ledger.spawned += energy as i64;
+                    Cell::Predator { energy, age }
+                }
+                StampCell::Spore {
+                    mold,
+                    age,
+                    direction,
+                } => {
+                    placed[mold] = true;
+                    Cell::Spore {
+                        mold: molds[mold].clone(),
+                        age,
+                        direction,
+                    }
+                }
+                StampCell::MoldPart {
+                    mold,
+                    age,
+                    active_gene,
+                    direction,
+                } => {
+                    placed[mold] = true;
+                    Cell::MoldPart {
+                        mold: molds[mold].clone(),
+                        age,
+                        active_gene,
+                        direction,
+                    }
+                }
+            };
+            self.set_cell(x, y, cell);
+        }
+        // molds cut off entirely by a bounded edge never existed, so their energy isn't booked
+        for (mold, placed) in molds.iter().zip(placed) {
+            if placed {
+                self.energy_ledger.spawned += *mold.energy.borrow() as i64;
+            }
+        }
+    }
+}
@@ -0,0 +1,364 @@
+use std::collections::HashSet;
+use std::f64::consts::TAU;
+use std::rc::Rc;
+
+use crate::{Cell, Edges, Mold, MoldId, Simulation};
+
+/// Counts of what is currently on the grid.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Number of distinct living molds.
+    pub molds: usize,
+    /// Number of cells occupied by mold parts, not counting spores.
+    pub mold_cells: usize,
+    pub spores: usize,
+    pub predators: usize,
+    /// Energy of all molds and predators together.
+    pub total_energy: i64,
+    /// Number of distinct sets of genes among the living molds.
+    pub genomes: usize,
+    /// Sum of the generations of the living molds, see Stats::mean_generation.
+    pub generations: u64,
+}
+
+impl Stats {
+    /// Average number of spores between the living molds and their founders, 0 without molds.
+    pub fn mean_generation(&self) -> f64 {
+        if self.molds == 0 {
+            return 0.;
+        }
+        self.generations as f64 / self.molds as f64
+    }
+}
+
+/// Energy that entered and left the simulation since it was created. The balance always equals
+/// the total energy of everything on the grid.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EnergyLedger {
+    /// Light given to molds.
+    pub light: i64,
+    /// Energy of predators placed with Simulation::spawn_predator, of molds pasted with stamps,
+    /// and given to molds with Simulation::add_energy.
+    pub spawned: i64,
+    /// Energy used up every tick by mold cells and predators.
+    pub upkeep: i64,
+    /// Energy molds paid for growing cells and spores, see Simulation::set_growth_cost.
+    pub growth: i64,
+    /// Energy left in molds and predators when they were removed. Molds and predators are removed
+    /// once they run out of energy, so this is usually negative.
+    pub removed: i64,
+}
+
+impl EnergyLedger {
+    /// Energy that should currently be on the grid according to the ledger.
+    pub fn balance(&self) -> i64 {
+        self.light + self.spawned - self.upkeep - self.growth - self.removed
+    }
+}
+
+impl Simulation {
+    /// Count the molds, cells and energy on the grid.
+    pub fn stats(&self) -> Stats {
+        let mut stats = Stats::default();
+        let mut seen: HashSet<*const Mold> = HashSet::new();
+        let mut genomes = HashSet::new();
+        for cell in self.grid.iter().flatten() {
+            let mold = match cell {
+                Cell::Empty | Cell::Wall => continue,
+                Cell::Predator { energy, .. } => {
+                    stats.predators += 1;
+                    stats.total_energy += *energy as i64;
+                    continue;
+                }
+                Cell::MoldPart { mold, .. } => {
+                    stats.mold_cells += 1;
+                    mold
+                }
+                Cell::Spore { mold, .. } => {
+                    stats.spores += 1;
+                    mold
+                }
+            };
+            if seen.insert(Rc::as_ptr(mold)) {
+                stats.molds += 1;
+                stats.total_energy += *mold.energy.borrow() as i64;
+                stats.generations += u64::from(mold.generation);
+                genomes.insert(mold.genome.hash64());
+            }
+        }
+        stats.genomes = genomes.len();
+        stats
+    }
+
+    /// Number of cells occupied by mold parts, not counting spores. Unlike Stats::mold_cells
+    /// this is kept up to date as cells change rather than counted on the grid.
+    pub fn mold_part_count(&self) -> usize {
+        self.mold_part_count
+    }
+
+    /// Number of spores on the grid, kept up to date like mold_part_count.
+    pub fn spore_count(&self) -> usize {
+        self.spore_count
+    }
+
+    /// Center of the cells and spores of all molds, in cells, or None if there are none. When the
+    /// grid wraps around, the mean is taken around each axis as around a circle, so a colony
+    /// lying across an edge is centered on that edge rather than in the middle of the grid.
+    pub fn mold_centroid(&self) -> Option<(f32, f32)> {
+        self.centroid(|_| true)
+    }
+
+    /// Center of the cells and spores of the mold with the given id like mold_centroid, or None
+    /// if it has none.
+    pub fn mold_centroid_of(&self, id: MoldId) -> Option<(f32, f32)> {
+        self.centroid(|mold| mold.id == id)
+    }
+
+    /// Center of the cells and spores of the molds for which `include` returns true, see
+    /// mold_centroid.
+    fn centroid(&self, include: impl Fn(&Mold) -> bool) -> Option<(f32, f32)> {
+        // per axis the sum of the positions, and of the sine and cosine of their angles
+        let mut sums = [(0f64, 0f64, 0f64); 2];
+        let mut count = 0;
+        for x in 0..self.size_x {
+            for y in 0..self.size_y {
+                match &self.grid[x][y] {
+                    Cell::MoldPart { mold, .. } | Cell::Spore { mold, .. } if include(mold) => {}
+                    _ => continue,
+                }
+                count += 1;
+                for (sum, (position, len)) in
+                    sums.iter_mut().zip([(x, self.size_x), (y, self.size_y)])
+                {
+                    let center = position as f64 + 0.5;
+                    let angle = center / len as f64 * TAU;
+                    sum.0 += center;
+                    sum.1 += angle.sin();
+                    sum.2 += angle.cos();
+                }
+            }
+        }
+        if count == 0 {
+            return None;
+        }
+        let mean = |(sum, sin, cos): (f64, f64, f64), len: usize| {
+            // evenly spread cells have no circular mean
+            let wraps = self.edges == Edges::Wrap && sin.hypot(cos) > count as f64 * 1e-6;
+            if wraps {
+                let mean = sin.atan2(cos).rem_euclid(TAU) / TAU * len as f64;
+                // a mean just below the length can round up to it
+                (mean as f32).rem_euclid(len as f32)
+            } else {
+                (sum / count as f64) as f32
+            }
+        };
+        Some((mean(sums[0], self.size_x), mean(sums[1], self.size_y)))
+    }
+
+    /// Whether no mold is left on the grid, not even as a spore. Predators and walls don't count.
+    pub fn is_extinct(&self) -> bool {
+        self.mold_part_count == 0 && self.spore_count == 0
+    }
+
+    /// Number of empty cells next to two or more different molds. With the exclusive energy
+    /// policy these give no light to anyone, so this measures how much light is lost where molds
+    /// meet.
+    pub fn contested_empty_cells(&self) -> usize {
+        let mut contested = 0;
+        for x in 0..self.size_x {
+            for y in 0..self.size_y {
+                if matches!(self.grid[x][y], Cell::Empty) && self.neighbor_molds(x, y).len() >= 2 {
+                    contested += 1;
+                }
+            }
+        }
+        contested
+    }
+
+    /// Energy that entered and left the simulation so far.
+    pub fn energy_ledger(&self) -> EnergyLedger {
+        self.energy_ledger
+    }
+
+    /// Number of cells molds grew into in each absolute direction (down, right, up, left) since
+    /// the simulation was created or reset_growth_by_direction was called. Sprouting spores are
+    /// not counted.
+    pub fn growth_by_direction(&self) -> [u64; 4] {
+        self.growth_by_direction
+    }
+
+    /// Start counting growth by direction from zero.
+    pub fn reset_growth_by_direction(&mut self) {
+        self.growth_by_direction = [0; 4];
+    }
+
+    /// Panic if energy appeared or disappeared without being booked in the ledger.
+    #[cfg(debug_assertions)]
+    pub(crate) fn check_energy_balance(&self) {
+        let total_energy = self.stats().total_energy;
+        assert_eq!(
+            self.energy_ledger.balance(),
+            total_energy,
+            "energy ledger out of balance at tick {}",
+            self.tick
+        );
+    }
+
+    /// Panic if the running counts of mold parts and spores differ from those on the grid.
+    #[cfg(debug_assertions)]
+    pub(crate) fn check_cell_counts(&self) {
+        let stats = self.stats();
+        assert_eq!(
+            (self.mold_part_count, self.spore_count),
+            (stats.mold_cells, stats.spores),
+            "cell counts out of date at tick {}",
+            self.tick
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Genome, Rect, GENE_DIRECTIONS, GENOME_SIZE};
+
+    /// A 20x10 grid with one mold covering `cells` and another one at (10, 8).
+    fn colony(edges: Edges, cells: &[(usize, usize)]) -> (Simulation, MoldId) {
+        let mut simulation = Simulation::builder(20, 10).edges(edges).build();
+        let (x, y) = cells[0];
+        assert!(simulation.generate_mold(x, y));
+        let part = simulation.grid[x][y].clone();
+        for &(x, y) in &cells[1..] {
+            simulation.set_cell(x, y, part.clone());
+        }
+        assert!(simulation.generate_mold(10, 8));
+        let id = simulation.mold_at(x, y).unwrap().id;
+        (simulation, id)
+    }
+
+    fn assert_near(actual: (f32, f32), expected: (f32, f32)) {
+        let near = (actual.0 - expected.0).abs() < 1e-4 && (actual.1 - expected.1).abs() < 1e-4;
+        assert!(near, "{actual:?} is not {expected:?}");
+    }
+
+    #[test]
+    fn centroid_across_left_edge() {
+        let cells = [(18, 4), (19, 4), (0, 4), (1, 4), (0, 5), (19, 5)];
+        let (simulation, id) = colony(Edges::Wrap, &cells);
+        let (x, y) = simulation.mold_centroid_of(id).unwrap();
+        // the edge itself may come out as 0 or just below 20
+        assert!(!(1e-4..=20. - 1e-4).contains(&x), "{x}");
+        assert!(y > 4.5 && y < 5., "{y}");
+        let (simulation, id) = colony(Edges::Bounded, &cells);
+        assert_near(simulation.mold_centroid_of(id).unwrap(), (10., 4.8333));
+    }
+
+    #[test]
+    fn centroid_of_one_or_all_molds() {
+        let (mut simulation, id) = colony(Edges::Bounded, &[(3, 2), (4, 2), (4, 3), (3, 3)]);
+        assert_near(simulation.mold_centroid_of(id).unwrap(), (4., 3.));
+        let other = simulation.mold_at(10, 8).unwrap().id;
+        assert_near(simulation.mold_centroid_of(other).unwrap(), (10.5, 8.5));
+        assert_near(simulation.mold_centroid().unwrap(), (5.3, 4.1));
+        simulation.clear();
+        assert_eq!(simulation.mold_centroid(), None);
+        assert_eq!(simulation.mold_centroid_of(id), None);
+    }
+
+    #[test]
+    fn centroid_of_spread_out_mold() {
+        // no circular mean exists, so the plain mean is used
+        let cells: Vec<(usize, usize)> = (0..20).map(|x| (x, 2)).collect();
+        let (simulation, id) = colony(Edges::Wrap, &cells);
+        assert_near(simulation.mold_centroid_of(id).unwrap(), (10., 2.5));
+    }
+
+    /// Spawn a mold at (x, y) with the given genes for its first genes and no growth in the
+    /// others.
+    fn spawn(simulation: &mut Simulation, x: usize, y: usize, first_genes: &[isize]) {
+        let mut genes = [-2; GENOME_SIZE * GENE_DIRECTIONS];
+        genes[..first_genes.len()].copy_from_slice(first_genes);
+        let genome = Genome::from_genes(&genes, 0x808080).unwrap();
+        assert!(simulation.spawn_mold(x, y, genome));
+    }
+
+    #[test]
+    fn growth_counted_by_absolute_direction() {
+        let mut simulation = Simulation::builder(10, 10).edges(Edges::Bounded).build();
+        // keeps growing straight ahead, which is down for a new mold
+        spawn(&mut simulation, 0, 0, &[-2, 0, -2]);
+        // grows left and right once, then up from the left cell, which faces left
+        spawn(&mut simulation, 5, 5, &[1, -2, 2, -1, -2, -2]);
+        simulation.run(3);
+        assert_eq!(simulation.growth_by_direction(), [3, 1, 1, 1]);
+        simulation.run(2);
+        assert_eq!(simulation.growth_by_direction(), [5, 1, 1, 1]);
+
+        simulation.reset_growth_by_direction();
+        assert_eq!(simulation.growth_by_direction(), [0; 4]);
+        simulation.run(1);
+        assert_eq!(simulation.growth_by_direction(), [1, 0, 0, 0]);
+    }
+
+    /// Check the running counts of mold parts and spores against a count on the grid, and return
+    /// them.
+    fn counts(simulation: &Simulation) -> (usize, usize) {
+        let stats = simulation.stats();
+        let counts = (simulation.mold_part_count(), simulation.spore_count());
+        assert_eq!(counts, (stats.mold_cells, stats.spores));
+        counts
+    }
+
+    #[test]
+    fn cell_counts_follow_the_grid() {
+        let mut simulation = Simulation::builder(60, 40).seed(2).build();
+        assert_eq!(counts(&simulation), (0, 0));
+        assert!(simulation.is_extinct());
+        simulation.generate_random_molds(30);
+        assert_eq!(counts(&simulation), (30, 0));
+        for _ in 0..10 {
+            simulation.run(50);
+            counts(&simulation);
+        }
+        let (parts, spores) = counts(&simulation);
+        assert!(parts > 0 && spores > 0, "{parts} parts and {spores} spores");
+
+        simulation.clear_region(Rect {
+            x: 0,
+            y: 0,
+            width: 30,
+            height: 40,
+        });
+        assert!(simulation.grid[..30]
+            .iter()
+            .flatten()
+            .all(|cell| matches!(cell, Cell::Empty)));
+        counts(&simulation);
+        for x in 30..60 {
+            simulation.erase_cell(x, 20);
+        }
+        counts(&simulation);
+        simulation.resize(20, 30);
+        counts(&simulation);
+        simulation.clear();
+        assert_eq!(counts(&simulation), (0, 0));
+        assert!(simulation.is_extinct());
+    }
+
+    #[test]
+    fn contested_cells_touch_two_different_molds() {
+        // A . B # C . C . #: the empty cells touch A and B, C twice, and C once
+        let mut simulation = Simulation::builder(9, 1).edges(Edges::Bounded).build();
+        let inert = || Genome::from_genes(&[-2; GENOME_SIZE * GENE_DIRECTIONS], 0x808080).unwrap();
+        for x in [0, 2, 4] {
+            assert!(simulation.spawn_mold(x, 0, inert()));
+        }
+        let part = simulation.grid[4][0].clone();
+        simulation.set_cell(6, 0, part);
+        for x in [3, 8] {
+            simulation.set_wall(x, 0, true);
+        }
+        assert_eq!(simulation.stats().molds, 3);
+        assert_eq!(simulation.contested_empty_cells(), 1);
+    }
+}
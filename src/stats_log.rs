@@ -0,0 +1,79 @@
+//! Writing the stats of a headless run to a CSV file every so many ticks, to compare runs in
+//! other tools afterwards.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use rustymold::{Simulation, Stats};
+
+/// ticks between two rows when --stats-interval isn't given
+pub const STATS_INTERVAL: u64 = 100;
+
+const HEADER: &str = "tick,occupied,molds,genomes,energy,mean_generation";
+
+/// An open CSV file with one row of stats per `interval` ticks.
+pub struct StatsLog {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    interval: u64,
+}
+
+impl StatsLog {
+    /// Create the file at `path`, replacing it if it exists, and write the header and the row of
+    /// the current state of the simulation.
+    pub fn create(path: &Path, interval: u64, simulation: &Simulation) -> Result<Self, String> {
+        let file =
+            File::create(path).map_err(|e| format!("cannot create {}: {e}", path.display()))?;
+        let mut log = Self {
+            path: path.to_path_buf(),
+            writer: BufWriter::new(file),
+            interval: interval.max(1),
+        };
+        log.write(format_args!("{HEADER}"))?;
+        log.write_row(simulation.tick(), &simulation.stats())?;
+        Ok(log)
+    }
+
+    /// Write a row if the tick of the simulation is a multiple of the interval. Meant to be
+    /// called after every update so no row is skipped.
+    pub fn record(&mut self, simulation: &Simulation) -> Result<(), String> {
+        let tick = simulation.tick();
+        if !tick.is_multiple_of(self.interval) {
+            return Ok(());
+        }
+        self.write_row(tick, &simulation.stats())
+    }
+
+    /// Write a comment line summing up the run of `ticks` updates and close the file.
+    pub fn finish(mut self, simulation: &Simulation, ticks: usize) -> Result<(), String> {
+        let stats = simulation.stats();
+        self.write(format_args!(
+            "# ran {ticks} ticks, ending at tick {} with {} molds of {} genomes in {} cells",
+            simulation.tick(),
+            stats.molds,
+            stats.genomes,
+            stats.mold_cells + stats.spores,
+        ))?;
+        self.writer
+            .flush()
+            .map_err(|e| format!("cannot write {}: {e}", self.path.display()))
+    }
+
+    fn write_row(&mut self, tick: u64, stats: &Stats) -> Result<(), String> {
+        self.write(format_args!(
+            "{tick},{},{},{},{},{:.3}",
+            stats.mold_cells + stats.spores,
+            stats.molds,
+            stats.genomes,
+            stats.total_energy,
+            stats.mean_generation(),
+        ))
+    }
+
+    /// Write one line to the file.
+    fn write(&mut self, line: std::fmt::Arguments) -> Result<(), String> {
+        writeln!(self.writer, "{line}")
+            .map_err(|e| format!("cannot write {}: {e}", self.path.display()))
+    }
+}
@@ -0,0 +1,43 @@
+//! Writing the whole grid to a numbered PNG file every so many ticks, to assemble a time-lapse of
+//! a long run from later.
+
+use std::path::PathBuf;
+
+use rustymold::Simulation;
+
+/// ticks between two frames when --timelapse-interval isn't given
+pub const TIMELAPSE_INTERVAL: u64 = 500;
+
+/// Where and how often frames are written.
+#[derive(Clone, Debug)]
+pub struct Timelapse {
+    pub dir: PathBuf,
+    /// Ticks between two frames, at least 1.
+    pub interval: u64,
+}
+
+impl Timelapse {
+    /// Write the whole grid at one pixel per cell to a file in `dir` named after the tick if the
+    /// tick is a multiple of the interval, creating `dir` if needed. Meant to be called after
+    /// every update so no frame is skipped.
+    pub fn capture(&self, simulation: &Simulation) -> Result<(), String> {
+        let tick = simulation.tick();
+        if !tick.is_multiple_of(self.interval.max(1)) {
+            return Ok(());
+        }
+        let path = self.dir.join(format!("tick-{tick:012}.png"));
+        #[cfg(feature = "image")]
+        {
+            std::fs::create_dir_all(&self.dir)
+                .map_err(|e| format!("cannot create {}: {e}", self.dir.display()))?;
+            simulation
+                .save_png(&path, rustymold::RenderOptions::default())
+                .map_err(|e| format!("cannot write {}: {e}", path.display()))
+        }
+        #[cfg(not(feature = "image"))]
+        Err(format!(
+            "cannot write {}: built without the image feature",
+            path.display()
+        ))
+    }
+}
@@ -0,0 +1,191 @@
+//! Pacing of simulation updates by wall clock time, independent of the frame rate.
+
+use std::time::Duration;
+
+/// How the window decides how many updates to run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Speed {
+    /// The ticks per second of the FixedTimestep, whatever the frame rate.
+    Paced,
+    /// A fixed number of updates every frame, however long they take.
+    PerFrame(usize),
+    /// One update every this many frames, rendering and handling input every frame in between.
+    Slow(u32),
+    /// As many updates as possible, rendering only now and then.
+    Turbo,
+}
+
+impl Speed {
+    /// Speed of the number key preset with the given index: 1, 2, 4 and so on updates every
+    /// frame, or in slow motion one update every 2, 4 and so on frames. The first preset is one
+    /// update every frame either way.
+    pub fn preset(index: usize, slow: bool) -> Self {
+        match (slow, index) {
+            (true, 1..) => Speed::Slow(1 << index),
+            _ => Speed::PerFrame(1 << index),
+        }
+    }
+
+    /// Text shown after the updates per second, with the target of a paced speed.
+    pub fn label(self, ticks_per_second: u32) -> String {
+        match self {
+            Speed::Paced => format!("/{ticks_per_second}"),
+            Speed::PerFrame(steps) => format!(" ({steps} per frame)"),
+            Speed::Slow(frames) => format!(" (1/{frames} per frame)"),
+            Speed::Turbo => " (turbo)".to_string(),
+        }
+    }
+}
+
+/// Decides how many updates are due each frame to run the simulation at a fixed number of ticks
+/// per second, whatever the frame rate.
+pub struct FixedTimestep {
+    ticks_per_second: u32,
+    /// most updates that may be due in a single frame
+    max_steps: usize,
+    /// time that passed without an update covering it, in seconds
+    accumulated: f64,
+}
+
+impl FixedTimestep {
+    pub fn new(ticks_per_second: u32, max_steps: usize) -> Self {
+        Self {
+            ticks_per_second,
+            max_steps,
+            accumulated: 0.,
+        }
+    }
+
+    pub fn ticks_per_second(&self) -> u32 {
+        self.ticks_per_second
+    }
+
+    /// Change the target rate. A rate of 0 means no updates are ever due.
+    pub fn set_ticks_per_second(&mut self, ticks_per_second: u32) {
+        self.ticks_per_second = ticks_per_second;
+        self.accumulated = 0.;
+    }
+
+    /// Account for `elapsed` time passing and return the number of updates now due, at most
+    /// max_steps. When more are due than that, the time they cover is dropped instead of carried
+    /// over, so a slow frame can't start a backlog that keeps growing.
+    pub fn advance(&mut self, elapsed: Duration) -> usize {
+        if self.ticks_per_second == 0 {
+            return 0;
+        }
+        let step = 1. / self.ticks_per_second as f64;
+        self.accumulated += elapsed.as_secs_f64();
+        let due = (self.accumulated / step) as usize;
+        if due > self.max_steps {
+            self.accumulated = 0.;
+            return self.max_steps;
+        }
+        self.accumulated -= due as f64 * step;
+        due
+    }
+}
+
+/// Decides which frames run an update in slow motion, see Speed::Slow.
+#[derive(Debug, Default)]
+pub struct SlowMotion {
+    /// frames since the last update, not counting frames while paused
+    frames: u32,
+}
+
+impl SlowMotion {
+    /// Count a frame and return the number of updates now due: 1 on every `frames`th frame and 0
+    /// on the frames in between.
+    pub fn advance(&mut self, frames: u32) -> usize {
+        self.frames += 1;
+        if self.frames < frames {
+            return 0;
+        }
+        self.frames = 0;
+        1
+    }
+
+    /// Start counting frames from 0 again, so the next update is a whole period away.
+    pub fn reset(&mut self) {
+        self.frames = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 1/8 of a second, which like its multiples is exact as seconds in floating point
+    const EIGHTH: Duration = Duration::from_millis(125);
+
+    #[test]
+    fn carries_time_over() {
+        let mut timestep = FixedTimestep::new(4, 10);
+        assert_eq!(timestep.advance(EIGHTH), 0);
+        assert_eq!(timestep.advance(EIGHTH), 1);
+        assert_eq!(timestep.advance(EIGHTH * 3), 1);
+        assert_eq!(timestep.advance(EIGHTH), 1);
+        assert_eq!(timestep.advance(EIGHTH * 8), 4);
+        assert_eq!(timestep.advance(Duration::ZERO), 0);
+    }
+
+    #[test]
+    fn drops_backlog() {
+        let mut timestep = FixedTimestep::new(4, 3);
+        assert_eq!(timestep.advance(EIGHTH * 6), 3);
+        assert_eq!(timestep.advance(EIGHTH * 3), 1);
+        // the half second past the 3 updates was dropped, not only the part that didn't fit
+        assert_eq!(timestep.advance(Duration::from_secs(10)), 3);
+        assert_eq!(timestep.advance(EIGHTH), 0);
+    }
+
+    #[test]
+    fn changing_rate_starts_over() {
+        let mut timestep = FixedTimestep::new(4, 10);
+        assert_eq!(timestep.advance(EIGHTH), 0);
+        timestep.set_ticks_per_second(8);
+        assert_eq!(timestep.ticks_per_second(), 8);
+        assert_eq!(timestep.advance(EIGHTH / 2), 0);
+        assert_eq!(timestep.advance(EIGHTH / 2), 1);
+        timestep.set_ticks_per_second(0);
+        assert_eq!(timestep.advance(Duration::from_secs(10)), 0);
+        timestep.set_ticks_per_second(4);
+        assert_eq!(timestep.advance(EIGHTH), 0);
+    }
+
+    #[test]
+    fn presets_and_labels() {
+        assert_eq!(Speed::preset(0, false), Speed::PerFrame(1));
+        assert_eq!(Speed::preset(8, false), Speed::PerFrame(256));
+        assert_eq!(Speed::preset(0, true), Speed::PerFrame(1));
+        assert_eq!(Speed::preset(1, true), Speed::Slow(2));
+        assert_eq!(Speed::preset(8, true), Speed::Slow(256));
+        let labels = [
+            Speed::Paced,
+            Speed::PerFrame(4),
+            Speed::Slow(4),
+            Speed::Turbo,
+        ]
+        .map(|speed| speed.label(60));
+        assert_eq!(
+            labels,
+            ["/60", " (4 per frame)", " (1/4 per frame)", " (turbo)"]
+        );
+    }
+
+    #[test]
+    fn slow_motion_updates_every_few_frames() {
+        let mut slow_motion = SlowMotion::default();
+        let due: Vec<usize> = (0..8).map(|_| slow_motion.advance(4)).collect();
+        assert_eq!(due, [0, 0, 0, 1, 0, 0, 0, 1]);
+        assert_eq!(slow_motion.advance(4), 0);
+        assert_eq!(slow_motion.advance(4), 0);
+        slow_motion.reset();
+        let due: Vec<usize> = (0..4).map(|_| slow_motion.advance(4)).collect();
+        assert_eq!(due, [0, 0, 0, 1]);
+        // switching to a shorter period updates as soon as it has passed
+        slow_motion.advance(8);
+        slow_motion.advance(8);
+        assert_eq!(slow_motion.advance(2), 1);
+        assert!((0..5).all(|_| slow_motion.advance(1) == 1));
+    }
+}
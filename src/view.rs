@@ -0,0 +1,208 @@
+//! What the window shows of a simulation, kept apart from it so clearing or loading a simulation
+//! doesn't change the view.
+
+use rustymold::{BufferRegion, Camera, Simulation, ViewMode};
+
+/// fraction of the activity of a cell kept from one tick to the next in the activity view
+pub const ACTIVITY_DECAY: f32 = 0.97;
+/// smallest zoom at which grid lines are drawn, below it they would cover most of the cells
+pub const MIN_GRID_LINE_ZOOM: f32 = 4.;
+
+/// View mode and layers shown for one simulation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ViewState {
+    pub view_mode: ViewMode,
+    pub light_overlay: bool,
+    pub trail: bool,
+    pub grid_lines: bool,
+}
+
+impl ViewState {
+    /// View of a simulation with nothing shown over the cells.
+    pub fn new(view_mode: ViewMode) -> Self {
+        Self {
+            view_mode,
+            light_overlay: false,
+            trail: false,
+            grid_lines: false,
+        }
+    }
+
+    /// Switch to the next view mode, see next_view_mode.
+    pub fn cycle_view_mode(&mut self) {
+        self.view_mode = next_view_mode(self.view_mode);
+    }
+
+    /// Switch to the activity view, or from it back to `configured`, or the default view mode if
+    /// that is the activity view itself.
+    pub fn toggle_activity(&mut self, configured: ViewMode) {
+        self.view_mode = match (self.view_mode, configured) {
+            (ViewMode::Activity, ViewMode::Activity) => ViewMode::default(),
+            (ViewMode::Activity, configured) => configured,
+            _ => ViewMode::Activity,
+        };
+    }
+
+    /// Bring the render settings of a simulation in line with this view. Only settings that
+    /// differ are changed, so applying the same view every frame doesn't force a full render.
+    /// Activity is tracked only while the activity view is shown.
+    pub fn apply(&self, simulation: &mut Simulation) {
+        if simulation.view_mode() != self.view_mode {
+            simulation.set_view_mode(self.view_mode);
+        }
+        if simulation.light_overlay() != self.light_overlay {
+            simulation.set_light_overlay(self.light_overlay);
+        }
+        if simulation.has_trail() != self.trail {
+            simulation.set_trail(self.trail);
+        }
+        let activity = (self.view_mode == ViewMode::Activity).then_some(ACTIVITY_DECAY);
+        if simulation.activity_trail() != activity {
+            simulation.set_activity_trail(activity);
+        }
+    }
+}
+
+/// The view mode following `view_mode` when cycling through them with the V key.
+pub fn next_view_mode(view_mode: ViewMode) -> ViewMode {
+    match view_mode {
+        ViewMode::Color => ViewMode::Energy,
+        ViewMode::Energy => ViewMode::SpeciesHash,
+        ViewMode::SpeciesHash => ViewMode::Lineage,
+        ViewMode::Lineage => ViewMode::Activity,
+        ViewMode::Activity => ViewMode::Color,
+    }
+}
+
+/// Draw lines along the edges of the cells rendered into a region of a buffer with a camera,
+/// when it is zoomed in at least MIN_GRID_LINE_ZOOM.
+pub fn draw_grid_lines(
+    buffer: &mut [u32],
+    region: BufferRegion,
+    camera: &Camera,
+    grid_size: (usize, usize),
+    color: u32,
+) {
+    if camera.zoom < MIN_GRID_LINE_ZOOM {
+        return;
+    }
+    let columns: Vec<usize> = (0..region.size.0)
+        .filter(|&x| camera.is_cell_edge((x, 0), grid_size).0)
+        .collect();
+    for y in 0..region.size.1 {
+        let start = (region.origin.1 + y) * region.stride + region.origin.0;
+        let row = &mut buffer[start..start + region.size.0];
+        if camera.is_cell_edge((0, y), grid_size).1 {
+            row.fill(color);
+            continue;
+        }
+        for &x in &columns {
+            row[x] = color;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A view with every layer shown.
+    fn layered(view_mode: ViewMode) -> ViewState {
+        ViewState {
+            light_overlay: true,
+            trail: true,
+            ..ViewState::new(view_mode)
+        }
+    }
+
+    /// Whether the render settings of a simulation are those of a view.
+    fn shows(simulation: &Simulation, view: &ViewState) -> bool {
+        let activity = (view.view_mode == ViewMode::Activity).then_some(ACTIVITY_DECAY);
+        simulation.view_mode() == view.view_mode
+            && simulation.light_overlay() == view.light_overlay
+            && simulation.has_trail() == view.trail
+            && simulation.activity_trail() == activity
+    }
+
+    #[test]
+    fn cycles_through_every_view_mode() {
+        let mut view = ViewState::new(ViewMode::Color);
+        let mut seen = Vec::new();
+        for _ in 0..5 {
+            view.cycle_view_mode();
+            assert!(!seen.contains(&view.view_mode), "{seen:?}");
+            seen.push(view.view_mode);
+        }
+        assert_eq!(
+            seen,
+            [
+                ViewMode::Energy,
+                ViewMode::SpeciesHash,
+                ViewMode::Lineage,
+                ViewMode::Activity,
+                ViewMode::Color
+            ]
+        );
+    }
+
+    #[test]
+    fn activity_toggles_back_to_configured_view() {
+        let mut view = ViewState::new(ViewMode::Energy);
+        view.toggle_activity(ViewMode::Lineage);
+        assert_eq!(view.view_mode, ViewMode::Activity);
+        view.toggle_activity(ViewMode::Lineage);
+        assert_eq!(view.view_mode, ViewMode::Lineage);
+        view.toggle_activity(ViewMode::Activity);
+        view.toggle_activity(ViewMode::Activity);
+        assert_eq!(view.view_mode, ViewMode::default());
+    }
+
+    #[test]
+    fn view_survives_clear_and_load() {
+        let mut simulation = Simulation::builder(20, 10).seed(1).build();
+        simulation.generate_random_molds(10);
+        for view_mode in [ViewMode::Energy, ViewMode::Activity] {
+            let view = layered(view_mode);
+            view.apply(&mut simulation);
+            simulation.run(10);
+            assert!(shows(&simulation, &view));
+            simulation.clear();
+            view.apply(&mut simulation);
+            assert!(shows(&simulation, &view));
+
+            let mut bytes = Vec::new();
+            simulation.write_snapshot(&mut bytes).unwrap();
+            let mut loaded = Simulation::read_snapshot(bytes.as_slice()).unwrap();
+            view.apply(&mut loaded);
+            assert!(shows(&loaded, &view));
+            ViewState::new(ViewMode::Color).apply(&mut loaded);
+            assert!(shows(&loaded, &ViewState::new(ViewMode::Color)));
+        }
+    }
+
+    #[test]
+    fn applying_same_view_keeps_render() {
+        let mut simulation = Simulation::builder(20, 10).seed(1).build();
+        simulation.generate_random_molds(10);
+        simulation.run(10);
+        let buffer_size = (40, 20);
+        let mut buffer = vec![0; buffer_size.0 * buffer_size.1];
+        let camera = Camera::default();
+        // without trails and activity, which change every tick and are always rendered again
+        let lit = ViewState {
+            light_overlay: true,
+            ..ViewState::new(ViewMode::Color)
+        };
+        for view in [lit, ViewState::new(ViewMode::Lineage), lit] {
+            view.apply(&mut simulation);
+            assert!(simulation
+                .render_dirty(&mut buffer, buffer_size, &camera)
+                .is_some());
+            view.apply(&mut simulation);
+            assert_eq!(
+                simulation.render_dirty(&mut buffer, buffer_size, &camera),
+                None
+            );
+        }
+    }
+}
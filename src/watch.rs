@@ -0,0 +1,94 @@
+//! Following the numbers of a single mold as they change, cheaply enough to query every frame.
+
+use std::rc::{Rc, Weak};
+
+use crate::{Cell, Mold, MoldId, Simulation};
+
+/// The mold being watched, with its cells counted during the last energy pass.
+#[derive(Clone, Debug)]
+pub(crate) struct Watch {
+    pub(crate) id: MoldId,
+    pub(crate) mold: Weak<Mold>,
+    pub(crate) cells: usize,
+    pub(crate) oldest_age: u32,
+}
+
+impl Watch {
+    /// Count a cell of age `age` if it belongs to the watched mold.
+    #[inline]
+    pub(crate) fn count(&mut self, mold: &Rc<Mold>, age: u32) {
+        if std::ptr::eq(self.mold.as_ptr(), Rc::as_ptr(mold)) {
+            self.cells += 1;
+            self.oldest_age = self.oldest_age.max(age);
+        }
+    }
+}
+
+/// Numbers of the watched mold, see Simulation::watch.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WatchedMold {
+    pub id: MoldId,
+    /// Whether the mold still has cells or spores on the grid. The other numbers are 0 once it
+    /// died.
+    pub alive: bool,
+    pub energy: i32,
+    /// Number of cells of the mold, including its spores.
+    pub cells: usize,
+    /// Age of the oldest cell or spore of the mold.
+    pub oldest_age: u32,
+    /// Number of spores between the founder of the lineage and the mold.
+    pub generation: u32,
+}
+
+impl Simulation {
+    /// Start watching the mold with the given id, or stop watching with None. Its energy is read
+    /// live and its cells are counted along with every energy pass, so watched is cheap enough to
+    /// call every frame. Finding the mold takes a pass over the grid; if there is no mold with
+    /// the id nothing is watched.
+    pub fn watch(&mut self, id: Option<MoldId>) {
+        self.watch = id.and_then(|id| {
+            let mold = self.grid.iter().flatten().find_map(|cell| match cell {
+                Cell::MoldPart { mold, .. } | Cell::Spore { mold, .. } if mold.id == id => {
+                    Some(mold)
+                }
+                _ => None,
+            })?;
+            let mut watch = Watch {
+                id,
+                mold: Rc::downgrade(mold),
+                cells: 0,
+                oldest_age: 0,
+            };
+            for cell in self.grid.iter().flatten() {
+                if let Cell::MoldPart { mold, age, .. } | Cell::Spore { mold, age, .. } = cell {
+                    watch.count(mold, *age);
+                }
+            }
+            Some(watch)
+        });
+    }
+
+    /// The watched mold, or None if no mold is watched. Once it died it is still returned, with
+    /// `alive` false, until watch is called again.
+    pub fn watched(&self) -> Option<WatchedMold> {
+        let watch = self.watch.as_ref()?;
+        Some(match watch.mold.upgrade() {
+            Some(mold) => WatchedMold {
+                id: watch.id,
+                alive: true,
+                energy: *mold.energy.borrow(),
+                cells: watch.cells,
+                oldest_age: watch.oldest_age,
+                generation: mold.generation,
+            },
+            None => WatchedMold {
+                id: watch.id,
+                alive: false,
+                energy: 0,
+                cells: 0,
+                oldest_age: 0,
+                generation: 0,
+            },
+        })
+    }
+}
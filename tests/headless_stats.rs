@@ -0,0 +1,130 @@
+//! Runs the binary without a window and checks the CSV file written with --stats-out.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+#[cfg(unix)]
+use std::time::Duration;
+
+/// A directory of its own for a test, removed when dropped.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("rustymold-{}-{name}", std::process::id()));
+        fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// The binary without a window on a small grid, writing its stats to `stats` and its settings
+/// next to them, with the given extra arguments.
+fn headless(dir: &TempDir, stats: &Path, args: &[&str]) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_rustymold"));
+    command
+        .arg("--headless")
+        .args(["--grid", "40x30", "--seed", "3"])
+        .arg("--config")
+        .arg(dir.0.join("config.toml"))
+        .arg("--stats-out")
+        .arg(stats)
+        .args(args);
+    command
+}
+
+/// Run `ticks` updates on a small grid with the given extra arguments, and return the lines of
+/// the stats file.
+fn run_headless(name: &str, ticks: usize, args: &[&str]) -> Vec<String> {
+    let dir = TempDir::new(name);
+    let stats = dir.0.join("stats.csv");
+    let output = headless(&dir, &stats, args)
+        .args(["--ticks", &ticks.to_string()])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    fs::read_to_string(&stats)
+        .unwrap()
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+/// Check the header, that there is a row for each of `ticks` and the summary line last.
+fn check_stats(lines: &[String], ticks: &[u64], run: usize) {
+    assert_eq!(
+        lines[0],
+        "tick,occupied,molds,genomes,energy,mean_generation"
+    );
+    let rows = &lines[1..lines.len() - 1];
+    assert_eq!(rows.len(), ticks.len(), "{lines:?}");
+    for (row, tick) in rows.iter().zip(ticks) {
+        let fields: Vec<&str> = row.split(',').collect();
+        assert_eq!(fields.len(), 6, "{row}");
+        assert_eq!(fields[0], tick.to_string());
+        for field in &fields[1..5] {
+            field.parse::<i64>().unwrap();
+        }
+        fields[5].parse::<f64>().unwrap();
+    }
+    let summary = lines.last().unwrap();
+    let expected = format!("# ran {run} ticks, ending at tick {run} with ");
+    assert!(summary.starts_with(&expected), "{summary}");
+}
+
+#[test]
+fn stats_every_interval() {
+    let lines = run_headless("interval", 250, &["--stats-interval", "50"]);
+    check_stats(&lines, &[0, 50, 100, 150, 200, 250], 250);
+}
+
+#[test]
+fn stats_every_default_interval() {
+    let lines = run_headless("default", 250, &[]);
+    check_stats(&lines, &[0, 100, 200], 250);
+}
+
+#[cfg(unix)]
+#[test]
+fn interrupt_ends_the_stats_with_the_summary() {
+    let dir = TempDir::new("interrupt");
+    let stats = dir.0.join("stats.csv");
+    let mut child = headless(
+        &dir,
+        &stats,
+        &["--ticks", "100000000", "--stats-interval", "10"],
+    )
+    .spawn()
+    .unwrap();
+    // Ctrl-C is only caught once the file is open, wait for rows after the first one
+    while fs::read_to_string(&stats).map_or(0, |text| text.lines().count()) < 3 {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    // SAFETY: kill has no memory safety requirements
+    assert_eq!(
+        unsafe { libc::kill(child.id() as libc::pid_t, libc::SIGINT) },
+        0
+    );
+    assert!(child.wait().unwrap().success());
+
+    let text = fs::read_to_string(&stats).unwrap();
+    let summary = text.lines().last().unwrap();
+    let ran: u64 = summary
+        .strip_prefix("# ran ")
+        .and_then(|rest| rest.split_once(" ticks, ending at tick "))
+        .and_then(|(ran, rest)| rest.starts_with(&format!("{ran} with ")).then_some(ran))
+        .unwrap_or_else(|| panic!("{summary}"))
+        .parse()
+        .unwrap();
+    assert!(ran < 100000000, "{summary}");
+    assert!(text.ends_with('\n'));
+}